@@ -1,8 +1,14 @@
+#![feature(const_type_id)]
+
 extern crate brotli;
 extern crate bytesize;
 extern crate failure;
 extern crate prost_build;
 extern crate prost_simple_rpc_build;
+extern crate pulldown_cmark;
+extern crate semantic;
+#[macro_use]
+extern crate semantic_derive;
 extern crate vergen;
 extern crate walkdir;
 
@@ -11,12 +17,29 @@ use std::ffi;
 use std::fs;
 use std::path;
 
+/// A stand-in for the error module of the main crate, which the document model refers to.
+#[allow(dead_code)]
+mod error {
+    pub type Result<A> = ::std::result::Result<A, ::failure::Error>;
+}
+
+/// The document model of the main crate, so that code can be generated from its classes.
+#[allow(dead_code)]
+#[path = "src/data"]
+mod data {
+    pub mod json;
+    pub mod markdown;
+    pub mod model;
+}
+
 fn main() {
     prost_build::Config::new()
         .service_generator(Box::new(prost_simple_rpc_build::ServiceGenerator::new()))
         .compile_protos(
             &[
+                "src/schema/se/document/document.proto",
                 "src/schema/se/service/service.proto",
+                "src/schema/se/slate/operation.proto",
                 "src/schema/se/slate/schema.proto",
                 "src/schema/se/transport/transport.proto",
             ],
@@ -25,6 +48,9 @@ fn main() {
 
     vergen::vergen(vergen::ConstantsFlags::all()).unwrap();
 
+    let manifest_dir = path::PathBuf::from(env::var_os("CARGO_MANIFEST_DIR").unwrap());
+    generate_typescript(&manifest_dir.join("src/data/index.d.ts")).unwrap();
+
     if cfg!(feature = "standalone") {
         let build_dir =
             path::PathBuf::from(env::var_os("CARGO_MANIFEST_DIR").unwrap()).join("build");
@@ -36,6 +62,19 @@ fn main() {
     }
 }
 
+/// Writes TypeScript declarations of the document model, for type-checking the frontend.
+fn generate_typescript(path: &path::Path) -> Result<(), failure::Error> {
+    let registry = semantic::Registry::of::<data::model::Data>();
+    let declarations = semantic::typescript::declarations(&registry);
+
+    // Only touch the file when it changes, so that cargo and the frontend tooling don't keep
+    // rebuilding because of it
+    if fs::read_to_string(path).ok().as_ref() != Some(&declarations) {
+        fs::write(path, declarations)?;
+    }
+    Ok(())
+}
+
 fn create_bundle(build_dir: &path::Path, out_dir: &path::Path) -> Result<(), failure::Error> {
     use std::io::Write;
 
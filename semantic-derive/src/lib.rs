@@ -5,39 +5,72 @@ extern crate proc_macro2;
 #[macro_use]
 extern crate quote;
 extern crate semantic;
+#[macro_use]
 extern crate syn;
 
+use std::iter;
+use std::ops;
+
 struct Attributes {
     role: Option<semantic::Role>,
+    /// The node types to generate `Visitor`, `VisitorMut` and `Fold` traits for.
+    visitors: Option<Vec<syn::Ident>>,
+    /// The schema version of a document.
+    version: Option<u32>,
+    /// The `type` tag of the JSON of a document, instead of `"root"`.
+    tag: Option<String>,
 }
 
 struct FieldAttributes {
-    ident: syn::Ident,
+    name: String,
+    member: syn::Member,
     ty: syn::Type,
     is_children: bool,
+    rename: Option<String>,
+    skip: bool,
+    default: Option<String>,
+    doc: Option<String>,
+    range: Option<(ops::Bound<f64>, ops::Bound<f64>)>,
+    non_empty: bool,
+}
+
+enum VariantShape {
+    Unit,
+    Newtype(syn::Type),
+    Record(RecordAttributes),
+}
+
+/// The fields of a record, along with the members of the fields that are skipped.
+struct RecordAttributes {
+    fields: Vec<FieldAttributes>,
+    skipped: Vec<syn::Member>,
 }
 
 struct VariantAttributes {
     ident: syn::Ident,
-    ty: syn::Type,
+    shape: VariantShape,
+    doc: Option<String>,
 }
 
 #[proc_macro_derive(Semantic, attributes(semantic))]
 pub fn semantic(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
-    let ast = syn::parse(input).unwrap();
-    let gen = impl_semantic(ast);
+    let ast = parse_macro_input!(input as syn::DeriveInput);
+    let gen = impl_semantic(ast).unwrap_or_else(|e| e.to_compile_error());
     gen.into()
 }
 
-fn impl_semantic(ast: syn::DeriveInput) -> proc_macro2::TokenStream {
+fn impl_semantic(ast: syn::DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
     let name = &ast.ident;
     let mut attributes = Attributes::new();
-    attributes.set_from_attrs(ast.attrs.as_slice());
+    attributes.set_from_attrs(ast.attrs.as_slice())?;
 
     let InferredStructure {
         structure,
         visit_classes,
-    } = infer_structure(&ast).expect("can't infer semantic structure; is your type too complex?");
+        reflect,
+        from_node,
+        walk,
+    } = infer_structure(&ast)?;
 
     let visit_classes = visit_classes
         .map(|v| {
@@ -49,253 +82,1153 @@ fn impl_semantic(ast: syn::DeriveInput) -> proc_macro2::TokenStream {
         })
         .unwrap_or(quote!());
 
-    let role = syn::Ident::new(
-        &format!(
-            "{:?}",
-            attributes
-                .role
-                .expect("missing role attribute, like #[semantic(role = \"...\")]")
-        ),
-        ast.ident.span(),
-    );
+    let role = attributes.role.ok_or_else(|| {
+        syn::Error::new(
+            ast.ident.span(),
+            "missing role attribute, like #[semantic(role = \"...\")]",
+        )
+    })?;
+    if attributes.version.is_some() && role != semantic::Role::Document {
+        return Err(syn::Error::new(
+            ast.ident.span(),
+            "only documents can have a schema version",
+        ));
+    }
+    if attributes.tag.is_some() && role != semantic::Role::Document {
+        return Err(syn::Error::new(
+            ast.ident.span(),
+            "only documents can have a type tag",
+        ));
+    }
+    let role = syn::Ident::new(&format!("{:?}", role), ast.ident.span());
+    let version = option_tokens(attributes.version);
+    let tag = option_tokens(attributes.tag.as_ref());
+    let doc = option_tokens(get_doc(ast.attrs.as_slice())?);
 
-    quote! {
-        impl ::semantic::Semantic for #name {
+    let generics = add_bounds(ast.generics.clone(), parse_quote!(::semantic::Semantic));
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let walk_generics = add_bounds(ast.generics.clone(), parse_quote!(::semantic::Walk));
+    let (walk_impl_generics, walk_ty_generics, walk_where_clause) = walk_generics.split_for_impl();
+    let WalkTokens {
+        walk,
+        walk_mut,
+        fold_children,
+    } = walk;
+
+    let visitors = attributes
+        .visitors
+        .as_ref()
+        .map(|types| visitors_tokens(name, types))
+        .unwrap_or(quote!());
+
+    Ok(quote! {
+        impl #impl_generics ::semantic::Semantic for #name #ty_generics #where_clause {
             const CLASS: ::semantic::Class<'static> = ::semantic::Class {
-                id: <Self as ::type_info::TypeInfo>::TYPE.id,
+                id: ::std::any::TypeId::of::<Self>(),
                 role: ::semantic::Role::#role,
                 structure: #structure,
+                doc: #doc,
+                version: #version,
+                tag: #tag,
             };
 
             #visit_classes
+
+            fn from_node(node: ::semantic::Node) -> ::semantic::Result<Self> {
+                #from_node
+            }
         }
 
-        impl ::semantic::DynamicSemantic for #name {
-            fn class(&self) -> ::semantic::Class<'static> {
-                <Self as ::semantic::Semantic>::CLASS
+        impl #impl_generics ::semantic::DynamicSemantic for #name #ty_generics #where_clause {
+            fn class(&self) -> &'static ::semantic::Class<'static> {
+                &<Self as ::semantic::Semantic>::CLASS
+            }
+
+            fn reflect(&self) -> ::semantic::Value {
+                #reflect
+            }
+        }
+
+        impl #walk_impl_generics ::semantic::Walk for #name #walk_ty_generics #walk_where_clause {
+            #[allow(unused_variables)]
+            fn walk<V>(&self, visitor: &mut V) where V: ::semantic::Visit + ?Sized {
+                #walk
+            }
+
+            #[allow(unused_variables)]
+            fn walk_mut<V>(&mut self, visitor: &mut V) where V: ::semantic::VisitMut + ?Sized {
+                #walk_mut
+            }
+
+            #[allow(unused_variables)]
+            fn fold_children<F>(self, folder: &mut F) -> Self where F: ::semantic::Folder + ?Sized {
+                #fold_children
             }
         }
+
+        #visitors
+    })
+}
+
+/// Requires every type parameter to implement `bound` itself, since the class of a generic type is
+/// built from the classes of its parameters (and walking it means walking values of its
+/// parameters).
+fn add_bounds(mut generics: syn::Generics, bound: syn::TypeParamBound) -> syn::Generics {
+    for param in &mut generics.params {
+        if let syn::GenericParam::Type(ref mut type_param) = *param {
+            type_param.bounds.push(bound.clone());
+        }
     }
+    generics
 }
 
 struct InferredStructure {
     structure: proc_macro2::TokenStream,
     visit_classes: Option<proc_macro2::TokenStream>,
+    reflect: proc_macro2::TokenStream,
+    from_node: proc_macro2::TokenStream,
+    walk: WalkTokens,
 }
 
-fn infer_structure(ast: &syn::DeriveInput) -> Option<InferredStructure> {
-    let name = ast.ident.to_string();
+/// The bodies of the methods of `Walk`.
+struct WalkTokens {
+    walk: proc_macro2::TokenStream,
+    walk_mut: proc_macro2::TokenStream,
+    fold_children: proc_macro2::TokenStream,
+}
+
+fn infer_structure(ast: &syn::DeriveInput) -> syn::Result<InferredStructure> {
+    let ident = &ast.ident;
+    let name = ident.to_string();
     match ast.data {
         syn::Data::Struct(syn::DataStruct {
             fields: syn::Fields::Unit,
             ..
-        }) => Some(InferredStructure {
+        }) => Ok(InferredStructure {
             structure: quote!(::semantic::Structure::Unit {
                 name: concat!(module_path!(), "::", #name),
             }),
             visit_classes: None,
+            reflect: quote!(::semantic::Value::Unit),
+            walk: walk_tokens(vec![walk_arm_tokens(quote!(#ident), &VariantShape::Unit)]),
+            from_node: quote! {
+                match node.content {
+                    ::semantic::Content::Unit => Ok(#ident),
+                    _ => Err(::semantic::Error::new(concat!("expected a unit of type ", #name))),
+                }
+            },
         }),
         syn::Data::Struct(syn::DataStruct { ref fields, .. }) => {
-            let field_attributes = struct_field_attributes(fields);
-            let field_names = field_attributes.iter().map(|f| f.ident.to_string());
-            let field_types1 = field_attributes.iter().map(|f| &f.ty);
-            let field_types2 = field_attributes.iter().map(|f| &f.ty);
-            let field_types3 = field_attributes.iter().map(|f| &f.ty);
-            let field_is_childrens = field_attributes.iter().map(|f| f.is_children);
-
-            Some(InferredStructure {
+            let record = build_record_attributes(fields)?;
+            let fields = fields_tokens(&record.fields);
+            let visit_fields = visit_fields_tokens(&record.fields);
+            let members = record.fields.iter().map(|f| &f.member);
+            let construct = construct_record_tokens(quote!(#ident), &record);
+            let walk = walk_tokens(vec![record_walk_arm_tokens(quote!(#ident), &record)]);
+
+            Ok(InferredStructure {
                 structure: quote! {
                     ::semantic::Structure::Record {
                         name: concat!(module_path!(), "::", #name),
-                        fields: &[#(
-                            ::semantic::Field {
-                                name: #field_names,
-                                ty: ::std::any::TypeId::of::<#field_types1>(),
-                                is_children: #field_is_childrens,
-                            },
-                        )*]
+                        fields: #fields,
+                    }
+                },
+                visit_classes: Some(visit_fields),
+                reflect: quote! {
+                    ::semantic::Value::Record {
+                        fields: vec![#(::semantic::dynamic(&self.#members)),*],
                     }
                 },
-                visit_classes: Some(quote! {
-                    #(
-                        if visitor(&<#field_types2 as ::semantic::Semantic>::CLASS) {
-                            <#field_types3 as ::semantic::Semantic>::visit_classes(visitor);
+                from_node: quote! {
+                    match node.content {
+                        ::semantic::Content::Record { fields } => {
+                            #[allow(unused_mut)]
+                            let mut fields = fields.into_iter();
+                            Ok(#construct)
                         }
-                    )*
-                }),
+                        _ => Err(::semantic::Error::new(concat!("expected a record of type ", #name))),
+                    }
+                },
+                walk,
             })
         }
         syn::Data::Enum(syn::DataEnum { ref variants, .. }) => {
-            if variants.iter().all(is_union_variant) {
-                let variant_attributes = enum_union_variant_attributes(variants.iter());
-                let variant_names = variant_attributes.iter().map(|f| f.ident.to_string());
-                let variant_types1 = variant_attributes.iter().map(|f| &f.ty);
-                let variant_types2 = variant_attributes.iter().map(|f| &f.ty);
-                let variant_types3 = variant_attributes.iter().map(|f| &f.ty);
-
-                Some(InferredStructure {
+            let variant_attributes = build_variant_attributes(variants.iter())?;
+            let variants = variants_tokens(&variant_attributes);
+            let walk = walk_tokens(
+                variant_attributes
+                    .iter()
+                    .map(|v| {
+                        let variant_ident = &v.ident;
+                        walk_arm_tokens(quote!(#ident::#variant_ident), &v.shape)
+                    }).collect(),
+            );
+
+            if variant_attributes.iter().all(|v| match v.shape {
+                VariantShape::Unit => true,
+                _ => false,
+            }) {
+                let idents = iter::repeat(ident);
+                let variant_idents = variant_attributes.iter().map(|v| &v.ident);
+                let variant_indices = 0..variant_attributes.len();
+                let from_idents = iter::repeat(ident);
+                let from_variant_idents = variant_attributes.iter().map(|v| &v.ident);
+                let from_variant_indices = 0..variant_attributes.len();
+
+                Ok(InferredStructure {
                     structure: quote! {
-                        ::semantic::Structure::Union {
-                            variants: &[#(
-                                ::semantic::Variant {
-                                    name: #variant_names,
-                                    ty: ::std::any::TypeId::of::<#variant_types1>(),
+                        ::semantic::Structure::Enumeration {
+                            name: concat!(module_path!(), "::", #name),
+                            variants: #variants,
+                        }
+                    },
+                    visit_classes: None,
+                    reflect: quote! {
+                        match *self {
+                            #(
+                                #idents::#variant_idents => ::semantic::Value::Enumeration {
+                                    variant: #variant_indices,
                                 },
-                            )*],
+                            )*
                         }
                     },
-                    visit_classes: Some(quote! {
-                        #(
-                            if visitor(&#variant_types2::CLASS) {
-                                #variant_types3::visit_classes(visitor);
-                            }
-                        )*
-                    }),
+                    from_node: quote! {
+                        match node.content {
+                            ::semantic::Content::Enumeration { variant } => match variant {
+                                #(#from_variant_indices => Ok(#from_idents::#from_variant_idents),)*
+                                _ => Err(::semantic::Error::new(format!(
+                                    "there is no variant with index {}",
+                                    variant
+                                ))),
+                            },
+                            _ => Err(::semantic::Error::new(concat!(
+                                "expected an enumeration of type ",
+                                #name
+                            ))),
+                        }
+                    },
+                    walk,
                 })
-            } else if variants.iter().all(is_enumeration_variant) {
-                Some(InferredStructure {
+            } else {
+                let visit_variants = variant_attributes.iter().map(|v| match v.shape {
+                    VariantShape::Unit => quote!(),
+                    VariantShape::Newtype(ref ty) => visit_type_tokens(ty),
+                    VariantShape::Record(ref record) => visit_fields_tokens(&record.fields),
+                });
+                let reflect_variants = variant_attributes
+                    .iter()
+                    .enumerate()
+                    .map(|(i, v)| reflect_variant_tokens(ident, i, v));
+                let from_variants = variant_attributes
+                    .iter()
+                    .enumerate()
+                    .map(|(i, v)| from_variant_tokens(ident, i, v));
+
+                Ok(InferredStructure {
                     structure: quote! {
-                        ::semantic::Structure::Enumeration {
-                            variants: &[],
+                        ::semantic::Structure::Union {
+                            name: concat!(module_path!(), "::", #name),
+                            variants: #variants,
                         }
                     },
-                    visit_classes: None,
+                    visit_classes: Some(quote! {
+                        #(#visit_variants)*
+                    }),
+                    reflect: quote! {
+                        match *self {
+                            #(#reflect_variants)*
+                        }
+                    },
+                    from_node: quote! {
+                        match node.content {
+                            ::semantic::Content::Union { variant, fields } => {
+                                #[allow(unused_mut)]
+                                let mut fields = fields.into_iter();
+                                match variant {
+                                    #(#from_variants)*
+                                    _ => Err(::semantic::Error::new(format!(
+                                        "there is no variant with index {}",
+                                        variant
+                                    ))),
+                                }
+                            }
+                            _ => Err(::semantic::Error::new(concat!(
+                                "expected a union of type ",
+                                #name
+                            ))),
+                        }
+                    },
+                    walk,
                 })
-            } else {
-                None
             }
         }
-        _ => None,
+        syn::Data::Union(syn::DataUnion { ref union_token, .. }) => Err(syn::Error::new(
+            union_token.span,
+            "semantic structure can't be derived for unions; use an enum instead",
+        )),
     }
 }
 
-fn struct_field_attributes(fields: &syn::Fields) -> Vec<FieldAttributes> {
-    build_field_attributes(fields.iter())
+fn reflect_variant_tokens(
+    ident: &syn::Ident,
+    index: usize,
+    variant: &VariantAttributes,
+) -> proc_macro2::TokenStream {
+    let variant_ident = &variant.ident;
+    match variant.shape {
+        VariantShape::Unit => quote! {
+            #ident::#variant_ident => ::semantic::Value::Union {
+                variant: #index,
+                fields: vec![],
+            },
+        },
+        VariantShape::Newtype(_) => quote! {
+            #ident::#variant_ident(ref value) => ::semantic::Value::Union {
+                variant: #index,
+                fields: vec![::semantic::dynamic(value)],
+            },
+        },
+        VariantShape::Record(ref record) => {
+            let members = record.fields.iter().map(|f| &f.member);
+            let bindings = (0..record.fields.len())
+                .map(|i| syn::Ident::new(&format!("field{}", i), proc_macro2::Span::call_site()))
+                .collect::<Vec<_>>();
+            let bindings1 = bindings.iter();
+            let bindings2 = bindings.iter();
+            quote! {
+                #ident::#variant_ident { #(#members: ref #bindings1,)* .. } => {
+                    ::semantic::Value::Union {
+                        variant: #index,
+                        fields: vec![#(::semantic::dynamic(#bindings2)),*],
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn from_variant_tokens(
+    ident: &syn::Ident,
+    index: usize,
+    variant: &VariantAttributes,
+) -> proc_macro2::TokenStream {
+    let variant_ident = &variant.ident;
+    match variant.shape {
+        VariantShape::Unit => quote! {
+            #index => Ok(#ident::#variant_ident),
+        },
+        VariantShape::Newtype(_) => quote! {
+            #index => Ok(#ident::#variant_ident(::semantic::from_next_node(&mut fields, 0)?)),
+        },
+        VariantShape::Record(ref record) => {
+            let construct = construct_record_tokens(quote!(#ident::#variant_ident), record);
+            quote! {
+                #index => Ok(#construct),
+            }
+        }
+    }
+}
+
+/// Combines the match arms for every variant (or the struct itself) into the bodies of the methods
+/// of `Walk`.
+fn walk_tokens(arms: Vec<WalkTokens>) -> WalkTokens {
+    let walk = arms.iter().map(|a| &a.walk);
+    let walk_mut = arms.iter().map(|a| &a.walk_mut);
+    let fold_children = arms.iter().map(|a| &a.fold_children);
+
+    WalkTokens {
+        walk: quote!(match *self { #(#walk)* }),
+        walk_mut: quote!(match *self { #(#walk_mut)* }),
+        fold_children: quote!(match self { #(#fold_children)* }),
+    }
+}
+
+fn walk_arm_tokens(path: proc_macro2::TokenStream, shape: &VariantShape) -> WalkTokens {
+    match *shape {
+        VariantShape::Unit => WalkTokens {
+            walk: quote!(#path => {}),
+            walk_mut: quote!(#path => {}),
+            fold_children: quote!(#path => #path,),
+        },
+        VariantShape::Newtype(_) => WalkTokens {
+            walk: quote!(#path(ref value) => visitor.visit(value),),
+            walk_mut: quote!(#path(ref mut value) => visitor.visit_mut(value),),
+            fold_children: quote!(#path(value) => #path(folder.fold(value)),),
+        },
+        VariantShape::Record(ref record) => record_walk_arm_tokens(path, record),
+    }
+}
+
+/// Walks the fields of a record, leaving skipped fields alone.
+fn record_walk_arm_tokens(path: proc_macro2::TokenStream, record: &RecordAttributes) -> WalkTokens {
+    let bindings = (0..record.fields.len())
+        .map(|i| syn::Ident::new(&format!("field{}", i), proc_macro2::Span::call_site()))
+        .collect::<Vec<_>>();
+    let skipped_bindings = (0..record.skipped.len())
+        .map(|i| syn::Ident::new(&format!("skipped{}", i), proc_macro2::Span::call_site()))
+        .collect::<Vec<_>>();
+
+    let members = record.fields.iter().map(|f| &f.member).collect::<Vec<_>>();
+    let (members1, members2, members3, members4) = (
+        members.iter(),
+        members.iter(),
+        members.iter(),
+        members.iter(),
+    );
+    let (bindings1, bindings2, bindings3, bindings4, bindings5, bindings6) = (
+        bindings.iter(),
+        bindings.iter(),
+        bindings.iter(),
+        bindings.iter(),
+        bindings.iter(),
+        bindings.iter(),
+    );
+    let (skipped1, skipped2) = (record.skipped.iter(), record.skipped.iter());
+    let (skipped_bindings1, skipped_bindings2) = (skipped_bindings.iter(), skipped_bindings.iter());
+
+    WalkTokens {
+        walk: quote! {
+            #path { #(#members1: ref #bindings1,)* .. } => {
+                #(visitor.visit(#bindings2);)*
+            }
+        },
+        walk_mut: quote! {
+            #path { #(#members2: ref mut #bindings3,)* .. } => {
+                #(visitor.visit_mut(#bindings4);)*
+            }
+        },
+        fold_children: quote! {
+            #path {
+                #(#members3: #bindings5,)*
+                #(#skipped1: #skipped_bindings1,)*
+            } => #path {
+                #(#members4: folder.fold(#bindings6),)*
+                #(#skipped2: #skipped_bindings2,)*
+            },
+        },
+    }
+}
+
+/// Generates the `Visitor`, `VisitorMut` and `Fold` traits for a format with the specified node
+/// types, along with the adapters that dispatch `Walk`ed children to their methods.
+///
+/// The traits are put in a module named after the format (like `markdown_visitors` for
+/// `Markdown`), so that several formats can be defined in the same module.
+fn visitors_tokens(name: &syn::Ident, types: &[syn::Ident]) -> proc_macro2::TokenStream {
+    let module = syn::Ident::new(
+        &format!("{}_visitors", snake_case(&name.to_string())),
+        name.span(),
+    );
+    let doc = format!(
+        "The `Visitor`, `VisitorMut` and `Fold` traits for `{}` trees.",
+        name
+    );
+    let visitor = visitor_tokens(types);
+    let visitor_mut = visitor_mut_tokens(types);
+    let fold = fold_tokens(types);
+
+    quote! {
+        #[doc = #doc]
+        pub mod #module {
+            #visitor
+            #visitor_mut
+            #fold
+        }
+    }
+}
+
+/// The method names for every type, like `visit_inline_code` for `"visit_{}"`.
+fn method_idents(types: &[syn::Ident], format: &str) -> Vec<syn::Ident> {
+    types
+        .iter()
+        .map(|t| {
+            let name = format.replace("{}", &snake_case(&t.to_string()));
+            syn::Ident::new(&name, t.span())
+        }).collect()
+}
+
+fn visitor_tokens(types: &[syn::Ident]) -> proc_macro2::TokenStream {
+    let methods = method_idents(types, "visit_{}");
+    let (methods1, methods2) = (methods.iter(), methods.iter());
+    let (types1, types2) = (types.iter(), types.iter());
+    let docs = types.iter().map(|t| format!("Visits a `{}` node.", t));
+
+    quote! {
+        /// Visits the nodes of a tree by shared reference.
+        ///
+        /// Every method visits the children of its node by default, so implementations only need
+        /// to override the methods for the nodes that they are interested in.  Overridden methods
+        /// can call `walk` to keep visiting the children.
+        pub trait Visitor {
+            /// Visits a value of any type, using the method for its type if there is one.
+            fn visit<A>(&mut self, node: &A) where A: ::semantic::Walk {
+                ::semantic::Visit::visit(&mut VisitorAdapter(self), node)
+            }
+
+            /// Visits the children of a value.
+            fn walk<A>(&mut self, node: &A) where A: ::semantic::Walk {
+                ::semantic::Walk::walk(node, &mut VisitorAdapter(self))
+            }
+
+            #(
+                #[doc = #docs]
+                fn #methods1(&mut self, node: &super::#types1) {
+                    self.walk(node)
+                }
+            )*
+        }
+
+        struct VisitorAdapter<'a, V: 'a + ?Sized>(&'a mut V);
+
+        impl<'a, V> ::semantic::Visit for VisitorAdapter<'a, V> where V: Visitor + ?Sized {
+            fn visit<A>(&mut self, node: &A) where A: ::semantic::Walk {
+                #(
+                    if let Some(node) = (node as &::std::any::Any).downcast_ref::<super::#types2>() {
+                        return self.0.#methods2(node);
+                    }
+                )*
+                ::semantic::Walk::walk(node, self)
+            }
+        }
+    }
+}
+
+fn visitor_mut_tokens(types: &[syn::Ident]) -> proc_macro2::TokenStream {
+    let methods = method_idents(types, "visit_{}_mut");
+    let (methods1, methods2) = (methods.iter(), methods.iter());
+    let (types1, types2) = (types.iter(), types.iter());
+    let docs = types.iter().map(|t| format!("Visits a `{}` node.", t));
+
+    quote! {
+        /// Visits the nodes of a tree by mutable reference, so that they can be changed in place.
+        ///
+        /// Every method visits the children of its node by default, so implementations only need
+        /// to override the methods for the nodes that they are interested in.  Overridden methods
+        /// can call `walk_mut` to keep visiting the children.
+        pub trait VisitorMut {
+            /// Visits a value of any type, using the method for its type if there is one.
+            fn visit_mut<A>(&mut self, node: &mut A) where A: ::semantic::Walk {
+                ::semantic::VisitMut::visit_mut(&mut VisitorMutAdapter(self), node)
+            }
+
+            /// Visits the children of a value.
+            fn walk_mut<A>(&mut self, node: &mut A) where A: ::semantic::Walk {
+                ::semantic::Walk::walk_mut(node, &mut VisitorMutAdapter(self))
+            }
+
+            #(
+                #[doc = #docs]
+                fn #methods1(&mut self, node: &mut super::#types1) {
+                    self.walk_mut(node)
+                }
+            )*
+        }
+
+        struct VisitorMutAdapter<'a, V: 'a + ?Sized>(&'a mut V);
+
+        impl<'a, V> ::semantic::VisitMut for VisitorMutAdapter<'a, V> where V: VisitorMut + ?Sized {
+            fn visit_mut<A>(&mut self, node: &mut A) where A: ::semantic::Walk {
+                #(
+                    if let Some(node) =
+                        (&mut *node as &mut ::std::any::Any).downcast_mut::<super::#types2>()
+                    {
+                        return self.0.#methods2(node);
+                    }
+                )*
+                ::semantic::Walk::walk_mut(node, self)
+            }
+        }
+    }
 }
 
-fn build_field_attributes<'a, I>(fields: I) -> Vec<FieldAttributes>
+fn fold_tokens(types: &[syn::Ident]) -> proc_macro2::TokenStream {
+    let methods = method_idents(types, "fold_{}");
+    let (methods1, methods2) = (methods.iter(), methods.iter());
+    let (types1, types2, types3) = (types.iter(), types.iter(), types.iter());
+    let docs = types.iter().map(|t| format!("Folds a `{}` node.", t));
+
+    quote! {
+        /// Rebuilds a tree from owned nodes.
+        ///
+        /// Every method rebuilds its node from its folded children by default, so implementations
+        /// only need to override the methods for the nodes that they want to replace.  Overridden
+        /// methods can call `fold_children` to keep folding the children.
+        pub trait Fold {
+            /// Folds a value of any type, using the method for its type if there is one.
+            fn fold<A>(&mut self, node: A) -> A where A: ::semantic::Walk {
+                ::semantic::Folder::fold(&mut FoldAdapter(self), node)
+            }
+
+            /// Rebuilds a value from its folded children.
+            fn fold_children<A>(&mut self, node: A) -> A where A: ::semantic::Walk {
+                ::semantic::Walk::fold_children(node, &mut FoldAdapter(self))
+            }
+
+            #(
+                #[doc = #docs]
+                fn #methods1(&mut self, node: super::#types1) -> super::#types2 {
+                    self.fold_children(node)
+                }
+            )*
+        }
+
+        struct FoldAdapter<'a, F: 'a + ?Sized>(&'a mut F);
+
+        impl<'a, F> ::semantic::Folder for FoldAdapter<'a, F> where F: Fold + ?Sized {
+            fn fold<A>(&mut self, node: A) -> A where A: ::semantic::Walk {
+                let id = ::std::any::TypeId::of::<A>();
+                #(
+                    if id == ::std::any::TypeId::of::<super::#types3>() {
+                        return ::semantic::cast(self.0.#methods2(::semantic::cast(node)));
+                    }
+                )*
+                ::semantic::Walk::fold_children(node, self)
+            }
+        }
+    }
+}
+
+/// Converts a type name (like `InlineCode`) into snake_case (like `inline_code`).
+fn snake_case(name: &str) -> String {
+    let mut result = String::with_capacity(name.len());
+
+    for (i, c) in name.chars().enumerate() {
+        if c.is_uppercase() {
+            if i > 0 {
+                result.push('_');
+            }
+            result.extend(c.to_lowercase());
+        } else {
+            result.push(c);
+        }
+    }
+
+    result
+}
+
+/// Builds a record from the nodes in the `fields` iterator, filling in skipped fields with their
+/// default values.
+fn construct_record_tokens(
+    path: proc_macro2::TokenStream,
+    record: &RecordAttributes,
+) -> proc_macro2::TokenStream {
+    let members = record.fields.iter().map(|f| &f.member);
+    let indices = 0..record.fields.len();
+    let skipped = record.skipped.iter();
+
+    quote! {
+        #path {
+            #(#members: ::semantic::from_next_node(&mut fields, #indices)?,)*
+            #(#skipped: ::std::default::Default::default(),)*
+        }
+    }
+}
+
+fn fields_tokens(fields: &[FieldAttributes]) -> proc_macro2::TokenStream {
+    let field_names = fields.iter().map(|f| f.rename.as_ref().unwrap_or(&f.name));
+    let field_types = fields.iter().map(|f| &f.ty);
+    let field_is_childrens = fields.iter().map(|f| f.is_children);
+    let field_defaults = fields.iter().map(|f| option_tokens(f.default.as_ref()));
+    let field_docs = fields.iter().map(|f| option_tokens(f.doc.as_ref()));
+    let field_constraints = fields.iter().map(constraints_tokens);
+
+    quote! {
+        &[#(
+            ::semantic::Field {
+                name: #field_names,
+                ty: ::std::any::TypeId::of::<#field_types>(),
+                is_children: #field_is_childrens,
+                default: #field_defaults,
+                doc: #field_docs,
+                constraints: #field_constraints,
+            },
+        )*]
+    }
+}
+
+fn constraints_tokens(field: &FieldAttributes) -> proc_macro2::TokenStream {
+    let mut constraints = Vec::new();
+
+    if let Some((start, end)) = field.range {
+        let start = bound_tokens(start);
+        let end = bound_tokens(end);
+        constraints.push(quote! {
+            ::semantic::Constraint::Range {
+                start: #start,
+                end: #end,
+            }
+        });
+    }
+
+    if field.non_empty {
+        constraints.push(quote!(::semantic::Constraint::NonEmpty));
+    }
+
+    quote!(&[#(#constraints,)*])
+}
+
+fn bound_tokens(bound: ops::Bound<f64>) -> proc_macro2::TokenStream {
+    match bound {
+        ops::Bound::Included(v) => quote!(::std::ops::Bound::Included(#v)),
+        ops::Bound::Excluded(v) => quote!(::std::ops::Bound::Excluded(#v)),
+        ops::Bound::Unbounded => quote!(::std::ops::Bound::Unbounded),
+    }
+}
+
+fn option_tokens<A>(option: Option<A>) -> proc_macro2::TokenStream
 where
-    I: Iterator<Item = &'a syn::Field>,
+    A: quote::ToTokens,
 {
-    fields
-        .filter(|f| f.ident.is_some())
-        .map(|f| {
-            let mut result = FieldAttributes::new(f.ident.as_ref().unwrap().clone(), f.ty.clone());
-            result.set_from_attrs(f.attrs.as_slice());
-            result
-        }).collect()
+    match option {
+        Some(v) => quote!(Some(#v)),
+        None => quote!(None),
+    }
+}
+
+fn variants_tokens(variants: &[VariantAttributes]) -> proc_macro2::TokenStream {
+    let variant_names = variants.iter().map(|v| v.ident.to_string());
+    let variant_docs = variants.iter().map(|v| option_tokens(v.doc.as_ref()));
+    let variant_kinds = variants.iter().map(|v| match v.shape {
+        VariantShape::Unit => quote!(::semantic::VariantKind::Unit),
+        VariantShape::Newtype(ref ty) => quote! {
+            ::semantic::VariantKind::Newtype {
+                ty: ::std::any::TypeId::of::<#ty>(),
+            }
+        },
+        VariantShape::Record(ref record) => {
+            let fields = fields_tokens(&record.fields);
+            quote! {
+                ::semantic::VariantKind::Record {
+                    fields: #fields,
+                }
+            }
+        }
+    });
+
+    quote! {
+        &[#(
+            ::semantic::Variant {
+                name: #variant_names,
+                kind: #variant_kinds,
+                doc: #variant_docs,
+            },
+        )*]
+    }
+}
+
+fn visit_fields_tokens(fields: &[FieldAttributes]) -> proc_macro2::TokenStream {
+    let visit_types = fields.iter().map(|f| visit_type_tokens(&f.ty));
+    quote! {
+        #(#visit_types)*
+    }
+}
+
+fn visit_type_tokens(ty: &syn::Type) -> proc_macro2::TokenStream {
+    quote! {
+        if visitor(&<#ty as ::semantic::Semantic>::CLASS) {
+            <#ty as ::semantic::Semantic>::visit_classes(visitor);
+        }
+    }
 }
 
-fn enum_union_variant_attributes<'a, I>(variants: I) -> Vec<VariantAttributes>
+fn build_record_attributes(fields: &syn::Fields) -> syn::Result<RecordAttributes> {
+    let (skipped, fields): (Vec<_>, Vec<_>) = fields
+        .iter()
+        .enumerate()
+        .map(|(i, f)| {
+            // Tuple fields are named by their position, just like when accessing them in Rust
+            let name = f
+                .ident
+                .as_ref()
+                .map(|ident| ident.to_string())
+                .unwrap_or_else(|| i.to_string());
+            let member = f
+                .ident
+                .clone()
+                .map(syn::Member::Named)
+                .unwrap_or_else(|| syn::Member::Unnamed(syn::Index::from(i)));
+            let mut result = FieldAttributes::new(name, member, f.ty.clone());
+            result.set_from_attrs(f.attrs.as_slice())?;
+            if result.doc.is_none() {
+                result.doc = get_doc(f.attrs.as_slice())?;
+            }
+            Ok(result)
+        }).collect::<syn::Result<Vec<_>>>()?
+        .into_iter()
+        .partition(|f| f.skip);
+
+    let skipped = skipped.into_iter().map(|f| f.member).collect();
+    Ok(RecordAttributes { fields, skipped })
+}
+
+fn build_variant_attributes<'a, I>(variants: I) -> syn::Result<Vec<VariantAttributes>>
 where
     I: Iterator<Item = &'a syn::Variant>,
 {
     variants
-        .map(|v| VariantAttributes {
-            ident: v.ident.clone(),
-            ty: match v.fields {
-                syn::Fields::Unnamed(syn::FieldsUnnamed { ref unnamed, .. }) => {
-                    // We have already established that this is an union enum, so this should be
-                    // safe
-                    unnamed.iter().next().unwrap().ty.clone()
-                }
-                _ => unreachable!(),
-            },
+        .map(|v| {
+            // Variants don't support any attributes of their own yet, but we still want to reject
+            // misplaced ones
+            if let Some(meta_item) = get_semantic_meta_items(v.attrs.as_slice())?.first() {
+                return Err(unknown_attribute(meta_item));
+            }
+
+            Ok(VariantAttributes {
+                ident: v.ident.clone(),
+                doc: get_doc(v.attrs.as_slice())?,
+                shape: match v.fields {
+                    syn::Fields::Unit => VariantShape::Unit,
+                    syn::Fields::Unnamed(syn::FieldsUnnamed { ref unnamed, .. })
+                        if unnamed.len() == 1 =>
+                    {
+                        let field = unnamed.iter().next().unwrap();
+                        if let Some(meta_item) =
+                            get_semantic_meta_items(field.attrs.as_slice())?.first()
+                        {
+                            return Err(unknown_attribute(meta_item));
+                        }
+                        VariantShape::Newtype(field.ty.clone())
+                    }
+                    ref fields => VariantShape::Record(build_record_attributes(fields)?),
+                },
+            })
         }).collect()
 }
 
 impl Attributes {
     fn new() -> Attributes {
-        Attributes { role: None }
+        Attributes {
+            role: None,
+            visitors: None,
+            version: None,
+            tag: None,
+        }
     }
 
-    fn set_from_attrs(&mut self, attrs: &[syn::Attribute]) {
+    fn set_from_attrs(&mut self, attrs: &[syn::Attribute]) -> syn::Result<()> {
         use syn::NestedMeta::*;
 
-        for meta_items in attrs.iter().filter_map(get_semantic_meta_items) {
-            for meta_item in meta_items {
-                match meta_item {
-                    Meta(syn::Meta::NameValue(ref m)) if m.ident == "role" => {
-                        if let Ok(s) =
-                            get_lit_str(&m.ident.to_string(), &m.ident.to_string(), &m.lit)
-                        {
-                            let value = s.value();
-                            match value.parse() {
-                                Ok(role) => self.role = Some(role),
-                                Err(()) => panic!("invalid role value {:?}", value),
-                            }
+        for meta_item in get_semantic_meta_items(attrs)? {
+            match meta_item {
+                Meta(syn::Meta::NameValue(ref m)) if m.ident == "role" => {
+                    if self.role.is_some() {
+                        return Err(duplicate_attribute(&m.ident));
+                    }
+                    let s = get_lit_str(&m.ident, &m.lit)?;
+                    match s.value().parse() {
+                        Ok(role) => self.role = Some(role),
+                        Err(()) => {
+                            return Err(syn::Error::new(
+                                s.span(),
+                                format!(
+                                    "invalid role {:?}; expected one of \"root\", \"document\", \
+                                     \"block\", \"inline\" or \"attribute\"",
+                                    s.value()
+                                ),
+                            ))
                         }
                     }
-                    _ => {}
                 }
+                Meta(ref m) if m.name() == "role" => {
+                    return Err(syn::Error::new_spanned(
+                        m,
+                        "expected semantic role attribute to be a string: `role = \"...\"`",
+                    ))
+                }
+                Meta(syn::Meta::List(ref m)) if m.ident == "visitors" => {
+                    let types = m
+                        .nested
+                        .iter()
+                        .map(|nested| match *nested {
+                            Meta(syn::Meta::Word(ref ident)) => Ok(ident.clone()),
+                            ref nested => Err(syn::Error::new_spanned(
+                                nested,
+                                "expected a type name in semantic visitors attribute",
+                            )),
+                        }).collect::<syn::Result<Vec<_>>>()?;
+                    set_value(&mut self.visitors, &m.ident, types)?
+                }
+                Meta(ref m) if m.name() == "visitors" => {
+                    return Err(syn::Error::new_spanned(
+                        m,
+                        "expected semantic visitors attribute to be a list of types: \
+                         `visitors(...)`",
+                    ))
+                }
+                Meta(syn::Meta::NameValue(ref m)) if m.ident == "version" => {
+                    let version = match m.lit {
+                        syn::Lit::Int(ref i)
+                            if i.value() >= 1 && i.value() <= u64::from(u32::max_value()) =>
+                        {
+                            i.value() as u32
+                        }
+                        ref lit => {
+                            return Err(syn::Error::new_spanned(
+                                lit,
+                                "expected semantic version attribute to be a positive integer: \
+                                 `version = 1`",
+                            ))
+                        }
+                    };
+                    set_value(&mut self.version, &m.ident, version)?
+                }
+                Meta(ref m) if m.name() == "version" => {
+                    return Err(syn::Error::new_spanned(
+                        m,
+                        "expected semantic version attribute to be a positive integer: \
+                         `version = 1`",
+                    ))
+                }
+                Meta(syn::Meta::NameValue(ref m)) if m.ident == "tag" => {
+                    let tag = get_lit_str(&m.ident, &m.lit)?.value();
+                    set_value(&mut self.tag, &m.ident, tag)?
+                }
+                Meta(ref m) if m.name() == "tag" => {
+                    return Err(syn::Error::new_spanned(
+                        m,
+                        "expected semantic tag attribute to be a string: `tag = \"...\"`",
+                    ))
+                }
+                ref meta_item => return Err(unknown_attribute(meta_item)),
             }
         }
+        Ok(())
     }
 }
 
 impl FieldAttributes {
-    fn new(ident: syn::Ident, ty: syn::Type) -> FieldAttributes {
+    fn new(name: String, member: syn::Member, ty: syn::Type) -> FieldAttributes {
         let is_children = false;
+        let rename = None;
+        let skip = false;
+        let default = None;
+        let doc = None;
+        let range = None;
+        let non_empty = false;
         FieldAttributes {
-            ident,
+            name,
+            member,
             ty,
             is_children,
+            rename,
+            skip,
+            default,
+            doc,
+            range,
+            non_empty,
         }
     }
 
-    fn set_from_attrs(&mut self, attrs: &[syn::Attribute]) {
+    fn set_from_attrs(&mut self, attrs: &[syn::Attribute]) -> syn::Result<()> {
         use syn::NestedMeta::*;
 
-        for meta_items in attrs.iter().filter_map(get_semantic_meta_items) {
-            for meta_item in meta_items {
-                match meta_item {
-                    Meta(syn::Meta::Word(ref ident)) if ident == "children" => {
-                        self.is_children = true
+        let meta_items = get_semantic_meta_items(attrs)?;
+
+        for meta_item in &meta_items {
+            match *meta_item {
+                Meta(syn::Meta::Word(ref ident)) if ident == "children" => {
+                    set_flag(&mut self.is_children, ident)?
+                }
+                Meta(syn::Meta::Word(ref ident)) if ident == "skip" => {
+                    set_flag(&mut self.skip, ident)?
+                }
+                Meta(syn::Meta::Word(ref ident)) if ident == "non_empty" => {
+                    set_flag(&mut self.non_empty, ident)?
+                }
+                Meta(ref m) if is_flag_name(m.name()) => {
+                    return Err(syn::Error::new_spanned(
+                        m,
+                        format!(
+                            "expected semantic {} attribute to be a plain word: `{}`",
+                            m.name(),
+                            m.name()
+                        ),
+                    ))
+                }
+                Meta(syn::Meta::NameValue(ref m)) if m.ident == "rename" => {
+                    let s = get_lit_str(&m.ident, &m.lit)?;
+                    if s.value().is_empty() {
+                        return Err(syn::Error::new(s.span(), "the field name must not be empty"));
                     }
-                    _ => {}
+                    set_value(&mut self.rename, &m.ident, s.value())?
+                }
+                Meta(syn::Meta::NameValue(ref m)) if m.ident == "default" => {
+                    let s = get_lit_str(&m.ident, &m.lit)?;
+                    set_value(&mut self.default, &m.ident, s.value())?
                 }
+                Meta(syn::Meta::NameValue(ref m)) if m.ident == "doc" => {
+                    let s = get_lit_str(&m.ident, &m.lit)?;
+                    set_value(&mut self.doc, &m.ident, s.value())?
+                }
+                Meta(syn::Meta::NameValue(ref m)) if m.ident == "range" => {
+                    let s = get_lit_str(&m.ident, &m.lit)?;
+                    let range = parse_range(&s.value())
+                        .map_err(|message| syn::Error::new(s.span(), message))?;
+                    set_value(&mut self.range, &m.ident, range)?
+                }
+                Meta(ref m) if is_value_name(m.name()) => {
+                    return Err(syn::Error::new_spanned(
+                        m,
+                        format!(
+                            "expected semantic {} attribute to be a string: `{} = \"...\"`",
+                            m.name(),
+                            m.name()
+                        ),
+                    ))
+                }
+                ref meta_item => return Err(unknown_attribute(meta_item)),
             }
         }
+
+        if self.skip && meta_items.len() > 1 {
+            return Err(syn::Error::new_spanned(
+                &meta_items[0],
+                "semantic skip attribute can't be combined with other semantic attributes",
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+fn is_flag_name(ident: syn::Ident) -> bool {
+    ident == "children" || ident == "skip" || ident == "non_empty"
+}
+
+fn is_value_name(ident: syn::Ident) -> bool {
+    ident == "rename" || ident == "default" || ident == "doc" || ident == "range"
+}
+
+fn set_flag(flag: &mut bool, ident: &syn::Ident) -> syn::Result<()> {
+    if *flag {
+        Err(duplicate_attribute(ident))
+    } else {
+        *flag = true;
+        Ok(())
+    }
+}
+
+fn set_value<A>(option: &mut Option<A>, ident: &syn::Ident, value: A) -> syn::Result<()> {
+    if option.is_some() {
+        Err(duplicate_attribute(ident))
+    } else {
+        *option = Some(value);
+        Ok(())
     }
 }
 
-fn is_enumeration_variant(variant: &syn::Variant) -> bool {
-    match variant.fields {
-        syn::Fields::Unit => true,
-        _ => false,
+/// Parses a Rust-like range expression, like `1..=6`, `..10` or `0.5..`.
+fn parse_range(range: &str) -> Result<(ops::Bound<f64>, ops::Bound<f64>), String> {
+    let invalid = || {
+        format!(
+            "invalid range {:?}; expected a range like \"1..=6\", \"1..7\", \"1..\" or \"..=6\"",
+            range
+        )
+    };
+
+    let split = range.find("..").ok_or_else(invalid)?;
+    let (start, end) = (range[..split].trim(), range[split + 2..].trim());
+
+    let start = if start.is_empty() {
+        ops::Bound::Unbounded
+    } else {
+        ops::Bound::Included(start.parse().map_err(|_| invalid())?)
+    };
+
+    let end = if end.starts_with('=') {
+        ops::Bound::Included(end[1..].trim().parse().map_err(|_| invalid())?)
+    } else if end.is_empty() {
+        ops::Bound::Unbounded
+    } else {
+        ops::Bound::Excluded(end.parse().map_err(|_| invalid())?)
+    };
+
+    match (start, end) {
+        (ops::Bound::Included(s), ops::Bound::Included(e)) if s > e => {
+            Err(format!("the range {:?} is empty", range))
+        }
+        (ops::Bound::Included(s), ops::Bound::Excluded(e)) if s >= e => {
+            Err(format!("the range {:?} is empty", range))
+        }
+        bounds => Ok(bounds),
     }
 }
 
-fn is_union_variant(variant: &syn::Variant) -> bool {
-    match variant.fields {
-        syn::Fields::Unnamed(syn::FieldsUnnamed { ref unnamed, .. }) => unnamed.len() == 1,
-        _ => false,
+/// Collects the items of all `#[semantic(...)]` attributes in `attrs`.
+fn get_semantic_meta_items(attrs: &[syn::Attribute]) -> syn::Result<Vec<syn::NestedMeta>> {
+    let mut result = Vec::new();
+
+    for attr in attrs {
+        if attr.path.segments.len() == 1 && attr.path.segments[0].ident == "semantic" {
+            match attr.parse_meta()? {
+                syn::Meta::List(ref meta) => result.extend(meta.nested.iter().cloned()),
+                ref meta => {
+                    return Err(syn::Error::new_spanned(
+                        meta,
+                        "expected semantic attribute to be a list: `#[semantic(...)]`",
+                    ))
+                }
+            }
+        }
     }
+
+    Ok(result)
 }
 
-fn get_semantic_meta_items(attr: &syn::Attribute) -> Option<Vec<syn::NestedMeta>> {
-    if attr.path.segments.len() == 1 && attr.path.segments[0].ident == "semantic" {
-        match attr.interpret_meta() {
-            Some(syn::Meta::List(ref meta)) => Some(meta.nested.iter().cloned().collect()),
-            _ => None,
+/// Collects the doc comments in `attrs` into a single string, if there are any.
+fn get_doc(attrs: &[syn::Attribute]) -> syn::Result<Option<String>> {
+    let mut lines = Vec::new();
+
+    for attr in attrs {
+        if attr.path.segments.len() == 1 && attr.path.segments[0].ident == "doc" {
+            if let syn::Meta::NameValue(ref m) = attr.parse_meta()? {
+                if let syn::Lit::Str(ref s) = m.lit {
+                    let line = s.value();
+                    // Doc comments are written like `/// Text`, so strip the space after `///`
+                    lines.push(if line.starts_with(' ') {
+                        line[1..].to_owned()
+                    } else {
+                        line
+                    });
+                }
+            }
         }
-    } else {
-        None
     }
+
+    let doc = lines.join("\n").trim().to_owned();
+    Ok(if doc.is_empty() { None } else { Some(doc) })
 }
 
-fn get_lit_str<'a>(
-    attr_name: &str,
-    meta_item_name: &str,
-    lit: &'a syn::Lit,
-) -> Result<&'a syn::LitStr, ()> {
+fn get_lit_str<'a>(ident: &syn::Ident, lit: &'a syn::Lit) -> syn::Result<&'a syn::LitStr> {
     if let syn::Lit::Str(ref lit) = *lit {
         Ok(lit)
     } else {
-        format!(
-            "expected semantic {} attribute to be a string: `{} = \"...\"`",
-            attr_name, meta_item_name
-        );
-        Err(())
+        Err(syn::Error::new_spanned(
+            lit,
+            format!(
+                "expected semantic {} attribute to be a string: `{} = \"...\"`",
+                ident, ident
+            ),
+        ))
     }
 }
+
+fn unknown_attribute(meta_item: &syn::NestedMeta) -> syn::Error {
+    match *meta_item {
+        syn::NestedMeta::Meta(ref m) => syn::Error::new_spanned(
+            m.name(),
+            format!("unknown semantic attribute `{}`", m.name()),
+        ),
+        syn::NestedMeta::Literal(ref lit) => {
+            syn::Error::new_spanned(lit, "unexpected literal in semantic attribute")
+        }
+    }
+}
+
+fn duplicate_attribute(ident: &syn::Ident) -> syn::Error {
+    syn::Error::new(
+        ident.span(),
+        format!("duplicate semantic attribute `{}`", ident),
+    )
+}
@@ -0,0 +1,14 @@
+#![feature(const_type_id)]
+
+extern crate semantic;
+#[macro_use]
+extern crate semantic_derive;
+
+#[derive(Debug, Semantic)]
+#[semantic(role = "block")]
+struct Paragraph {
+    #[semantic(children = "true")] //~ ERROR expected semantic children attribute to be a plain word
+    children: Vec<u32>,
+}
+
+fn main() {}
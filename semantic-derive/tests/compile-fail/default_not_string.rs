@@ -0,0 +1,14 @@
+#![feature(const_type_id)]
+
+extern crate semantic;
+#[macro_use]
+extern crate semantic_derive;
+
+#[derive(Debug, Semantic)]
+#[semantic(role = "block")]
+struct Heading {
+    #[semantic(default = 1)] //~ ERROR expected semantic default attribute to be a string
+    depth: u32,
+}
+
+fn main() {}
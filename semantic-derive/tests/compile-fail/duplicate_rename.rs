@@ -0,0 +1,14 @@
+#![feature(const_type_id)]
+
+extern crate semantic;
+#[macro_use]
+extern crate semantic_derive;
+
+#[derive(Debug, Semantic)]
+#[semantic(role = "block")]
+struct Heading {
+    #[semantic(rename = "level", rename = "depth")] //~ ERROR duplicate semantic attribute `rename`
+    depth: u32,
+}
+
+fn main() {}
@@ -0,0 +1,11 @@
+#![feature(const_type_id)]
+
+extern crate semantic;
+#[macro_use]
+extern crate semantic_derive;
+
+#[derive(Debug, Semantic)]
+#[semantic(role = "block", role = "inline")] //~ ERROR duplicate semantic attribute `role`
+struct ThematicBreak;
+
+fn main() {}
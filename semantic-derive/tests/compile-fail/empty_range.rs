@@ -0,0 +1,14 @@
+#![feature(const_type_id)]
+
+extern crate semantic;
+#[macro_use]
+extern crate semantic_derive;
+
+#[derive(Debug, Semantic)]
+#[semantic(role = "block")]
+struct Heading {
+    #[semantic(range = "6..=1")] //~ ERROR the range "6..=1" is empty
+    depth: u32,
+}
+
+fn main() {}
@@ -0,0 +1,14 @@
+#![feature(const_type_id)]
+
+extern crate semantic;
+#[macro_use]
+extern crate semantic_derive;
+
+#[derive(Debug, Semantic)]
+#[semantic(role = "block")]
+struct Heading {
+    #[semantic(range = "1-6")] //~ ERROR invalid range "1-6"
+    depth: u32,
+}
+
+fn main() {}
@@ -0,0 +1,11 @@
+#![feature(const_type_id)]
+
+extern crate semantic;
+#[macro_use]
+extern crate semantic_derive;
+
+#[derive(Debug, Semantic)]
+#[semantic(role = "blok")] //~ ERROR invalid role "blok"
+struct ThematicBreak;
+
+fn main() {}
@@ -0,0 +1,10 @@
+#![feature(const_type_id)]
+
+extern crate semantic;
+#[macro_use]
+extern crate semantic_derive;
+
+#[derive(Debug, Semantic)]
+struct ThematicBreak; //~ ERROR missing role attribute
+
+fn main() {}
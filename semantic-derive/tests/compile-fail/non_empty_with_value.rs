@@ -0,0 +1,14 @@
+#![feature(const_type_id)]
+
+extern crate semantic;
+#[macro_use]
+extern crate semantic_derive;
+
+#[derive(Debug, Semantic)]
+#[semantic(role = "inline")]
+struct Link {
+    #[semantic(non_empty = "true")] //~ ERROR expected semantic non_empty attribute to be a plain word
+    url: String,
+}
+
+fn main() {}
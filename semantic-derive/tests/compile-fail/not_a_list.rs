@@ -0,0 +1,11 @@
+#![feature(const_type_id)]
+
+extern crate semantic;
+#[macro_use]
+extern crate semantic_derive;
+
+#[derive(Debug, Semantic)]
+#[semantic = "block"] //~ ERROR expected semantic attribute to be a list
+struct ThematicBreak;
+
+fn main() {}
@@ -0,0 +1,14 @@
+#![feature(const_type_id)]
+
+extern crate semantic;
+#[macro_use]
+extern crate semantic_derive;
+
+#[derive(Debug, Semantic)]
+#[semantic(role = "block")]
+struct Heading {
+    #[semantic(skip, rename = "level")] //~ ERROR semantic skip attribute can't be combined
+    depth: u32,
+}
+
+fn main() {}
@@ -0,0 +1,13 @@
+#![feature(const_type_id)]
+
+extern crate semantic;
+#[macro_use]
+extern crate semantic_derive;
+
+#[derive(Debug, Semantic)]
+#[semantic(role = "block", tag = "paragraph")]
+struct Paragraph { //~ ERROR only documents can have a type tag
+    text: String,
+}
+
+fn main() {}
@@ -0,0 +1,14 @@
+#![feature(const_type_id)]
+
+extern crate semantic;
+#[macro_use]
+extern crate semantic_derive;
+
+#[derive(Semantic)]
+#[semantic(role = "block")]
+union Number { //~ ERROR semantic structure can't be derived for unions
+    int: u32,
+    float: f32,
+}
+
+fn main() {}
@@ -0,0 +1,11 @@
+#![feature(const_type_id)]
+
+extern crate semantic;
+#[macro_use]
+extern crate semantic_derive;
+
+#[derive(Debug, Semantic)]
+#[semantic(role = "block", rol = "inline")] //~ ERROR unknown semantic attribute `rol`
+struct ThematicBreak;
+
+fn main() {}
@@ -0,0 +1,14 @@
+#![feature(const_type_id)]
+
+extern crate semantic;
+#[macro_use]
+extern crate semantic_derive;
+
+#[derive(Debug, Semantic)]
+#[semantic(role = "block")]
+struct Paragraph {
+    #[semantic(chidlren)] //~ ERROR unknown semantic attribute `chidlren`
+    children: Vec<u32>,
+}
+
+fn main() {}
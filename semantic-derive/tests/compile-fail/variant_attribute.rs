@@ -0,0 +1,15 @@
+#![feature(const_type_id)]
+
+extern crate semantic;
+#[macro_use]
+extern crate semantic_derive;
+
+#[derive(Debug, Semantic)]
+#[semantic(role = "attribute")]
+enum AlignType {
+    #[semantic(children)] //~ ERROR unknown semantic attribute `children`
+    Left,
+    Right,
+}
+
+fn main() {}
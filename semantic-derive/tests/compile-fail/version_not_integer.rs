@@ -0,0 +1,13 @@
+#![feature(const_type_id)]
+
+extern crate semantic;
+#[macro_use]
+extern crate semantic_derive;
+
+#[derive(Debug, Semantic)]
+#[semantic(role = "document", version = "2")] //~ ERROR expected semantic version attribute to be a positive integer
+struct Markdown {
+    text: String,
+}
+
+fn main() {}
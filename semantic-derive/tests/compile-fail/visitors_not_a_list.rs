@@ -0,0 +1,19 @@
+#![feature(const_type_id)]
+
+extern crate semantic;
+#[macro_use]
+extern crate semantic_derive;
+
+#[derive(Debug, Semantic)]
+#[semantic(role = "document", visitors = "Heading")] //~ ERROR expected semantic visitors attribute to be a list of types
+struct Markdown {
+    children: Vec<Heading>,
+}
+
+#[derive(Debug, Semantic)]
+#[semantic(role = "block")]
+struct Heading {
+    depth: u32,
+}
+
+fn main() {}
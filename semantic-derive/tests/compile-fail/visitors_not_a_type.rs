@@ -0,0 +1,19 @@
+#![feature(const_type_id)]
+
+extern crate semantic;
+#[macro_use]
+extern crate semantic_derive;
+
+#[derive(Debug, Semantic)]
+#[semantic(role = "document", visitors(Heading, "Link"))] //~ ERROR expected a type name in semantic visitors attribute
+struct Markdown {
+    children: Vec<Heading>,
+}
+
+#[derive(Debug, Semantic)]
+#[semantic(role = "block")]
+struct Heading {
+    depth: u32,
+}
+
+fn main() {}
@@ -0,0 +1,26 @@
+//! Checks that `#[derive(Semantic)]` generates the expected classes and code, and that it rejects
+//! invalid input with helpful, spanned errors.
+extern crate compiletest_rs as compiletest;
+
+use std::path;
+
+fn run_mode(mode: &'static str) {
+    let mut config = compiletest::Config::default();
+
+    config.mode = mode.parse().expect("invalid compiletest mode");
+    config.src_base = path::PathBuf::from(format!("tests/{}", mode));
+    config.link_deps();
+    config.clean_rmeta();
+
+    compiletest::run_tests(&config);
+}
+
+#[test]
+fn compile_fail() {
+    run_mode("compile-fail");
+}
+
+#[test]
+fn run_pass() {
+    run_mode("run-pass");
+}
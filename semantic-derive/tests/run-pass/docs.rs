@@ -0,0 +1,91 @@
+#![feature(const_type_id)]
+
+extern crate semantic;
+#[macro_use]
+extern crate semantic_derive;
+
+use semantic::Semantic;
+use semantic::Structure;
+use semantic::VariantKind;
+
+/// A section of a page.
+///
+/// Sections can be nested.
+#[derive(Debug, Semantic)]
+#[semantic(role = "block")]
+struct Section {
+    /// The title, shown above the section.
+    title: String,
+    #[semantic(doc = "Overrides the doc comment.")]
+    /// Not used.
+    depth: u32,
+    children: Vec<Section>,
+}
+
+/// How to align text.
+#[derive(Debug, Semantic)]
+#[semantic(role = "attribute")]
+enum Align {
+    /// Towards the start of the line.
+    Start,
+    End,
+}
+
+#[derive(Debug, Semantic)]
+#[semantic(role = "block")]
+enum Block {
+    Section(Section),
+    /// A horizontal line.
+    Rule {
+        /// The thickness in pixels.
+        width: u32,
+    },
+}
+
+fn types_keep_their_doc_comments() {
+    assert_eq!(
+        Some("A section of a page.\n\nSections can be nested."),
+        Section::CLASS.doc
+    );
+    assert_eq!(None, Block::CLASS.doc);
+}
+
+fn fields_keep_their_doc_comments() {
+    let fields = match Section::CLASS.structure {
+        Structure::Record { fields, .. } => fields,
+        ref other => panic!("expected a record, but got {:?}", other),
+    };
+    assert_eq!(Some("The title, shown above the section."), fields[0].doc);
+    assert_eq!(Some("Overrides the doc comment."), fields[1].doc);
+    assert_eq!(None, fields[2].doc);
+}
+
+fn variants_keep_their_doc_comments() {
+    match Align::CLASS.structure {
+        Structure::Enumeration { variants, .. } => {
+            assert_eq!(Some("Towards the start of the line."), variants[0].doc);
+            assert_eq!(None, variants[1].doc);
+        }
+        ref other => panic!("expected an enumeration, but got {:?}", other),
+    }
+
+    match Block::CLASS.structure {
+        Structure::Union { variants, .. } => {
+            assert_eq!(None, variants[0].doc);
+            assert_eq!(Some("A horizontal line."), variants[1].doc);
+            match variants[1].kind {
+                VariantKind::Record { fields } => {
+                    assert_eq!(Some("The thickness in pixels."), fields[0].doc)
+                }
+                ref other => panic!("expected a record variant, but got {:?}", other),
+            }
+        }
+        ref other => panic!("expected a union, but got {:?}", other),
+    }
+}
+
+fn main() {
+    types_keep_their_doc_comments();
+    fields_keep_their_doc_comments();
+    variants_keep_their_doc_comments();
+}
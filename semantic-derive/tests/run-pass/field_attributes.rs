@@ -0,0 +1,88 @@
+#![feature(const_type_id)]
+
+extern crate semantic;
+#[macro_use]
+extern crate semantic_derive;
+
+use semantic::json;
+use semantic::Node;
+use semantic::Semantic;
+use semantic::Structure;
+
+#[derive(Debug, Semantic)]
+#[semantic(role = "document")]
+struct Page {
+    #[semantic(rename = "title")]
+    heading: String,
+    #[semantic(skip)]
+    cache: Vec<u32>,
+    #[semantic(default = "3")]
+    depth: u32,
+    #[semantic(default = "untitled")]
+    slug: String,
+}
+
+fn fields() -> &'static [semantic::Field<'static>] {
+    match Page::CLASS.structure {
+        Structure::Record { fields, .. } => fields,
+        ref other => panic!("expected a record, but got {:?}", other),
+    }
+}
+
+fn renamed_fields_use_their_new_name() {
+    assert_eq!("title", fields()[0].name);
+
+    let page = Page {
+        heading: "Hello".to_owned(),
+        cache: vec![],
+        depth: 1,
+        slug: "hello".to_owned(),
+    };
+    let json = json::to_json(&page);
+    assert_eq!("Hello", json["title"]);
+    assert!(json.get("heading").is_none());
+}
+
+fn skipped_fields_are_left_out_and_filled_with_their_default() {
+    assert_eq!(
+        vec!["title", "depth", "slug"],
+        fields().iter().map(|f| f.name).collect::<Vec<_>>()
+    );
+
+    let page = Page {
+        heading: "Hello".to_owned(),
+        cache: vec![1, 2],
+        depth: 1,
+        slug: "hello".to_owned(),
+    };
+    let page = Page::from_node(Node::from_value(&page)).unwrap();
+    assert_eq!(Vec::<u32>::new(), page.cache);
+    assert_eq!("Hello", page.heading);
+}
+
+fn defaults_are_recorded_and_used_for_missing_fields() {
+    assert_eq!(None, fields()[0].default);
+    assert_eq!(Some("3"), fields()[1].default);
+    assert_eq!(Some("untitled"), fields()[2].default);
+
+    let mut object = json::to_json(&Page {
+        heading: "Hello".to_owned(),
+        cache: vec![],
+        depth: 1,
+        slug: "hello".to_owned(),
+    });
+    {
+        let object = object.as_object_mut().unwrap();
+        object.remove("depth");
+        object.remove("slug");
+    }
+    let page = json::from_json::<Page>(&object).unwrap();
+    assert_eq!(3, page.depth);
+    assert_eq!("untitled", page.slug);
+}
+
+fn main() {
+    renamed_fields_use_their_new_name();
+    skipped_fields_are_left_out_and_filled_with_their_default();
+    defaults_are_recorded_and_used_for_missing_fields();
+}
@@ -0,0 +1,128 @@
+#![feature(const_type_id)]
+
+extern crate semantic;
+#[macro_use]
+extern crate semantic_derive;
+
+use std::any;
+
+use semantic::Node;
+use semantic::Semantic;
+use semantic::Structure;
+use semantic::VariantKind;
+
+#[derive(Debug, PartialEq, Semantic)]
+#[semantic(role = "attribute")]
+enum Align {
+    Left,
+    Right,
+}
+
+#[derive(Debug, PartialEq, Semantic)]
+#[semantic(role = "inline")]
+struct Span(String, u32);
+
+#[derive(Debug, PartialEq, Semantic)]
+#[semantic(role = "block")]
+enum Block {
+    Rule,
+    Span(Span),
+    Cell { align: Align, text: String },
+}
+
+#[derive(Debug, PartialEq, Semantic)]
+#[semantic(role = "block")]
+struct List<A> {
+    items: Vec<A>,
+}
+
+fn variant_names<'a>(structure: &Structure<'a>) -> Vec<&'a str> {
+    match *structure {
+        Structure::Enumeration { variants, .. } | Structure::Union { variants, .. } => {
+            variants.iter().map(|v| v.name).collect()
+        }
+        ref other => panic!("expected variants, but got {:?}", other),
+    }
+}
+
+fn field_names<'a>(structure: &Structure<'a>) -> Vec<&'a str> {
+    match *structure {
+        Structure::Record { fields, .. } => fields.iter().map(|f| f.name).collect(),
+        ref other => panic!("expected a record, but got {:?}", other),
+    }
+}
+
+fn round_trip<A>(value: &A) -> A
+where
+    A: Semantic,
+{
+    A::from_node(Node::from_value(value)).unwrap()
+}
+
+fn enumerations_name_their_variants() {
+    assert_eq!(vec!["Left", "Right"], variant_names(&Align::CLASS.structure));
+    assert_eq!(Align::Right, round_trip(&Align::Right));
+}
+
+fn tuple_struct_fields_are_named_by_position() {
+    assert_eq!(vec!["0", "1"], field_names(&Span::CLASS.structure));
+    let span = Span("a".to_owned(), 2);
+    assert_eq!(span, round_trip(&span));
+}
+
+fn unions_describe_each_kind_of_variant() {
+    assert_eq!(vec!["Rule", "Span", "Cell"], variant_names(&Block::CLASS.structure));
+
+    let variants = match Block::CLASS.structure {
+        Structure::Union { variants, .. } => variants,
+        ref other => panic!("expected a union, but got {:?}", other),
+    };
+    match variants[0].kind {
+        VariantKind::Unit => {}
+        ref other => panic!("expected a unit variant, but got {:?}", other),
+    }
+    match variants[1].kind {
+        VariantKind::Newtype { ty } => assert_eq!(any::TypeId::of::<Span>(), ty),
+        ref other => panic!("expected a newtype variant, but got {:?}", other),
+    }
+    match variants[2].kind {
+        VariantKind::Record { fields } => {
+            assert_eq!(
+                vec!["align", "text"],
+                fields.iter().map(|f| f.name).collect::<Vec<_>>()
+            );
+            assert_eq!(any::TypeId::of::<Align>(), fields[0].ty);
+        }
+        ref other => panic!("expected a record variant, but got {:?}", other),
+    }
+
+    let cell = Block::Cell {
+        align: Align::Left,
+        text: "a".to_owned(),
+    };
+    assert_eq!(cell, round_trip(&cell));
+    assert_eq!(Block::Rule, round_trip(&Block::Rule));
+}
+
+fn generic_types_have_a_class_per_instantiation() {
+    assert_eq!(any::TypeId::of::<List<Span>>(), List::<Span>::CLASS.id);
+    assert_ne!(List::<Span>::CLASS.id, List::<Align>::CLASS.id);
+
+    let item_class = |structure: &Structure| match *structure {
+        Structure::Record { fields, .. } => fields[0].ty,
+        ref other => panic!("expected a record, but got {:?}", other),
+    };
+    assert_eq!(any::TypeId::of::<Vec<Span>>(), item_class(&List::<Span>::CLASS.structure));
+
+    let list = List {
+        items: vec![Align::Left, Align::Right],
+    };
+    assert_eq!(list, round_trip(&list));
+}
+
+fn main() {
+    enumerations_name_their_variants();
+    tuple_struct_fields_are_named_by_position();
+    unions_describe_each_kind_of_variant();
+    generic_types_have_a_class_per_instantiation();
+}
@@ -0,0 +1,174 @@
+#![feature(const_type_id)]
+
+extern crate semantic;
+#[macro_use]
+extern crate semantic_derive;
+
+// Two formats in the same module, to check that their traits don't collide
+#[derive(Debug, PartialEq, Semantic)]
+#[semantic(role = "document", visitors(Page, Block, Paragraph, Text))]
+struct Page {
+    #[semantic(children)]
+    blocks: Vec<Block>,
+}
+
+#[derive(Debug, PartialEq, Semantic)]
+#[semantic(role = "block")]
+enum Block {
+    Paragraph(Paragraph),
+    Rule,
+}
+
+#[derive(Debug, PartialEq, Semantic)]
+#[semantic(role = "block")]
+struct Paragraph {
+    #[semantic(children)]
+    texts: Vec<Text>,
+}
+
+#[derive(Debug, PartialEq, Semantic)]
+#[semantic(role = "inline")]
+struct Text {
+    value: String,
+}
+
+#[derive(Debug, PartialEq, Semantic)]
+#[semantic(role = "document", visitors(Sheet, Cell))]
+struct Sheet {
+    #[semantic(children)]
+    cells: Vec<Cell>,
+}
+
+#[derive(Debug, PartialEq, Semantic)]
+#[semantic(role = "block")]
+struct Cell {
+    value: f64,
+}
+
+fn text(value: &str) -> Text {
+    Text {
+        value: value.to_owned(),
+    }
+}
+
+fn page() -> Page {
+    Page {
+        blocks: vec![
+            Block::Paragraph(Paragraph {
+                texts: vec![text("a"), text("b")],
+            }),
+            Block::Rule,
+            Block::Paragraph(Paragraph {
+                texts: vec![text("c")],
+            }),
+        ],
+    }
+}
+
+/// Collects the text of a page, without looking at the other nodes.
+struct Texts(Vec<String>);
+
+impl page_visitors::Visitor for Texts {
+    fn visit_text(&mut self, node: &Text) {
+        self.0.push(node.value.clone());
+    }
+}
+
+/// Counts the paragraphs of a page, but not the texts in them.
+struct Paragraphs(usize);
+
+impl page_visitors::Visitor for Paragraphs {
+    fn visit_paragraph(&mut self, _node: &Paragraph) {
+        self.0 += 1;
+    }
+}
+
+struct Shout;
+
+impl page_visitors::VisitorMut for Shout {
+    fn visit_text_mut(&mut self, node: &mut Text) {
+        node.value = node.value.to_uppercase();
+    }
+}
+
+/// Replaces empty paragraphs with rules.
+struct RemoveEmpty;
+
+impl page_visitors::Fold for RemoveEmpty {
+    fn fold_block(&mut self, node: Block) -> Block {
+        match self.fold_children(node) {
+            Block::Paragraph(ref p) if p.texts.is_empty() => Block::Rule,
+            block => block,
+        }
+    }
+
+    fn fold_paragraph(&mut self, node: Paragraph) -> Paragraph {
+        Paragraph {
+            texts: node.texts.into_iter().filter(|t| t.value != "b").collect(),
+        }
+    }
+}
+
+struct Total(f64);
+
+impl sheet_visitors::Visitor for Total {
+    fn visit_cell(&mut self, node: &Cell) {
+        self.0 += node.value;
+    }
+}
+
+fn visitors_reach_nested_nodes() {
+    use page_visitors::Visitor;
+
+    let mut texts = Texts(Vec::new());
+    texts.visit(&page());
+    assert_eq!(vec!["a", "b", "c"], texts.0);
+}
+
+fn overridden_methods_stop_the_walk() {
+    use page_visitors::Visitor;
+
+    let mut paragraphs = Paragraphs(0);
+    paragraphs.visit(&page());
+    assert_eq!(2, paragraphs.0);
+}
+
+fn mutable_visitors_change_nodes_in_place() {
+    use page_visitors::VisitorMut;
+
+    let mut page = page();
+    Shout.visit_mut(&mut page);
+    let mut texts = Texts(Vec::new());
+    page_visitors::Visitor::visit(&mut texts, &page);
+    assert_eq!(vec!["A", "B", "C"], texts.0);
+}
+
+fn folds_rebuild_the_tree() {
+    use page_visitors::Fold;
+
+    let page = Page {
+        blocks: vec![Block::Paragraph(Paragraph {
+            texts: vec![text("b")],
+        })],
+    };
+    let page = RemoveEmpty.fold(page);
+    assert_eq!(vec![Block::Rule], page.blocks);
+}
+
+fn formats_in_the_same_module_have_separate_traits() {
+    use sheet_visitors::Visitor;
+
+    let mut total = Total(0.0);
+    total.visit(&Sheet {
+        cells: vec![Cell { value: 1.5 }, Cell { value: 2.0 }],
+    });
+    assert_eq!(3.5, total.0);
+}
+
+fn main() {
+    visitors_reach_nested_nodes();
+    overridden_methods_stop_the_walk();
+    mutable_visitors_change_nodes_in_place();
+    folds_rebuild_the_tree();
+    formats_in_the_same_module_have_separate_traits();
+}
@@ -0,0 +1,946 @@
+//! A conflict-free replicated data type (CRDT) for semantic trees.
+//!
+//! A `Document` is a semantic tree that several replicas (like the frames of the editor in
+//! different browser tabs) can edit at the same time without coordinating.  Every edit becomes an
+//! `Operation` that is applied locally right away and then sent to the other replicas, in any order
+//! and as many times as needed.  Once all replicas have seen the same operations, they have the
+//! same tree.
+//!
+//! Every node in the tree has a unique `NodeId`, so that operations keep referring to the right
+//! node no matter what else happens concurrently.  Places that hold a single node (fields, optional
+//! items, the root and collection elements) are last-writer-wins registers, and collections are
+//! replicated growable arrays (RGAs), where concurrent insertions at the same place are ordered by
+//! their timestamps.
+//!
+//! Operations can be converted to and from JSON, to send them between processes (like from the
+//! backend to the frames).
+use std::any;
+use std::cmp;
+use std::collections::HashMap;
+use std::collections::HashSet;
+
+use serde_json;
+
+use diff;
+use json;
+use Class;
+use Content;
+use DynamicSemantic;
+use Error;
+use Node;
+use NodePath;
+use Result;
+use Registry;
+use Semantic;
+use Structure;
+use VariantKind;
+
+/// A Lamport timestamp that uniquely identifies an operation.
+///
+/// Timestamps are ordered by counter first and replica second.  This is a total order that is
+/// consistent with causality: an operation always has a later timestamp than the operations that
+/// its replica had seen when it was made.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Timestamp {
+    pub counter: u64,
+    pub replica: u64,
+}
+
+/// Uniquely identifies a node in a `Document`.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct NodeId {
+    /// The timestamp of the operation that created the node.
+    pub created: Timestamp,
+    /// The position of the node in a pre-order traversal of the subtree that the operation created.
+    pub index: u32,
+}
+
+/// A place in a `Document` that holds a single node.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Slot {
+    /// The root of the document.
+    Root,
+    /// A field of a record or union node.
+    Field { parent: NodeId, index: usize },
+    /// The item of an optional node.
+    Item { parent: NodeId },
+    /// An element of a collection node.
+    Element { parent: NodeId, element: NodeId },
+}
+
+/// An edit of a `Document`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Operation {
+    pub timestamp: Timestamp,
+    pub kind: OperationKind,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum OperationKind {
+    /// Replaces the node in `slot` with a new subtree, or removes the item of an optional node if
+    /// `node` is `None`.
+    ///
+    /// Of several concurrent replacements of the same slot, the one with the latest timestamp wins.
+    Set { slot: Slot, node: Option<Node> },
+    /// Inserts a new subtree into the collection `parent`, right after the element `after`, or at
+    /// the start of the collection if `after` is `None`.
+    Insert {
+        parent: NodeId,
+        after: Option<NodeId>,
+        node: Node,
+    },
+    /// Deletes the element `element` from the collection `parent`.
+    Delete { parent: NodeId, element: NodeId },
+}
+
+/// A semantic tree that can be edited concurrently by several replicas.
+#[derive(Clone, Debug)]
+pub struct Document {
+    replica: u64,
+    clock: u64,
+    root: Register,
+    entries: HashMap<NodeId, Entry>,
+    applied: HashSet<Timestamp>,
+    history: Vec<Operation>,
+    pending: Vec<Operation>,
+}
+
+/// A last-writer-wins register holding a node.
+#[derive(Clone, Debug)]
+struct Register {
+    value: Option<NodeId>,
+    written: Timestamp,
+}
+
+/// An element of a collection.
+///
+/// The element keeps its identity (and its place in the collection) even when the node in it is
+/// replaced.
+#[derive(Clone, Debug)]
+struct Element {
+    id: NodeId,
+    value: Register,
+    deleted: bool,
+}
+
+#[derive(Clone, Debug)]
+enum Entry {
+    /// A primitive, unit or enumeration node, which can only be replaced as a whole.
+    Leaf(Node),
+    /// A record node, or a union node with the specified variant.
+    Fields {
+        class: &'static Class<'static>,
+        variant: Option<usize>,
+        fields: Vec<Register>,
+    },
+    Optional {
+        class: &'static Class<'static>,
+        item: Register,
+    },
+    Collection {
+        class: &'static Class<'static>,
+        elements: Vec<Element>,
+    },
+}
+
+/// The timestamp of the initial contents of every document.
+const GENESIS: Timestamp = Timestamp {
+    counter: 0,
+    replica: 0,
+};
+
+impl Document {
+    /// Creates a replica of a document with the initial contents `value`.
+    ///
+    /// All replicas of the same document must be created from the same initial contents, and must
+    /// have unique non-zero `replica` identifiers.
+    pub fn new(replica: u64, value: &DynamicSemantic) -> Document {
+        let mut document = Document {
+            replica,
+            clock: 0,
+            root: Register {
+                value: None,
+                written: GENESIS,
+            },
+            entries: HashMap::new(),
+            applied: HashSet::new(),
+            history: Vec::new(),
+            pending: Vec::new(),
+        };
+
+        let root = document.create(GENESIS, &Node::from_value(value));
+        document.root.value = Some(root);
+        document
+    }
+
+    /// The identifier of this replica.
+    pub fn replica(&self) -> u64 {
+        self.replica
+    }
+
+    /// All of the operations that have been applied to this replica, in the order that they were
+    /// applied.
+    ///
+    /// Sending these to a new replica brings it up to date.
+    pub fn operations(&self) -> &[Operation] {
+        &self.history
+    }
+
+    /// The current contents of the document.
+    pub fn to_node(&self) -> Node {
+        self.node(self.root.value.expect("documents always have a root"))
+    }
+
+    /// Converts the current contents of the document into a concrete semantic value.
+    pub fn to_value<A>(&self) -> Result<A>
+    where
+        A: Semantic,
+    {
+        self.to_node().into_value()
+    }
+
+    /// The identifier of the node at `path` in the current contents of the document.
+    pub fn node_id(&self, path: &NodePath) -> Option<NodeId> {
+        let mut id = self.root.value?;
+        for &step in path.steps() {
+            id = *self.child_ids(id).get(step)?;
+        }
+        Some(id)
+    }
+
+    /// The slot that holds the node at `path` in the current contents of the document.
+    pub fn slot(&self, path: &NodePath) -> Option<Slot> {
+        let index = match path.last() {
+            Some(index) => index,
+            None => return Some(Slot::Root),
+        };
+        let parent = self.node_id(&path.parent()?)?;
+
+        match *self.entries.get(&parent)? {
+            Entry::Leaf(_) => None,
+            Entry::Fields { ref fields, .. } if index < fields.len() => {
+                Some(Slot::Field { parent, index })
+            }
+            Entry::Fields { .. } => None,
+            Entry::Optional { .. } if index == 0 => Some(Slot::Item { parent }),
+            Entry::Optional { .. } => None,
+            Entry::Collection { .. } => Some(Slot::Element {
+                parent,
+                element: self.element_id(parent, index)?,
+            }),
+        }
+    }
+
+    /// The identifier of the element at `index` in the collection `parent`, ignoring deleted
+    /// elements.
+    pub fn element_id(&self, parent: NodeId, index: usize) -> Option<NodeId> {
+        match *self.entries.get(&parent)? {
+            Entry::Collection { ref elements, .. } => elements
+                .iter()
+                .filter(|e| !e.deleted)
+                .nth(index)
+                .map(|e| e.id),
+            _ => None,
+        }
+    }
+
+    /// Replaces the node in `slot`, and returns the operation to send to other replicas.
+    pub fn set(&mut self, slot: Slot, node: Option<Node>) -> Result<Operation> {
+        self.apply_local(OperationKind::Set { slot, node })
+    }
+
+    /// Inserts a node into a collection, and returns the operation to send to other replicas.
+    pub fn insert(
+        &mut self,
+        parent: NodeId,
+        after: Option<NodeId>,
+        node: Node,
+    ) -> Result<Operation> {
+        self.apply_local(OperationKind::Insert {
+            parent,
+            after,
+            node,
+        })
+    }
+
+    /// Deletes an element from a collection, and returns the operation to send to other replicas.
+    pub fn delete(&mut self, parent: NodeId, element: NodeId) -> Result<Operation> {
+        self.apply_local(OperationKind::Delete { parent, element })
+    }
+
+    /// Edits the document to match `value`, and returns the operations to send to other replicas.
+    ///
+    /// Nodes that didn't change keep their identities, and so do the elements of collections that
+    /// are matched up the way that `diff` does it, so that concurrent edits inside of them aren't
+    /// lost.  Nodes whose class or variant changed are replaced as a whole.
+    pub fn update(&mut self, value: &DynamicSemantic) -> Result<Vec<Operation>> {
+        let root = self.root.value.expect("documents always have a root");
+        let mut operations = Vec::new();
+        self.update_node(
+            Slot::Root,
+            root,
+            &Node::from_value(value),
+            &mut operations,
+        )?;
+        Ok(operations)
+    }
+
+    /// Applies an operation from another replica.
+    ///
+    /// Operations that depend on operations that haven't been applied yet are buffered until
+    /// those arrive, and operations that have already been applied are ignored.
+    pub fn apply(&mut self, operation: Operation) {
+        self.pending.push(operation);
+
+        while let Some(i) = self.pending.iter().position(|o| self.is_ready(o)) {
+            let operation = self.pending.remove(i);
+            self.integrate(operation);
+        }
+    }
+
+    fn update_node(
+        &mut self,
+        slot: Slot,
+        id: NodeId,
+        new: &Node,
+        operations: &mut Vec<Operation>,
+    ) -> Result<()> {
+        let old = self.node(id);
+        if old == *new {
+            return Ok(());
+        }
+
+        let children = self.child_ids(id);
+        let same_class = old.class.id == new.class.id;
+        match (&old.content, &new.content) {
+            (&Content::Record { .. }, &Content::Record { ref fields }) if same_class => {
+                for (index, (&child, field)) in children.iter().zip(fields).enumerate() {
+                    let slot = Slot::Field { parent: id, index };
+                    self.update_node(slot, child, field, operations)?;
+                }
+            }
+            (
+                &Content::Union { variant: a, .. },
+                &Content::Union {
+                    variant: b,
+                    ref fields,
+                },
+            )
+                if same_class && a == b =>
+            {
+                for (index, (&child, field)) in children.iter().zip(fields).enumerate() {
+                    let slot = Slot::Field { parent: id, index };
+                    self.update_node(slot, child, field, operations)?;
+                }
+            }
+            (&Content::Optional { .. }, &Content::Optional { ref item }) if same_class => {
+                let slot = Slot::Item { parent: id };
+                match (children.first(), item) {
+                    (Some(&child), &Some(ref item)) => {
+                        self.update_node(slot, child, item, operations)?
+                    }
+                    (_, item) => {
+                        let node = item.as_ref().map(|item| (**item).clone());
+                        operations.push(self.apply_local(OperationKind::Set { slot, node })?);
+                    }
+                }
+            }
+            (
+                &Content::Collection {
+                    items: ref old_items,
+                },
+                &Content::Collection {
+                    items: ref new_items,
+                },
+            )
+                if same_class =>
+            {
+                let elements = (0..old_items.len())
+                    .map(|i| self.element_id(id, i).expect("every item has an element"))
+                    .collect::<Vec<_>>();
+
+                // Moved items are deleted and inserted again, since elements have a fixed place
+                let mut kept = vec![None; new_items.len()];
+                let mut last = None;
+                for (i, j) in diff::match_items(old_items, new_items) {
+                    if last.map_or(true, |last| i > last) {
+                        kept[j] = Some(i);
+                        last = Some(i);
+                    }
+                }
+
+                let kept_items = kept.iter().filter_map(|&i| i).collect::<HashSet<_>>();
+                for (i, &element) in elements.iter().enumerate() {
+                    if !kept_items.contains(&i) {
+                        let kind = OperationKind::Delete {
+                            parent: id,
+                            element,
+                        };
+                        operations.push(self.apply_local(kind)?);
+                    }
+                }
+
+                let mut after = None;
+                for (item, &i) in new_items.iter().zip(&kept) {
+                    match i {
+                        Some(i) => {
+                            let slot = Slot::Element {
+                                parent: id,
+                                element: elements[i],
+                            };
+                            self.update_node(slot, children[i], item, operations)?;
+                            after = Some(elements[i]);
+                        }
+                        None => {
+                            let operation = self.apply_local(OperationKind::Insert {
+                                parent: id,
+                                after,
+                                node: item.clone(),
+                            })?;
+                            after = Some(NodeId {
+                                created: operation.timestamp,
+                                index: 0,
+                            });
+                            operations.push(operation);
+                        }
+                    }
+                }
+            }
+            _ => {
+                let node = Some(new.clone());
+                operations.push(self.apply_local(OperationKind::Set { slot, node })?);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn apply_local(&mut self, kind: OperationKind) -> Result<Operation> {
+        self.clock += 1;
+        let operation = Operation {
+            timestamp: Timestamp {
+                counter: self.clock,
+                replica: self.replica,
+            },
+            kind,
+        };
+
+        if self.is_ready(&operation) && self.is_valid(&operation) {
+            self.integrate(operation.clone());
+            Ok(operation)
+        } else {
+            self.clock -= 1;
+            Err(Error::new(
+                "the edit refers to a node that doesn't exist or has the wrong class",
+            ))
+        }
+    }
+
+    /// Whether all of the nodes that `operation` refers to exist.
+    fn is_ready(&self, operation: &Operation) -> bool {
+        match operation.kind {
+            OperationKind::Set { slot, .. } => match slot {
+                Slot::Root => true,
+                Slot::Field { parent, .. } | Slot::Item { parent } => self.knows(parent, None),
+                Slot::Element { parent, element } => self.knows(parent, Some(element)),
+            },
+            OperationKind::Insert { parent, after, .. } => self.knows(parent, after),
+            OperationKind::Delete { parent, element } => self.knows(parent, Some(element)),
+        }
+    }
+
+    fn knows(&self, parent: NodeId, element: Option<NodeId>) -> bool {
+        match self.entries.get(&parent) {
+            None => false,
+            Some(&Entry::Collection { ref elements, .. }) => {
+                element.map_or(true, |element| elements.iter().any(|e| e.id == element))
+            }
+            Some(_) => true,
+        }
+    }
+
+    /// Whether `operation` fits the document, assuming that it is ready.
+    ///
+    /// Operations that don't fit are ignored by every replica, so they don't break convergence.
+    fn is_valid(&self, operation: &Operation) -> bool {
+        match operation.kind {
+            OperationKind::Set {
+                slot,
+                node: Some(ref node),
+            } => self.slot_type(slot) == Some(node.class.id),
+            OperationKind::Set { slot, node: None } => match slot {
+                Slot::Item { parent } => match self.entries.get(&parent) {
+                    Some(&Entry::Optional { .. }) => true,
+                    _ => false,
+                },
+                _ => false,
+            },
+            OperationKind::Insert {
+                parent, ref node, ..
+            } => self.item_type(parent) == Some(node.class.id),
+            OperationKind::Delete { parent, .. } => self.item_type(parent).is_some(),
+        }
+    }
+
+    /// The type of the nodes that `slot` can hold.
+    fn slot_type(&self, slot: Slot) -> Option<any::TypeId> {
+        match slot {
+            Slot::Root => self.root.value.map(|id| self.class(id).id),
+            Slot::Field { parent, index } => match *self.entries.get(&parent)? {
+                Entry::Fields { class, variant, .. } => field_type(class, variant, index),
+                _ => None,
+            },
+            Slot::Item { parent } => match *self.entries.get(&parent)? {
+                Entry::Optional { class, .. } => match class.structure {
+                    Structure::Optional { item } => Some(item.id),
+                    _ => None,
+                },
+                _ => None,
+            },
+            Slot::Element { parent, .. } => self.item_type(parent),
+        }
+    }
+
+    /// The type of the items of the collection `parent`.
+    fn item_type(&self, parent: NodeId) -> Option<any::TypeId> {
+        match *self.entries.get(&parent)? {
+            Entry::Collection { class, .. } => match class.structure {
+                Structure::Collection { item } => Some(item.id),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    fn integrate(&mut self, operation: Operation) {
+        let timestamp = operation.timestamp;
+        if !self.applied.insert(timestamp) {
+            return;
+        }
+        self.clock = cmp::max(self.clock, timestamp.counter);
+
+        if self.is_valid(&operation) {
+            match operation.kind {
+                OperationKind::Set { slot, ref node } => {
+                    let value = node.as_ref().map(|n| self.create(timestamp, n));
+                    if let Some(register) = self.register_mut(slot) {
+                        if register.written < timestamp {
+                            register.value = value;
+                            register.written = timestamp;
+                        }
+                    }
+                }
+                OperationKind::Insert {
+                    parent,
+                    after,
+                    ref node,
+                } => {
+                    let id = self.create(timestamp, node);
+                    if let Some(&mut Entry::Collection {
+                        ref mut elements, ..
+                    }) = self.entries.get_mut(&parent)
+                    {
+                        let mut position = match after {
+                            Some(after) => elements.iter().position(|e| e.id == after).unwrap() + 1,
+                            None => 0,
+                        };
+                        // Concurrent insertions at the same place end up in timestamp order, latest
+                        // first.  Elements that were inserted after those have even later
+                        // timestamps, so they are skipped too.
+                        while position < elements.len() && elements[position].id > id {
+                            position += 1;
+                        }
+                        elements.insert(
+                            position,
+                            Element {
+                                id,
+                                value: Register {
+                                    value: Some(id),
+                                    written: timestamp,
+                                },
+                                deleted: false,
+                            },
+                        );
+                    }
+                }
+                OperationKind::Delete { parent, element } => {
+                    if let Some(&mut Entry::Collection {
+                        ref mut elements, ..
+                    }) = self.entries.get_mut(&parent)
+                    {
+                        for e in elements.iter_mut().filter(|e| e.id == element) {
+                            e.deleted = true;
+                        }
+                    }
+                }
+            }
+        }
+
+        self.history.push(operation);
+    }
+
+    fn register_mut(&mut self, slot: Slot) -> Option<&mut Register> {
+        match slot {
+            Slot::Root => Some(&mut self.root),
+            Slot::Field { parent, index } => match self.entries.get_mut(&parent) {
+                Some(&mut Entry::Fields { ref mut fields, .. }) => fields.get_mut(index),
+                _ => None,
+            },
+            Slot::Item { parent } => match self.entries.get_mut(&parent) {
+                Some(&mut Entry::Optional { ref mut item, .. }) => Some(item),
+                _ => None,
+            },
+            Slot::Element { parent, element } => match self.entries.get_mut(&parent) {
+                Some(&mut Entry::Collection {
+                    ref mut elements, ..
+                }) => elements
+                    .iter_mut()
+                    .find(|e| e.id == element)
+                    .map(|e| &mut e.value),
+                _ => None,
+            },
+        }
+    }
+
+    /// Creates entries for a new subtree, and returns the identifier of its root.
+    fn create(&mut self, created: Timestamp, node: &Node) -> NodeId {
+        let mut index = 0;
+        self.create_entries(created, node, &mut index)
+    }
+
+    fn create_entries(&mut self, created: Timestamp, node: &Node, index: &mut u32) -> NodeId {
+        let id = NodeId {
+            created,
+            index: *index,
+        };
+        *index += 1;
+
+        let class = node.class;
+        let entry = match node.content {
+            Content::Primitive(_) | Content::Unit | Content::Enumeration { .. } => {
+                Entry::Leaf(node.clone())
+            }
+            Content::Record { ref fields } => Entry::Fields {
+                class,
+                variant: None,
+                fields: self.create_registers(created, fields, index),
+            },
+            Content::Union {
+                variant,
+                ref fields,
+            } => Entry::Fields {
+                class,
+                variant: Some(variant),
+                fields: self.create_registers(created, fields, index),
+            },
+            Content::Optional { ref item } => Entry::Optional {
+                class,
+                item: Register {
+                    value: item
+                        .as_ref()
+                        .map(|item| self.create_entries(created, item, index)),
+                    written: created,
+                },
+            },
+            Content::Collection { ref items } => Entry::Collection {
+                class,
+                elements: self
+                    .create_registers(created, items, index)
+                    .into_iter()
+                    .map(|value| Element {
+                        id: value.value.unwrap(),
+                        value,
+                        deleted: false,
+                    }).collect(),
+            },
+        };
+
+        self.entries.insert(id, entry);
+        id
+    }
+
+    fn create_registers(
+        &mut self,
+        created: Timestamp,
+        nodes: &[Node],
+        index: &mut u32,
+    ) -> Vec<Register> {
+        nodes
+            .iter()
+            .map(|node| Register {
+                value: Some(self.create_entries(created, node, index)),
+                written: created,
+            }).collect()
+    }
+
+    fn class(&self, id: NodeId) -> &'static Class<'static> {
+        match self.entries[&id] {
+            Entry::Leaf(ref node) => node.class,
+            Entry::Fields { class, .. }
+            | Entry::Optional { class, .. }
+            | Entry::Collection { class, .. } => class,
+        }
+    }
+
+    /// The identifiers of the current children of a node, in the order that `NodePath`s address
+    /// them.
+    fn child_ids(&self, id: NodeId) -> Vec<NodeId> {
+        match self.entries[&id] {
+            Entry::Leaf(_) => Vec::new(),
+            Entry::Fields { ref fields, .. } => fields.iter().filter_map(|r| r.value).collect(),
+            Entry::Optional { ref item, .. } => item.value.into_iter().collect(),
+            Entry::Collection { ref elements, .. } => elements
+                .iter()
+                .filter(|e| !e.deleted)
+                .filter_map(|e| e.value.value)
+                .collect(),
+        }
+    }
+
+    fn node(&self, id: NodeId) -> Node {
+        let content = match self.entries[&id] {
+            Entry::Leaf(ref node) => return node.clone(),
+            Entry::Fields { variant, .. } => {
+                let fields = self.child_nodes(id);
+                match variant {
+                    Some(variant) => Content::Union { variant, fields },
+                    None => Content::Record { fields },
+                }
+            }
+            Entry::Optional { ref item, .. } => Content::Optional {
+                item: item.value.map(|item| Box::new(self.node(item))),
+            },
+            Entry::Collection { .. } => Content::Collection {
+                items: self.child_nodes(id),
+            },
+        };
+
+        Node {
+            class: self.class(id),
+            content,
+        }
+    }
+
+    fn child_nodes(&self, id: NodeId) -> Vec<Node> {
+        self.child_ids(id)
+            .into_iter()
+            .map(|child| self.node(child))
+            .collect()
+    }
+}
+
+impl Operation {
+    /// Converts the operation into JSON, to send it to replicas in other processes.
+    ///
+    /// Nodes are in their usual JSON representation (see `json`), next to the name of their class
+    /// in `Registry::class_names`, so the replicas must agree on the classes of their registries
+    /// (which they do if they have the same `Registry::schema_hash`).  Timestamps and node
+    /// identifiers become arrays of numbers, which JavaScript only reads exactly if the replica
+    /// identifiers are below 2^53.
+    pub fn to_json(&self, registry: &Registry) -> Result<serde_json::Value> {
+        let mut object = serde_json::Map::new();
+        let tag = match self.kind {
+            OperationKind::Set { slot, ref node } => {
+                object.insert("slot".to_owned(), slot_to_json(slot));
+                insert_node(&mut object, registry, node.as_ref())?;
+                "set"
+            }
+            OperationKind::Insert {
+                parent,
+                after,
+                ref node,
+            } => {
+                object.insert("parent".to_owned(), id_to_json(parent));
+                object.insert(
+                    "after".to_owned(),
+                    after.map_or(serde_json::Value::Null, id_to_json),
+                );
+                insert_node(&mut object, registry, Some(node))?;
+                "insert"
+            }
+            OperationKind::Delete { parent, element } => {
+                object.insert("parent".to_owned(), id_to_json(parent));
+                object.insert("element".to_owned(), id_to_json(element));
+                "delete"
+            }
+        };
+        object.insert(json::TYPE_KEY.to_owned(), tag.into());
+        object.insert(
+            "timestamp".to_owned(),
+            numbers_to_json(&[self.timestamp.counter, self.timestamp.replica]),
+        );
+
+        Ok(serde_json::Value::Object(object))
+    }
+
+    /// Converts JSON from `to_json` back into an operation.
+    pub fn from_json(registry: &Registry, json: &serde_json::Value) -> Result<Operation> {
+        let timestamp = numbers_from_json(member(json, "timestamp")?, 2)?;
+        let timestamp = Timestamp {
+            counter: timestamp[0],
+            replica: timestamp[1],
+        };
+
+        let kind = match member(json, json::TYPE_KEY)?.as_str() {
+            Some("set") => OperationKind::Set {
+                slot: slot_from_json(member(json, "slot")?)?,
+                node: node_from_json(registry, json)?,
+            },
+            Some("insert") => OperationKind::Insert {
+                parent: id_from_json(member(json, "parent")?)?,
+                after: match *member(json, "after")? {
+                    serde_json::Value::Null => None,
+                    ref after => Some(id_from_json(after)?),
+                },
+                node: node_from_json(registry, json)?
+                    .ok_or_else(|| Error::new("insertions must have a node"))?,
+            },
+            Some("delete") => OperationKind::Delete {
+                parent: id_from_json(member(json, "parent")?)?,
+                element: id_from_json(member(json, "element")?)?,
+            },
+            _ => return Err(Error::new("unknown kind of operation")),
+        };
+
+        Ok(Operation { timestamp, kind })
+    }
+}
+
+/// The type of field `index` of a record (or a union with the specified variant).
+fn field_type(class: &Class, variant: Option<usize>, index: usize) -> Option<any::TypeId> {
+    match (class.structure, variant) {
+        (Structure::Record { fields, .. }, None) => fields.get(index).map(|f| f.ty),
+        (Structure::Union { variants, .. }, Some(variant)) => match variants.get(variant)?.kind {
+            VariantKind::Newtype { ty } if index == 0 => Some(ty),
+            VariantKind::Record { fields } => fields.get(index).map(|f| f.ty),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn member<'a>(json: &'a serde_json::Value, key: &str) -> Result<&'a serde_json::Value> {
+    json.get(key)
+        .ok_or_else(|| Error::new(format!("missing {:?}", key)))
+}
+
+fn numbers_to_json(numbers: &[u64]) -> serde_json::Value {
+    serde_json::Value::Array(numbers.iter().map(|&n| n.into()).collect())
+}
+
+fn numbers_from_json(json: &serde_json::Value, len: usize) -> Result<Vec<u64>> {
+    let numbers = json
+        .as_array()
+        .map(|items| items.iter().filter_map(|n| n.as_u64()).collect::<Vec<_>>())
+        .unwrap_or_default();
+
+    if numbers.len() == len && json.as_array().map(|items| items.len()) == Some(len) {
+        Ok(numbers)
+    } else {
+        Err(Error::new(format!("expected an array of {} numbers", len)))
+    }
+}
+
+fn id_to_json(id: NodeId) -> serde_json::Value {
+    numbers_to_json(&[id.created.counter, id.created.replica, u64::from(id.index)])
+}
+
+fn id_from_json(json: &serde_json::Value) -> Result<NodeId> {
+    let numbers = numbers_from_json(json, 3)?;
+    if numbers[2] > u64::from(u32::max_value()) {
+        return Err(Error::new("the index of the node is out of range"));
+    }
+
+    Ok(NodeId {
+        created: Timestamp {
+            counter: numbers[0],
+            replica: numbers[1],
+        },
+        index: numbers[2] as u32,
+    })
+}
+
+fn slot_to_json(slot: Slot) -> serde_json::Value {
+    let mut object = serde_json::Map::new();
+    let tag = match slot {
+        Slot::Root => "root",
+        Slot::Field { parent, index } => {
+            object.insert("parent".to_owned(), id_to_json(parent));
+            object.insert("index".to_owned(), index.into());
+            "field"
+        }
+        Slot::Item { parent } => {
+            object.insert("parent".to_owned(), id_to_json(parent));
+            "item"
+        }
+        Slot::Element { parent, element } => {
+            object.insert("parent".to_owned(), id_to_json(parent));
+            object.insert("element".to_owned(), id_to_json(element));
+            "element"
+        }
+    };
+    object.insert(json::TYPE_KEY.to_owned(), tag.into());
+    serde_json::Value::Object(object)
+}
+
+fn slot_from_json(json: &serde_json::Value) -> Result<Slot> {
+    match member(json, json::TYPE_KEY)?.as_str() {
+        Some("root") => Ok(Slot::Root),
+        Some("field") => Ok(Slot::Field {
+            parent: id_from_json(member(json, "parent")?)?,
+            index: member(json, "index")?
+                .as_u64()
+                .ok_or_else(|| Error::new("expected a field index"))? as usize,
+        }),
+        Some("item") => Ok(Slot::Item {
+            parent: id_from_json(member(json, "parent")?)?,
+        }),
+        Some("element") => Ok(Slot::Element {
+            parent: id_from_json(member(json, "parent")?)?,
+            element: id_from_json(member(json, "element")?)?,
+        }),
+        _ => Err(Error::new("unknown kind of slot")),
+    }
+}
+
+/// Adds the node of an operation and the name of its class to `object`.
+///
+/// The class is left out for a missing node, so that it can be told apart from a node that is
+/// `null` in JSON, like a missing optional value.
+fn insert_node(
+    object: &mut serde_json::Map<String, serde_json::Value>,
+    registry: &Registry,
+    node: Option<&Node>,
+) -> Result<()> {
+    match node {
+        Some(node) => {
+            let class = registry
+                .class_names()
+                .remove(&node.class.id)
+                .ok_or_else(|| Error::new("the registry doesn't know the class of the node"))?;
+            object.insert("class".to_owned(), class.into());
+            object.insert("node".to_owned(), json::to_json(node));
+        }
+        None => {
+            object.insert("node".to_owned(), serde_json::Value::Null);
+        }
+    }
+    Ok(())
+}
+
+fn node_from_json(registry: &Registry, json: &serde_json::Value) -> Result<Option<Node>> {
+    let name = match json.get("class") {
+        Some(name) => name
+            .as_str()
+            .ok_or_else(|| Error::new("expected a class name"))?,
+        None => return Ok(None),
+    };
+    let class = registry
+        .class_names()
+        .into_iter()
+        .find(|&(_, ref n)| n == name)
+        .and_then(|(id, _)| registry.get(id))
+        .ok_or_else(|| Error::new(format!("unknown class {:?}", name)))?;
+
+    json::node_from_json(registry, class, member(json, "node")?).map(Some)
+}
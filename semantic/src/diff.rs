@@ -0,0 +1,503 @@
+use std::collections;
+use std::fmt;
+
+use json;
+use Content;
+use DynamicSemantic;
+use Error;
+use Node;
+use NodePath;
+use Result;
+
+/// How similar two nodes in a collection must be for them to be considered the same node with
+/// some changes, instead of a deleted and an inserted node.
+const SIMILARITY_THRESHOLD: f64 = 0.5;
+
+/// A single change in an edit script produced by `diff`.
+///
+/// The paths of an edit script use two coordinate systems: the path of a `Delete` and the `from`
+/// of a `Move` are positions in the old tree, while the path of an `Insert` or `Update` and the
+/// `to` of a `Move` are positions in the new tree.  This way no path depends on the order in
+/// which the other edits are applied; see `patch`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Edit {
+    /// The node `node` was inserted at `path` in the new tree.
+    Insert { path: NodePath, node: Node },
+    /// The node `node` at `path` in the old tree was deleted.
+    Delete { path: NodePath, node: Node },
+    /// The node at `from` in the old tree was moved to `to` in the new tree.
+    ///
+    /// Changes inside of the moved node are reported as separate edits.
+    Move { from: NodePath, to: NodePath },
+    /// The node at `path` in the new tree changed from `old` to `new`.
+    ///
+    /// This is used for changed attributes like primitives and enumerations, and for nodes whose
+    /// class or variant changed.
+    Update { path: NodePath, old: Node, new: Node },
+}
+
+/// Computes an edit script that turns `old` into `new`.
+///
+/// Nodes in collections are matched up by class and content, so that a paragraph that was edited
+/// and moved shows up as a move and a few updates, and not as a deleted and an inserted paragraph.
+pub fn diff(old: &DynamicSemantic, new: &DynamicSemantic) -> Vec<Edit> {
+    let old = Node::from_value(old);
+    let new = Node::from_value(new);
+    let root = NodePath::root();
+
+    let mut edits = Vec::new();
+    diff_nodes(&old, &new, &root, &root, &mut edits);
+    detect_moves(edits)
+}
+
+/// Applies an edit script produced by `diff` to `old`, returning the new tree.
+///
+/// Deletions and move sources are removed first, from the last path to the first so that their
+/// old-tree paths stay valid.  Insertions and move targets are then added in new-tree order, and
+/// updates are applied last.
+pub fn patch(old: &DynamicSemantic, edits: &[Edit]) -> Result<Node> {
+    let mut root = Node::from_value(old);
+
+    let mut removals = edits
+        .iter()
+        .enumerate()
+        .filter_map(|(k, e)| match *e {
+            Edit::Delete { ref path, .. } => Some((path, k)),
+            Edit::Move { ref from, .. } => Some((from, k)),
+            _ => None,
+        }).collect::<Vec<_>>();
+    removals.sort_by(|a, b| b.0.steps().cmp(a.0.steps()));
+
+    let mut moved = collections::HashMap::new();
+    for (path, k) in removals {
+        let node = take(&mut root, path)?;
+        if let Edit::Move { .. } = edits[k] {
+            moved.insert(k, node);
+        }
+    }
+
+    let mut insertions = edits
+        .iter()
+        .enumerate()
+        .filter_map(|(k, e)| match *e {
+            Edit::Insert { ref path, .. } => Some((path, k)),
+            Edit::Move { ref to, .. } => Some((to, k)),
+            _ => None,
+        }).collect::<Vec<_>>();
+    insertions.sort_by(|a, b| a.0.steps().cmp(b.0.steps()));
+
+    for (path, k) in insertions {
+        let node = match edits[k] {
+            Edit::Insert { ref node, .. } => node.clone(),
+            _ => moved
+                .remove(&k)
+                .expect("the source of every move was taken above"),
+        };
+        put(&mut root, path, node)?;
+    }
+
+    for edit in edits {
+        if let Edit::Update { ref path, ref new, .. } = *edit {
+            *node_mut(&mut root, path)? = new.clone();
+        }
+    }
+
+    Ok(root)
+}
+
+fn node_mut<'a>(root: &'a mut Node, path: &NodePath) -> Result<&'a mut Node> {
+    let mut node = root;
+    for &step in path.steps() {
+        let current = node;
+        node = current
+            .children_mut()
+            .into_iter()
+            .nth(step)
+            .ok_or_else(|| Error::new(format!("there is no node at {}", path)))?;
+    }
+    Ok(node)
+}
+
+/// Removes the item at `path` from its collection or optional parent.
+fn take(root: &mut Node, path: &NodePath) -> Result<Node> {
+    let index = path.last().unwrap_or(0);
+    let parent = match path.parent() {
+        Some(parent) => node_mut(root, &parent)?,
+        None => return Err(Error::new("the root can't be removed")),
+    };
+
+    match parent.content {
+        Content::Collection { ref mut items } if index < items.len() => Ok(items.remove(index)),
+        Content::Optional { ref mut item } if index == 0 && item.is_some() => {
+            Ok(*item.take().expect("the item was just checked"))
+        }
+        _ => Err(Error::new(format!("there is no item to remove at {}", path))),
+    }
+}
+
+/// Adds `node` as the item at `path` of its collection or optional parent.
+fn put(root: &mut Node, path: &NodePath, node: Node) -> Result<()> {
+    let index = path.last().unwrap_or(0);
+    let parent = match path.parent() {
+        Some(parent) => node_mut(root, &parent)?,
+        None => return Err(Error::new("the root can't be inserted")),
+    };
+
+    match parent.content {
+        Content::Collection { ref mut items } if index <= items.len() => {
+            items.insert(index, node);
+            Ok(())
+        }
+        Content::Optional { ref mut item } if index == 0 && item.is_none() => {
+            *item = Some(Box::new(node));
+            Ok(())
+        }
+        _ => Err(Error::new(format!("an item can't be inserted at {}", path))),
+    }
+}
+
+fn diff_nodes(
+    old: &Node,
+    new: &Node,
+    old_path: &NodePath,
+    new_path: &NodePath,
+    edits: &mut Vec<Edit>,
+) {
+    if old == new {
+        return;
+    }
+
+    match (&old.content, &new.content) {
+        _ if old.class.id != new.class.id => update(old, new, new_path, edits),
+        (&Content::Record { fields: ref a }, &Content::Record { fields: ref b }) => {
+            diff_fields(a, b, old_path, new_path, edits);
+        }
+        (
+            &Content::Union {
+                variant: va,
+                fields: ref a,
+            },
+            &Content::Union {
+                variant: vb,
+                fields: ref b,
+            },
+        )
+            if va == vb =>
+        {
+            diff_fields(a, b, old_path, new_path, edits);
+        }
+        (&Content::Collection { items: ref a }, &Content::Collection { items: ref b }) => {
+            diff_items(a, b, old_path, new_path, edits);
+        }
+        (&Content::Optional { item: Some(ref a) }, &Content::Optional { item: Some(ref b) }) => {
+            diff_nodes(a, b, &old_path.child(0), &new_path.child(0), edits);
+        }
+        (&Content::Optional { item: None }, &Content::Optional { item: Some(ref b) }) => {
+            edits.push(Edit::Insert {
+                path: new_path.child(0),
+                node: (**b).clone(),
+            });
+        }
+        (&Content::Optional { item: Some(ref a) }, &Content::Optional { item: None }) => {
+            edits.push(Edit::Delete {
+                path: old_path.child(0),
+                node: (**a).clone(),
+            });
+        }
+        _ => update(old, new, new_path, edits),
+    }
+}
+
+fn update(old: &Node, new: &Node, path: &NodePath, edits: &mut Vec<Edit>) {
+    edits.push(Edit::Update {
+        path: path.clone(),
+        old: old.clone(),
+        new: new.clone(),
+    });
+}
+
+fn diff_fields(
+    old: &[Node],
+    new: &[Node],
+    old_path: &NodePath,
+    new_path: &NodePath,
+    edits: &mut Vec<Edit>,
+) {
+    for (i, (a, b)) in old.iter().zip(new).enumerate() {
+        diff_nodes(a, b, &old_path.child(i), &new_path.child(i), edits);
+    }
+}
+
+fn diff_items(
+    old: &[Node],
+    new: &[Node],
+    old_path: &NodePath,
+    new_path: &NodePath,
+    edits: &mut Vec<Edit>,
+) {
+    let matches = match_items(old, new);
+    let in_order = longest_increasing(&matches.iter().map(|&(i, _)| i).collect::<Vec<_>>());
+
+    let mut old_matched = vec![false; old.len()];
+    let mut new_matched = vec![false; new.len()];
+
+    for (&(i, j), in_order) in matches.iter().zip(in_order) {
+        old_matched[i] = true;
+        new_matched[j] = true;
+
+        let from = old_path.child(i);
+        let to = new_path.child(j);
+        if !in_order {
+            edits.push(Edit::Move {
+                from: from.clone(),
+                to: to.clone(),
+            });
+        }
+        diff_nodes(&old[i], &new[j], &from, &to, edits);
+    }
+
+    for (i, node) in old.iter().enumerate().filter(|&(i, _)| !old_matched[i]) {
+        edits.push(Edit::Delete {
+            path: old_path.child(i),
+            node: node.clone(),
+        });
+    }
+
+    for (j, node) in new.iter().enumerate().filter(|&(j, _)| !new_matched[j]) {
+        edits.push(Edit::Insert {
+            path: new_path.child(j),
+            node: node.clone(),
+        });
+    }
+}
+
+/// Pairs up the items of two collections, returning `(old index, new index)` pairs ordered by the
+/// new index.
+///
+/// Identical items in the same relative order are matched first, then identical items anywhere,
+/// then items that are similar enough, and finally items of the same class in the same position.
+pub fn match_items(old: &[Node], new: &[Node]) -> Vec<(usize, usize)> {
+    let mut old_for_new = vec![None; new.len()];
+    let mut old_matched = vec![false; old.len()];
+
+    for (i, j) in common_subsequence(old, new) {
+        old_for_new[j] = Some(i);
+        old_matched[i] = true;
+    }
+
+    for j in 0..new.len() {
+        if old_for_new[j].is_none() {
+            if let Some(i) = (0..old.len()).find(|&i| !old_matched[i] && old[i] == new[j]) {
+                old_for_new[j] = Some(i);
+                old_matched[i] = true;
+            }
+        }
+    }
+
+    for j in 0..new.len() {
+        if old_for_new[j].is_none() {
+            let best = (0..old.len())
+                .filter(|&i| !old_matched[i])
+                .map(|i| (i, similarity(&old[i], &new[j])))
+                .filter(|&(_, s)| s >= SIMILARITY_THRESHOLD)
+                .fold(None, |best: Option<(usize, f64)>, (i, s)| match best {
+                    Some((_, best_s)) if best_s >= s => best,
+                    _ => Some((i, s)),
+                });
+
+            if let Some((i, _)) = best {
+                old_for_new[j] = Some(i);
+                old_matched[i] = true;
+            }
+        }
+    }
+
+    // Items of the same class that sit between the same matched neighbours are probably edits of
+    // each other, even if they have nothing in common anymore
+    for j in 0..new.len() {
+        if old_for_new[j].is_none() {
+            let start = old_for_new[..j]
+                .iter()
+                .rev()
+                .filter_map(|&i| i)
+                .next()
+                .map(|i| i + 1)
+                .unwrap_or(0);
+            let end = old_for_new[j + 1..]
+                .iter()
+                .filter_map(|&i| i)
+                .next()
+                .unwrap_or(old.len());
+
+            if let Some(i) = (start..end)
+                .find(|&i| !old_matched[i] && old[i].class.id == new[j].class.id)
+            {
+                old_for_new[j] = Some(i);
+                old_matched[i] = true;
+            }
+        }
+    }
+
+    old_for_new
+        .into_iter()
+        .enumerate()
+        .filter_map(|(j, i)| i.map(|i| (i, j)))
+        .collect()
+}
+
+/// The longest common subsequence of identical nodes, as `(old index, new index)` pairs.
+fn common_subsequence(old: &[Node], new: &[Node]) -> Vec<(usize, usize)> {
+    // lengths[i][j] is the length of the longest common subsequence of old[i..] and new[j..]
+    let mut lengths = vec![vec![0; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lengths[i][j] = if old[i] == new[j] {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+
+    let mut result = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() && j < new.len() {
+        if old[i] == new[j] {
+            result.push((i, j));
+            i += 1;
+            j += 1;
+        } else if lengths[i + 1][j] >= lengths[i][j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    result
+}
+
+/// Marks the elements that are part of a longest increasing subsequence of `values`.
+///
+/// Matched items that are not part of it are the ones that were moved.
+fn longest_increasing(values: &[usize]) -> Vec<bool> {
+    // lengths[k] is the length of the longest increasing subsequence ending at values[k]
+    let mut lengths = vec![1; values.len()];
+    let mut previous = vec![None; values.len()];
+
+    for k in 0..values.len() {
+        for l in 0..k {
+            if values[l] < values[k] && lengths[l] + 1 > lengths[k] {
+                lengths[k] = lengths[l] + 1;
+                previous[k] = Some(l);
+            }
+        }
+    }
+
+    let mut result = vec![false; values.len()];
+    let mut next = (0..values.len()).max_by_key(|&k| lengths[k]);
+    while let Some(k) = next {
+        result[k] = true;
+        next = previous[k];
+    }
+    result
+}
+
+/// How similar two nodes are, from 0 (nothing in common) to 1 (the same contents).
+///
+/// Nodes of different classes are never similar.  Otherwise, this is the fraction of the leaf
+/// values (primitives and enumerations) that the nodes have in common.
+fn similarity(a: &Node, b: &Node) -> f64 {
+    if a.class.id != b.class.id {
+        return 0.0;
+    }
+
+    let a_leaves = leaves(a);
+    let b_leaves = leaves(b);
+    if a_leaves.is_empty() && b_leaves.is_empty() {
+        return 1.0;
+    }
+
+    let mut used = vec![false; b_leaves.len()];
+    let mut common = 0;
+    for leaf in &a_leaves {
+        if let Some(k) = (0..b_leaves.len()).find(|&k| !used[k] && b_leaves[k] == *leaf) {
+            used[k] = true;
+            common += 1;
+        }
+    }
+
+    (2 * common) as f64 / (a_leaves.len() + b_leaves.len()) as f64
+}
+
+fn leaves(node: &Node) -> Vec<&Node> {
+    match node.content {
+        Content::Primitive(_) | Content::Enumeration { .. } => vec![node],
+        _ => node.children().into_iter().flat_map(leaves).collect(),
+    }
+}
+
+/// Turns deletions and insertions of identical nodes into moves.
+///
+/// This catches nodes that moved between different parents, which `diff_items` can't see.
+fn detect_moves(edits: Vec<Edit>) -> Vec<Edit> {
+    let mut replaced = vec![None; edits.len()];
+    let mut removed = vec![false; edits.len()];
+
+    {
+        let mut deletes = edits
+            .iter()
+            .enumerate()
+            .filter_map(|(k, e)| match *e {
+                Edit::Delete { ref path, ref node } => Some((k, path, node)),
+                _ => None,
+            }).collect::<Vec<_>>();
+
+        for (k, edit) in edits.iter().enumerate() {
+            if let Edit::Insert { ref path, ref node } = *edit {
+                if let Some(d) = deletes.iter().position(|&(_, _, deleted)| deleted == node) {
+                    let (delete_index, from, _) = deletes.remove(d);
+                    replaced[delete_index] = Some(Edit::Move {
+                        from: from.clone(),
+                        to: path.clone(),
+                    });
+                    removed[k] = true;
+                }
+            }
+        }
+    }
+
+    edits
+        .into_iter()
+        .zip(replaced.into_iter().zip(removed))
+        .filter_map(|(edit, (replacement, removed))| {
+            if removed {
+                None
+            } else {
+                Some(replacement.unwrap_or(edit))
+            }
+        }).collect()
+}
+
+impl fmt::Display for Edit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Edit::Insert { ref path, ref node } => {
+                write!(f, "inserted {} at {}", json::to_json(node), path)
+            }
+            Edit::Delete { ref path, ref node } => {
+                write!(f, "deleted {} at {}", json::to_json(node), path)
+            }
+            Edit::Move { ref from, ref to } => write!(f, "moved the node at {} to {}", from, to),
+            Edit::Update {
+                ref path,
+                ref old,
+                ref new,
+            } => write!(
+                f,
+                "changed {} to {} at {}",
+                json::to_json(old),
+                json::to_json(new),
+                path
+            ),
+        }
+    }
+}
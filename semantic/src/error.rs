@@ -0,0 +1,49 @@
+use std::error;
+use std::fmt;
+use std::result;
+
+use NodePath;
+
+/// A convenience type alias for creating a `Result` with the error being of type `Error`.
+pub type Result<A> = result::Result<A, Error>;
+
+/// A semantic value could not be built or converted.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Error {
+    /// The path of the node that caused the error.
+    pub path: NodePath,
+    pub message: String,
+}
+
+impl Error {
+    pub fn new<S>(message: S) -> Error
+    where
+        S: Into<String>,
+    {
+        let path = NodePath::root();
+        let message = message.into();
+        Error { path, message }
+    }
+
+    /// Moves the location of this error one step down, into the child at `index`.
+    ///
+    /// This is used to build up the full path of an error as it propagates out of a tree.
+    pub fn within(self, index: usize) -> Error {
+        let mut steps = vec![index];
+        steps.extend_from_slice(self.path.steps());
+        let path = NodePath::from_steps(steps);
+        Error { path, ..self }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.path, self.message)
+    }
+}
+
+impl error::Error for Error {
+    fn description(&self) -> &str {
+        &self.message
+    }
+}
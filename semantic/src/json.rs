@@ -0,0 +1,402 @@
+//! Conversion between semantic values and JSON.
+//!
+//! The JSON representation follows the conventions of the [unist](https://github.com/syntax-tree/unist)
+//! family of syntax trees (like mdast for Markdown):
+//!
+//!   - Records and unit structs become objects with a `type` tag, which is the camelCased name of
+//!     the Rust type (`InlineCode` becomes `"inlineCode"`), or `"root"` for documents (unless they
+//!     specify another tag, like `#[semantic(tag = "json")]`).
+//!   - Fields are named by their camelCased semantic name (`reference_type` becomes
+//!     `"referenceType"`).
+//!   - Newtype variants of unions are transparent, while other union variants become objects with
+//!     the camelCased variant name as their `type` tag.
+//!   - Enumerations become strings with the camelCased variant name.
+//!   - Collections become arrays, and missing optional values become `null`.
+//!   - Documents with a schema version carry it in a `schemaVersion` key next to their tag.
+use serde_json;
+
+use Atom;
+use Class;
+use Content;
+use DynamicSemantic;
+use Error;
+use Field;
+use Node;
+use Primitive;
+use PrimitiveKind;
+use Registry;
+use Result;
+use Role;
+use Semantic;
+use Structure;
+use Value;
+use Variant;
+use VariantKind;
+
+/// The key of the type tag of records and units.
+pub const TYPE_KEY: &str = "type";
+
+/// The key of the schema version of documents.
+pub const VERSION_KEY: &str = "schemaVersion";
+
+/// Converts any semantic value into JSON.
+///
+/// # Panics
+///
+/// If the value does not match the structure of its class.  That is a bug in the value's
+/// `DynamicSemantic` implementation, or a `Node` that was assembled by hand with the wrong
+/// content: values of derived types and nodes built by this crate always match their class.
+pub fn to_json(value: &DynamicSemantic) -> serde_json::Value {
+    let class = value.class();
+
+    match (value.reflect(), &class.structure) {
+        (Value::Primitive(p), _) => primitive_to_json(p),
+        (Value::Unit, _) => versioned(class, tagged_object(type_tag(class), &[], Vec::new())),
+        (Value::Enumeration { variant }, &Structure::Enumeration { variants, .. })
+            if variant < variants.len() =>
+        {
+            serde_json::Value::String(camel_case(variants[variant].name))
+        }
+        (Value::Record { fields }, &Structure::Record { fields: metas, .. }) => {
+            versioned(class, tagged_object(type_tag(class), metas, fields))
+        }
+        (Value::Union { variant, fields }, &Structure::Union { variants, .. })
+            if variant < variants.len() =>
+        {
+            let variant = &variants[variant];
+            match variant.kind {
+                VariantKind::Newtype { .. } => to_json(fields[0]),
+                VariantKind::Unit => tagged_object(Some(camel_case(variant.name)), &[], fields),
+                VariantKind::Record { fields: metas } => {
+                    tagged_object(Some(camel_case(variant.name)), metas, fields)
+                }
+            }
+        }
+        (Value::Collection { items }, _) => {
+            serde_json::Value::Array(items.into_iter().map(to_json).collect())
+        }
+        (Value::Optional { item }, _) => item.map(to_json).unwrap_or(serde_json::Value::Null),
+        (value, structure) => panic!(
+            "value {:?} does not match its class structure {:?}",
+            value, structure
+        ),
+    }
+}
+
+/// Converts JSON into a semantic value of type `A`.
+pub fn from_json<A>(json: &serde_json::Value) -> Result<A>
+where
+    A: Semantic,
+{
+    let registry = Registry::of::<A>();
+    node_from_json(&registry, registry.root(), json)?.into_value()
+}
+
+/// Converts JSON into a node of the specified class.
+///
+/// The registry is used to look up the classes of any nested values.
+pub fn node_from_json(
+    registry: &Registry,
+    class: &'static Class<'static>,
+    json: &serde_json::Value,
+) -> Result<Node> {
+    let content = match class.structure {
+        Structure::Primitive { kind } => Content::Primitive(atom_from_json(kind, json)?),
+        Structure::Unit { .. } => {
+            check_type_tag(type_tag(class), json)?;
+            check_version(class, json)?;
+            Content::Unit
+        }
+        Structure::Enumeration { variants, .. } => {
+            let name = json
+                .as_str()
+                .ok_or_else(|| Error::new("expected a string"))?;
+            let variant = variants
+                .iter()
+                .position(|v| camel_case(v.name) == name)
+                .ok_or_else(|| Error::new(format!("unknown variant {:?}", name)))?;
+            Content::Enumeration { variant }
+        }
+        Structure::Record { fields, .. } => {
+            check_type_tag(type_tag(class), json)?;
+            check_version(class, json)?;
+            Content::Record {
+                fields: fields_from_json(registry, fields, json)?,
+            }
+        }
+        Structure::Union { variants, .. } => {
+            let (variant, v) = variants
+                .iter()
+                .enumerate()
+                .find(|&(_, v)| variant_matches(registry, v, json))
+                .ok_or_else(|| Error::new("the value does not match any variant"))?;
+
+            let fields = match v.kind {
+                VariantKind::Unit => Vec::new(),
+                VariantKind::Newtype { ty } => {
+                    let class = lookup(registry, ty)?;
+                    vec![node_from_json(registry, class, json).map_err(|e| e.within(0))?]
+                }
+                VariantKind::Record { fields } => fields_from_json(registry, fields, json)?,
+            };
+
+            Content::Union { variant, fields }
+        }
+        Structure::Collection { item } => {
+            let items = json
+                .as_array()
+                .ok_or_else(|| Error::new("expected an array"))?
+                .iter()
+                .enumerate()
+                .map(|(i, json)| node_from_json(registry, item, json).map_err(|e| e.within(i)))
+                .collect::<Result<_>>()?;
+            Content::Collection { items }
+        }
+        Structure::Optional { item } => {
+            if json.is_null() {
+                Content::Optional { item: None }
+            } else {
+                let node = node_from_json(registry, item, json).map_err(|e| e.within(0))?;
+                Content::Optional {
+                    item: Some(Box::new(node)),
+                }
+            }
+        }
+    };
+
+    Ok(Node { class, content })
+}
+
+/// The `type` tag of values of the specified class, if they have one.
+///
+/// Following unist, the tag of a document is `"root"`, unless it has a tag of its own so that it
+/// can be told apart from other documents.
+pub fn type_tag(class: &Class) -> Option<String> {
+    match class.structure {
+        Structure::Unit { .. } | Structure::Record { .. } if class.role == Role::Document => {
+            Some(class.tag.unwrap_or("root").to_owned())
+        }
+        Structure::Unit { name } | Structure::Record { name, .. } => {
+            Some(camel_case(name.rsplit("::").next().unwrap_or(name)))
+        }
+        _ => None,
+    }
+}
+
+/// The schema version of the JSON of a document, if it has one.
+pub fn version(json: &serde_json::Value) -> Result<Option<u32>> {
+    match json.get(VERSION_KEY) {
+        None => Ok(None),
+        Some(version) => version
+            .as_u64()
+            .filter(|&v| v >= 1 && v <= u64::from(u32::max_value()))
+            .map(|v| Some(v as u32))
+            .ok_or_else(|| Error::new(format!("invalid schema version {}", version))),
+    }
+}
+
+/// Sets the schema version of the JSON of a document.
+pub fn set_version(json: &mut serde_json::Value, version: u32) -> Result<()> {
+    let object = json
+        .as_object_mut()
+        .ok_or_else(|| Error::new("expected an object"))?;
+    object.insert(VERSION_KEY.to_owned(), version.into());
+    Ok(())
+}
+
+/// Converts a Rust-style name (like `InlineCode` or `reference_type`) into camelCase.
+pub fn camel_case(name: &str) -> String {
+    let mut result = String::with_capacity(name.len());
+
+    for (i, part) in name.split('_').filter(|p| !p.is_empty()).enumerate() {
+        let mut chars = part.chars();
+        if let Some(first) = chars.next() {
+            if i == 0 {
+                result.extend(first.to_lowercase());
+            } else {
+                result.extend(first.to_uppercase());
+            }
+            result.push_str(chars.as_str());
+        }
+    }
+
+    result
+}
+
+fn tagged_object(
+    tag: Option<String>,
+    metas: &[Field],
+    fields: Vec<&DynamicSemantic>,
+) -> serde_json::Value {
+    let mut object = serde_json::Map::new();
+
+    if let Some(tag) = tag {
+        object.insert(TYPE_KEY.to_owned(), serde_json::Value::String(tag));
+    }
+
+    for (meta, field) in metas.iter().zip(fields) {
+        object.insert(camel_case(meta.name), to_json(field));
+    }
+
+    serde_json::Value::Object(object)
+}
+
+/// Adds the schema version of `class` to its JSON object, if it has one.
+fn versioned(class: &Class, mut json: serde_json::Value) -> serde_json::Value {
+    if let (Some(version), Some(object)) = (class.version, json.as_object_mut()) {
+        object.insert(VERSION_KEY.to_owned(), version.into());
+    }
+    json
+}
+
+fn fields_from_json(
+    registry: &Registry,
+    metas: &'static [Field<'static>],
+    json: &serde_json::Value,
+) -> Result<Vec<Node>> {
+    let object = json
+        .as_object()
+        .ok_or_else(|| Error::new("expected an object"))?;
+
+    metas
+        .iter()
+        .enumerate()
+        .map(|(i, meta)| {
+            let class = lookup(registry, meta.ty)?;
+            let name = camel_case(meta.name);
+
+            match object.get(&name) {
+                Some(json) => node_from_json(registry, class, json),
+                None => missing_field(registry, class, meta, &name),
+            }.map_err(|e| e.within(i))
+        }).collect()
+}
+
+/// Builds the value for a field that isn't present in a JSON object.
+fn missing_field(
+    registry: &Registry,
+    class: &'static Class<'static>,
+    meta: &Field,
+    name: &str,
+) -> Result<Node> {
+    if let Some(default) = meta.default {
+        default_from_str(registry, class, default)
+    } else {
+        Node::empty(class).ok_or_else(|| Error::new(format!("missing field {:?}", name)))
+    }
+}
+
+/// Parses the default value of a field, as specified in `Field::default`.
+pub(crate) fn default_from_str(
+    registry: &Registry,
+    class: &'static Class<'static>,
+    default: &str,
+) -> Result<Node> {
+    // Defaults are usually valid JSON (like numbers or `true`), but might also be plain strings
+    let json = serde_json::from_str(default)
+        .unwrap_or_else(|_| serde_json::Value::String(default.to_owned()));
+    node_from_json(registry, class, &json)
+}
+
+fn check_type_tag(expected: Option<String>, json: &serde_json::Value) -> Result<()> {
+    match (expected, json.get(TYPE_KEY)) {
+        (Some(ref expected), Some(actual)) if actual.as_str() != Some(expected) => Err(Error::new(
+            format!("expected type {:?} but got {}", expected, actual),
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Checks that a document is of the current schema version of its class, since older documents
+/// need to be migrated first.
+fn check_version(class: &Class, json: &serde_json::Value) -> Result<()> {
+    match (class.version, version(json)?.unwrap_or(1)) {
+        (Some(expected), actual) if actual != expected => Err(Error::new(format!(
+            "expected schema version {} but got {}; the document needs to be migrated",
+            expected, actual
+        ))),
+        _ => Ok(()),
+    }
+}
+
+/// Whether `json` looks like a value of the specified class.
+///
+/// This is used to find the right variant of a union, so it only needs to look at the shape and
+/// `type` tag of the JSON value, not at its full contents.
+fn matches(registry: &Registry, class: &Class, json: &serde_json::Value) -> bool {
+    match class.structure {
+        Structure::Primitive { kind } => match kind {
+            PrimitiveKind::Bool => json.is_boolean(),
+            PrimitiveKind::String => json.is_string(),
+            _ => json.is_number(),
+        },
+        Structure::Unit { .. } | Structure::Record { .. } => {
+            has_type_tag(json, type_tag(class).as_ref().map(String::as_str))
+        }
+        Structure::Enumeration { variants, .. } => json
+            .as_str()
+            .map(|s| variants.iter().any(|v| camel_case(v.name) == s))
+            .unwrap_or(false),
+        Structure::Union { variants, .. } => {
+            variants.iter().any(|v| variant_matches(registry, v, json))
+        }
+        Structure::Collection { .. } => json.is_array(),
+        Structure::Optional { item } => json.is_null() || matches(registry, item, json),
+    }
+}
+
+fn variant_matches(registry: &Registry, variant: &Variant, json: &serde_json::Value) -> bool {
+    match variant.kind {
+        VariantKind::Newtype { ty } => registry
+            .get(ty)
+            .map(|class| matches(registry, class, json))
+            .unwrap_or(false),
+        VariantKind::Unit | VariantKind::Record { .. } => {
+            has_type_tag(json, Some(&camel_case(variant.name)))
+        }
+    }
+}
+
+fn has_type_tag(json: &serde_json::Value, tag: Option<&str>) -> bool {
+    json.get(TYPE_KEY).and_then(|t| t.as_str()) == tag
+}
+
+fn lookup(registry: &Registry, id: ::std::any::TypeId) -> Result<&'static Class<'static>> {
+    registry
+        .get(id)
+        .ok_or_else(|| Error::new(format!("unknown class {:?}", id)))
+}
+
+fn primitive_to_json(primitive: Primitive) -> serde_json::Value {
+    match primitive {
+        Primitive::Bool(v) => v.into(),
+        Primitive::U8(v) => v.into(),
+        Primitive::I8(v) => v.into(),
+        Primitive::U16(v) => v.into(),
+        Primitive::I16(v) => v.into(),
+        Primitive::U32(v) => v.into(),
+        Primitive::I32(v) => v.into(),
+        Primitive::F32(v) => v.into(),
+        Primitive::U64(v) => v.into(),
+        Primitive::I64(v) => v.into(),
+        Primitive::F64(v) => v.into(),
+        Primitive::String(v) => v.into(),
+    }
+}
+
+fn atom_from_json(kind: PrimitiveKind, json: &serde_json::Value) -> Result<Atom> {
+    let atom = match kind {
+        PrimitiveKind::Bool => json.as_bool().map(Atom::Bool),
+        PrimitiveKind::String => json.as_str().map(|s| Atom::String(s.to_owned())),
+        PrimitiveKind::F32 | PrimitiveKind::F64 => {
+            json.as_f64().and_then(|n| Atom::from_float(kind, n))
+        }
+        _ => json
+            .as_i64()
+            .map(i128::from)
+            .or_else(|| json.as_u64().map(i128::from))
+            .and_then(|n| Atom::from_integer(kind, n)),
+    };
+
+    atom.ok_or_else(|| Error::new(format!("expected a value of type {:?}", kind)))
+}
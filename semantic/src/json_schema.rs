@@ -0,0 +1,365 @@
+//! Export of the semantic JSON format as a [JSON Schema](https://json-schema.org/).
+//!
+//! The schema describes exactly the JSON that `json::to_json` produces and `json::from_json`
+//! accepts, so that other tools can validate documents without linking against Rust code:
+//!
+//!   - Records and units are objects with a constant `type` tag.
+//!   - Unions become `oneOf` the schemas of their variants, which are told apart by their tags.
+//!   - Records, units and unions are listed under `definitions` by their type name, and referred
+//!     to with `$ref`, so that recursive structures can refer to them.
+//!   - Documents have a constant `schemaVersion` if their class has a version.
+//!   - Enumerations become string `enum`s, collections become arrays and optional values are
+//!     `anyOf` null and their item.
+//!   - Range constraints become `minimum`/`maximum` (or their exclusive variants), and non-empty
+//!     constraints become `minLength` or `minItems`.
+use std::any;
+use std::collections;
+use std::ops;
+
+use serde_json;
+
+use json;
+use Class;
+use Constraint;
+use Field;
+use Node;
+use PrimitiveKind;
+use Registry;
+use Structure;
+use Variant;
+use VariantKind;
+
+/// The JSON Schema dialect that generated schemas use.
+pub const DRAFT: &str = "http://json-schema.org/draft-07/schema#";
+
+/// Generates a JSON Schema for the JSON representation of the root class of `registry`.
+pub fn schema(registry: &Registry) -> serde_json::Value {
+    let mut generator = Generator {
+        registry,
+        names: collections::HashMap::new(),
+        definitions: serde_json::Map::new(),
+    };
+
+    let root = generator.class_schema(registry.root());
+    let mut schema = serde_json::Map::new();
+    schema.insert("$schema".to_owned(), DRAFT.into());
+    match root {
+        // Keywords next to a `$ref` are ignored, and that would include the definitions
+        serde_json::Value::Object(ref root) if root.contains_key("$ref") => {
+            schema.insert("allOf".to_owned(), vec![root.clone()].into());
+        }
+        serde_json::Value::Object(root) => schema.extend(root),
+        _ => unreachable!("class schemas are always objects"),
+    }
+    schema.insert(
+        "definitions".to_owned(),
+        serde_json::Value::Object(generator.definitions),
+    );
+
+    serde_json::Value::Object(schema)
+}
+
+struct Generator<'a> {
+    registry: &'a Registry,
+    /// The names of the classes that have a definition (or are getting one).
+    names: collections::HashMap<any::TypeId, String>,
+    definitions: serde_json::Map<String, serde_json::Value>,
+}
+
+impl<'a> Generator<'a> {
+    /// The schema of values of `class`, which is a reference for records, units and unions.
+    ///
+    /// Every recursive structure goes through one of those, so generating a schema always ends.
+    fn class_schema(&mut self, class: &'static Class<'static>) -> serde_json::Value {
+        match class.structure {
+            Structure::Unit { name }
+            | Structure::Record { name, .. }
+            | Structure::Union { name, .. } => {
+                let name = self.define(class, name);
+                object(vec![("$ref", format!("#/definitions/{}", name).into())])
+            }
+            _ => with_description(self.inline_schema(class), class.doc),
+        }
+    }
+
+    /// Adds a definition for a record, unit or union class, unless it already has one, and returns
+    /// its name.
+    fn define(&mut self, class: &'static Class<'static>, name: &str) -> String {
+        if let Some(name) = self.names.get(&class.id) {
+            return name.clone();
+        }
+
+        // Types of different modules might have the same name
+        let base = name.rsplit("::").next().unwrap_or(name);
+        let mut name = base.to_owned();
+        let mut suffix = 1;
+        while self.definitions.contains_key(&name) {
+            suffix += 1;
+            name = format!("{}{}", base, suffix);
+        }
+
+        self.names.insert(class.id, name.clone());
+        // Reserve the name before generating the schema, which might refer back to this class
+        self.definitions
+            .insert(name.clone(), serde_json::Value::Null);
+
+        let mut schema = match class.structure {
+            Structure::Record { fields, .. } => self.object_schema(json::type_tag(class), fields),
+            Structure::Union { variants, .. } => self.union_schema(variants),
+            _ => self.object_schema(json::type_tag(class), &[]),
+        };
+        if let Some(version) = class.version {
+            add_version(&mut schema, version);
+        }
+        self.definitions
+            .insert(name.clone(), with_description(schema, class.doc));
+        name
+    }
+
+    fn inline_schema(&mut self, class: &'static Class<'static>) -> serde_json::Value {
+        match class.structure {
+            Structure::Primitive { kind } => primitive_schema(kind),
+            Structure::Unit { .. } | Structure::Record { .. } | Structure::Union { .. } => {
+                self.class_schema(class)
+            }
+            Structure::Enumeration { variants, .. } => object(vec![
+                ("type", "string".into()),
+                (
+                    "enum",
+                    variants
+                        .iter()
+                        .map(|v| json::camel_case(v.name).into())
+                        .collect::<Vec<serde_json::Value>>()
+                        .into(),
+                ),
+            ]),
+            Structure::Collection { item } => object(vec![
+                ("type", "array".into()),
+                ("items", self.class_schema(item)),
+            ]),
+            Structure::Optional { item } => object(vec![(
+                "anyOf",
+                vec![object(vec![("type", "null".into())]), self.class_schema(item)].into(),
+            )]),
+        }
+    }
+
+    fn union_schema(&mut self, variants: &'static [Variant<'static>]) -> serde_json::Value {
+        let variants = variants
+            .iter()
+            .map(|variant| match variant.kind {
+                // Newtype variants are transparent, so their class describes them
+                VariantKind::Newtype { ty } => match self.registry.get(ty) {
+                    Some(class) => self.class_schema(class),
+                    None => object(Vec::new()),
+                },
+                VariantKind::Unit => with_description(
+                    self.object_schema(Some(json::camel_case(variant.name)), &[]),
+                    variant.doc,
+                ),
+                VariantKind::Record { fields } => with_description(
+                    self.object_schema(Some(json::camel_case(variant.name)), fields),
+                    variant.doc,
+                ),
+            }).collect::<Vec<_>>();
+        object(vec![("oneOf", variants.into())])
+    }
+
+    /// The schema of an object with the specified `type` tag and fields.
+    fn object_schema(
+        &mut self,
+        tag: Option<String>,
+        fields: &'static [Field<'static>],
+    ) -> serde_json::Value {
+        let mut properties = serde_json::Map::new();
+        let mut required = Vec::new();
+
+        if let Some(tag) = tag {
+            properties.insert(json::TYPE_KEY.to_owned(), object(vec![("const", tag.into())]));
+            required.push(serde_json::Value::from(json::TYPE_KEY));
+        }
+
+        for field in fields {
+            let name = json::camel_case(field.name);
+            let class = match self.registry.get(field.ty) {
+                Some(class) => class,
+                None => {
+                    properties.insert(name, object(Vec::new()));
+                    continue;
+                }
+            };
+
+            // Fields can only be left out when `json::from_json` has a value to fill in
+            if field.default.is_none() && Node::empty(class).is_none() {
+                required.push(name.clone().into());
+            }
+            properties.insert(name, self.field_schema(field, class));
+        }
+
+        object(vec![
+            ("type", "object".into()),
+            ("properties", serde_json::Value::Object(properties)),
+            ("required", required.into()),
+        ])
+    }
+
+    fn field_schema(
+        &mut self,
+        field: &Field,
+        class: &'static Class<'static>,
+    ) -> serde_json::Value {
+        let non_empty = field.constraints.iter().any(|c| match *c {
+            Constraint::NonEmpty => true,
+            Constraint::Range { .. } => false,
+        });
+
+        let mut schema = match class.structure {
+            // The constraints of the field apply to the item, and a non-empty optional value must
+            // be present
+            Structure::Optional { item } if non_empty => self.constrained_schema(field, item),
+            Structure::Optional { item } if !field.constraints.is_empty() => object(vec![(
+                "anyOf",
+                vec![
+                    object(vec![("type", "null".into())]),
+                    self.constrained_schema(field, item),
+                ].into(),
+            )]),
+            _ => self.constrained_schema(field, class),
+        };
+
+        if let Some(default) = field.default {
+            let default = serde_json::from_str(default)
+                .unwrap_or_else(|_| serde_json::Value::String(default.to_owned()));
+            schema = with_keyword(schema, "default", default);
+        }
+        with_description(schema, field.doc)
+    }
+
+    /// The schema of `class`, with the constraints of `field` added to it.
+    fn constrained_schema(
+        &mut self,
+        field: &Field,
+        class: &'static Class<'static>,
+    ) -> serde_json::Value {
+        let mut schema = self.class_schema(class);
+
+        for constraint in field.constraints {
+            match *constraint {
+                Constraint::Range { start, end } => {
+                    match start {
+                        ops::Bound::Included(s) => {
+                            schema = with_keyword(schema, "minimum", number(s))
+                        }
+                        ops::Bound::Excluded(s) => {
+                            schema = with_keyword(schema, "exclusiveMinimum", number(s))
+                        }
+                        ops::Bound::Unbounded => {}
+                    }
+                    match end {
+                        ops::Bound::Included(e) => {
+                            schema = with_keyword(schema, "maximum", number(e))
+                        }
+                        ops::Bound::Excluded(e) => {
+                            schema = with_keyword(schema, "exclusiveMaximum", number(e))
+                        }
+                        ops::Bound::Unbounded => {}
+                    }
+                }
+                Constraint::NonEmpty => match class.structure {
+                    Structure::Collection { .. } => schema = with_keyword(schema, "minItems", 1),
+                    Structure::Primitive {
+                        kind: PrimitiveKind::String,
+                    } => schema = with_keyword(schema, "minLength", 1),
+                    _ => {}
+                },
+            }
+        }
+
+        schema
+    }
+}
+
+/// Adds the `schemaVersion` key of documents to an object schema.
+///
+/// Documents without a version are of version 1, so the key is only required for later versions.
+fn add_version(schema: &mut serde_json::Value, version: u32) {
+    if let Some(properties) = schema["properties"].as_object_mut() {
+        properties.insert(
+            json::VERSION_KEY.to_owned(),
+            object(vec![("const", version.into())]),
+        );
+    }
+    if version > 1 {
+        if let Some(required) = schema["required"].as_array_mut() {
+            required.push(json::VERSION_KEY.into());
+        }
+    }
+}
+
+fn primitive_schema(kind: PrimitiveKind) -> serde_json::Value {
+    let (minimum, maximum): (serde_json::Value, serde_json::Value) = match kind {
+        PrimitiveKind::Bool => return object(vec![("type", "boolean".into())]),
+        PrimitiveKind::String => return object(vec![("type", "string".into())]),
+        PrimitiveKind::F32 | PrimitiveKind::F64 => return object(vec![("type", "number".into())]),
+        PrimitiveKind::U8 => (u8::min_value().into(), u8::max_value().into()),
+        PrimitiveKind::I8 => (i8::min_value().into(), i8::max_value().into()),
+        PrimitiveKind::U16 => (u16::min_value().into(), u16::max_value().into()),
+        PrimitiveKind::I16 => (i16::min_value().into(), i16::max_value().into()),
+        PrimitiveKind::U32 => (u32::min_value().into(), u32::max_value().into()),
+        PrimitiveKind::I32 => (i32::min_value().into(), i32::max_value().into()),
+        PrimitiveKind::U64 => (u64::min_value().into(), u64::max_value().into()),
+        PrimitiveKind::I64 => (i64::min_value().into(), i64::max_value().into()),
+    };
+
+    object(vec![
+        ("type", "integer".into()),
+        ("minimum", minimum),
+        ("maximum", maximum),
+    ])
+}
+
+/// Adds a keyword to a schema.
+///
+/// Other keywords next to a `$ref` are ignored by JSON Schema, so references are wrapped in an
+/// `allOf` first.
+fn with_keyword<V>(schema: serde_json::Value, keyword: &str, value: V) -> serde_json::Value
+where
+    V: Into<serde_json::Value>,
+{
+    let mut schema = match schema {
+        serde_json::Value::Object(ref map) if map.contains_key("$ref") => {
+            object(vec![("allOf", vec![schema.clone()].into())])
+        }
+        schema => schema,
+    };
+
+    if let Some(map) = schema.as_object_mut() {
+        map.insert(keyword.to_owned(), value.into());
+    }
+    schema
+}
+
+fn with_description(schema: serde_json::Value, doc: Option<&str>) -> serde_json::Value {
+    match doc {
+        Some(doc) => with_keyword(schema, "description", doc),
+        None => schema,
+    }
+}
+
+/// A JSON number, which is an integer if possible so that it reads naturally next to integers.
+fn number(n: f64) -> serde_json::Value {
+    if n.fract() == 0.0 && n.abs() < 2f64.powi(53) {
+        (n as i64).into()
+    } else {
+        n.into()
+    }
+}
+
+fn object(entries: Vec<(&str, serde_json::Value)>) -> serde_json::Value {
+    serde_json::Value::Object(
+        entries
+            .into_iter()
+            .map(|(k, v)| (k.to_owned(), v))
+            .collect(),
+    )
+}
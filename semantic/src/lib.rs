@@ -1,8 +1,44 @@
 #![feature(const_type_id)]
-extern crate type_info;
+extern crate serde_json;
+
+mod crdt;
+mod diff;
+mod error;
+pub mod json;
+pub mod json_schema;
+mod merge;
+mod migrate;
+mod node;
+mod path;
+pub mod protobuf;
+mod registry;
+mod select;
+mod snapshot;
+mod store;
+pub mod typescript;
+mod validate;
+mod value;
+mod visit;
+
+pub use crdt::{Document, NodeId, Operation, OperationKind, Slot, Timestamp};
+pub use diff::{diff, patch, Edit};
+pub use error::{Error, Result};
+pub use merge::{merge, Conflict, ConflictKind, Merge};
+pub use migrate::{migrate, Migration};
+pub use node::{from_next_node, Atom, Content, Node};
+pub use path::NodePath;
+pub use registry::{Insertable, Registry};
+pub use select::{select, Selector};
+pub use snapshot::{History, Snapshot};
+pub use store::{NodeKey, Store};
+pub use validate::{validate, Violation, ViolationKind};
+pub use value::{dynamic, Primitive, PrimitiveKind, Value};
+pub use visit::{cast, Folder, Visit, VisitMut, Walk};
 
 use std::any;
 use std::fmt;
+use std::ops;
+use std::result;
 use std::str;
 
 pub trait Semantic: DynamicSemantic {
@@ -13,41 +49,64 @@ pub trait Semantic: DynamicSemantic {
         F: FnMut(&'static Class<'static>) -> bool,
     {
     }
+
+    /// Builds a value from its generic node representation.
+    fn from_node(node: Node) -> Result<Self>
+    where
+        Self: Sized;
 }
 
 pub trait DynamicSemantic: any::Any + fmt::Debug {
-    fn class(&self) -> Class<'static>;
+    fn class(&self) -> &'static Class<'static>;
+
+    /// Reflects the contents of this value, in the shape described by its class.
+    fn reflect(&self) -> Value;
 }
 
-#[derive(Debug)]
+#[derive(Clone, Copy, Debug)]
 pub struct Class<'a> {
     pub id: any::TypeId,
     pub role: Role,
     pub structure: Structure<'a>,
+    /// A human readable description of the class.
+    pub doc: Option<&'a str>,
+    /// The schema version of documents of this class, which is bumped whenever their serialized
+    /// form changes (see `migrate`).
+    pub version: Option<u32>,
+    /// The `type` tag of the JSON of documents of this class, if it isn't `"root"` (see
+    /// `json::type_tag`).
+    pub tag: Option<&'a str>,
 }
 
-#[derive(Debug)]
+#[derive(Clone, Copy, Debug)]
 pub enum Structure<'a> {
-    Primitive,
+    Primitive {
+        kind: PrimitiveKind,
+    },
     Unit {
         name: &'a str,
     },
     Enumeration {
-        variants: &'a [&'a str],
+        name: &'a str,
+        variants: &'a [Variant<'a>],
     },
     Record {
         name: &'a str,
         fields: &'a [Field<'a>],
     },
     Union {
+        name: &'a str,
         variants: &'a [Variant<'a>],
     },
     Collection {
         item: &'a Class<'a>,
     },
+    Optional {
+        item: &'a Class<'a>,
+    },
 }
 
-#[derive(Debug)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum Role {
     Root,
     Document,
@@ -56,17 +115,44 @@ pub enum Role {
     Attribute,
 }
 
-#[derive(Debug)]
+#[derive(Clone, Copy, Debug)]
 pub struct Field<'a> {
+    /// The externally visible name of the field.
     pub name: &'a str,
     pub ty: any::TypeId,
     pub is_children: bool,
+    /// The textual representation of the value to use when the field is not specified.
+    pub default: Option<&'a str>,
+    /// A human readable description of the field.
+    pub doc: Option<&'a str>,
+    /// Constraints that all values of the field must satisfy.
+    pub constraints: &'a [Constraint],
 }
 
-#[derive(Debug)]
+#[derive(Clone, Copy, Debug)]
+pub enum Constraint {
+    /// The (numeric) value must be within the specified bounds.
+    Range {
+        start: ops::Bound<f64>,
+        end: ops::Bound<f64>,
+    },
+    /// The value (a string or collection) must not be empty.
+    NonEmpty,
+}
+
+#[derive(Clone, Copy, Debug)]
 pub struct Variant<'a> {
     pub name: &'a str,
-    pub ty: any::TypeId,
+    pub kind: VariantKind<'a>,
+    /// A human readable description of the variant.
+    pub doc: Option<&'a str>,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum VariantKind<'a> {
+    Unit,
+    Newtype { ty: any::TypeId },
+    Record { fields: &'a [Field<'a>] },
 }
 
 #[derive(Debug)]
@@ -75,7 +161,7 @@ pub struct Data {}
 impl str::FromStr for Role {
     type Err = ();
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
+    fn from_str(s: &str) -> result::Result<Self, Self::Err> {
         match s {
             "root" => Ok(Role::Root),
             "document" => Ok(Role::Document),
@@ -88,36 +174,81 @@ impl str::FromStr for Role {
 }
 
 macro_rules! semantic_primitive {
-    ($t:ty) => {
+    ($t:ty, $v:ident) => {
         impl Semantic for $t {
             const CLASS: Class<'static> = Class {
                 id: any::TypeId::of::<$t>(),
                 role: Role::Attribute,
-                structure: Structure::Primitive,
+                structure: Structure::Primitive {
+                    kind: PrimitiveKind::$v,
+                },
+                doc: None,
+                version: None,
+                tag: None,
             };
+
+            fn from_node(node: Node) -> Result<Self> {
+                match node.content {
+                    Content::Primitive(Atom::$v(v)) => Ok(v),
+                    _ => Err(Error::new(concat!("expected a value of type ", stringify!($t)))),
+                }
+            }
         }
 
         impl DynamicSemantic for $t {
-            fn class(&self) -> Class<'static> {
-                Self::CLASS
+            fn class(&self) -> &'static Class<'static> {
+                &Self::CLASS
+            }
+
+            fn reflect(&self) -> Value {
+                Value::Primitive(Primitive::$v(*self))
             }
         }
     };
 }
 
-semantic_primitive!(bool);
-semantic_primitive!(u8);
-semantic_primitive!(i8);
-semantic_primitive!(u16);
-semantic_primitive!(i16);
-semantic_primitive!(u32);
-semantic_primitive!(i32);
-semantic_primitive!(f32);
-semantic_primitive!(u64);
-semantic_primitive!(i64);
-semantic_primitive!(f64);
+semantic_primitive!(bool, Bool);
+semantic_primitive!(u8, U8);
+semantic_primitive!(i8, I8);
+semantic_primitive!(u16, U16);
+semantic_primitive!(i16, I16);
+semantic_primitive!(u32, U32);
+semantic_primitive!(i32, I32);
+semantic_primitive!(f32, F32);
+semantic_primitive!(u64, U64);
+semantic_primitive!(i64, I64);
+semantic_primitive!(f64, F64);
 // Not implemented for usize, isize intentionally, since they are not cross-platform
-semantic_primitive!(String);
+
+impl Semantic for String {
+    const CLASS: Class<'static> = Class {
+        id: any::TypeId::of::<String>(),
+        role: Role::Attribute,
+        structure: Structure::Primitive {
+            kind: PrimitiveKind::String,
+        },
+        doc: None,
+        version: None,
+        tag: None,
+    };
+
+    fn from_node(node: Node) -> Result<Self> {
+        match node.content {
+            Content::Primitive(Atom::String(v)) => Ok(v),
+            _ => Err(Error::new("expected a value of type String")),
+        }
+    }
+}
+
+impl DynamicSemantic for String {
+    fn class(&self) -> &'static Class<'static> {
+        &Self::CLASS
+    }
+
+    fn reflect(&self) -> Value {
+        Value::Primitive(Primitive::String(self))
+    }
+}
 
 impl<A> Semantic for Vec<A>
 where
@@ -127,6 +258,9 @@ where
         id: any::TypeId::of::<Vec<A>>(),
         role: A::CLASS.role,
         structure: Structure::Collection { item: &A::CLASS },
+        doc: None,
+        version: None,
+        tag: None,
     };
 
     fn visit_classes<F>(visitor: &mut F)
@@ -137,14 +271,31 @@ where
             A::visit_classes(visitor);
         }
     }
+
+    fn from_node(node: Node) -> Result<Self> {
+        match node.content {
+            Content::Collection { items } => items
+                .into_iter()
+                .enumerate()
+                .map(|(i, item)| A::from_node(item).map_err(|e| e.within(i)))
+                .collect(),
+            _ => Err(Error::new("expected a collection")),
+        }
+    }
 }
 
 impl<A> DynamicSemantic for Vec<A>
 where
     A: Semantic,
 {
-    fn class(&self) -> Class<'static> {
-        Self::CLASS
+    fn class(&self) -> &'static Class<'static> {
+        &Self::CLASS
+    }
+
+    fn reflect(&self) -> Value {
+        Value::Collection {
+            items: self.iter().map(dynamic).collect(),
+        }
     }
 }
 
@@ -153,9 +304,12 @@ where
     A: Semantic,
 {
     const CLASS: Class<'static> = Class {
-        id: any::TypeId::of::<Vec<A>>(),
+        id: any::TypeId::of::<Option<A>>(),
         role: A::CLASS.role,
-        structure: Structure::Collection { item: &A::CLASS },
+        structure: Structure::Optional { item: &A::CLASS },
+        doc: None,
+        version: None,
+        tag: None,
     };
 
     fn visit_classes<F>(visitor: &mut F)
@@ -166,13 +320,29 @@ where
             A::visit_classes(visitor);
         }
     }
+
+    fn from_node(node: Node) -> Result<Self> {
+        match node.content {
+            Content::Optional { item: Some(item) } => {
+                Ok(Some(A::from_node(*item).map_err(|e| e.within(0))?))
+            }
+            Content::Optional { item: None } => Ok(None),
+            _ => Err(Error::new("expected an optional value")),
+        }
+    }
 }
 
 impl<A> DynamicSemantic for Option<A>
 where
     A: Semantic,
 {
-    fn class(&self) -> Class<'static> {
-        Self::CLASS
+    fn class(&self) -> &'static Class<'static> {
+        &Self::CLASS
+    }
+
+    fn reflect(&self) -> Value {
+        Value::Optional {
+            item: self.as_ref().map(dynamic),
+        }
     }
 }
@@ -0,0 +1,423 @@
+use std::fmt;
+
+use diff;
+use json;
+use Content;
+use DynamicSemantic;
+use Node;
+use NodePath;
+
+/// The result of a three-way merge.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Merge {
+    /// The merged tree.
+    ///
+    /// Where there are conflicts, this contains one of the conflicting versions, as described by
+    /// `ConflictKind`.
+    pub node: Node,
+    /// The places where both sides made incompatible changes.
+    pub conflicts: Vec<Conflict>,
+}
+
+/// Both sides of a merge changed the same node in incompatible ways.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Conflict {
+    /// The path of the conflicting node in the merged tree.
+    pub path: NodePath,
+    pub kind: ConflictKind,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ConflictKind {
+    /// Both sides changed the node in different ways.  The merged tree contains our version.
+    BothChanged { base: Node, ours: Node, theirs: Node },
+    /// We deleted a node that they changed.  The merged tree contains their version.
+    DeletedByUs { base: Node, theirs: Node },
+    /// They deleted a node that we changed.  The merged tree contains our version.
+    DeletedByThem { base: Node, ours: Node },
+    /// Both sides reordered a collection in different ways.  The merged collection is in our
+    /// order.
+    BothMoved,
+}
+
+impl Merge {
+    /// Whether the merge succeeded without any conflicts.
+    pub fn is_clean(&self) -> bool {
+        self.conflicts.is_empty()
+    }
+}
+
+/// Merges the changes that were made to `base` in `ours` and in `theirs`.
+///
+/// Changes to different parts of the tree are combined automatically, including insertions,
+/// deletions and moves within collections.  When both sides change the same node, the conflict is
+/// recorded in the result instead of failing the merge.
+pub fn merge(base: &DynamicSemantic, ours: &DynamicSemantic, theirs: &DynamicSemantic) -> Merge {
+    let base = Node::from_value(base);
+    let ours = Node::from_value(ours);
+    let theirs = Node::from_value(theirs);
+
+    let mut conflicts = Vec::new();
+    let node = merge_nodes(&base, &ours, &theirs, &NodePath::root(), &mut conflicts);
+    Merge { node, conflicts }
+}
+
+fn merge_nodes(
+    base: &Node,
+    ours: &Node,
+    theirs: &Node,
+    path: &NodePath,
+    conflicts: &mut Vec<Conflict>,
+) -> Node {
+    if ours == theirs || base == theirs {
+        return ours.clone();
+    }
+    if base == ours {
+        return theirs.clone();
+    }
+
+    let same_class = base.class.id == ours.class.id && base.class.id == theirs.class.id;
+    let content = match (&base.content, &ours.content, &theirs.content) {
+        _ if !same_class => None,
+        (
+            &Content::Record { fields: ref b },
+            &Content::Record { fields: ref o },
+            &Content::Record { fields: ref t },
+        ) => Some(Content::Record {
+            fields: merge_fields(b, o, t, path, conflicts),
+        }),
+        (
+            &Content::Union {
+                variant,
+                fields: ref b,
+            },
+            &Content::Union {
+                variant: vo,
+                fields: ref o,
+            },
+            &Content::Union {
+                variant: vt,
+                fields: ref t,
+            },
+        )
+            if variant == vo && variant == vt =>
+        {
+            Some(Content::Union {
+                variant,
+                fields: merge_fields(b, o, t, path, conflicts),
+            })
+        }
+        (
+            &Content::Collection { items: ref b },
+            &Content::Collection { items: ref o },
+            &Content::Collection { items: ref t },
+        ) => Some(Content::Collection {
+            items: merge_items(b, o, t, path, conflicts),
+        }),
+        (
+            &Content::Optional { item: Some(ref b) },
+            &Content::Optional { item: Some(ref o) },
+            &Content::Optional { item: Some(ref t) },
+        ) => Some(Content::Optional {
+            item: Some(Box::new(merge_nodes(
+                b,
+                o,
+                t,
+                &path.child(0),
+                conflicts,
+            ))),
+        }),
+        (
+            &Content::Optional { item: Some(ref b) },
+            &Content::Optional { item: None },
+            &Content::Optional { item: Some(ref t) },
+        ) => {
+            conflicts.push(Conflict {
+                path: path.child(0),
+                kind: ConflictKind::DeletedByUs {
+                    base: (**b).clone(),
+                    theirs: (**t).clone(),
+                },
+            });
+            Some(theirs.content.clone())
+        }
+        (
+            &Content::Optional { item: Some(ref b) },
+            &Content::Optional { item: Some(ref o) },
+            &Content::Optional { item: None },
+        ) => {
+            conflicts.push(Conflict {
+                path: path.child(0),
+                kind: ConflictKind::DeletedByThem {
+                    base: (**b).clone(),
+                    ours: (**o).clone(),
+                },
+            });
+            Some(ours.content.clone())
+        }
+        _ => None,
+    };
+
+    match content {
+        Some(content) => Node {
+            class: ours.class,
+            content,
+        },
+        None => {
+            conflicts.push(Conflict {
+                path: path.clone(),
+                kind: ConflictKind::BothChanged {
+                    base: base.clone(),
+                    ours: ours.clone(),
+                    theirs: theirs.clone(),
+                },
+            });
+            ours.clone()
+        }
+    }
+}
+
+fn merge_fields(
+    base: &[Node],
+    ours: &[Node],
+    theirs: &[Node],
+    path: &NodePath,
+    conflicts: &mut Vec<Conflict>,
+) -> Vec<Node> {
+    base.iter()
+        .zip(ours)
+        .zip(theirs)
+        .enumerate()
+        .map(|(i, ((b, o), t))| merge_nodes(b, o, t, &path.child(i), conflicts))
+        .collect()
+}
+
+/// One side of a collection merge: the items of that side, along with the index of the matching
+/// base item for every item.
+struct Side<'a> {
+    items: &'a [Node],
+    base_indices: Vec<Option<usize>>,
+    is_ours: bool,
+}
+
+fn merge_items(
+    base: &[Node],
+    ours: &[Node],
+    theirs: &[Node],
+    path: &NodePath,
+    conflicts: &mut Vec<Conflict>,
+) -> Vec<Node> {
+    let ours = Side::new(base, ours, true);
+    let theirs = Side::new(base, theirs, false);
+
+    let ours_order = ours.surviving_order(&theirs);
+    let theirs_order = theirs.surviving_order(&ours);
+    let ours_moved = !is_increasing(&ours_order);
+    let theirs_moved = !is_increasing(&theirs_order);
+
+    if ours_moved && theirs_moved && ours_order != theirs_order {
+        conflicts.push(Conflict {
+            path: path.clone(),
+            kind: ConflictKind::BothMoved,
+        });
+    }
+
+    // The order of the merged collection is taken from one side, and the additions of the other
+    // side are slotted into it.  That side is ours, unless only they moved items around
+    if theirs_moved && !ours_moved {
+        merge_sequences(base, &theirs, &ours, path, conflicts)
+    } else {
+        merge_sequences(base, &ours, &theirs, path, conflicts)
+    }
+}
+
+fn merge_sequences(
+    base: &[Node],
+    primary: &Side,
+    secondary: &Side,
+    path: &NodePath,
+    conflicts: &mut Vec<Conflict>,
+) -> Vec<Node> {
+    let primary_for_base = primary.for_base(base.len());
+    let secondary_for_base = secondary.for_base(base.len());
+
+    // Items of the secondary side that need to be slotted into the primary side, as (base index
+    // of the item they follow, secondary index) pairs
+    let mut pending = Vec::new();
+    let mut anchor = None;
+    for (k, base_index) in secondary.base_indices.iter().enumerate() {
+        match *base_index {
+            Some(i) if primary_for_base[i].is_some() => anchor = Some(i),
+            // Deleted by the primary side, but changed by the secondary side
+            Some(i) if secondary.items[k] != base[i] => pending.push((anchor, k)),
+            Some(_) => {}
+            // Inserted by the secondary side, unless the primary side inserted the same thing
+            None if !primary.inserted(&secondary.items[k]) => pending.push((anchor, k)),
+            None => {}
+        }
+    }
+
+    let mut result = Vec::new();
+    push_pending(None, base, secondary, &pending, path, &mut result, conflicts);
+
+    for (j, base_index) in primary.base_indices.iter().enumerate() {
+        let item = &primary.items[j];
+        let item_path = path.child(result.len());
+
+        match *base_index {
+            Some(i) => match secondary_for_base[i] {
+                Some(k) => {
+                    let other = &secondary.items[k];
+                    let (ours, theirs) = if primary.is_ours {
+                        (item, other)
+                    } else {
+                        (other, item)
+                    };
+                    result.push(merge_nodes(&base[i], ours, theirs, &item_path, conflicts));
+                }
+                None => {
+                    // Deleted by the secondary side
+                    if *item != base[i] {
+                        conflicts.push(Conflict {
+                            path: item_path,
+                            kind: deleted_conflict(&base[i], item, primary.is_ours),
+                        });
+                        result.push(item.clone());
+                    }
+                }
+            },
+            None => result.push(item.clone()),
+        }
+
+        if base_index.is_some() {
+            push_pending(
+                *base_index,
+                base,
+                secondary,
+                &pending,
+                path,
+                &mut result,
+                conflicts,
+            );
+        }
+    }
+
+    result
+}
+
+fn push_pending(
+    anchor: Option<usize>,
+    base: &[Node],
+    secondary: &Side,
+    pending: &[(Option<usize>, usize)],
+    path: &NodePath,
+    result: &mut Vec<Node>,
+    conflicts: &mut Vec<Conflict>,
+) {
+    for &(_, k) in pending.iter().filter(|&&(a, _)| a == anchor) {
+        let item = &secondary.items[k];
+        if let Some(i) = secondary.base_indices[k] {
+            conflicts.push(Conflict {
+                path: path.child(result.len()),
+                kind: deleted_conflict(&base[i], item, secondary.is_ours),
+            });
+        }
+        result.push(item.clone());
+    }
+}
+
+/// A conflict for a node that was changed to `changed` by one side and deleted by the other.
+fn deleted_conflict(base: &Node, changed: &Node, changed_by_us: bool) -> ConflictKind {
+    if changed_by_us {
+        ConflictKind::DeletedByThem {
+            base: base.clone(),
+            ours: changed.clone(),
+        }
+    } else {
+        ConflictKind::DeletedByUs {
+            base: base.clone(),
+            theirs: changed.clone(),
+        }
+    }
+}
+
+fn is_increasing(values: &[usize]) -> bool {
+    values.windows(2).all(|w| w[0] < w[1])
+}
+
+impl<'a> Side<'a> {
+    fn new(base: &[Node], items: &'a [Node], is_ours: bool) -> Side<'a> {
+        let mut base_indices = vec![None; items.len()];
+        for (i, j) in diff::match_items(base, items) {
+            base_indices[j] = Some(i);
+        }
+
+        Side {
+            items,
+            base_indices,
+            is_ours,
+        }
+    }
+
+    /// The index of the matching item on this side for every base item.
+    fn for_base(&self, len: usize) -> Vec<Option<usize>> {
+        let mut result = vec![None; len];
+        for (j, base_index) in self.base_indices.iter().enumerate() {
+            if let Some(i) = *base_index {
+                result[i] = Some(j);
+            }
+        }
+        result
+    }
+
+    /// The base indices of the items that exist on both sides, in the order of this side.
+    fn surviving_order(&self, other: &Side) -> Vec<usize> {
+        self.base_indices
+            .iter()
+            .filter_map(|&i| i)
+            .filter(|i| other.base_indices.contains(&Some(*i)))
+            .collect()
+    }
+
+    /// Whether this side inserted an item identical to `item`.
+    fn inserted(&self, item: &Node) -> bool {
+        self.items
+            .iter()
+            .zip(&self.base_indices)
+            .any(|(i, base_index)| base_index.is_none() && i == item)
+    }
+}
+
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.path, self.kind)
+    }
+}
+
+impl fmt::Display for ConflictKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ConflictKind::BothChanged {
+                ref ours,
+                ref theirs,
+                ..
+            } => write!(
+                f,
+                "both sides changed the node; ours is {} and theirs is {}",
+                json::to_json(ours),
+                json::to_json(theirs)
+            ),
+            ConflictKind::DeletedByUs { ref theirs, .. } => write!(
+                f,
+                "we deleted the node, but they changed it to {}",
+                json::to_json(theirs)
+            ),
+            ConflictKind::DeletedByThem { ref ours, .. } => write!(
+                f,
+                "they deleted the node, but we changed it to {}",
+                json::to_json(ours)
+            ),
+            ConflictKind::BothMoved => write!(f, "both sides reordered the items differently"),
+        }
+    }
+}
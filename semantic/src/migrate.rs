@@ -0,0 +1,66 @@
+use serde_json;
+
+use json;
+use Class;
+use Error;
+use Result;
+
+/// A step that upgrades the JSON of a document from one schema version to the next.
+///
+/// Migrations work on JSON rather than on semantic values, since older documents by definition
+/// don't match the current classes anymore.
+#[derive(Clone, Copy)]
+pub struct Migration {
+    /// The version that this migration upgrades from, to `from + 1`.
+    pub from: u32,
+    /// A short description of what changed, for logging.
+    pub description: &'static str,
+    /// Rewrites the JSON of a document of version `from` into version `from + 1`.
+    pub migrate: fn(&mut serde_json::Value) -> Result<()>,
+}
+
+/// Upgrades the JSON of a document of `class` to the current schema version of the class, by
+/// applying `migrations` one version at a time.
+///
+/// Documents without a version are assumed to be of version 1, since they were written before
+/// the class had a version.  Returns the version that the document had.
+pub fn migrate(
+    class: &Class,
+    migrations: &[Migration],
+    json: &mut serde_json::Value,
+) -> Result<u32> {
+    let current = class
+        .version
+        .ok_or_else(|| Error::new("the class of the document has no schema version"))?;
+    let original = json::version(json)?.unwrap_or(1);
+
+    if original > current {
+        return Err(Error::new(format!(
+            "the document has schema version {}, but only versions up to {} are supported",
+            original, current
+        )));
+    }
+
+    for version in original..current {
+        let migration = migrations
+            .iter()
+            .find(|m| m.from == version)
+            .ok_or_else(|| {
+                Error::new(format!(
+                    "there is no migration from schema version {} to {}",
+                    version,
+                    version + 1
+                ))
+            })?;
+        (migration.migrate)(json).map_err(|e| Error {
+            message: format!(
+                "could not migrate from schema version {} ({}): {}",
+                version, migration.description, e.message
+            ),
+            ..e
+        })?;
+        json::set_version(json, version + 1)?;
+    }
+
+    Ok(original)
+}
@@ -0,0 +1,306 @@
+use std::fmt;
+
+use dynamic;
+use Class;
+use DynamicSemantic;
+use Error;
+use Primitive;
+use PrimitiveKind;
+use Result;
+use Semantic;
+use Structure;
+use Value;
+
+/// A generic, owned representation of a semantic value.
+///
+/// Nodes can represent the values of any class, and can be built and inspected without knowing
+/// the concrete Rust types involved.  They can be converted to and from concrete values with
+/// `Node::from_value` and `Semantic::from_node`.
+#[derive(Clone)]
+pub struct Node {
+    pub class: &'static Class<'static>,
+    pub content: Content,
+}
+
+/// The contents of a `Node`, mirroring the `Structure` of its class.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Content {
+    Primitive(Atom),
+    Unit,
+    Enumeration { variant: usize },
+    Record { fields: Vec<Node> },
+    Union { variant: usize, fields: Vec<Node> },
+    Collection { items: Vec<Node> },
+    Optional { item: Option<Box<Node>> },
+}
+
+/// An owned primitive value.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Atom {
+    Bool(bool),
+    U8(u8),
+    I8(i8),
+    U16(u16),
+    I16(i16),
+    U32(u32),
+    I32(i32),
+    F32(f32),
+    U64(u64),
+    I64(i64),
+    F64(f64),
+    String(String),
+}
+
+impl Node {
+    /// Copies any semantic value into a node tree.
+    pub fn from_value(value: &DynamicSemantic) -> Node {
+        let class = value.class();
+        let content = match value.reflect() {
+            Value::Primitive(p) => Content::Primitive(p.to_atom()),
+            Value::Unit => Content::Unit,
+            Value::Enumeration { variant } => Content::Enumeration { variant },
+            Value::Record { fields } => Content::Record {
+                fields: from_values(fields),
+            },
+            Value::Union { variant, fields } => Content::Union {
+                variant,
+                fields: from_values(fields),
+            },
+            Value::Collection { items } => Content::Collection {
+                items: from_values(items),
+            },
+            Value::Optional { item } => Content::Optional {
+                item: item.map(|i| Box::new(Node::from_value(i))),
+            },
+        };
+
+        Node { class, content }
+    }
+
+    /// The node that stands in for a missing value of the specified class, if there is one.
+    ///
+    /// Missing optional values are `None`, and missing collections are empty.
+    pub fn empty(class: &'static Class<'static>) -> Option<Node> {
+        let content = match class.structure {
+            Structure::Optional { .. } => Content::Optional { item: None },
+            Structure::Collection { .. } => Content::Collection { items: Vec::new() },
+            _ => return None,
+        };
+
+        Some(Node { class, content })
+    }
+
+    /// Converts this node into a concrete semantic value.
+    pub fn into_value<A>(self) -> Result<A>
+    where
+        A: Semantic,
+    {
+        A::from_node(self)
+    }
+
+    /// The nested nodes of this node, in the order that `NodePath`s address them.
+    pub fn children(&self) -> Vec<&Node> {
+        match self.content {
+            Content::Primitive(_) | Content::Unit | Content::Enumeration { .. } => Vec::new(),
+            Content::Record { ref fields } | Content::Union { ref fields, .. } => {
+                fields.iter().collect()
+            }
+            Content::Collection { ref items } => items.iter().collect(),
+            Content::Optional { ref item } => item.iter().map(|i| &**i).collect(),
+        }
+    }
+
+    /// The nested nodes of this node, in the order that `NodePath`s address them.
+    pub fn children_mut(&mut self) -> Vec<&mut Node> {
+        match self.content {
+            Content::Primitive(_) | Content::Unit | Content::Enumeration { .. } => Vec::new(),
+            Content::Record { ref mut fields } | Content::Union { ref mut fields, .. } => {
+                fields.iter_mut().collect()
+            }
+            Content::Collection { ref mut items } => items.iter_mut().collect(),
+            Content::Optional { ref mut item } => item.iter_mut().map(|i| &mut **i).collect(),
+        }
+    }
+
+    /// Separates this node from its children, leaving an empty shell of the same class and
+    /// variant.
+    pub(crate) fn split(self) -> (Node, Vec<Node>) {
+        let Node { class, content } = self;
+        let (content, children) = match content {
+            Content::Record { fields } => (Content::Record { fields: Vec::new() }, fields),
+            Content::Union { variant, fields } => (
+                Content::Union {
+                    variant,
+                    fields: Vec::new(),
+                },
+                fields,
+            ),
+            Content::Collection { items } => (Content::Collection { items: Vec::new() }, items),
+            Content::Optional { item } => (
+                Content::Optional { item: None },
+                item.into_iter().map(|i| *i).collect(),
+            ),
+            content => (content, Vec::new()),
+        };
+
+        (Node { class, content }, children)
+    }
+
+    /// Puts a shell that was separated by `split` back together with its children.
+    pub(crate) fn join(&self, mut children: Vec<Node>) -> Node {
+        let content = match self.content {
+            Content::Record { .. } => Content::Record { fields: children },
+            Content::Union { variant, .. } => Content::Union {
+                variant,
+                fields: children,
+            },
+            Content::Collection { .. } => Content::Collection { items: children },
+            Content::Optional { .. } => Content::Optional {
+                item: children.pop().map(Box::new),
+            },
+            ref content => content.clone(),
+        };
+
+        Node {
+            class: self.class,
+            content,
+        }
+    }
+}
+
+/// Builds the next field of a value from `nodes`, reporting errors at the field at `index`.
+///
+/// This is used by `#[derive(Semantic)]` to implement `Semantic::from_node`.
+pub fn from_next_node<A, I>(nodes: &mut I, index: usize) -> Result<A>
+where
+    A: Semantic,
+    I: Iterator<Item = Node>,
+{
+    match nodes.next() {
+        Some(node) => A::from_node(node).map_err(|e| e.within(index)),
+        None => Err(Error::new(format!("missing field with index {}", index))),
+    }
+}
+
+fn from_values(values: Vec<&DynamicSemantic>) -> Vec<Node> {
+    values.into_iter().map(Node::from_value).collect()
+}
+
+impl DynamicSemantic for Node {
+    fn class(&self) -> &'static Class<'static> {
+        self.class
+    }
+
+    fn reflect(&self) -> Value {
+        match self.content {
+            Content::Primitive(ref atom) => Value::Primitive(atom.as_primitive()),
+            Content::Unit => Value::Unit,
+            Content::Enumeration { variant } => Value::Enumeration { variant },
+            Content::Record { ref fields } => Value::Record {
+                fields: fields.iter().map(dynamic).collect(),
+            },
+            Content::Union {
+                variant,
+                ref fields,
+            } => Value::Union {
+                variant,
+                fields: fields.iter().map(dynamic).collect(),
+            },
+            Content::Collection { ref items } => Value::Collection {
+                items: items.iter().map(dynamic).collect(),
+            },
+            Content::Optional { ref item } => Value::Optional {
+                item: item.as_ref().map(|i| dynamic(&**i)),
+            },
+        }
+    }
+}
+
+impl PartialEq for Node {
+    fn eq(&self, other: &Node) -> bool {
+        self.class.id == other.class.id && self.content == other.content
+    }
+}
+
+impl fmt::Debug for Node {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // The full class is very verbose, so only show its identity
+        f.debug_struct("Node")
+            .field("class", &self.class.id)
+            .field("content", &self.content)
+            .finish()
+    }
+}
+
+impl Atom {
+    /// Builds an atom of the specified numeric kind, if `value` can be represented by it.
+    pub fn from_integer(kind: PrimitiveKind, value: i128) -> Option<Atom> {
+        macro_rules! integer {
+            ($v:ident, $t:ty) => {
+                if value >= <$t>::min_value() as i128 && value <= <$t>::max_value() as i128 {
+                    Some(Atom::$v(value as $t))
+                } else {
+                    None
+                }
+            };
+        }
+
+        match kind {
+            PrimitiveKind::U8 => integer!(U8, u8),
+            PrimitiveKind::I8 => integer!(I8, i8),
+            PrimitiveKind::U16 => integer!(U16, u16),
+            PrimitiveKind::I16 => integer!(I16, i16),
+            PrimitiveKind::U32 => integer!(U32, u32),
+            PrimitiveKind::I32 => integer!(I32, i32),
+            PrimitiveKind::U64 => integer!(U64, u64),
+            PrimitiveKind::I64 => integer!(I64, i64),
+            PrimitiveKind::F32 | PrimitiveKind::F64 => Atom::from_float(kind, value as f64),
+            PrimitiveKind::Bool | PrimitiveKind::String => None,
+        }
+    }
+
+    /// Builds an atom of the specified floating point kind.
+    pub fn from_float(kind: PrimitiveKind, value: f64) -> Option<Atom> {
+        match kind {
+            PrimitiveKind::F32 => Some(Atom::F32(value as f32)),
+            PrimitiveKind::F64 => Some(Atom::F64(value)),
+            _ => None,
+        }
+    }
+
+    pub fn as_primitive(&self) -> Primitive {
+        match *self {
+            Atom::Bool(v) => Primitive::Bool(v),
+            Atom::U8(v) => Primitive::U8(v),
+            Atom::I8(v) => Primitive::I8(v),
+            Atom::U16(v) => Primitive::U16(v),
+            Atom::I16(v) => Primitive::I16(v),
+            Atom::U32(v) => Primitive::U32(v),
+            Atom::I32(v) => Primitive::I32(v),
+            Atom::F32(v) => Primitive::F32(v),
+            Atom::U64(v) => Primitive::U64(v),
+            Atom::I64(v) => Primitive::I64(v),
+            Atom::F64(v) => Primitive::F64(v),
+            Atom::String(ref v) => Primitive::String(v),
+        }
+    }
+}
+
+impl<'a> Primitive<'a> {
+    pub fn to_atom(&self) -> Atom {
+        match *self {
+            Primitive::Bool(v) => Atom::Bool(v),
+            Primitive::U8(v) => Atom::U8(v),
+            Primitive::I8(v) => Atom::I8(v),
+            Primitive::U16(v) => Atom::U16(v),
+            Primitive::I16(v) => Atom::I16(v),
+            Primitive::U32(v) => Atom::U32(v),
+            Primitive::I32(v) => Atom::I32(v),
+            Primitive::F32(v) => Atom::F32(v),
+            Primitive::U64(v) => Atom::U64(v),
+            Primitive::I64(v) => Atom::I64(v),
+            Primitive::F64(v) => Atom::F64(v),
+            Primitive::String(v) => Atom::String(v.to_owned()),
+        }
+    }
+}
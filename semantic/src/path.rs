@@ -0,0 +1,82 @@
+use std::fmt;
+
+use DynamicSemantic;
+
+/// The location of a node within a semantic tree.
+///
+/// Each step is an index into the children of the current node (see `Value::children`), so a path
+/// can address any value in the tree: record fields, the fields of union variants, collection
+/// items and present optional values.
+#[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct NodePath {
+    steps: Vec<usize>,
+}
+
+impl NodePath {
+    /// The path of the root node.
+    pub fn root() -> NodePath {
+        NodePath::default()
+    }
+
+    /// Creates a path from its individual steps.
+    pub fn from_steps(steps: Vec<usize>) -> NodePath {
+        NodePath { steps }
+    }
+
+    pub fn steps(&self) -> &[usize] {
+        &self.steps
+    }
+
+    pub fn is_root(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    /// The path of the child at `index` of the node at this path.
+    pub fn child(&self, index: usize) -> NodePath {
+        let mut steps = self.steps.clone();
+        steps.push(index);
+        NodePath { steps }
+    }
+
+    /// The path of the parent of this node, or `None` for the root path.
+    pub fn parent(&self) -> Option<NodePath> {
+        if self.steps.is_empty() {
+            None
+        } else {
+            let steps = self.steps[..self.steps.len() - 1].to_vec();
+            Some(NodePath { steps })
+        }
+    }
+
+    /// The index of this node within its parent, or `None` for the root path.
+    pub fn last(&self) -> Option<usize> {
+        self.steps.last().cloned()
+    }
+
+    /// Whether this path is an ancestor of (or equal to) `other`.
+    pub fn is_prefix_of(&self, other: &NodePath) -> bool {
+        other.steps.starts_with(&self.steps)
+    }
+
+    /// Finds the node that this path points to, starting at `root`.
+    pub fn resolve<'a>(&self, root: &'a DynamicSemantic) -> Option<&'a DynamicSemantic> {
+        let mut current = root;
+        for &step in &self.steps {
+            current = *current.reflect().children().get(step)?;
+        }
+        Some(current)
+    }
+}
+
+impl fmt::Display for NodePath {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.steps.is_empty() {
+            return write!(f, "/");
+        }
+
+        for step in &self.steps {
+            write!(f, "/{}", step)?;
+        }
+        Ok(())
+    }
+}
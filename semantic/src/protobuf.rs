@@ -0,0 +1,315 @@
+//! Generation of typed Protocol Buffers definitions from semantic classes.
+//!
+//! Unlike a generic tree encoding, the generated messages mirror the Rust types, so that other
+//! services can consume documents with ordinary protoc-generated code:
+//!
+//!   - Records become messages, with a field for every semantic field (numbered in order), and
+//!     units become empty messages.
+//!   - Unions become messages with a `oneof`.  Variants that aren't newtypes get a nested message
+//!     of their own.
+//!   - Enumerations become enums, whose values are prefixed with the name of the enum.
+//!   - Collections become `repeated` fields.  Optional values use the well-known wrapper types for
+//!     scalars, and messages are optional anyway.
+//!
+//! Protobuf can't directly express collections of collections or optional values, or optional
+//! collections and enums, so those are wrapped in generated messages.
+use std::any;
+use std::collections;
+use std::fmt::Write;
+
+use Class;
+use Field;
+use PrimitiveKind;
+use Registry;
+use Structure;
+use Variant;
+use VariantKind;
+
+/// Generates a `.proto` file in `package` with a definition for every class in `registry`.
+pub fn definitions(registry: &Registry, package: &str) -> String {
+    let mut generator = Generator {
+        registry,
+        names: registry.type_names(),
+        wrappers: collections::BTreeMap::new(),
+        uses_well_known_types: false,
+    };
+
+    let mut definitions = Vec::new();
+    for class in registry.classes() {
+        if let Some(definition) = generator.definition(class) {
+            definitions.push(definition);
+        }
+    }
+    definitions.extend(generator.wrappers.values().cloned());
+
+    let mut output = String::from(
+        "// This file is generated from semantic classes.  Do not edit it by hand.\n\n\
+         syntax = \"proto3\";\n\n",
+    );
+    writeln!(output, "package {};\n", package).unwrap();
+    if generator.uses_well_known_types {
+        output.push_str("import \"google/protobuf/wrappers.proto\";\n\n");
+    }
+    output.push_str(&definitions.join("\n"));
+    output
+}
+
+struct Generator<'a> {
+    registry: &'a Registry,
+    names: collections::HashMap<any::TypeId, String>,
+    /// The messages that wrap types that can't be used directly, by name.
+    wrappers: collections::BTreeMap<String, String>,
+    uses_well_known_types: bool,
+}
+
+impl<'a> Generator<'a> {
+    /// The top-level definition of a named class.
+    fn definition(&mut self, class: &'static Class<'static>) -> Option<String> {
+        let name = self.names.get(&class.id)?.clone();
+
+        let mut output = comment(class.doc, "");
+        match class.structure {
+            Structure::Unit { .. } => writeln!(output, "message {} {{}}", name).unwrap(),
+            Structure::Record { fields, .. } => {
+                writeln!(output, "message {} {{", name).unwrap();
+                output.push_str(&self.fields(fields, "  "));
+                output.push_str("}\n");
+            }
+            Structure::Union { variants, .. } => {
+                writeln!(output, "message {} {{", name).unwrap();
+                output.push_str(&self.union_body(variants));
+                output.push_str("}\n");
+            }
+            Structure::Enumeration { variants, .. } => {
+                writeln!(output, "enum {} {{", name).unwrap();
+                let prefix = upper_snake_case(&name);
+                for (i, variant) in variants.iter().enumerate() {
+                    output.push_str(&comment(variant.doc, "  "));
+                    let value = upper_snake_case(variant.name);
+                    writeln!(output, "  {}_{} = {};", prefix, value, i).unwrap();
+                }
+                output.push_str("}\n");
+            }
+            Structure::Primitive { .. }
+            | Structure::Collection { .. }
+            | Structure::Optional { .. } => return None,
+        }
+
+        Some(output)
+    }
+
+    /// The nested messages and `oneof` of a union message.
+    fn union_body(&mut self, variants: &'static [Variant<'static>]) -> String {
+        let mut nested = String::new();
+        let mut oneof = String::from("  oneof variant {\n");
+
+        for (i, variant) in variants.iter().enumerate() {
+            let ty = match variant.kind {
+                VariantKind::Newtype { ty } => self.singular_type(ty),
+                VariantKind::Unit => {
+                    nested.push_str(&comment(variant.doc, "  "));
+                    writeln!(nested, "  message {} {{}}", variant.name).unwrap();
+                    variant.name.to_owned()
+                }
+                VariantKind::Record { fields } => {
+                    nested.push_str(&comment(variant.doc, "  "));
+                    writeln!(nested, "  message {} {{", variant.name).unwrap();
+                    nested.push_str(&self.fields(fields, "    "));
+                    nested.push_str("  }\n");
+                    variant.name.to_owned()
+                }
+            };
+
+            writeln!(
+                oneof,
+                "    {} {} = {};",
+                ty,
+                snake_case(variant.name),
+                i + 1
+            ).unwrap();
+        }
+
+        oneof.push_str("  }\n");
+        nested + &oneof
+    }
+
+    /// The fields of a message, numbered in order.
+    fn fields(&mut self, fields: &'static [Field<'static>], indent: &str) -> String {
+        let mut output = String::new();
+
+        for (i, field) in fields.iter().enumerate() {
+            output.push_str(&comment(field.doc, indent));
+            writeln!(
+                output,
+                "{}{} {} = {};",
+                indent,
+                self.field_type(field.ty),
+                field_name(field.name),
+                i + 1
+            ).unwrap();
+        }
+
+        output
+    }
+
+    /// The type of a field holding a value of the class with the specified id, including the
+    /// `repeated` label for collections.
+    fn field_type(&mut self, id: any::TypeId) -> String {
+        match self.registry.get(id).map(|c| c.structure) {
+            Some(Structure::Collection { item }) => {
+                format!("repeated {}", self.singular_type(item.id))
+            }
+            _ => self.singular_type(id),
+        }
+    }
+
+    /// A type that can be used for a singular field or as a `oneof` member.
+    fn singular_type(&mut self, id: any::TypeId) -> String {
+        if let Some(name) = self.names.get(&id) {
+            return name.clone();
+        }
+
+        let class = match self.registry.get(id) {
+            Some(class) => class,
+            None => return "bytes".to_owned(),
+        };
+        match class.structure {
+            Structure::Primitive { kind } => scalar_type(kind).to_owned(),
+            Structure::Collection { item } => {
+                let item_type = self.singular_type(item.id);
+                self.wrapper(
+                    format!("{}List", pascal_case(&item_type)),
+                    format!("repeated {} items = 1;", item_type),
+                )
+            }
+            Structure::Optional { item } => self.optional_type(item),
+            Structure::Unit { .. }
+            | Structure::Record { .. }
+            | Structure::Union { .. }
+            | Structure::Enumeration { .. } => "bytes".to_owned(),
+        }
+    }
+
+    /// A message type that is absent for missing values of `item`.
+    fn optional_type(&mut self, item: &'static Class<'static>) -> String {
+        match item.structure {
+            Structure::Primitive { kind } => {
+                self.uses_well_known_types = true;
+                format!("google.protobuf.{}", wrapper_type(kind))
+            }
+            Structure::Unit { .. } | Structure::Record { .. } | Structure::Union { .. } => {
+                self.singular_type(item.id)
+            }
+            Structure::Enumeration { .. }
+            | Structure::Collection { .. }
+            | Structure::Optional { .. } => {
+                let item_type = self.field_type(item.id);
+                let item_name = item_type.trim_left_matches("repeated ");
+                let name = if item_type.starts_with("repeated ") {
+                    format!("Optional{}List", pascal_case(item_name))
+                } else {
+                    format!("Optional{}", pascal_case(item_name))
+                };
+                self.wrapper(name, format!("{} value = 1;", item_type))
+            }
+        }
+    }
+
+    /// Adds a wrapper message with a single field, unless it already exists, and returns its name.
+    fn wrapper(&mut self, name: String, field: String) -> String {
+        self.wrappers
+            .entry(name.clone())
+            .or_insert_with(|| format!("message {} {{\n  {}\n}}\n", name, field));
+        name
+    }
+}
+
+fn scalar_type(kind: PrimitiveKind) -> &'static str {
+    match kind {
+        PrimitiveKind::Bool => "bool",
+        PrimitiveKind::String => "string",
+        PrimitiveKind::U8 | PrimitiveKind::U16 | PrimitiveKind::U32 => "uint32",
+        PrimitiveKind::I8 | PrimitiveKind::I16 | PrimitiveKind::I32 => "int32",
+        PrimitiveKind::U64 => "uint64",
+        PrimitiveKind::I64 => "int64",
+        PrimitiveKind::F32 => "float",
+        PrimitiveKind::F64 => "double",
+    }
+}
+
+/// The well-known type in `google/protobuf/wrappers.proto` for optional values of a scalar.
+fn wrapper_type(kind: PrimitiveKind) -> &'static str {
+    match kind {
+        PrimitiveKind::Bool => "BoolValue",
+        PrimitiveKind::String => "StringValue",
+        PrimitiveKind::U8 | PrimitiveKind::U16 | PrimitiveKind::U32 => "UInt32Value",
+        PrimitiveKind::I8 | PrimitiveKind::I16 | PrimitiveKind::I32 => "Int32Value",
+        PrimitiveKind::U64 => "UInt64Value",
+        PrimitiveKind::I64 => "Int64Value",
+        PrimitiveKind::F32 => "FloatValue",
+        PrimitiveKind::F64 => "DoubleValue",
+    }
+}
+
+/// The name of a field, where the positional fields of tuple structs get a `field_` prefix.
+fn field_name(name: &str) -> String {
+    if name.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("field_{}", name)
+    } else {
+        snake_case(name)
+    }
+}
+
+/// Converts a name (like `InlineCode` or `reference_type`) into snake_case.
+fn snake_case(name: &str) -> String {
+    let mut result = String::with_capacity(name.len());
+
+    for (i, c) in name.chars().enumerate() {
+        if c.is_uppercase() {
+            if i > 0 && !result.ends_with('_') {
+                result.push('_');
+            }
+            result.extend(c.to_lowercase());
+        } else {
+            result.push(c);
+        }
+    }
+
+    result
+}
+
+fn upper_snake_case(name: &str) -> String {
+    snake_case(name).to_uppercase()
+}
+
+/// Converts a type name (like `uint32` or `google.protobuf.StringValue`) into PascalCase, for
+/// naming wrappers.
+fn pascal_case(name: &str) -> String {
+    let name = name.rsplit('.').next().unwrap_or(name);
+    let mut result = String::with_capacity(name.len());
+
+    for part in name.split('_') {
+        let mut chars = part.chars();
+        if let Some(first) = chars.next() {
+            result.extend(first.to_uppercase());
+            result.push_str(chars.as_str());
+        }
+    }
+
+    result
+}
+
+/// A comment with the specified text, indented by `indent`.
+fn comment(doc: Option<&str>, indent: &str) -> String {
+    let mut result = String::new();
+
+    for line in doc.into_iter().flat_map(str::lines) {
+        if line.is_empty() {
+            writeln!(result, "{}//", indent).unwrap();
+        } else {
+            writeln!(result, "{}// {}", indent, line).unwrap();
+        }
+    }
+
+    result
+}
@@ -0,0 +1,506 @@
+use std::any;
+use std::collections;
+use std::ops;
+
+use json;
+use Atom;
+use Class;
+use Constraint;
+use Content;
+use DynamicSemantic;
+use Error;
+use Field;
+use Node;
+use NodePath;
+use PrimitiveKind;
+use Result;
+use Semantic;
+use Structure;
+use Value;
+use Variant;
+use VariantKind;
+
+/// All of the classes that are reachable from a root class.
+///
+/// Classes refer to each other by `TypeId`, so a registry is needed to navigate the class graph.
+#[derive(Clone, Debug)]
+pub struct Registry {
+    classes: Vec<&'static Class<'static>>,
+    index: collections::HashMap<any::TypeId, usize>,
+}
+
+/// A kind of node that can be inserted at some position of a tree, as offered by an insert menu.
+#[derive(Clone, Debug)]
+pub struct Insertable {
+    /// The class of the inserted value.  For newtype variants of unions, this is the class of the
+    /// wrapped value.
+    pub class: &'static Class<'static>,
+    /// The union variant that the inserted value is wrapped in, if the position holds a union.
+    pub variant: Option<&'static str>,
+    /// A minimal valid node to insert, as built by `Registry::instantiate`.
+    pub node: Node,
+}
+
+impl Registry {
+    /// Collects the classes reachable from the class of `A`.
+    pub fn of<A>() -> Registry
+    where
+        A: Semantic,
+    {
+        let mut registry = Registry {
+            classes: Vec::new(),
+            index: collections::HashMap::new(),
+        };
+
+        registry.insert(&A::CLASS);
+        A::visit_classes(&mut |class| registry.insert(class));
+
+        registry
+    }
+
+    /// The class that this registry was created from.
+    pub fn root(&self) -> &'static Class<'static> {
+        self.classes[0]
+    }
+
+    pub fn get(&self, id: any::TypeId) -> Option<&'static Class<'static>> {
+        self.index.get(&id).map(|&i| self.classes[i])
+    }
+
+    /// All of the classes in this registry, in the order that they were discovered from the root.
+    pub fn classes<'a>(&'a self) -> impl Iterator<Item = &'static Class<'static>> + 'a {
+        self.classes.iter().cloned()
+    }
+
+    /// Picks a short, unique name for every class that has a name in Rust, for use in code that is
+    /// generated from the classes.
+    ///
+    /// Names are the type names without their module path, with a numeric suffix if types of
+    /// different modules have the same name.
+    pub fn type_names(&self) -> collections::HashMap<any::TypeId, String> {
+        let mut names = collections::HashMap::new();
+        let mut taken = collections::HashSet::new();
+
+        for class in self.classes() {
+            let name = match class.structure {
+                Structure::Unit { name }
+                | Structure::Record { name, .. }
+                | Structure::Union { name, .. }
+                | Structure::Enumeration { name, .. } => short_name(name),
+                Structure::Primitive { .. }
+                | Structure::Collection { .. }
+                | Structure::Optional { .. } => continue,
+            };
+
+            let mut unique = name.to_owned();
+            let mut suffix = 1;
+            while taken.contains(&unique) {
+                suffix += 1;
+                unique = format!("{}{}", name, suffix);
+            }
+
+            taken.insert(unique.clone());
+            names.insert(class.id, unique);
+        }
+
+        names
+    }
+
+    /// Names every class in this registry uniquely, for referring to classes outside of the
+    /// process, like in serialized operations.
+    ///
+    /// Classes with a name in Rust use their `type_names`, while the others are named by their
+    /// structure: primitives by their lowercase kind (like `string`), collections like `[Block]`
+    /// and optional values like `Title?`.
+    pub fn class_names(&self) -> collections::HashMap<any::TypeId, String> {
+        let mut names = self.type_names();
+        for class in self.classes() {
+            let name = self.structural_name(&names, class.id);
+            names.insert(class.id, name);
+        }
+        names
+    }
+
+    fn structural_name(
+        &self,
+        names: &collections::HashMap<any::TypeId, String>,
+        id: any::TypeId,
+    ) -> String {
+        if let Some(name) = names.get(&id) {
+            return name.clone();
+        }
+
+        match self.get(id).map(|c| c.structure) {
+            Some(Structure::Primitive { kind }) => format!("{:?}", kind).to_lowercase(),
+            Some(Structure::Collection { item }) => {
+                format!("[{}]", self.structural_name(names, item.id))
+            }
+            Some(Structure::Optional { item }) => {
+                format!("{}?", self.structural_name(names, item.id))
+            }
+            _ => format!("{:?}", id),
+        }
+    }
+
+    /// A hash of the shape of all classes in this registry, which is the same for all builds that
+    /// agree on how values are serialized.
+    ///
+    /// Type names, roles, fields, variants, constraints and schema versions are part of the hash,
+    /// but module paths and docs aren't.  Unlike `TypeId`s, the hash is stable across compilers and
+    /// targets, so a frontend and a backend can compare their hashes to check that they agree on
+    /// the document model.
+    pub fn schema_hash(&self) -> u64 {
+        use std::fmt::Write;
+
+        let mut description = String::new();
+        for class in self.classes() {
+            write!(description, "{:?} {:?}", class.role, class.version).unwrap();
+            match class.structure {
+                Structure::Primitive { kind } => {
+                    write!(description, " primitive {:?}", kind).unwrap()
+                }
+                Structure::Unit { name } => {
+                    write!(description, " unit {}", short_name(name)).unwrap()
+                }
+                Structure::Enumeration { name, variants } => {
+                    write!(description, " enumeration {}", short_name(name)).unwrap();
+                    for variant in variants {
+                        write!(description, " {}", variant.name).unwrap();
+                    }
+                }
+                Structure::Record { name, fields } => {
+                    write!(description, " record {}", short_name(name)).unwrap();
+                    self.describe_fields(&mut description, fields);
+                }
+                Structure::Union { name, variants } => {
+                    write!(description, " union {}", short_name(name)).unwrap();
+                    for variant in variants {
+                        write!(description, " ({}", variant.name).unwrap();
+                        match variant.kind {
+                            VariantKind::Unit => {}
+                            VariantKind::Newtype { ty } => {
+                                write!(description, " {:?}", self.index.get(&ty)).unwrap()
+                            }
+                            VariantKind::Record { fields } => {
+                                self.describe_fields(&mut description, fields)
+                            }
+                        }
+                        description.push(')');
+                    }
+                }
+                Structure::Collection { item } => {
+                    write!(description, " collection {:?}", self.index.get(&item.id)).unwrap()
+                }
+                Structure::Optional { item } => {
+                    write!(description, " optional {:?}", self.index.get(&item.id)).unwrap()
+                }
+            }
+            description.push('\n');
+        }
+
+        // 64-bit FNV-1a, which is simple enough to be identical everywhere
+        description.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+        })
+    }
+
+    fn describe_fields(&self, description: &mut String, fields: &[Field]) {
+        use std::fmt::Write;
+
+        for field in fields {
+            write!(
+                description,
+                " [{} {:?} {} {:?}",
+                field.name,
+                self.index.get(&field.ty),
+                field.is_children,
+                field.default
+            ).unwrap();
+            for constraint in field.constraints {
+                match *constraint {
+                    Constraint::Range { start, end } => {
+                        write!(description, " {:?}..{:?}", start, end).unwrap()
+                    }
+                    Constraint::NonEmpty => description.push_str(" non-empty"),
+                }
+            }
+            description.push(']');
+        }
+    }
+
+    /// Builds a minimal valid node of the class with the specified id.
+    ///
+    /// Fields take their declared defaults where they have one.  Otherwise, primitives are zero,
+    /// `false` or empty (or the closest value allowed by their constraints), collections are empty
+    /// and optional values are missing, unless they must not be empty.  Enumerations and unions use
+    /// their first variant that can be built without infinite recursion.
+    pub fn instantiate(&self, id: any::TypeId) -> Result<Node> {
+        let class = self.lookup(id)?;
+        self.instantiate_class(class, &mut Vec::new())
+    }
+
+    /// Builds a minimal valid value for `field`, the same way that `instantiate` does for the
+    /// fields of a record.
+    pub fn instantiate_field(&self, field: &'static Field<'static>) -> Result<Node> {
+        self.instantiate_field_in(field, &mut Vec::new())
+    }
+
+    /// Lists the kinds of nodes that can be inserted at `path` in `root`.
+    ///
+    /// The parent of `path` must be a collection (where the last step of `path` is the insertion
+    /// index, up to its length) or a missing optional value (where the last step is zero).  When
+    /// the collection holds a union, every variant is listed separately.  Any other position
+    /// doesn't allow insertions, so the list is empty.
+    pub fn insertable(&self, root: &DynamicSemantic, path: &NodePath) -> Vec<Insertable> {
+        let index = match path.last() {
+            Some(index) => index,
+            None => return Vec::new(),
+        };
+        let parent = match path.parent().and_then(|p| p.resolve(root)) {
+            Some(parent) => parent,
+            None => return Vec::new(),
+        };
+
+        let item = match (parent.reflect(), &parent.class().structure) {
+            (Value::Collection { items }, &Structure::Collection { item })
+                if index <= items.len() =>
+            {
+                item
+            }
+            (Value::Optional { item: None }, &Structure::Optional { item }) if index == 0 => item,
+            _ => return Vec::new(),
+        };
+
+        match item.structure {
+            Structure::Union { variants, .. } => variants
+                .iter()
+                .enumerate()
+                .filter_map(|(i, variant)| {
+                    let class = match variant.kind {
+                        VariantKind::Newtype { ty } => self.get(ty)?,
+                        VariantKind::Unit | VariantKind::Record { .. } => item,
+                    };
+                    let content = self.instantiate_variant(i, variant, &mut vec![item.id]).ok()?;
+
+                    Some(Insertable {
+                        class,
+                        variant: Some(variant.name),
+                        node: Node {
+                            class: item,
+                            content,
+                        },
+                    })
+                }).collect(),
+            _ => self
+                .instantiate_class(item, &mut Vec::new())
+                .map(|node| Insertable {
+                    class: item,
+                    variant: None,
+                    node,
+                }).into_iter()
+                .collect(),
+        }
+    }
+
+    fn lookup(&self, id: any::TypeId) -> Result<&'static Class<'static>> {
+        self.get(id)
+            .ok_or_else(|| Error::new(format!("unknown class {:?}", id)))
+    }
+
+    /// Instantiates `class`, unless it is already being instantiated further up in `visiting`.
+    fn instantiate_class(
+        &self,
+        class: &'static Class<'static>,
+        visiting: &mut Vec<any::TypeId>,
+    ) -> Result<Node> {
+        if visiting.contains(&class.id) {
+            return Err(Error::new("the class can only be instantiated recursively"));
+        }
+
+        visiting.push(class.id);
+        let content = self.instantiate_content(class, visiting);
+        visiting.pop();
+
+        Ok(Node {
+            class,
+            content: content?,
+        })
+    }
+
+    fn instantiate_content(
+        &self,
+        class: &'static Class<'static>,
+        visiting: &mut Vec<any::TypeId>,
+    ) -> Result<Content> {
+        Ok(match class.structure {
+            Structure::Primitive { kind } => Content::Primitive(minimal_atom(kind, None)?),
+            Structure::Unit { .. } => Content::Unit,
+            Structure::Enumeration { variants, .. } => {
+                if variants.is_empty() {
+                    return Err(Error::new("the enumeration has no variants"));
+                }
+                Content::Enumeration { variant: 0 }
+            }
+            Structure::Record { fields, .. } => Content::Record {
+                fields: self.instantiate_fields(fields, visiting)?,
+            },
+            Structure::Union { variants, .. } => {
+                let mut last_error = Error::new("the union has no variants");
+                for (i, variant) in variants.iter().enumerate() {
+                    match self.instantiate_variant(i, variant, visiting) {
+                        Ok(content) => return Ok(content),
+                        Err(e) => last_error = e,
+                    }
+                }
+                return Err(last_error);
+            }
+            Structure::Collection { .. } => Content::Collection { items: Vec::new() },
+            Structure::Optional { .. } => Content::Optional { item: None },
+        })
+    }
+
+    fn instantiate_variant(
+        &self,
+        index: usize,
+        variant: &'static Variant<'static>,
+        visiting: &mut Vec<any::TypeId>,
+    ) -> Result<Content> {
+        let fields = match variant.kind {
+            VariantKind::Unit => Vec::new(),
+            VariantKind::Newtype { ty } => {
+                let class = self.lookup(ty)?;
+                vec![self.instantiate_class(class, visiting).map_err(|e| e.within(0))?]
+            }
+            VariantKind::Record { fields } => self.instantiate_fields(fields, visiting)?,
+        };
+
+        Ok(Content::Union {
+            variant: index,
+            fields,
+        })
+    }
+
+    fn instantiate_fields(
+        &self,
+        fields: &'static [Field<'static>],
+        visiting: &mut Vec<any::TypeId>,
+    ) -> Result<Vec<Node>> {
+        fields
+            .iter()
+            .enumerate()
+            .map(|(i, field)| {
+                self.instantiate_field_in(field, visiting)
+                    .map_err(|e| e.within(i))
+            }).collect()
+    }
+
+    fn instantiate_field_in(
+        &self,
+        field: &'static Field<'static>,
+        visiting: &mut Vec<any::TypeId>,
+    ) -> Result<Node> {
+        let class = self.lookup(field.ty)?;
+        if let Some(default) = field.default {
+            return json::default_from_str(self, class, default);
+        }
+
+        let non_empty = field.constraints.iter().any(|c| match *c {
+            Constraint::NonEmpty => true,
+            Constraint::Range { .. } => false,
+        });
+
+        let content = match class.structure {
+            Structure::Primitive { kind } => Content::Primitive(minimal_atom(kind, Some(field))?),
+            Structure::Collection { item } if non_empty => Content::Collection {
+                items: vec![self.instantiate_class(item, visiting)?],
+            },
+            Structure::Optional { item } if non_empty => {
+                let node = match item.structure {
+                    Structure::Primitive { kind } => Node {
+                        class: item,
+                        content: Content::Primitive(minimal_atom(kind, Some(field))?),
+                    },
+                    _ => self.instantiate_class(item, visiting)?,
+                };
+                Content::Optional {
+                    item: Some(Box::new(node)),
+                }
+            }
+            _ => return self.instantiate_class(class, visiting),
+        };
+
+        Ok(Node { class, content })
+    }
+
+    fn insert(&mut self, class: &'static Class<'static>) -> bool {
+        if self.index.contains_key(&class.id) {
+            false
+        } else {
+            self.index.insert(class.id, self.classes.len());
+            self.classes.push(class);
+            true
+        }
+    }
+}
+
+/// A type name without its module path.
+fn short_name(name: &str) -> &str {
+    name.rsplit("::").next().unwrap_or(name)
+}
+
+/// The simplest value of a primitive kind that satisfies the constraints of `field`.
+///
+/// Strings that must not be empty use the name of their field as a placeholder.
+fn minimal_atom(kind: PrimitiveKind, field: Option<&Field>) -> Result<Atom> {
+    let constraints = field.map(|f| f.constraints).unwrap_or(&[]);
+    let atom = match kind {
+        PrimitiveKind::Bool => Some(Atom::Bool(false)),
+        PrimitiveKind::String => {
+            let non_empty = constraints.iter().any(|c| match *c {
+                Constraint::NonEmpty => true,
+                Constraint::Range { .. } => false,
+            });
+            let text = match field {
+                Some(field) if non_empty => field.name.to_owned(),
+                _ => String::new(),
+            };
+            Some(Atom::String(text))
+        }
+        PrimitiveKind::F32 | PrimitiveKind::F64 => {
+            Atom::from_float(kind, minimal_number(constraints, false))
+        }
+        _ => Atom::from_integer(kind, minimal_number(constraints, true) as i128),
+    };
+
+    atom.ok_or_else(|| Error::new(format!("no value of type {:?} fits the constraints", kind)))
+}
+
+/// The number closest to zero that is within all of the range constraints.
+fn minimal_number(constraints: &[Constraint], integer: bool) -> f64 {
+    let mut value = 0.0;
+
+    for constraint in constraints {
+        if let Constraint::Range { start, end } = *constraint {
+            match start {
+                ops::Bound::Included(s) if value < s => {
+                    value = if integer { s.ceil() } else { s };
+                }
+                ops::Bound::Excluded(s) if value <= s => {
+                    value = if integer { s.floor() + 1.0 } else { s + 1.0 };
+                }
+                _ => {}
+            }
+            match end {
+                ops::Bound::Included(e) if value > e => {
+                    value = if integer { e.floor() } else { e };
+                }
+                ops::Bound::Excluded(e) if value >= e => {
+                    value = if integer { e.ceil() - 1.0 } else { e - 1.0 };
+                }
+                _ => {}
+            }
+        }
+    }
+
+    value
+}
@@ -0,0 +1,531 @@
+//! A CSS-like query language for semantic trees.
+//!
+//! Selectors are matched against the *elements* of a tree: records, unit structs, and record or
+//! unit variants of unions.  Other values (primitives, enumerations, collections, optional values
+//! and newtype variants) are transparent, so `heading > link` matches links directly inside
+//! headings, even though they are stored in a `Vec<Inline>`.
+//!
+//! The supported syntax is:
+//!
+//!   - `link` or `Link` matches elements by name, either as the Rust type (or variant) name or as
+//!     the JSON `type` tag (so documents are also called `root`).  `*` matches any element.
+//!   - `[url]` matches elements that have a `url` field, unless it is a missing optional value.
+//!   - `[url=value]`, `[url^=value]`, `[url$=value]` and `[url*=value]` match elements whose
+//!     `url` field is equal to, starts with, ends with or contains `value`.  Values can be quoted
+//!     with `"` or `'`.  Enumerations are compared by variant name.
+//!   - `:nth-child(An+B)`, `:nth-child(odd)` and `:nth-child(even)` match elements by their
+//!     (1-based) position among the elements within the same parent element.
+//!   - `a b` matches `b` elements anywhere inside `a` elements, and `a > b` matches `b` elements
+//!     directly inside `a` elements.
+//!   - `a, b` matches elements that match either `a` or `b`.
+use std::fmt;
+use std::str;
+
+use json;
+use json::camel_case;
+use DynamicSemantic;
+use Error;
+use NodePath;
+use Primitive;
+use Result;
+use Structure;
+use Value;
+use VariantKind;
+
+/// A parsed selector.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Selector {
+    alternatives: Vec<Complex>,
+}
+
+/// A chain of compound selectors joined by combinators.
+#[derive(Clone, Debug, PartialEq)]
+struct Complex {
+    compounds: Vec<Compound>,
+    /// The combinator between `compounds[i]` and `compounds[i + 1]`.
+    combinators: Vec<Combinator>,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Combinator {
+    Descendant,
+    Child,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Compound {
+    /// The element name to match, or `None` for any element.
+    name: Option<String>,
+    predicates: Vec<Predicate>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Predicate {
+    Attribute {
+        name: String,
+        comparison: Option<(Operator, String)>,
+    },
+    NthChild {
+        step: i64,
+        offset: i64,
+    },
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Operator {
+    Equals,
+    StartsWith,
+    EndsWith,
+    Contains,
+}
+
+/// An element of the tree that is being searched.
+struct Element<'a> {
+    name: &'static str,
+    /// The JSON `type` tag of the element.
+    tag: String,
+    fields: Vec<(&'static str, &'a DynamicSemantic)>,
+    /// The 1-based position among the elements with the same parent element.
+    position: usize,
+}
+
+/// Finds the paths of all nodes under `root` (including `root` itself) that match `selector`, in
+/// document order.
+pub fn select(root: &DynamicSemantic, selector: &str) -> Result<Vec<NodePath>> {
+    Ok(selector.parse::<Selector>()?.select(root))
+}
+
+impl Selector {
+    /// Finds the paths of all nodes under `root` (including `root` itself) that match this
+    /// selector, in document order.
+    pub fn select(&self, root: &DynamicSemantic) -> Vec<NodePath> {
+        let mut result = Vec::new();
+        self.walk(root, &NodePath::root(), &mut Vec::new(), &mut 0, &mut result);
+        result
+    }
+
+    fn walk<'a>(
+        &self,
+        value: &'a DynamicSemantic,
+        path: &NodePath,
+        ancestors: &mut Vec<Element<'a>>,
+        siblings: &mut usize,
+        result: &mut Vec<NodePath>,
+    ) {
+        let reflected = value.reflect();
+
+        match element(value, &reflected) {
+            Some(mut element) => {
+                *siblings += 1;
+                element.position = *siblings;
+                ancestors.push(element);
+
+                if self.alternatives.iter().any(|c| c.matches(ancestors)) {
+                    result.push(path.clone());
+                }
+
+                let mut children = 0;
+                for (i, child) in reflected.children().into_iter().enumerate() {
+                    self.walk(child, &path.child(i), ancestors, &mut children, result);
+                }
+
+                ancestors.pop();
+            }
+            None => {
+                for (i, child) in reflected.children().into_iter().enumerate() {
+                    self.walk(child, &path.child(i), ancestors, siblings, result);
+                }
+            }
+        }
+    }
+}
+
+/// Describes `value` as an element, if it is one.
+fn element<'a>(value: &'a DynamicSemantic, reflected: &Value<'a>) -> Option<Element<'a>> {
+    let class = value.class();
+    let (name, metas, fields) = match (class.structure, reflected) {
+        (Structure::Unit { name }, _) => (name, &[][..], Vec::new()),
+        (Structure::Record { name, fields: metas }, &Value::Record { ref fields }) => {
+            (name, metas, fields.clone())
+        }
+        (Structure::Union { variants, .. }, &Value::Union { variant, ref fields }) => {
+            let variant = &variants[variant];
+            match variant.kind {
+                VariantKind::Unit => (variant.name, &[][..], Vec::new()),
+                VariantKind::Record { fields: metas } => (variant.name, metas, fields.clone()),
+                VariantKind::Newtype { .. } => return None,
+            }
+        }
+        _ => return None,
+    };
+
+    let name = name.rsplit("::").next().unwrap_or(name);
+    Some(Element {
+        name,
+        tag: json::type_tag(class).unwrap_or_else(|| camel_case(name)),
+        fields: metas.iter().map(|m| m.name).zip(fields).collect(),
+        position: 0,
+    })
+}
+
+/// The textual forms of an attribute value that comparisons are made against.
+fn attribute_texts(value: &DynamicSemantic) -> Option<Vec<String>> {
+    match (value.reflect(), value.class().structure) {
+        (Value::Primitive(Primitive::String(s)), _) => Some(vec![s.to_owned()]),
+        (Value::Primitive(p), _) => Some(vec![p.to_string()]),
+        (Value::Enumeration { variant }, Structure::Enumeration { variants, .. }) => {
+            let name = variants[variant].name;
+            Some(vec![name.to_owned(), camel_case(name)])
+        }
+        (Value::Optional { item }, _) => item.and_then(attribute_texts),
+        _ => None,
+    }
+}
+
+fn names_match(pattern: &str, name: &str) -> bool {
+    pattern == name || pattern == camel_case(name)
+}
+
+impl Complex {
+    /// Whether the last element of `elements` matches, given its ancestors.
+    fn matches(&self, elements: &[Element]) -> bool {
+        let last = self.compounds.len() - 1;
+        let e = elements.len() - 1;
+        self.compounds[last].matches(&elements[e]) && self.ancestors_match(last, elements, e)
+    }
+
+    /// Whether `compounds[..k]` match the ancestors of `elements[e]`, which matches `compounds[k]`.
+    fn ancestors_match(&self, k: usize, elements: &[Element], e: usize) -> bool {
+        if k == 0 {
+            return true;
+        }
+
+        let compound = &self.compounds[k - 1];
+        match self.combinators[k - 1] {
+            Combinator::Child => {
+                e > 0
+                    && compound.matches(&elements[e - 1])
+                    && self.ancestors_match(k - 1, elements, e - 1)
+            }
+            Combinator::Descendant => (0..e).rev().any(|a| {
+                compound.matches(&elements[a]) && self.ancestors_match(k - 1, elements, a)
+            }),
+        }
+    }
+}
+
+impl Compound {
+    fn matches(&self, element: &Element) -> bool {
+        self.name
+            .as_ref()
+            .map_or(true, |name| *name == element.name || *name == element.tag)
+            && self.predicates.iter().all(|p| p.matches(element))
+    }
+}
+
+impl Predicate {
+    fn matches(&self, element: &Element) -> bool {
+        match *self {
+            Predicate::Attribute {
+                ref name,
+                ref comparison,
+            } => {
+                let field = element.fields.iter().find(|&&(n, _)| names_match(name, n));
+                match (field, comparison) {
+                    (None, _) => false,
+                    (Some(&(_, value)), &None) => match value.reflect() {
+                        Value::Optional { item } => item.is_some(),
+                        _ => true,
+                    },
+                    (Some(&(_, value)), &Some((operator, ref expected))) => attribute_texts(value)
+                        .map_or(false, |texts| {
+                            texts.iter().any(|text| operator.compare(text, expected))
+                        }),
+                }
+            }
+            Predicate::NthChild { step, offset } => {
+                let position = element.position as i64;
+                if step == 0 {
+                    position == offset
+                } else {
+                    let n = position - offset;
+                    n % step == 0 && n / step >= 0
+                }
+            }
+        }
+    }
+}
+
+impl Operator {
+    fn compare(self, text: &str, expected: &str) -> bool {
+        match self {
+            Operator::Equals => text == expected,
+            Operator::StartsWith => text.starts_with(expected),
+            Operator::EndsWith => text.ends_with(expected),
+            Operator::Contains => text.contains(expected),
+        }
+    }
+}
+
+impl str::FromStr for Selector {
+    type Err = Error;
+
+    fn from_str(source: &str) -> Result<Selector> {
+        let mut parser = Parser {
+            source,
+            position: 0,
+        };
+
+        let mut alternatives = vec![parser.complex()?];
+        while parser.eat(',') {
+            alternatives.push(parser.complex()?);
+        }
+
+        if parser.peek().is_some() {
+            return Err(parser.error("expected `,` or the end of the selector"));
+        }
+
+        Ok(Selector { alternatives })
+    }
+}
+
+struct Parser<'a> {
+    source: &'a str,
+    position: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn complex(&mut self) -> Result<Complex> {
+        self.skip_whitespace();
+        let mut compounds = vec![self.compound()?];
+        let mut combinators = Vec::new();
+
+        loop {
+            let had_whitespace = self.skip_whitespace();
+            let combinator = match self.peek() {
+                Some('>') => {
+                    self.bump();
+                    self.skip_whitespace();
+                    Combinator::Child
+                }
+                Some(',') | None => break,
+                Some(_) if had_whitespace => Combinator::Descendant,
+                Some(c) => return Err(self.error(format!("unexpected {:?}", c))),
+            };
+
+            combinators.push(combinator);
+            compounds.push(self.compound()?);
+        }
+
+        Ok(Complex {
+            compounds,
+            combinators,
+        })
+    }
+
+    fn compound(&mut self) -> Result<Compound> {
+        let universal = self.eat('*');
+        let name = if universal { None } else { self.identifier() };
+
+        let mut predicates = Vec::new();
+        loop {
+            if self.eat('[') {
+                predicates.push(self.attribute()?);
+            } else if self.eat(':') {
+                predicates.push(self.pseudo_class()?);
+            } else {
+                break;
+            }
+        }
+
+        if name.is_none() && !universal && predicates.is_empty() {
+            return Err(self.error("expected an element name, `*`, `[` or `:`"));
+        }
+
+        Ok(Compound { name, predicates })
+    }
+
+    fn attribute(&mut self) -> Result<Predicate> {
+        self.skip_whitespace();
+        let name = self
+            .identifier()
+            .ok_or_else(|| self.error("expected an attribute name"))?;
+        self.skip_whitespace();
+
+        let operator = if self.eat('=') {
+            Some(Operator::Equals)
+        } else if self.eat_str("^=") {
+            Some(Operator::StartsWith)
+        } else if self.eat_str("$=") {
+            Some(Operator::EndsWith)
+        } else if self.eat_str("*=") {
+            Some(Operator::Contains)
+        } else {
+            None
+        };
+
+        let comparison = match operator {
+            Some(operator) => {
+                self.skip_whitespace();
+                let value = self.attribute_value()?;
+                self.skip_whitespace();
+                Some((operator, value))
+            }
+            None => None,
+        };
+
+        if !self.eat(']') {
+            return Err(self.error("expected `]`"));
+        }
+
+        Ok(Predicate::Attribute { name, comparison })
+    }
+
+    fn attribute_value(&mut self) -> Result<String> {
+        match self.peek() {
+            Some(quote) if quote == '"' || quote == '\'' => {
+                self.bump();
+                let start = self.position;
+                while self.peek().map_or(false, |c| c != quote) {
+                    self.bump();
+                }
+                let value = self.source[start..self.position].to_owned();
+                if !self.eat(quote) {
+                    return Err(self.error("unterminated string"));
+                }
+                Ok(value)
+            }
+            _ => {
+                let value = self.take_while(|c| !c.is_whitespace() && c != ']');
+                if value.is_empty() {
+                    Err(self.error("expected an attribute value"))
+                } else {
+                    Ok(value.to_owned())
+                }
+            }
+        }
+    }
+
+    fn pseudo_class(&mut self) -> Result<Predicate> {
+        let start = self.position;
+        match self.identifier() {
+            Some(ref name) if name == "nth-child" => {}
+            Some(name) => {
+                self.position = start;
+                return Err(self.error(format!("unknown pseudo-class {:?}", name)));
+            }
+            None => return Err(self.error("expected a pseudo-class name")),
+        }
+
+        if !self.eat('(') {
+            return Err(self.error("expected `(`"));
+        }
+        let start = self.position;
+        let formula = self.take_while(|c| c != ')').to_owned();
+        if !self.eat(')') {
+            return Err(self.error("expected `)`"));
+        }
+
+        let (step, offset) = parse_formula(&formula).ok_or_else(|| Error::new(format!(
+            "invalid :nth-child formula {:?} at position {} of the selector",
+            formula, start
+        )))?;
+        Ok(Predicate::NthChild { step, offset })
+    }
+
+    fn identifier(&mut self) -> Option<String> {
+        let identifier = self.take_while(|c| c.is_alphanumeric() || c == '_' || c == '-');
+        if identifier.is_empty() {
+            None
+        } else {
+            Some(identifier.to_owned())
+        }
+    }
+
+    fn take_while<F>(&mut self, predicate: F) -> &'a str
+    where
+        F: Fn(char) -> bool,
+    {
+        let start = self.position;
+        while self.peek().map_or(false, &predicate) {
+            self.bump();
+        }
+        &self.source[start..self.position]
+    }
+
+    /// Skips whitespace, returning whether there was any.
+    fn skip_whitespace(&mut self) -> bool {
+        !self.take_while(char::is_whitespace).is_empty()
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.source[self.position..].chars().next()
+    }
+
+    fn bump(&mut self) {
+        if let Some(c) = self.peek() {
+            self.position += c.len_utf8();
+        }
+    }
+
+    fn eat(&mut self, expected: char) -> bool {
+        if self.peek() == Some(expected) {
+            self.bump();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn eat_str(&mut self, expected: &str) -> bool {
+        if self.source[self.position..].starts_with(expected) {
+            self.position += expected.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn error<S>(&self, message: S) -> Error
+    where
+        S: fmt::Display,
+    {
+        Error::new(format!(
+            "{} at position {} of the selector",
+            message, self.position
+        ))
+    }
+}
+
+/// Parses an `An+B` formula (or `odd` or `even`) into `(A, B)`.
+fn parse_formula(formula: &str) -> Option<(i64, i64)> {
+    let formula = formula
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect::<String>()
+        .to_lowercase();
+
+    match formula.as_str() {
+        "odd" => return Some((2, 1)),
+        "even" => return Some((2, 0)),
+        _ => {}
+    }
+
+    match formula.find('n') {
+        Some(n) => {
+            let step = match &formula[..n] {
+                "" | "+" => 1,
+                "-" => -1,
+                step => step.parse().ok()?,
+            };
+            let offset = match &formula[n + 1..] {
+                "" => 0,
+                offset if offset.starts_with('+') => offset[1..].parse().ok()?,
+                offset if offset.starts_with('-') => offset.parse().ok()?,
+                _ => return None,
+            };
+            Some((step, offset))
+        }
+        None => Some((0, formula.parse().ok()?)),
+    }
+}
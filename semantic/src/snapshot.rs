@@ -0,0 +1,302 @@
+//! Persistent semantic trees, for undo stacks and document history.
+//!
+//! A `Snapshot` never changes once it is built.  Editing it returns a new snapshot that shares
+//! every subtree that wasn't touched by the edit with the old one, so keeping many versions of a
+//! large document around only costs memory for the parts that differ between them.
+//!
+//! Snapshots implement `DynamicSemantic`, so they can be validated, diffed, merged, searched and
+//! serialized like any other semantic value.
+use std::mem;
+use std::sync;
+
+use dynamic;
+use Class;
+use Content;
+use DynamicSemantic;
+use Error;
+use Node;
+use NodePath;
+use Result;
+use Semantic;
+use Structure;
+use Value;
+
+/// An immutable version of a semantic tree.
+///
+/// Cloning a snapshot is cheap, since it only increments a reference count.
+#[derive(Clone, Debug)]
+pub struct Snapshot(sync::Arc<Shared>);
+
+#[derive(Debug)]
+struct Shared {
+    /// The node itself, without any children; those are in `children` instead.
+    node: Node,
+    children: Vec<Snapshot>,
+}
+
+/// A linear undo history of snapshots.
+#[derive(Clone, Debug)]
+pub struct History {
+    past: Vec<Snapshot>,
+    present: Snapshot,
+    future: Vec<Snapshot>,
+    limit: Option<usize>,
+}
+
+impl Snapshot {
+    /// Takes a snapshot of `value`.
+    pub fn new(value: &DynamicSemantic) -> Snapshot {
+        Snapshot::from_node(Node::from_value(value))
+    }
+
+    pub fn from_node(node: Node) -> Snapshot {
+        let (shell, children) = node.split();
+        Snapshot(sync::Arc::new(Shared {
+            node: shell,
+            children: children.into_iter().map(Snapshot::from_node).collect(),
+        }))
+    }
+
+    /// The children of the root of this snapshot, as snapshots of their own.
+    pub fn children(&self) -> &[Snapshot] {
+        &self.0.children
+    }
+
+    /// The subtree at `path`.
+    pub fn get(&self, path: &NodePath) -> Option<&Snapshot> {
+        let mut current = self;
+        for &step in path.steps() {
+            current = current.children().get(step)?;
+        }
+        Some(current)
+    }
+
+    /// Whether two snapshots are the very same tree in memory, as opposed to merely equal trees.
+    ///
+    /// This is a cheap way to find the parts of two versions of a document that can't differ.
+    pub fn shares(&self, other: &Snapshot) -> bool {
+        sync::Arc::ptr_eq(&self.0, &other.0)
+    }
+
+    pub fn to_node(&self) -> Node {
+        self.0
+            .node
+            .join(self.children().iter().map(Snapshot::to_node).collect())
+    }
+
+    /// Converts this snapshot into a concrete semantic value.
+    pub fn to_value<A>(&self) -> Result<A>
+    where
+        A: Semantic,
+    {
+        self.to_node().into_value()
+    }
+
+    /// Replaces the node at `path` with a new subtree of the same class.
+    pub fn replace(&self, path: &NodePath, node: Node) -> Result<Snapshot> {
+        self.update(path.steps(), &mut |old| {
+            if old.class().id == node.class.id {
+                Ok(Snapshot::from_node(node.clone()))
+            } else {
+                Err(Error::new(
+                    "the new node has a different class than the replaced node",
+                ))
+            }
+        })
+    }
+
+    /// Inserts a new subtree at `path`, which must be a position in a collection (up to its
+    /// length) or the item of a missing optional value.
+    pub fn insert(&self, path: &NodePath, node: Node) -> Result<Snapshot> {
+        let (parent, index) = split_path(path)?;
+        let class = node.class;
+        let mut node = Some(node);
+
+        self.update(parent, &mut |parent| {
+            let item = match parent.class().structure {
+                Structure::Collection { item } if index <= parent.children().len() => item,
+                Structure::Optional { item } if index == 0 && parent.children().is_empty() => item,
+                _ => {
+                    return Err(Error::new(
+                        "nodes can only be inserted into collections and missing optional values",
+                    ))
+                }
+            };
+            if item.id != class.id {
+                return Err(Error::new(
+                    "the node has a different class than the items of its new parent",
+                ));
+            }
+
+            let node = node.take().expect("a node is only inserted once");
+            let mut children = parent.children().to_vec();
+            children.insert(index, Snapshot::from_node(node));
+            Ok(parent.with_children(children))
+        })
+    }
+
+    /// Removes the node at `path` from the collection or optional value that contains it.
+    pub fn remove(&self, path: &NodePath) -> Result<Snapshot> {
+        let (parent, index) = split_path(path)?;
+
+        self.update(parent, &mut |parent| match parent.class().structure {
+            Structure::Collection { .. } | Structure::Optional { .. }
+                if index < parent.children().len() =>
+            {
+                let mut children = parent.children().to_vec();
+                children.remove(index);
+                Ok(parent.with_children(children))
+            }
+            _ => Err(Error::new(
+                "only items of collections and optional values can be removed",
+            )),
+        })
+    }
+
+    /// Rebuilds the path to the node at `steps`, with that node replaced by the result of `f`.
+    fn update(
+        &self,
+        steps: &[usize],
+        f: &mut FnMut(&Snapshot) -> Result<Snapshot>,
+    ) -> Result<Snapshot> {
+        match steps.split_first() {
+            None => f(self),
+            Some((&step, rest)) => {
+                let child = self
+                    .children()
+                    .get(step)
+                    .ok_or_else(|| Error::new("there is no node at the path").within(step))?;
+                let child = child.update(rest, f).map_err(|e| e.within(step))?;
+
+                let mut children = self.children().to_vec();
+                children[step] = child;
+                Ok(self.with_children(children))
+            }
+        }
+    }
+
+    /// A snapshot of the same node with different children.
+    fn with_children(&self, children: Vec<Snapshot>) -> Snapshot {
+        Snapshot(sync::Arc::new(Shared {
+            node: self.0.node.clone(),
+            children,
+        }))
+    }
+}
+
+impl DynamicSemantic for Snapshot {
+    fn class(&self) -> &'static Class<'static> {
+        self.0.node.class
+    }
+
+    fn reflect(&self) -> Value {
+        let children = self.children().iter().map(dynamic);
+
+        match self.0.node.content {
+            Content::Record { .. } => Value::Record {
+                fields: children.collect(),
+            },
+            Content::Union { variant, .. } => Value::Union {
+                variant,
+                fields: children.collect(),
+            },
+            Content::Collection { .. } => Value::Collection {
+                items: children.collect(),
+            },
+            Content::Optional { .. } => Value::Optional {
+                item: self.children().first().map(dynamic),
+            },
+            _ => self.0.node.reflect(),
+        }
+    }
+}
+
+impl PartialEq for Snapshot {
+    fn eq(&self, other: &Snapshot) -> bool {
+        self.shares(other) || (self.0.node == other.0.node && self.children() == other.children())
+    }
+}
+
+impl History {
+    /// Starts a history at `present`, keeping every earlier version.
+    pub fn new(present: Snapshot) -> History {
+        History {
+            past: Vec::new(),
+            present,
+            future: Vec::new(),
+            limit: None,
+        }
+    }
+
+    /// Starts a history at `present`, keeping at most `limit` earlier versions.
+    pub fn with_limit(present: Snapshot, limit: usize) -> History {
+        History {
+            limit: Some(limit),
+            ..History::new(present)
+        }
+    }
+
+    /// The current version.
+    pub fn present(&self) -> &Snapshot {
+        &self.present
+    }
+
+    /// The earlier versions that can be returned to with `undo`, oldest first.
+    pub fn past(&self) -> &[Snapshot] {
+        &self.past
+    }
+
+    /// Makes `snapshot` the current version, and forgets the versions that could be redone.
+    pub fn commit(&mut self, snapshot: Snapshot) {
+        let previous = mem::replace(&mut self.present, snapshot);
+        self.past.push(previous);
+        self.future.clear();
+
+        if let Some(limit) = self.limit {
+            if self.past.len() > limit {
+                let excess = self.past.len() - limit;
+                self.past.drain(..excess);
+            }
+        }
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.past.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.future.is_empty()
+    }
+
+    /// Goes back to the previous version, returning whether there was one.
+    pub fn undo(&mut self) -> bool {
+        match self.past.pop() {
+            Some(previous) => {
+                let present = mem::replace(&mut self.present, previous);
+                self.future.push(present);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Goes forward to the version that was last undone, returning whether there was one.
+    pub fn redo(&mut self) -> bool {
+        match self.future.pop() {
+            Some(next) => {
+                let present = mem::replace(&mut self.present, next);
+                self.past.push(present);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Splits a path into the steps to the parent and the index within the parent.
+fn split_path(path: &NodePath) -> Result<(&[usize], usize)> {
+    match path.steps().split_last() {
+        Some((&index, parent)) => Ok((parent, index)),
+        None => Err(Error::new("the root has no parent to insert into or remove from")),
+    }
+}
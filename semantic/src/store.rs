@@ -0,0 +1,374 @@
+//! An arena of semantic nodes with stable identities.
+//!
+//! A `NodePath` says where a node currently is, which changes whenever something before it is
+//! inserted, removed or moved.  A `Store` instead gives every node a `NodeKey` that stays the same
+//! for as long as the node exists, even when it is moved somewhere else, so that edits,
+//! selections and comments can keep referring to it.
+use std::collections;
+use std::fmt;
+
+use Class;
+use DynamicSemantic;
+use Error;
+use Node;
+use NodePath;
+use Result;
+use Semantic;
+use Structure;
+
+/// Identifies a node in a `Store`.
+///
+/// Keys are never reused within a store, so a key of a removed node never refers to a different
+/// node later on.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct NodeKey(pub u64);
+
+/// A semantic tree in which every node has a stable `NodeKey`.
+///
+/// Looking up a node by key and finding its parent take constant time, and removed nodes don't
+/// take up any space.
+#[derive(Clone, Debug)]
+pub struct Store {
+    entries: collections::HashMap<NodeKey, Entry>,
+    root: NodeKey,
+    /// The key that the next added node gets.
+    next_key: u64,
+}
+
+#[derive(Clone, Debug)]
+struct Entry {
+    /// The node itself, without any children; those are in `children` instead.
+    node: Node,
+    parent: Option<NodeKey>,
+    children: Vec<NodeKey>,
+}
+
+impl Store {
+    /// Creates a store holding a copy of `value`.
+    pub fn new(value: &DynamicSemantic) -> Store {
+        Store::from_node(Node::from_value(value))
+    }
+
+    /// Creates a store holding `node`.
+    pub fn from_node(node: Node) -> Store {
+        let mut store = Store {
+            entries: collections::HashMap::new(),
+            root: NodeKey(0),
+            next_key: 0,
+        };
+        store.root = store.add(node, None);
+        store
+    }
+
+    /// The key of the root node.
+    pub fn root(&self) -> NodeKey {
+        self.root
+    }
+
+    /// Whether the node with the specified key is still in the store.
+    pub fn contains(&self, key: NodeKey) -> bool {
+        self.entry(key).is_some()
+    }
+
+    pub fn class(&self, key: NodeKey) -> Option<&'static Class<'static>> {
+        self.entry(key).map(|e| e.node.class)
+    }
+
+    /// The parent of a node, or `None` for the root (and for nodes that don't exist).
+    pub fn parent(&self, key: NodeKey) -> Option<NodeKey> {
+        self.entry(key).and_then(|e| e.parent)
+    }
+
+    /// The children of a node, in the order that `NodePath`s address them.
+    pub fn children(&self, key: NodeKey) -> &[NodeKey] {
+        self.entry(key).map(|e| &e.children[..]).unwrap_or(&[])
+    }
+
+    /// The current location of a node.
+    pub fn path(&self, key: NodeKey) -> Option<NodePath> {
+        let mut steps = Vec::new();
+        let mut current = key;
+        self.entry(key)?;
+
+        while let Some(parent) = self.parent(current) {
+            let index = self
+                .children(parent)
+                .iter()
+                .position(|&c| c == current)
+                .expect("a node is missing from the children of its parent");
+            steps.push(index);
+            current = parent;
+        }
+
+        steps.reverse();
+        Some(NodePath::from_steps(steps))
+    }
+
+    /// The key of the node at `path`.
+    pub fn key(&self, path: &NodePath) -> Option<NodeKey> {
+        let mut current = self.root;
+        for &step in path.steps() {
+            current = *self.children(current).get(step)?;
+        }
+        Some(current)
+    }
+
+    /// A copy of the subtree rooted at a node.
+    pub fn to_node(&self, key: NodeKey) -> Option<Node> {
+        let entry = self.entry(key)?;
+        let children = entry
+            .children
+            .iter()
+            .map(|&c| self.to_node(c))
+            .collect::<Option<Vec<_>>>()?;
+        Some(entry.node.join(children))
+    }
+
+    /// Converts the whole tree into a concrete semantic value.
+    pub fn to_value<A>(&self) -> Result<A>
+    where
+        A: Semantic,
+    {
+        self.to_node(self.root)
+            .expect("the root of a store is always present")
+            .into_value()
+    }
+
+    /// Replaces a node with a new subtree of the same class.
+    ///
+    /// The node keeps its key, but all of its descendants are replaced by new nodes.
+    pub fn replace(&mut self, key: NodeKey, node: Node) -> Result<()> {
+        let class = self.class(key).ok_or_else(|| unknown(key))?;
+        if class.id != node.class.id {
+            return Err(Error::new("the new node has a different class than the replaced node"));
+        }
+
+        for child in self.children(key).to_vec() {
+            self.free(child);
+        }
+
+        let (shell, children) = node.split();
+        let children = children
+            .into_iter()
+            .map(|child| self.add(child, Some(key)))
+            .collect();
+
+        let entry = self.entry_mut(key);
+        entry.node = shell;
+        entry.children = children;
+        Ok(())
+    }
+
+    /// Changes the subtree at a node into `node`, keeping the keys of the nodes that stay.
+    ///
+    /// Unlike `replace`, this only replaces the parts of the tree that actually differ.  Nodes
+    /// whose contents changed keep their keys, and items that are inserted into or removed from a
+    /// collection don't affect the keys of the items around them.
+    pub fn update(&mut self, key: NodeKey, node: Node) -> Result<()> {
+        let (shell, children) = node.split();
+        let same_shell = {
+            let entry = self.entry(key).ok_or_else(|| unknown(key))?;
+            entry.node.class.id == shell.class.id && entry.node == shell
+        };
+        if !same_shell {
+            return self.replace(key, shell.join(children));
+        }
+
+        let old = self.children(key).to_vec();
+        if old.len() == children.len() {
+            for (child, node) in old.into_iter().zip(children) {
+                self.update(child, node)?;
+            }
+            return Ok(());
+        }
+
+        // Only collections and optional values can have a different number of children.  Keep the
+        // unchanged items at both ends, update as many items as possible in between, and insert
+        // or remove the rest
+        let is_same = |store: &Store, key: NodeKey, node: &Node| {
+            store.to_node(key).map_or(false, |n| n == *node)
+        };
+        let shortest = old.len().min(children.len());
+        let mut prefix = 0;
+        while prefix < shortest && is_same(self, old[prefix], &children[prefix]) {
+            prefix += 1;
+        }
+        let mut suffix = 0;
+        while suffix < shortest - prefix
+            && is_same(
+                self,
+                old[old.len() - 1 - suffix],
+                &children[children.len() - 1 - suffix],
+            ) {
+            suffix += 1;
+        }
+
+        let mut children = children;
+        let end = children.len() - suffix;
+        children.truncate(end);
+        let mut middle = children.split_off(prefix).into_iter();
+        let old_middle = &old[prefix..old.len() - suffix];
+
+        let mut index = prefix;
+        for &child in old_middle {
+            match middle.next() {
+                Some(node) => self.update(child, node)?,
+                None => {
+                    self.remove(child)?;
+                    continue;
+                }
+            }
+            index += 1;
+        }
+        for node in middle {
+            self.insert(key, index, node)?;
+            index += 1;
+        }
+
+        Ok(())
+    }
+
+    /// Inserts a new subtree at position `index` of the collection `parent`, or as the item of
+    /// the missing optional value `parent` (where `index` must be zero).
+    pub fn insert(&mut self, parent: NodeKey, index: usize, node: Node) -> Result<NodeKey> {
+        self.check_insertion(parent, index, node.class)?;
+
+        let key = self.add(node, Some(parent));
+        self.entry_mut(parent).children.insert(index, key);
+        Ok(key)
+    }
+
+    /// Removes a node from the collection or optional value that contains it, and returns it.
+    pub fn remove(&mut self, key: NodeKey) -> Result<Node> {
+        let parent = self.check_removal(key)?;
+        let node = self.to_node(key).ok_or_else(|| unknown(key))?;
+
+        self.entry_mut(parent).children.retain(|&c| c != key);
+        self.free(key);
+        Ok(node)
+    }
+
+    /// Moves a node (that is in a collection or optional value) to position `index` of another
+    /// collection or optional value.
+    ///
+    /// The node and all of its descendants keep their keys.  The `index` is the position after
+    /// the node was removed from its old place.
+    pub fn move_node(&mut self, key: NodeKey, parent: NodeKey, index: usize) -> Result<()> {
+        let old_parent = self.check_removal(key)?;
+
+        let mut ancestor = Some(parent);
+        while let Some(a) = ancestor {
+            if a == key {
+                return Err(Error::new("a node can't be moved into itself"));
+            }
+            ancestor = self.parent(a);
+        }
+
+        let class = self.class(key).ok_or_else(|| unknown(key))?;
+        let position = self
+            .children(old_parent)
+            .iter()
+            .position(|&c| c == key)
+            .expect("a node is missing from the children of its parent");
+        self.entry_mut(old_parent).children.remove(position);
+
+        if let Err(e) = self.check_insertion(parent, index, class) {
+            self.entry_mut(old_parent).children.insert(position, key);
+            return Err(e);
+        }
+
+        self.entry_mut(parent).children.insert(index, key);
+        self.entry_mut(key).parent = Some(parent);
+        Ok(())
+    }
+
+    fn entry(&self, key: NodeKey) -> Option<&Entry> {
+        self.entries.get(&key)
+    }
+
+    fn entry_mut(&mut self, key: NodeKey) -> &mut Entry {
+        self.entries
+            .get_mut(&key)
+            .expect("a node is missing from the store")
+    }
+
+    /// Adds a subtree to the arena, and returns the key of its root.
+    fn add(&mut self, node: Node, parent: Option<NodeKey>) -> NodeKey {
+        let key = NodeKey(self.next_key);
+        self.next_key += 1;
+        let (shell, children) = node.split();
+        self.entries.insert(
+            key,
+            Entry {
+                node: shell,
+                parent,
+                children: Vec::new(),
+            },
+        );
+
+        let children = children
+            .into_iter()
+            .map(|child| self.add(child, Some(key)))
+            .collect();
+        self.entry_mut(key).children = children;
+        key
+    }
+
+    /// Removes a subtree from the arena.
+    fn free(&mut self, key: NodeKey) {
+        if let Some(entry) = self.entries.remove(&key) {
+            for child in entry.children {
+                self.free(child);
+            }
+        }
+    }
+
+    fn check_insertion(
+        &self,
+        parent: NodeKey,
+        index: usize,
+        class: &'static Class<'static>,
+    ) -> Result<()> {
+        let entry = self.entry(parent).ok_or_else(|| unknown(parent))?;
+        let item = match entry.node.class.structure {
+            Structure::Collection { item } if index <= entry.children.len() => item,
+            Structure::Optional { item } if index == 0 && entry.children.is_empty() => item,
+            _ => {
+                return Err(Error::new(
+                    "nodes can only be inserted into collections and missing optional values",
+                ))
+            }
+        };
+
+        if item.id == class.id {
+            Ok(())
+        } else {
+            Err(Error::new("the node has a different class than the items of its new parent"))
+        }
+    }
+
+    /// Checks that a node can be taken out of its parent, and returns the parent.
+    fn check_removal(&self, key: NodeKey) -> Result<NodeKey> {
+        self.entry(key).ok_or_else(|| unknown(key))?;
+        let parent = self
+            .parent(key)
+            .ok_or_else(|| Error::new("the root can't be removed"))?;
+
+        match self.class(parent).map(|c| c.structure) {
+            Some(Structure::Collection { .. }) | Some(Structure::Optional { .. }) => Ok(parent),
+            _ => Err(Error::new(
+                "only items of collections and optional values can be removed",
+            )),
+        }
+    }
+}
+
+impl fmt::Display for NodeKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "#{}", self.0)
+    }
+}
+
+fn unknown(key: NodeKey) -> Error {
+    Error::new(format!("there is no node {}", key))
+}
@@ -0,0 +1,193 @@
+//! Generation of TypeScript declarations for the semantic JSON format.
+//!
+//! Every record, unit, union and enumeration becomes a named type, so that frontend code can be
+//! type-checked against the same JSON that `json::to_json` produces:
+//!
+//!   - Records and units become interfaces with a string literal `type` tag, so unions of them
+//!     are discriminated unions.
+//!   - Unions become union types, where the variants that aren't newtypes are object literal
+//!     types with their own tag.
+//!   - Enumerations become unions of string literals.
+//!   - Collections become arrays, and optional values are `T | null`.
+use std::any;
+use std::collections;
+use std::fmt::Write;
+
+use json;
+use Class;
+use Field;
+use PrimitiveKind;
+use Registry;
+use Structure;
+use VariantKind;
+
+/// Generates a TypeScript declaration file for every class in `registry`.
+pub fn declarations(registry: &Registry) -> String {
+    let names = registry.type_names();
+    let mut output = String::from(
+        "// This file is generated from semantic classes.  Do not edit it by hand, since it is\n\
+         // overwritten on every build.\n",
+    );
+
+    for class in registry.classes() {
+        let name = match names.get(&class.id) {
+            Some(name) => name,
+            None => continue,
+        };
+
+        output.push('\n');
+        output.push_str(&doc_comment(class.doc, ""));
+        match class.structure {
+            Structure::Unit { .. } | Structure::Record { .. } => {
+                let fields = match class.structure {
+                    Structure::Record { fields, .. } => fields,
+                    _ => &[],
+                };
+                let mut object = object_type(registry, &names, json::type_tag(class), fields, "");
+                if let Some(version) = class.version {
+                    // Documents without a version are of version 1
+                    let optional = if version > 1 { "" } else { "?" };
+                    let key = format!("  {}{}: {};\n", json::VERSION_KEY, optional, version);
+                    let end = object.len() - 1;
+                    object.insert_str(end, &key);
+                }
+                writeln!(output, "export interface {} {}", name, object).unwrap();
+            }
+            Structure::Union { variants, .. } => {
+                writeln!(output, "export type {} =", name).unwrap();
+                for variant in variants {
+                    let ty = match variant.kind {
+                        VariantKind::Newtype { ty } => type_expression(registry, &names, ty),
+                        VariantKind::Unit => object_type(
+                            registry,
+                            &names,
+                            Some(json::camel_case(variant.name)),
+                            &[],
+                            "  ",
+                        ),
+                        VariantKind::Record { fields } => object_type(
+                            registry,
+                            &names,
+                            Some(json::camel_case(variant.name)),
+                            fields,
+                            "  ",
+                        ),
+                    };
+                    writeln!(output, "  | {}", ty).unwrap();
+                }
+                // Terminate the type alias after the last variant
+                output.pop();
+                output.push_str(";\n");
+            }
+            Structure::Enumeration { variants, .. } => {
+                let literals = variants
+                    .iter()
+                    .map(|v| format!("{:?}", json::camel_case(v.name)))
+                    .collect::<Vec<_>>();
+                writeln!(output, "export type {} = {};", name, literals.join(" | ")).unwrap();
+            }
+            Structure::Primitive { .. }
+            | Structure::Collection { .. }
+            | Structure::Optional { .. } => {}
+        }
+    }
+
+    output
+}
+
+/// An object literal type with the specified `type` tag and fields, indented by `indent`.
+fn object_type(
+    registry: &Registry,
+    names: &collections::HashMap<any::TypeId, String>,
+    tag: Option<String>,
+    fields: &[Field],
+    indent: &str,
+) -> String {
+    let mut result = String::from("{\n");
+
+    if let Some(tag) = tag {
+        writeln!(result, "{}  {}: {:?};", indent, json::TYPE_KEY, tag).unwrap();
+    }
+    for field in fields {
+        let name = json::camel_case(field.name);
+        let is_identifier = name
+            .chars()
+            .next()
+            .map_or(false, |c| c.is_alphabetic() || c == '_' || c == '$');
+        let name = if is_identifier {
+            name
+        } else {
+            format!("{:?}", name)
+        };
+
+        result.push_str(&doc_comment(field.doc, &format!("{}  ", indent)));
+        writeln!(
+            result,
+            "{}  {}: {};",
+            indent,
+            name,
+            type_expression(registry, names, field.ty)
+        ).unwrap();
+    }
+
+    result.push_str(indent);
+    result.push('}');
+    result
+}
+
+/// The TypeScript type of the JSON representation of the class with the specified id.
+fn type_expression(
+    registry: &Registry,
+    names: &collections::HashMap<any::TypeId, String>,
+    id: any::TypeId,
+) -> String {
+    if let Some(name) = names.get(&id) {
+        return name.clone();
+    }
+
+    let class: &Class = match registry.get(id) {
+        Some(class) => class,
+        None => return "unknown".to_owned(),
+    };
+    match class.structure {
+        Structure::Primitive { kind } => match kind {
+            PrimitiveKind::Bool => "boolean",
+            PrimitiveKind::String => "string",
+            _ => "number",
+        }.to_owned(),
+        Structure::Collection { item } => {
+            let item = type_expression(registry, names, item.id);
+            if item.contains(' ') {
+                format!("Array<{}>", item)
+            } else {
+                format!("{}[]", item)
+            }
+        }
+        Structure::Optional { item } => {
+            format!("{} | null", type_expression(registry, names, item.id))
+        }
+        Structure::Unit { .. }
+        | Structure::Record { .. }
+        | Structure::Union { .. }
+        | Structure::Enumeration { .. } => "unknown".to_owned(),
+    }
+}
+
+/// A JSDoc comment with the specified text, indented by `indent`.
+fn doc_comment(doc: Option<&str>, indent: &str) -> String {
+    let doc = match doc {
+        Some(doc) => doc.replace("*/", "*\\/"),
+        None => return String::new(),
+    };
+
+    let mut result = format!("{}/**\n", indent);
+    for line in doc.lines() {
+        if line.is_empty() {
+            writeln!(result, "{} *", indent).unwrap();
+        } else {
+            writeln!(result, "{} * {}", indent, line).unwrap();
+        }
+    }
+    writeln!(result, "{} */", indent).unwrap();
+    result
+}
@@ -0,0 +1,300 @@
+use std::any;
+use std::fmt;
+use std::ops;
+
+use Class;
+use Constraint;
+use DynamicSemantic;
+use Field;
+use NodePath;
+use Primitive;
+use Role;
+use Structure;
+use Value;
+use VariantKind;
+
+/// A way in which a semantic value doesn't conform to its class.
+#[derive(Debug, PartialEq)]
+pub struct Violation {
+    /// The path of the offending node.
+    pub path: NodePath,
+    pub kind: ViolationKind,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ViolationKind {
+    /// A numeric field is outside of the range allowed by a `Constraint::Range`.
+    OutOfRange {
+        field: &'static str,
+        value: f64,
+        start: ops::Bound<f64>,
+        end: ops::Bound<f64>,
+    },
+    /// A field is empty despite having a `Constraint::NonEmpty`.
+    Empty { field: &'static str },
+    /// A node has a child with a role that it can't contain, like a block inside an inline.
+    RoleMismatch { parent: Role, child: Role },
+    /// A node lacks a field that its class requires.
+    MissingField { field: &'static str },
+    /// A node is of a different class than the one allowed at its position.
+    DisallowedClass {
+        expected: any::TypeId,
+        actual: any::TypeId,
+    },
+    /// A node refers to a variant that its class doesn't have.
+    UnknownVariant { index: usize },
+}
+
+/// Checks that `value` and all of its descendants conform to their classes, and reports every
+/// violation that was found.
+pub fn validate(value: &DynamicSemantic) -> Vec<Violation> {
+    let mut violations = Vec::new();
+    validate_node(value, &NodePath::root(), &mut violations);
+    violations
+}
+
+fn validate_node(value: &DynamicSemantic, path: &NodePath, violations: &mut Vec<Violation>) {
+    let class = value.class();
+
+    match (value.reflect(), &class.structure) {
+        (Value::Record { fields }, &Structure::Record { fields: ref metas, .. }) => {
+            validate_fields(class, metas, &fields, path, violations);
+        }
+        (Value::Union { variant, fields }, &Structure::Union { variants, .. }) => {
+            match variants.get(variant).map(|v| &v.kind) {
+                Some(&VariantKind::Unit) => {}
+                Some(&VariantKind::Newtype { ty }) => match fields.first() {
+                    Some(field) => {
+                        let path = path.child(0);
+                        validate_class(ty, *field, &path, violations);
+                        validate_node(*field, &path, violations);
+                    }
+                    None => violations.push(Violation {
+                        path: path.clone(),
+                        kind: ViolationKind::MissingField { field: "0" },
+                    }),
+                },
+                Some(&VariantKind::Record { fields: metas }) => {
+                    validate_fields(class, metas, &fields, path, violations);
+                }
+                None => violations.push(Violation {
+                    path: path.clone(),
+                    kind: ViolationKind::UnknownVariant { index: variant },
+                }),
+            }
+        }
+        (Value::Enumeration { variant }, &Structure::Enumeration { variants, .. }) => {
+            if variant >= variants.len() {
+                violations.push(Violation {
+                    path: path.clone(),
+                    kind: ViolationKind::UnknownVariant { index: variant },
+                });
+            }
+        }
+        (Value::Collection { items }, &Structure::Collection { item }) => {
+            for (i, child) in items.into_iter().enumerate() {
+                let path = path.child(i);
+                validate_class(item.id, child, &path, violations);
+                validate_node(child, &path, violations);
+            }
+        }
+        (Value::Optional { item: Some(child) }, &Structure::Optional { item }) => {
+            let path = path.child(0);
+            validate_class(item.id, child, &path, violations);
+            validate_node(child, &path, violations);
+        }
+        _ => {}
+    }
+}
+
+fn validate_fields(
+    class: &Class,
+    metas: &'static [Field<'static>],
+    fields: &[&DynamicSemantic],
+    path: &NodePath,
+    violations: &mut Vec<Violation>,
+) {
+    for (i, meta) in metas.iter().enumerate() {
+        match fields.get(i) {
+            Some(field) => {
+                let path = path.child(i);
+                validate_class(meta.ty, *field, &path, violations);
+                validate_constraints(meta, *field, &path, violations);
+                if meta.is_children {
+                    validate_roles(class.role, *field, &path, violations);
+                }
+                validate_node(*field, &path, violations);
+            }
+            None => violations.push(Violation {
+                path: path.clone(),
+                kind: ViolationKind::MissingField { field: meta.name },
+            }),
+        }
+    }
+}
+
+fn validate_class(
+    expected: any::TypeId,
+    value: &DynamicSemantic,
+    path: &NodePath,
+    violations: &mut Vec<Violation>,
+) {
+    let actual = value.class().id;
+    if actual != expected {
+        violations.push(Violation {
+            path: path.clone(),
+            kind: ViolationKind::DisallowedClass { expected, actual },
+        });
+    }
+}
+
+fn validate_constraints(
+    meta: &Field<'static>,
+    value: &DynamicSemantic,
+    path: &NodePath,
+    violations: &mut Vec<Violation>,
+) {
+    for constraint in meta.constraints {
+        match *constraint {
+            Constraint::Range { start, end } => {
+                if let Some(v) = numeric_value(value) {
+                    if !contains(start, end, v) {
+                        violations.push(Violation {
+                            path: path.clone(),
+                            kind: ViolationKind::OutOfRange {
+                                field: meta.name,
+                                value: v,
+                                start,
+                                end,
+                            },
+                        });
+                    }
+                }
+            }
+            Constraint::NonEmpty => {
+                if is_empty(value) {
+                    violations.push(Violation {
+                        path: path.clone(),
+                        kind: ViolationKind::Empty { field: meta.name },
+                    });
+                }
+            }
+        }
+    }
+}
+
+/// Checks the roles of the nodes contained in a children field of a node with the given role.
+fn validate_roles(
+    parent: Role,
+    children: &DynamicSemantic,
+    path: &NodePath,
+    violations: &mut Vec<Violation>,
+) {
+    match children.reflect() {
+        Value::Collection { items } => {
+            for (i, child) in items.into_iter().enumerate() {
+                validate_role(parent, child, &path.child(i), violations);
+            }
+        }
+        Value::Optional { item } => {
+            if let Some(child) = item {
+                validate_role(parent, child, &path.child(0), violations);
+            }
+        }
+        // A single child that isn't wrapped in a collection
+        _ => validate_role(parent, children, path, violations),
+    }
+}
+
+fn validate_role(
+    parent: Role,
+    child: &DynamicSemantic,
+    path: &NodePath,
+    violations: &mut Vec<Violation>,
+) {
+    let child = child.class().role;
+    let allowed = match (parent, child) {
+        (_, Role::Root) | (_, Role::Document) | (_, Role::Attribute) => false,
+        (Role::Inline, Role::Block) => false,
+        _ => true,
+    };
+
+    if !allowed {
+        violations.push(Violation {
+            path: path.clone(),
+            kind: ViolationKind::RoleMismatch { parent, child },
+        });
+    }
+}
+
+fn numeric_value(value: &DynamicSemantic) -> Option<f64> {
+    match value.reflect() {
+        Value::Primitive(p) => p.as_f64(),
+        Value::Optional { item: Some(item) } => numeric_value(item),
+        _ => None,
+    }
+}
+
+fn is_empty(value: &DynamicSemantic) -> bool {
+    match value.reflect() {
+        Value::Primitive(Primitive::String(s)) => s.is_empty(),
+        Value::Collection { items } => items.is_empty(),
+        Value::Optional { item: None } => true,
+        Value::Optional { item: Some(item) } => is_empty(item),
+        _ => false,
+    }
+}
+
+fn contains(start: ops::Bound<f64>, end: ops::Bound<f64>, value: f64) -> bool {
+    let after_start = match start {
+        ops::Bound::Included(s) => value >= s,
+        ops::Bound::Excluded(s) => value > s,
+        ops::Bound::Unbounded => true,
+    };
+    let before_end = match end {
+        ops::Bound::Included(e) => value <= e,
+        ops::Bound::Excluded(e) => value < e,
+        ops::Bound::Unbounded => true,
+    };
+    after_start && before_end
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.path, self.kind)
+    }
+}
+
+impl fmt::Display for ViolationKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ViolationKind::OutOfRange {
+                field,
+                value,
+                start,
+                end,
+            } => write!(
+                f,
+                "the value {} of field {:?} is outside of the range {:?}..{:?}",
+                value, field, start, end
+            ),
+            ViolationKind::Empty { field } => write!(f, "the field {:?} must not be empty", field),
+            ViolationKind::RoleMismatch { parent, child } => write!(
+                f,
+                "a node with role {:?} can't contain a child with role {:?}",
+                parent, child
+            ),
+            ViolationKind::MissingField { field } => {
+                write!(f, "the required field {:?} is missing", field)
+            }
+            ViolationKind::DisallowedClass { expected, actual } => write!(
+                f,
+                "a node of class {:?} is not allowed here, expected class {:?}",
+                actual, expected
+            ),
+            ViolationKind::UnknownVariant { index } => {
+                write!(f, "there is no variant with index {}", index)
+            }
+        }
+    }
+}
@@ -0,0 +1,140 @@
+use std::fmt;
+
+use DynamicSemantic;
+
+/// A reflected view of the contents of a semantic value.
+///
+/// The shape of a value mirrors the `Structure` of its class.
+#[derive(Debug)]
+pub enum Value<'a> {
+    Primitive(Primitive<'a>),
+    Unit,
+    Enumeration {
+        /// The index of the active variant.
+        variant: usize,
+    },
+    Record {
+        fields: Vec<&'a DynamicSemantic>,
+    },
+    Union {
+        /// The index of the active variant.
+        variant: usize,
+        /// The fields of the active variant; a newtype variant has exactly one field.
+        fields: Vec<&'a DynamicSemantic>,
+    },
+    Collection {
+        items: Vec<&'a DynamicSemantic>,
+    },
+    Optional {
+        item: Option<&'a DynamicSemantic>,
+    },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Primitive<'a> {
+    Bool(bool),
+    U8(u8),
+    I8(i8),
+    U16(u16),
+    I16(i16),
+    U32(u32),
+    I32(i32),
+    F32(f32),
+    U64(u64),
+    I64(i64),
+    F64(f64),
+    String(&'a str),
+}
+
+/// The type of a primitive value.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum PrimitiveKind {
+    Bool,
+    U8,
+    I8,
+    U16,
+    I16,
+    U32,
+    I32,
+    F32,
+    U64,
+    I64,
+    F64,
+    String,
+}
+
+/// Erases the type of a semantic value.
+pub fn dynamic<A>(value: &A) -> &DynamicSemantic
+where
+    A: DynamicSemantic,
+{
+    value
+}
+
+impl<'a> Value<'a> {
+    /// The nested values of this value, in the order that `NodePath`s address them.
+    pub fn children(&self) -> Vec<&'a DynamicSemantic> {
+        match *self {
+            Value::Primitive(_) | Value::Unit | Value::Enumeration { .. } => Vec::new(),
+            Value::Record { ref fields } | Value::Union { ref fields, .. } => fields.clone(),
+            Value::Collection { ref items } => items.clone(),
+            Value::Optional { item } => item.into_iter().collect(),
+        }
+    }
+}
+
+impl<'a> Primitive<'a> {
+    pub fn kind(&self) -> PrimitiveKind {
+        match *self {
+            Primitive::Bool(_) => PrimitiveKind::Bool,
+            Primitive::U8(_) => PrimitiveKind::U8,
+            Primitive::I8(_) => PrimitiveKind::I8,
+            Primitive::U16(_) => PrimitiveKind::U16,
+            Primitive::I16(_) => PrimitiveKind::I16,
+            Primitive::U32(_) => PrimitiveKind::U32,
+            Primitive::I32(_) => PrimitiveKind::I32,
+            Primitive::F32(_) => PrimitiveKind::F32,
+            Primitive::U64(_) => PrimitiveKind::U64,
+            Primitive::I64(_) => PrimitiveKind::I64,
+            Primitive::F64(_) => PrimitiveKind::F64,
+            Primitive::String(_) => PrimitiveKind::String,
+        }
+    }
+
+    /// The numeric value of this primitive, if it is a number.
+    pub fn as_f64(&self) -> Option<f64> {
+        match *self {
+            Primitive::Bool(_) | Primitive::String(_) => None,
+            Primitive::U8(v) => Some(f64::from(v)),
+            Primitive::I8(v) => Some(f64::from(v)),
+            Primitive::U16(v) => Some(f64::from(v)),
+            Primitive::I16(v) => Some(f64::from(v)),
+            Primitive::U32(v) => Some(f64::from(v)),
+            Primitive::I32(v) => Some(f64::from(v)),
+            Primitive::F32(v) => Some(f64::from(v)),
+            // These may lose precision, but are still good enough for checking constraints
+            Primitive::U64(v) => Some(v as f64),
+            Primitive::I64(v) => Some(v as f64),
+            Primitive::F64(v) => Some(v),
+        }
+    }
+}
+
+impl<'a> fmt::Display for Primitive<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Primitive::Bool(v) => v.fmt(f),
+            Primitive::U8(v) => v.fmt(f),
+            Primitive::I8(v) => v.fmt(f),
+            Primitive::U16(v) => v.fmt(f),
+            Primitive::I16(v) => v.fmt(f),
+            Primitive::U32(v) => v.fmt(f),
+            Primitive::I32(v) => v.fmt(f),
+            Primitive::F32(v) => v.fmt(f),
+            Primitive::U64(v) => v.fmt(f),
+            Primitive::I64(v) => v.fmt(f),
+            Primitive::F64(v) => v.fmt(f),
+            Primitive::String(v) => write!(f, "{:?}", v),
+        }
+    }
+}
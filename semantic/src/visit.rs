@@ -0,0 +1,169 @@
+//! Statically typed traversal of semantic values.
+//!
+//! `#[derive(Semantic)]` implements `Walk` for every type, which hands each direct child of a
+//! value to a `Visit`, `VisitMut` or `Folder`.  These are generic over the type of the child, so
+//! they can pick the right code for every type of node.
+//!
+//! Usually, these traits are not used directly, but through the `Visitor`, `VisitorMut` and `Fold`
+//! traits that `#[semantic(visitors(...))]` generates for a format, which have a method per type of
+//! node (like `visit_heading` and `fold_link`).  They are put in a module named after the format,
+//! like `markdown_visitors` for `Markdown`.
+use std::any;
+
+/// A value whose children can be traversed.
+pub trait Walk: any::Any {
+    /// Visits the direct children of this value.
+    fn walk<V>(&self, visitor: &mut V)
+    where
+        V: Visit + ?Sized;
+
+    /// Visits the direct children of this value, allowing them to be modified in place.
+    fn walk_mut<V>(&mut self, visitor: &mut V)
+    where
+        V: VisitMut + ?Sized;
+
+    /// Rebuilds this value from its folded direct children.
+    fn fold_children<F>(self, folder: &mut F) -> Self
+    where
+        F: Folder + ?Sized,
+        Self: Sized;
+}
+
+/// Visits values by shared reference.
+pub trait Visit {
+    fn visit<A>(&mut self, value: &A)
+    where
+        A: Walk;
+}
+
+/// Visits values by mutable reference.
+pub trait VisitMut {
+    fn visit_mut<A>(&mut self, value: &mut A)
+    where
+        A: Walk;
+}
+
+/// Transforms owned values into new values of the same type.
+pub trait Folder {
+    fn fold<A>(&mut self, value: A) -> A
+    where
+        A: Walk;
+}
+
+/// Converts a value into the same value with a different type parameter.
+///
+/// This is used by the generated `Fold` traits, after checking that the types are the same.
+///
+/// # Panics
+///
+/// If `A` and `B` are different types.
+#[doc(hidden)]
+pub fn cast<A, B>(value: A) -> B
+where
+    A: any::Any,
+    B: any::Any,
+{
+    let mut value = Some(value);
+    let result = (&mut value as &mut any::Any)
+        .downcast_mut::<Option<B>>()
+        .and_then(Option::take);
+    result.expect("cast between different types")
+}
+
+macro_rules! walk_leaf {
+    ($t:ty) => {
+        impl Walk for $t {
+            fn walk<V>(&self, _visitor: &mut V)
+            where
+                V: Visit + ?Sized,
+            {
+            }
+
+            fn walk_mut<V>(&mut self, _visitor: &mut V)
+            where
+                V: VisitMut + ?Sized,
+            {
+            }
+
+            fn fold_children<F>(self, _folder: &mut F) -> Self
+            where
+                F: Folder + ?Sized,
+            {
+                self
+            }
+        }
+    };
+}
+
+walk_leaf!(bool);
+walk_leaf!(u8);
+walk_leaf!(i8);
+walk_leaf!(u16);
+walk_leaf!(i16);
+walk_leaf!(u32);
+walk_leaf!(i32);
+walk_leaf!(f32);
+walk_leaf!(u64);
+walk_leaf!(i64);
+walk_leaf!(f64);
+walk_leaf!(String);
+
+impl<A> Walk for Vec<A>
+where
+    A: Walk,
+{
+    fn walk<V>(&self, visitor: &mut V)
+    where
+        V: Visit + ?Sized,
+    {
+        for item in self {
+            visitor.visit(item);
+        }
+    }
+
+    fn walk_mut<V>(&mut self, visitor: &mut V)
+    where
+        V: VisitMut + ?Sized,
+    {
+        for item in self {
+            visitor.visit_mut(item);
+        }
+    }
+
+    fn fold_children<F>(self, folder: &mut F) -> Self
+    where
+        F: Folder + ?Sized,
+    {
+        self.into_iter().map(|item| folder.fold(item)).collect()
+    }
+}
+
+impl<A> Walk for Option<A>
+where
+    A: Walk,
+{
+    fn walk<V>(&self, visitor: &mut V)
+    where
+        V: Visit + ?Sized,
+    {
+        if let Some(ref item) = *self {
+            visitor.visit(item);
+        }
+    }
+
+    fn walk_mut<V>(&mut self, visitor: &mut V)
+    where
+        V: VisitMut + ?Sized,
+    {
+        if let Some(ref mut item) = *self {
+            visitor.visit_mut(item);
+        }
+    }
+
+    fn fold_children<F>(self, folder: &mut F) -> Self
+    where
+        F: Folder + ?Sized,
+    {
+        self.map(|item| folder.fold(item))
+    }
+}
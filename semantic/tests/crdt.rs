@@ -0,0 +1,435 @@
+#![feature(const_type_id)]
+
+#[macro_use]
+extern crate proptest;
+extern crate semantic;
+#[macro_use]
+extern crate semantic_derive;
+extern crate serde_json;
+
+use proptest::prelude::*;
+use semantic::Document;
+use semantic::Node;
+use semantic::NodePath;
+use semantic::Operation;
+use semantic::Registry;
+use semantic::Slot;
+
+#[derive(Debug, Semantic)]
+#[semantic(role = "document")]
+struct Page {
+    title: String,
+    blocks: Vec<Block>,
+    note: Option<String>,
+}
+
+#[derive(Debug, Semantic)]
+#[semantic(role = "block")]
+enum Block {
+    Paragraph(Paragraph),
+    Rule,
+}
+
+#[derive(Debug, Semantic)]
+#[semantic(role = "block")]
+struct Paragraph {
+    words: Vec<String>,
+}
+
+const REPLICAS: usize = 3;
+
+/// A random edit, made by `replica` at the positions `a` and `b` (wrapped around to fit).
+#[derive(Clone, Debug)]
+struct Action {
+    replica: usize,
+    kind: u8,
+    a: usize,
+    b: usize,
+    text: String,
+}
+
+fn action() -> impl Strategy<Value = Action> {
+    (0..REPLICAS, 0..10u8, 0..8usize, 0..8usize, "[a-c]{1,3}").prop_map(
+        |(replica, kind, a, b, text)| Action {
+            replica,
+            kind,
+            a,
+            b,
+            text,
+        },
+    )
+}
+
+fn initial() -> Page {
+    Page {
+        title: "title".to_owned(),
+        blocks: vec![
+            Block::Paragraph(Paragraph {
+                words: vec!["hello".to_owned(), "world".to_owned()],
+            }),
+            Block::Rule,
+        ],
+        note: None,
+    }
+}
+
+fn path(steps: &[usize]) -> NodePath {
+    NodePath::from_steps(steps.to_vec())
+}
+
+fn string(text: &str) -> Node {
+    Node::from_value(&text.to_owned())
+}
+
+/// The number of children of the node at `path`, if there is one.
+fn len(document: &Document, steps: &[usize]) -> Option<usize> {
+    let node = document.to_node();
+    let mut node = &node;
+    for &step in steps {
+        node = node.children().into_iter().nth(step)?;
+    }
+    Some(node.children().len())
+}
+
+/// The index of a random paragraph, if there are any.
+fn paragraph(document: &Document, a: usize) -> Option<usize> {
+    let page = document.to_value::<Page>().unwrap();
+    let paragraphs = page
+        .blocks
+        .iter()
+        .enumerate()
+        .filter(|&(_, b)| match *b {
+            Block::Paragraph(_) => true,
+            Block::Rule => false,
+        }).map(|(i, _)| i)
+        .collect::<Vec<_>>();
+
+    if paragraphs.is_empty() {
+        None
+    } else {
+        Some(paragraphs[a % paragraphs.len()])
+    }
+}
+
+/// Inserts `node` at position `index` of the collection at `steps`.
+fn insert(document: &mut Document, steps: &[usize], index: usize, node: Node) {
+    let parent = document.node_id(&path(steps)).unwrap();
+    let after = if index == 0 {
+        None
+    } else {
+        document.element_id(parent, index - 1)
+    };
+    document.insert(parent, after, node).unwrap();
+}
+
+/// Deletes the element at position `index` of the collection at `steps`.
+fn delete(document: &mut Document, steps: &[usize], index: usize) {
+    let parent = document.node_id(&path(steps)).unwrap();
+    let element = document.element_id(parent, index).unwrap();
+    document.delete(parent, element).unwrap();
+}
+
+fn perform(document: &mut Document, action: &Action) {
+    let blocks = len(document, &[1]).unwrap();
+
+    match action.kind {
+        0 => {
+            let slot = document.slot(&path(&[0])).unwrap();
+            document.set(slot, Some(string(&action.text))).unwrap();
+        }
+        1 => {
+            let paragraph = Block::Paragraph(Paragraph {
+                words: vec![action.text.clone()],
+            });
+            insert(
+                document,
+                &[1],
+                action.a % (blocks + 1),
+                Node::from_value(&paragraph),
+            );
+        }
+        2 => insert(
+            document,
+            &[1],
+            action.a % (blocks + 1),
+            Node::from_value(&Block::Rule),
+        ),
+        3 if blocks > 0 => delete(document, &[1], action.a % blocks),
+        4 => {
+            if let Some(p) = paragraph(document, action.a) {
+                let words = len(document, &[1, p, 0, 0]).unwrap();
+                let index = action.b % (words + 1);
+                insert(document, &[1, p, 0, 0], index, string(&action.text));
+            }
+        }
+        5 => {
+            if let Some(p) = paragraph(document, action.a) {
+                let words = len(document, &[1, p, 0, 0]).unwrap();
+                if words > 0 {
+                    delete(document, &[1, p, 0, 0], action.b % words);
+                }
+            }
+        }
+        6 => {
+            let slot = document.slot(&path(&[2, 0])).unwrap();
+            document.set(slot, Some(string(&action.text))).unwrap();
+        }
+        7 => {
+            let slot = document.slot(&path(&[2, 0])).unwrap();
+            document.set(slot, None).unwrap();
+        }
+        8 => {
+            if let Some(p) = paragraph(document, action.a) {
+                let words = len(document, &[1, p, 0, 0]).unwrap();
+                if words > 0 {
+                    let slot = document
+                        .slot(&path(&[1, p, 0, 0, action.b % words]))
+                        .unwrap();
+                    document.set(slot, Some(string(&action.text))).unwrap();
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Shuffles `operations` with a simple linear congruential generator.
+fn shuffle(operations: &mut [Operation], mut seed: u64) {
+    for i in (1..operations.len()).rev() {
+        seed = seed
+            .wrapping_mul(6_364_136_223_846_793_005)
+            .wrapping_add(1_442_695_040_888_963_407);
+        operations.swap(i, (seed >> 33) as usize % (i + 1));
+    }
+}
+
+fn replicas() -> Vec<Document> {
+    (0..REPLICAS)
+        .map(|r| Document::new(r as u64 + 1, &initial()))
+        .collect()
+}
+
+proptest! {
+    #[test]
+    fn replicas_converge(
+        actions in prop::collection::vec(action(), 0..40),
+        syncs in prop::collection::vec((0..REPLICAS, 0..REPLICAS), 0..10),
+        seed in any::<u64>(),
+    ) {
+        let mut documents = replicas();
+
+        // Interleave the edits with some partial synchronization, so that later edits build on
+        // edits from other replicas
+        for (k, action) in actions.iter().enumerate() {
+            perform(&mut documents[action.replica], action);
+
+            if let Some(&(from, to)) = syncs.get(k) {
+                let operations = documents[from].operations().to_vec();
+                for operation in operations {
+                    documents[to].apply(operation);
+                }
+            }
+        }
+
+        let mut all = documents
+            .iter()
+            .flat_map(|d| d.operations().to_vec())
+            .collect::<Vec<_>>();
+
+        for (r, document) in documents.iter_mut().enumerate() {
+            shuffle(&mut all, seed.wrapping_add(r as u64));
+            for operation in all.clone() {
+                document.apply(operation);
+            }
+        }
+
+        let expected = documents[0].to_node();
+        for document in &documents[1..] {
+            prop_assert_eq!(&document.to_node(), &expected);
+        }
+        prop_assert!(documents[0].to_value::<Page>().is_ok());
+    }
+
+    #[test]
+    fn fresh_replica_catches_up(
+        actions in prop::collection::vec(action(), 0..20),
+        seed in any::<u64>(),
+    ) {
+        let mut document = Document::new(1, &initial());
+        for action in &actions {
+            perform(&mut document, action);
+        }
+
+        let mut operations = document.operations().to_vec();
+        shuffle(&mut operations, seed);
+
+        let mut fresh = Document::new(2, &initial());
+        for operation in operations {
+            fresh.apply(operation);
+        }
+
+        prop_assert_eq!(fresh.to_node(), document.to_node());
+    }
+
+    #[test]
+    fn operations_round_trip_through_json(actions in prop::collection::vec(action(), 0..20)) {
+        let registry = Registry::of::<Page>();
+        let mut document = Document::new(1, &initial());
+        for action in &actions {
+            perform(&mut document, action);
+        }
+
+        for operation in document.operations() {
+            let json = operation.to_json(&registry).unwrap();
+            prop_assert_eq!(&Operation::from_json(&registry, &json).unwrap(), operation);
+        }
+    }
+
+    #[test]
+    fn updates_reach_the_new_value(actions in prop::collection::vec(action(), 0..20)) {
+        let mut edited = Document::new(1, &initial());
+        for action in &actions {
+            perform(&mut edited, action);
+        }
+        let value = edited.to_node();
+
+        let mut document = Document::new(2, &initial());
+        let operations = document.update(&value).unwrap();
+        prop_assert_eq!(&document.to_node(), &value);
+
+        let mut fresh = Document::new(3, &initial());
+        for operation in operations {
+            fresh.apply(operation);
+        }
+        prop_assert_eq!(&fresh.to_node(), &value);
+    }
+}
+
+#[test]
+fn concurrent_inserts_keep_both() {
+    let mut documents = replicas();
+    let blocks = documents[0].node_id(&path(&[1])).unwrap();
+    let first = documents[0].element_id(blocks, 0).unwrap();
+
+    let a = documents[0]
+        .insert(blocks, Some(first), Node::from_value(&Block::Rule))
+        .unwrap();
+    let b = documents[1]
+        .insert(
+            blocks,
+            Some(first),
+            Node::from_value(&Block::Paragraph(Paragraph { words: Vec::new() })),
+        ).unwrap();
+
+    documents[0].apply(b.clone());
+    documents[1].apply(a.clone());
+
+    assert_eq!(documents[0].to_node(), documents[1].to_node());
+    assert_eq!(documents[0].to_value::<Page>().unwrap().blocks.len(), 4);
+}
+
+#[test]
+fn operations_wait_for_their_dependencies() {
+    let mut documents = replicas();
+    let blocks = documents[0].node_id(&path(&[1])).unwrap();
+
+    let insert = documents[0]
+        .insert(blocks, None, Node::from_value(&Block::Rule))
+        .unwrap();
+    let element = documents[0].element_id(blocks, 0).unwrap();
+    let delete = documents[0].delete(blocks, element).unwrap();
+
+    documents[1].apply(delete);
+    assert_eq!(documents[1].to_value::<Page>().unwrap().blocks.len(), 2);
+
+    documents[1].apply(insert);
+    assert_eq!(documents[1].to_node(), documents[0].to_node());
+}
+
+#[test]
+fn edits_with_the_wrong_class_are_rejected() {
+    let mut document = Document::new(1, &initial());
+    let slot = document.slot(&path(&[0])).unwrap();
+
+    assert!(document.set(slot, Some(Node::from_value(&Block::Rule))).is_err());
+    assert!(document.set(slot, None).is_err());
+    assert!(document.operations().is_empty());
+}
+
+#[test]
+fn later_writes_win() {
+    let mut documents = replicas();
+    let slot = documents[0].slot(&path(&[0])).unwrap();
+
+    let a = documents[0].set(slot, Some(string("first"))).unwrap();
+    documents[1].apply(a);
+    let b = documents[1].set(slot, Some(string("second"))).unwrap();
+    documents[0].apply(b);
+
+    for document in &documents[..2] {
+        assert_eq!(document.to_value::<Page>().unwrap().title, "second");
+    }
+
+    // Replacing the whole document also replaces everything that was edited inside of it
+    let reset = documents[2]
+        .set(Slot::Root, Some(Node::from_value(&initial())))
+        .unwrap();
+    documents[0].apply(reset);
+    assert_eq!(documents[0].to_value::<Page>().unwrap().title, "title");
+}
+
+#[test]
+fn updates_keep_concurrent_edits_of_unchanged_nodes() {
+    let mut documents = replicas();
+
+    let mut page = initial();
+    page.title = "renamed".to_owned();
+    page.blocks.push(Block::Rule);
+    let updates = documents[0].update(&page).unwrap();
+    assert_eq!(updates.len(), 2);
+
+    insert(&mut documents[1], &[1, 0, 0, 0], 2, string("again"));
+    for operation in documents[1].operations().to_vec() {
+        documents[0].apply(operation);
+    }
+    for operation in updates {
+        documents[1].apply(operation);
+    }
+
+    assert_eq!(documents[0].to_node(), documents[1].to_node());
+    let page = documents[0].to_value::<Page>().unwrap();
+    assert_eq!(page.title, "renamed");
+    assert_eq!(page.blocks.len(), 3);
+    match page.blocks[0] {
+        Block::Paragraph(ref p) => assert_eq!(p.words, vec!["hello", "world", "again"]),
+        Block::Rule => panic!("expected a paragraph"),
+    }
+}
+
+#[test]
+fn operations_in_json_refer_to_classes_by_name() {
+    let registry = Registry::of::<Page>();
+    let mut document = Document::new(1, &initial());
+    let blocks = document.node_id(&path(&[1])).unwrap();
+    let insert = document
+        .insert(blocks, None, Node::from_value(&Block::Rule))
+        .unwrap();
+    let note = document.slot(&path(&[2])).unwrap();
+    let clear = document.set(note, Some(Node::from_value(&None::<String>))).unwrap();
+
+    let json = insert.to_json(&registry).unwrap();
+    assert_eq!(json["type"], "insert");
+    assert_eq!(json["class"], "Block");
+    assert_eq!(json["after"], serde_json::Value::Null);
+    assert_eq!(Operation::from_json(&registry, &json).unwrap(), insert);
+
+    // A missing optional value is still a node
+    let json = clear.to_json(&registry).unwrap();
+    assert_eq!(json["class"], "string?");
+    assert_eq!(json["node"], serde_json::Value::Null);
+    assert_eq!(Operation::from_json(&registry, &json).unwrap(), clear);
+
+    let mut unknown = insert.to_json(&registry).unwrap();
+    unknown["class"] = "Chapter".into();
+    assert!(Operation::from_json(&registry, &unknown).is_err());
+}
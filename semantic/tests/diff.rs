@@ -0,0 +1,203 @@
+#![feature(const_type_id)]
+
+extern crate semantic;
+#[macro_use]
+extern crate semantic_derive;
+
+use semantic::Edit;
+use semantic::Node;
+use semantic::NodePath;
+
+#[derive(Clone, Debug, Semantic)]
+#[semantic(role = "document")]
+struct Page {
+    title: String,
+    blocks: Vec<Block>,
+    note: Option<String>,
+}
+
+#[derive(Clone, Debug, Semantic)]
+#[semantic(role = "block")]
+enum Block {
+    Paragraph(Paragraph),
+    Rule,
+}
+
+#[derive(Clone, Debug, Semantic)]
+#[semantic(role = "block")]
+struct Paragraph {
+    words: Vec<String>,
+}
+
+fn paragraph(words: &[&str]) -> Block {
+    Block::Paragraph(Paragraph {
+        words: words.iter().map(|&w| w.to_owned()).collect(),
+    })
+}
+
+fn page(title: &str, blocks: Vec<Block>) -> Page {
+    Page {
+        title: title.to_owned(),
+        blocks,
+        note: None,
+    }
+}
+
+fn path(steps: &[usize]) -> NodePath {
+    NodePath::from_steps(steps.to_vec())
+}
+
+/// Checks that `edits` turn `old` into `new`.
+fn assert_patches(old: &Page, new: &Page, edits: &[Edit]) {
+    assert_eq!(
+        semantic::patch(old, edits).unwrap(),
+        Node::from_value(new),
+        "patching with {:?}",
+        edits
+    );
+}
+
+#[test]
+fn finds_nothing_in_equal_trees() {
+    let old = page("Title", vec![paragraph(&["a"]), Block::Rule]);
+    assert!(semantic::diff(&old, &old.clone()).is_empty());
+}
+
+#[test]
+fn finds_a_reorder() {
+    let old = page(
+        "Title",
+        vec![paragraph(&["a"]), paragraph(&["b"]), paragraph(&["c"])],
+    );
+    let new = page(
+        "Title",
+        vec![paragraph(&["c"]), paragraph(&["a"]), paragraph(&["b"])],
+    );
+
+    let edits = semantic::diff(&old, &new);
+    assert_eq!(
+        edits,
+        vec![Edit::Move {
+            from: path(&[1, 2]),
+            to: path(&[1, 0]),
+        }]
+    );
+    assert_patches(&old, &new, &edits);
+}
+
+#[test]
+fn finds_a_field_update() {
+    let old = page("Title", vec![paragraph(&["a"])]);
+    let new = page("Better title", vec![paragraph(&["a"])]);
+
+    let edits = semantic::diff(&old, &new);
+    assert_eq!(edits.len(), 1);
+    match edits[0] {
+        Edit::Update {
+            ref path,
+            ref old,
+            ref new,
+        } => {
+            assert_eq!(*path, self::path(&[0]));
+            assert_eq!(*old, Node::from_value(&"Title".to_owned()));
+            assert_eq!(*new, Node::from_value(&"Better title".to_owned()));
+        }
+        ref edit => panic!("expected an update, got {:?}", edit),
+    }
+    assert_patches(&old, &new, &edits);
+}
+
+#[test]
+fn finds_an_insert() {
+    let old = page("Title", vec![paragraph(&["a"]), paragraph(&["c"])]);
+    let new = page(
+        "Title",
+        vec![paragraph(&["a"]), Block::Rule, paragraph(&["c"])],
+    );
+
+    let edits = semantic::diff(&old, &new);
+    assert_eq!(
+        edits,
+        vec![Edit::Insert {
+            path: path(&[1, 1]),
+            node: Node::from_value(&Block::Rule),
+        }]
+    );
+    assert_patches(&old, &new, &edits);
+}
+
+#[test]
+fn finds_a_delete() {
+    let old = page(
+        "Title",
+        vec![paragraph(&["a"]), Block::Rule, paragraph(&["c"])],
+    );
+    let new = page("Title", vec![paragraph(&["a"]), paragraph(&["c"])]);
+
+    let edits = semantic::diff(&old, &new);
+    assert_eq!(
+        edits,
+        vec![Edit::Delete {
+            path: path(&[1, 1]),
+            node: Node::from_value(&Block::Rule),
+        }]
+    );
+    assert_patches(&old, &new, &edits);
+}
+
+#[test]
+fn uses_old_paths_for_deletes_and_new_paths_for_the_rest() {
+    let old = page(
+        "Title",
+        vec![Block::Rule, paragraph(&["c"]), paragraph(&["a", "b"])],
+    );
+    let new = page("Title", vec![paragraph(&["a", "x"]), paragraph(&["c"])]);
+
+    let edits = semantic::diff(&old, &new);
+    assert!(edits.contains(&Edit::Delete {
+        path: path(&[1, 0]),
+        node: Node::from_value(&Block::Rule),
+    }));
+    assert!(edits.contains(&Edit::Update {
+        path: path(&[1, 0, 0, 0, 1]),
+        old: Node::from_value(&"b".to_owned()),
+        new: Node::from_value(&"x".to_owned()),
+    }));
+    assert_patches(&old, &new, &edits);
+}
+
+#[test]
+fn patches_nested_and_optional_changes() {
+    let old = page(
+        "Title",
+        vec![
+            paragraph(&["a", "b", "c"]),
+            Block::Rule,
+            paragraph(&["d", "e"]),
+            paragraph(&["f"]),
+        ],
+    );
+    let mut new = page(
+        "Title!",
+        vec![
+            paragraph(&["f", "g"]),
+            paragraph(&["e", "d"]),
+            paragraph(&["a", "c"]),
+        ],
+    );
+    new.note = Some("note".to_owned());
+
+    let edits = semantic::diff(&old, &new);
+    assert_patches(&old, &new, &edits);
+    assert_patches(&new, &old, &semantic::diff(&new, &old));
+}
+
+#[test]
+fn rejects_edits_that_do_not_fit() {
+    let old = page("Title", vec![]);
+    let edits = vec![Edit::Delete {
+        path: path(&[1, 3]),
+        node: Node::from_value(&Block::Rule),
+    }];
+    assert!(semantic::patch(&old, &edits).is_err());
+}
@@ -0,0 +1,150 @@
+#![feature(const_type_id)]
+
+extern crate semantic;
+#[macro_use]
+extern crate semantic_derive;
+
+use std::any;
+
+use semantic::Node;
+use semantic::NodePath;
+use semantic::Registry;
+
+#[derive(Debug, Semantic)]
+#[semantic(role = "document")]
+struct Page {
+    #[semantic(non_empty)]
+    title: String,
+    #[semantic(children)]
+    blocks: Vec<Block>,
+    note: Option<String>,
+}
+
+#[derive(Debug, Semantic)]
+#[semantic(role = "block")]
+enum Block {
+    Heading(Heading),
+    List(List),
+    Link {
+        #[semantic(non_empty)]
+        url: String,
+        #[semantic(range = "1..")]
+        weight: f64,
+    },
+    Rule,
+}
+
+#[derive(Debug, Semantic)]
+#[semantic(role = "block")]
+struct Heading {
+    #[semantic(range = "1..=6")]
+    depth: u32,
+    #[semantic(range = "3..7", default = "5")]
+    indent: u32,
+    align: Align,
+}
+
+#[derive(Debug, Semantic)]
+#[semantic(role = "attribute")]
+enum Align {
+    Left,
+    Right,
+}
+
+/// A list can nest other lists, so it must not recurse forever when it is instantiated.
+#[derive(Debug, Semantic)]
+#[semantic(role = "block")]
+struct List {
+    #[semantic(non_empty)]
+    items: Vec<Item>,
+    #[semantic(non_empty)]
+    label: Option<String>,
+}
+
+#[derive(Debug, Semantic)]
+#[semantic(role = "block")]
+enum Item {
+    Nested(List),
+    Text(String),
+}
+
+/// A class that can only be built from itself.
+#[derive(Debug, Semantic)]
+#[semantic(role = "block")]
+struct Endless {
+    #[semantic(non_empty)]
+    next: Vec<Endless>,
+}
+
+fn assert_valid(node: &Node) {
+    let violations = semantic::validate(node);
+    assert!(
+        violations.is_empty(),
+        "{:?} has violations: {:?}",
+        node,
+        violations.iter().map(|v| v.to_string()).collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn instantiates_valid_values_of_every_class() {
+    let registry = Registry::of::<Page>();
+    for class in registry.classes() {
+        let node = registry.instantiate(class.id).unwrap();
+        assert_eq!(node.class.id, class.id);
+        assert_valid(&node);
+    }
+}
+
+#[test]
+fn uses_defaults_and_the_closest_allowed_values() {
+    let registry = Registry::of::<Page>();
+    let heading: Heading = registry
+        .instantiate(any::TypeId::of::<Heading>())
+        .unwrap()
+        .into_value()
+        .unwrap();
+    assert_eq!(heading.depth, 1);
+    assert_eq!(heading.indent, 5);
+
+    let list: List = registry
+        .instantiate(any::TypeId::of::<List>())
+        .unwrap()
+        .into_value()
+        .unwrap();
+    assert_eq!(list.items.len(), 1);
+    assert!(list.label.map_or(false, |l| !l.is_empty()));
+}
+
+#[test]
+fn offers_valid_insertables() {
+    let registry = Registry::of::<Page>();
+    let page: Page = registry
+        .instantiate(any::TypeId::of::<Page>())
+        .unwrap()
+        .into_value()
+        .unwrap();
+
+    let insertables = registry.insertable(&page, &NodePath::from_steps(vec![1, 0]));
+    assert_eq!(
+        insertables.iter().map(|i| i.variant).collect::<Vec<_>>(),
+        vec![Some("Heading"), Some("List"), Some("Link"), Some("Rule")]
+    );
+    for insertable in &insertables {
+        assert_valid(&insertable.node);
+    }
+
+    let notes = registry.insertable(&page, &NodePath::from_steps(vec![2, 0]));
+    assert_eq!(notes.len(), 1);
+    assert_valid(&notes[0].node);
+
+    assert!(registry
+        .insertable(&page, &NodePath::from_steps(vec![1, 1]))
+        .is_empty());
+}
+
+#[test]
+fn fails_on_classes_that_only_recurse() {
+    let registry = Registry::of::<Endless>();
+    assert!(registry.instantiate(any::TypeId::of::<Endless>()).is_err());
+}
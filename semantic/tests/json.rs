@@ -0,0 +1,185 @@
+#![feature(const_type_id)]
+
+#[macro_use]
+extern crate serde_json;
+extern crate semantic;
+#[macro_use]
+extern crate semantic_derive;
+
+use semantic::json::from_json;
+use semantic::json::to_json;
+
+/// A small subset of mdast, enough to cover every rule of the JSON representation.
+#[derive(Debug, PartialEq, Semantic)]
+#[semantic(role = "document")]
+struct Root {
+    #[semantic(children)]
+    children: Vec<Block>,
+}
+
+#[derive(Debug, PartialEq, Semantic)]
+#[semantic(role = "block")]
+enum Block {
+    Paragraph(Paragraph),
+    Code(Code),
+    ThematicBreak,
+}
+
+#[derive(Debug, PartialEq, Semantic)]
+#[semantic(role = "block")]
+struct Paragraph {
+    #[semantic(children)]
+    children: Vec<Inline>,
+}
+
+#[derive(Debug, PartialEq, Semantic)]
+#[semantic(role = "block")]
+struct Code {
+    lang: Option<String>,
+    value: String,
+}
+
+#[derive(Debug, PartialEq, Semantic)]
+#[semantic(role = "inline")]
+enum Inline {
+    Text(Text),
+    InlineCode(InlineCode),
+    LinkReference(LinkReference),
+}
+
+#[derive(Debug, PartialEq, Semantic)]
+#[semantic(role = "inline")]
+struct Text {
+    value: String,
+}
+
+#[derive(Debug, PartialEq, Semantic)]
+#[semantic(role = "inline")]
+struct InlineCode {
+    value: String,
+}
+
+#[derive(Debug, PartialEq, Semantic)]
+#[semantic(role = "inline")]
+struct LinkReference {
+    identifier: String,
+    reference_type: ReferenceType,
+    #[semantic(children)]
+    children: Vec<Inline>,
+}
+
+#[derive(Debug, PartialEq, Semantic)]
+#[semantic(role = "inline")]
+enum ReferenceType {
+    Shortcut,
+    Collapsed,
+    Full,
+}
+
+fn sample() -> Root {
+    Root {
+        children: vec![
+            Block::Paragraph(Paragraph {
+                children: vec![
+                    Inline::Text(Text {
+                        value: "Run ".to_owned(),
+                    }),
+                    Inline::InlineCode(InlineCode {
+                        value: "make".to_owned(),
+                    }),
+                    Inline::LinkReference(LinkReference {
+                        identifier: "docs".to_owned(),
+                        reference_type: ReferenceType::Full,
+                        children: vec![Inline::Text(Text {
+                            value: "docs".to_owned(),
+                        })],
+                    }),
+                ],
+            }),
+            Block::Code(Code {
+                lang: Some("rust".to_owned()),
+                value: "fn main() {}".to_owned(),
+            }),
+            Block::Code(Code {
+                lang: None,
+                value: "plain".to_owned(),
+            }),
+            Block::ThematicBreak,
+        ],
+    }
+}
+
+#[test]
+fn matches_the_mdast_shape() {
+    assert_eq!(
+        to_json(&sample()),
+        json!({
+            "type": "root",
+            "children": [
+                {
+                    "type": "paragraph",
+                    "children": [
+                        { "type": "text", "value": "Run " },
+                        { "type": "inlineCode", "value": "make" },
+                        {
+                            "type": "linkReference",
+                            "identifier": "docs",
+                            "referenceType": "full",
+                            "children": [{ "type": "text", "value": "docs" }]
+                        }
+                    ]
+                },
+                { "type": "code", "lang": "rust", "value": "fn main() {}" },
+                { "type": "code", "lang": null, "value": "plain" },
+                { "type": "thematicBreak" }
+            ]
+        })
+    );
+}
+
+#[test]
+fn round_trips() {
+    let root = sample();
+    let json = to_json(&root);
+    assert_eq!(from_json::<Root>(&json).unwrap(), root);
+}
+
+#[test]
+fn rejects_the_wrong_type_tag() {
+    let json = json!({
+        "type": "root",
+        "children": [{ "type": "heading", "children": [] }]
+    });
+    assert!(from_json::<Root>(&json).is_err());
+}
+
+/// A document with a tag of its own, so that it can share a union with `Root`.
+#[derive(Debug, PartialEq, Semantic)]
+#[semantic(role = "document", tag = "notes")]
+struct Notes {
+    lines: Vec<String>,
+}
+
+#[derive(Debug, PartialEq, Semantic)]
+#[semantic(role = "root")]
+enum Data {
+    Root(Root),
+    Notes(Notes),
+}
+
+#[test]
+fn tells_documents_apart_by_their_tags() {
+    let notes = Notes {
+        lines: vec!["one".to_owned()],
+    };
+    assert_eq!(to_json(&notes), json!({"type": "notes", "lines": ["one"]}));
+    assert_eq!(
+        from_json::<Data>(&to_json(&notes)).unwrap(),
+        Data::Notes(notes)
+    );
+    assert_eq!(
+        from_json::<Data>(&to_json(&sample())).unwrap(),
+        Data::Root(sample())
+    );
+    assert!(from_json::<Notes>(&json!({"type": "root", "lines": []})).is_err());
+}
@@ -0,0 +1,230 @@
+#![feature(const_type_id)]
+
+#[macro_use]
+extern crate serde_json;
+extern crate semantic;
+#[macro_use]
+extern crate semantic_derive;
+
+use semantic::json::to_json;
+use semantic::Registry;
+
+#[derive(Debug, Semantic)]
+#[semantic(role = "document", version = 2)]
+struct Outline {
+    #[semantic(non_empty)]
+    title: String,
+    #[semantic(range = "1..=6")]
+    level: u8,
+    kind: Kind,
+    note: Option<String>,
+    #[semantic(non_empty)]
+    summary: Option<String>,
+    #[semantic(children)]
+    sections: Vec<Section>,
+}
+
+#[derive(Debug, Semantic)]
+#[semantic(role = "attribute")]
+enum Kind {
+    Draft,
+    Final,
+}
+
+/// A union that contains itself, both directly and inside of optional values.
+#[derive(Debug, Semantic)]
+#[semantic(role = "block")]
+enum Section {
+    Heading(Heading),
+    Group {
+        sections: Vec<Section>,
+        extra: Option<Vec<Section>>,
+    },
+    Rule,
+}
+
+#[derive(Debug, Semantic)]
+#[semantic(role = "block")]
+struct Heading {
+    text: String,
+    weight: Option<f64>,
+}
+
+fn outline() -> Outline {
+    Outline {
+        title: "Title".to_owned(),
+        level: 2,
+        kind: Kind::Final,
+        note: None,
+        summary: Some("Summary".to_owned()),
+        sections: vec![
+            Section::Heading(Heading {
+                text: "One".to_owned(),
+                weight: Some(0.5),
+            }),
+            Section::Group {
+                sections: vec![
+                    Section::Rule,
+                    Section::Group {
+                        sections: vec![],
+                        extra: None,
+                    },
+                ],
+                extra: Some(vec![Section::Heading(Heading {
+                    text: "Two".to_owned(),
+                    weight: None,
+                })]),
+            },
+        ],
+    }
+}
+
+/// Checks `value` against `schema`, looking up references in the definitions of `root`.
+///
+/// This supports the keywords that `json_schema::schema` generates, and fails on any others.
+fn is_valid(
+    root: &serde_json::Value,
+    schema: &serde_json::Value,
+    value: &serde_json::Value,
+) -> bool {
+    let number = |n: &serde_json::Value| n.as_f64().expect("expected a number");
+    let schemas = |s: &serde_json::Value| s.as_array().expect("expected schemas").clone();
+
+    schema
+        .as_object()
+        .expect("expected a schema object")
+        .iter()
+        .all(|(keyword, argument)| match keyword.as_str() {
+            "$ref" => {
+                let name = argument.as_str().unwrap().split('/').last().unwrap();
+                is_valid(root, &root["definitions"][name], value)
+            }
+            "allOf" => schemas(argument).iter().all(|s| is_valid(root, s, value)),
+            "anyOf" => schemas(argument).iter().any(|s| is_valid(root, s, value)),
+            "oneOf" => {
+                schemas(argument)
+                    .iter()
+                    .filter(|s| is_valid(root, s, value))
+                    .count()
+                    == 1
+            }
+            "type" => match argument.as_str().unwrap() {
+                "null" => value.is_null(),
+                "boolean" => value.is_boolean(),
+                "string" => value.is_string(),
+                "number" => value.is_number(),
+                "integer" => value.is_i64() || value.is_u64(),
+                "array" => value.is_array(),
+                "object" => value.is_object(),
+                other => panic!("unknown type {:?}", other),
+            },
+            "const" => value == argument,
+            "enum" => schemas(argument).contains(value),
+            "properties" => value.as_object().map_or(true, |object| {
+                argument.as_object().unwrap().iter().all(|(key, schema)| {
+                    object
+                        .get(key)
+                        .map_or(true, |value| is_valid(root, schema, value))
+                })
+            }),
+            "required" => value.as_object().map_or(true, |object| {
+                schemas(argument)
+                    .iter()
+                    .all(|key| object.contains_key(key.as_str().unwrap()))
+            }),
+            "items" => value
+                .as_array()
+                .map_or(true, |items| items.iter().all(|v| is_valid(root, argument, v))),
+            "minimum" => value.as_f64().map_or(true, |n| n >= number(argument)),
+            "maximum" => value.as_f64().map_or(true, |n| n <= number(argument)),
+            "exclusiveMinimum" => value.as_f64().map_or(true, |n| n > number(argument)),
+            "exclusiveMaximum" => value.as_f64().map_or(true, |n| n < number(argument)),
+            "minItems" => value
+                .as_array()
+                .map_or(true, |items| items.len() as f64 >= number(argument)),
+            "minLength" => value
+                .as_str()
+                .map_or(true, |s| s.chars().count() as f64 >= number(argument)),
+            "$schema" | "definitions" | "description" | "default" => true,
+            other => panic!("unsupported keyword {:?}", other),
+        })
+}
+
+fn assert_valid(value: &serde_json::Value) {
+    let schema = semantic::json_schema::schema(&Registry::of::<Outline>());
+    assert!(is_valid(&schema, &schema, value), "{} is invalid", value);
+}
+
+fn assert_invalid(value: &serde_json::Value) {
+    let schema = semantic::json_schema::schema(&Registry::of::<Outline>());
+    assert!(!is_valid(&schema, &schema, value), "{} is valid", value);
+}
+
+/// The JSON of the outline, with `edit` applied to it.
+fn edited<F>(edit: F) -> serde_json::Value
+where
+    F: FnOnce(&mut serde_json::Value),
+{
+    let mut json = to_json(&outline());
+    edit(&mut json);
+    json
+}
+
+#[test]
+fn accepts_the_json_of_values() {
+    assert_valid(&to_json(&outline()));
+    assert_valid(&to_json(&Outline {
+        kind: Kind::Draft,
+        note: Some(String::new()),
+        sections: vec![],
+        ..outline()
+    }));
+}
+
+#[test]
+fn accepts_null_optional_values_unambiguously() {
+    assert_valid(&edited(|json| json["note"] = json!(null)));
+    assert_valid(&edited(|json| json["sections"][1]["extra"] = json!(null)));
+    assert_valid(&edited(|json| json["sections"][0]["weight"] = json!(null)));
+}
+
+#[test]
+fn rejects_invalid_json() {
+    assert_invalid(&edited(|json| json["type"] = json!("section")));
+    assert_invalid(&edited(|json| json["schemaVersion"] = json!(1)));
+    assert_invalid(&edited(|json| json["title"] = json!("")));
+    assert_invalid(&edited(|json| json["level"] = json!(7)));
+    assert_invalid(&edited(|json| json["level"] = json!(1.5)));
+    assert_invalid(&edited(|json| json["kind"] = json!("published")));
+    assert_invalid(&edited(|json| json["summary"] = json!(null)));
+    assert_invalid(&edited(|json| json["note"] = json!(3)));
+    assert_invalid(&edited(|json| json["sections"][1]["type"] = json!("heading")));
+    assert_invalid(&edited(|json| json["sections"][1]["sections"][0] = json!(null)));
+    assert_invalid(&edited(|json| json["sections"][1]["extra"] = json!([{}])));
+    assert_invalid(&edited(|json| {
+        json.as_object_mut().unwrap().remove("title");
+    }));
+}
+
+#[test]
+fn refers_to_recursive_unions_by_name() {
+    let schema = semantic::json_schema::schema(&Registry::of::<Outline>());
+    let section = &schema["definitions"]["Section"];
+    assert_eq!(
+        section["description"],
+        json!("A union that contains itself, both directly and inside of optional values.")
+    );
+    let group = &section["oneOf"][1];
+    assert_eq!(
+        group["properties"]["sections"]["items"],
+        json!({"$ref": "#/definitions/Section"})
+    );
+    assert_eq!(
+        group["properties"]["extra"],
+        json!({"anyOf": [
+            {"type": "null"},
+            {"type": "array", "items": {"$ref": "#/definitions/Section"}},
+        ]})
+    );
+    assert_eq!(schema["allOf"], json!([{"$ref": "#/definitions/Outline"}]));
+}
@@ -0,0 +1,144 @@
+#![feature(const_type_id)]
+
+extern crate semantic;
+#[macro_use]
+extern crate semantic_derive;
+
+use semantic::ConflictKind;
+use semantic::Node;
+use semantic::NodePath;
+
+#[derive(Clone, Debug, Semantic)]
+#[semantic(role = "document")]
+struct Page {
+    title: String,
+    blocks: Vec<Block>,
+}
+
+#[derive(Clone, Debug, Semantic)]
+#[semantic(role = "block")]
+enum Block {
+    Paragraph(Paragraph),
+    Rule,
+}
+
+#[derive(Clone, Debug, Semantic)]
+#[semantic(role = "block")]
+struct Paragraph {
+    words: Vec<String>,
+}
+
+fn paragraph(words: &[&str]) -> Block {
+    Block::Paragraph(Paragraph {
+        words: words.iter().map(|&w| w.to_owned()).collect(),
+    })
+}
+
+fn page(title: &str, blocks: Vec<Block>) -> Page {
+    Page {
+        title: title.to_owned(),
+        blocks,
+    }
+}
+
+fn base() -> Page {
+    page(
+        "Title",
+        vec![paragraph(&["a", "b"]), Block::Rule, paragraph(&["c"])],
+    )
+}
+
+#[test]
+fn combines_edits_to_different_nodes() {
+    let ours = page(
+        "New title",
+        vec![paragraph(&["a", "b"]), Block::Rule, paragraph(&["c"])],
+    );
+    let theirs = page(
+        "Title",
+        vec![paragraph(&["a", "b"]), paragraph(&["c", "d"]), Block::Rule],
+    );
+
+    let merge = semantic::merge(&base(), &ours, &theirs);
+    assert!(merge.is_clean(), "unexpected conflicts: {:?}", merge.conflicts);
+    assert_eq!(
+        merge.node,
+        Node::from_value(&page(
+            "New title",
+            vec![paragraph(&["a", "b"]), paragraph(&["c", "d"]), Block::Rule],
+        ))
+    );
+}
+
+#[test]
+fn accepts_the_same_edit_on_both_sides() {
+    let edited = page(
+        "Title",
+        vec![paragraph(&["a", "x"]), Block::Rule, paragraph(&["c"])],
+    );
+
+    let merge = semantic::merge(&base(), &edited, &edited.clone());
+    assert!(merge.is_clean(), "unexpected conflicts: {:?}", merge.conflicts);
+    assert_eq!(merge.node, Node::from_value(&edited));
+}
+
+#[test]
+fn keeps_our_version_of_conflicting_edits() {
+    let ours = page("Our title", base().blocks);
+    let theirs = page("Their title", base().blocks);
+
+    let merge = semantic::merge(&base(), &ours, &theirs);
+    assert_eq!(merge.node, Node::from_value(&ours));
+    assert_eq!(merge.conflicts.len(), 1);
+    assert_eq!(merge.conflicts[0].path, NodePath::from_steps(vec![0]));
+    assert_eq!(
+        merge.conflicts[0].kind,
+        ConflictKind::BothChanged {
+            base: Node::from_value(&"Title".to_owned()),
+            ours: Node::from_value(&"Our title".to_owned()),
+            theirs: Node::from_value(&"Their title".to_owned()),
+        }
+    );
+}
+
+#[test]
+fn keeps_their_edit_of_a_node_that_we_deleted() {
+    let ours = page("Title", vec![Block::Rule, paragraph(&["c"])]);
+    let theirs = page(
+        "Title",
+        vec![paragraph(&["a", "x"]), Block::Rule, paragraph(&["c"])],
+    );
+
+    let merge = semantic::merge(&base(), &ours, &theirs);
+    assert_eq!(merge.node, Node::from_value(&theirs));
+    assert_eq!(merge.conflicts.len(), 1);
+    assert_eq!(merge.conflicts[0].path, NodePath::from_steps(vec![1, 0]));
+    assert_eq!(
+        merge.conflicts[0].kind,
+        ConflictKind::DeletedByUs {
+            base: Node::from_value(&paragraph(&["a", "b"])),
+            theirs: Node::from_value(&paragraph(&["a", "x"])),
+        }
+    );
+}
+
+#[test]
+fn keeps_our_edit_of_a_node_that_they_deleted() {
+    let ours = page(
+        "Title",
+        vec![paragraph(&["a", "x"]), Block::Rule, paragraph(&["c"])],
+    );
+    let theirs = page("Title", vec![Block::Rule, paragraph(&["c"])]);
+
+    let merge = semantic::merge(&base(), &ours, &theirs);
+    assert_eq!(merge.node, Node::from_value(&ours));
+    assert_eq!(merge.conflicts.len(), 1);
+    assert_eq!(merge.conflicts[0].path, NodePath::from_steps(vec![1, 0]));
+    assert_eq!(
+        merge.conflicts[0].kind,
+        ConflictKind::DeletedByThem {
+            base: Node::from_value(&paragraph(&["a", "b"])),
+            ours: Node::from_value(&paragraph(&["a", "x"])),
+        }
+    );
+}
@@ -0,0 +1,188 @@
+#![feature(const_type_id)]
+
+extern crate semantic;
+#[macro_use]
+extern crate semantic_derive;
+#[macro_use]
+extern crate serde_json;
+
+use semantic::json;
+use semantic::Error;
+use semantic::Migration;
+use semantic::Registry;
+use semantic::Semantic;
+
+/// A note, which was `{ name, tags: "a,b" }` in version 1 and `{ title, tags: "a,b" }` in
+/// version 2.
+#[derive(Debug, Semantic)]
+#[semantic(role = "document", version = 3)]
+struct Note {
+    title: String,
+    tags: Vec<String>,
+}
+
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        from: 1,
+        description: "rename name to title",
+        migrate: rename_name,
+    },
+    Migration {
+        from: 2,
+        description: "split tags",
+        migrate: split_tags,
+    },
+];
+
+fn rename_name(json: &mut serde_json::Value) -> semantic::Result<()> {
+    let object = json.as_object_mut().unwrap();
+    let name = object
+        .remove("name")
+        .ok_or_else(|| Error::new("missing name"))?;
+    object.insert("title".to_owned(), name);
+    Ok(())
+}
+
+fn split_tags(json: &mut serde_json::Value) -> semantic::Result<()> {
+    let tags = json["tags"]
+        .as_str()
+        .unwrap_or("")
+        .split(',')
+        .filter(|t| !t.is_empty())
+        .map(|t| serde_json::Value::from(t))
+        .collect::<Vec<_>>();
+    json["tags"] = tags.into();
+    Ok(())
+}
+
+#[test]
+fn unversioned_documents_are_migrated_from_version_1() {
+    let mut document = json!({"type": "root", "name": "Shopping", "tags": "food,home"});
+
+    let version = semantic::migrate(&Note::CLASS, MIGRATIONS, &mut document).unwrap();
+
+    assert_eq!(1, version);
+    assert_eq!(
+        json!({
+            "type": "root",
+            "title": "Shopping",
+            "tags": ["food", "home"],
+            "schemaVersion": 3,
+        }),
+        document
+    );
+    let note = json::from_json::<Note>(&document).unwrap();
+    assert_eq!("Shopping", note.title);
+    assert_eq!(vec!["food", "home"], note.tags);
+}
+
+#[test]
+fn migrations_start_at_the_version_of_the_document() {
+    let mut document =
+        json!({"type": "root", "schemaVersion": 2, "title": "Shopping", "tags": "food"});
+
+    let version = semantic::migrate(&Note::CLASS, MIGRATIONS, &mut document).unwrap();
+
+    assert_eq!(2, version);
+    assert_eq!(json!(["food"]), document["tags"]);
+    assert_eq!(json!(3), document["schemaVersion"]);
+}
+
+#[test]
+fn current_documents_are_left_alone() {
+    let original = json!({"type": "root", "schemaVersion": 3, "title": "Shopping", "tags": []});
+    let mut document = original.clone();
+
+    let version = semantic::migrate(&Note::CLASS, MIGRATIONS, &mut document).unwrap();
+
+    assert_eq!(3, version);
+    assert_eq!(original, document);
+}
+
+#[test]
+fn newer_documents_are_rejected() {
+    let mut document = json!({"type": "root", "schemaVersion": 4, "title": "Shopping"});
+
+    let error = semantic::migrate(&Note::CLASS, MIGRATIONS, &mut document).unwrap_err();
+
+    assert!(error.message.contains("only versions up to 3"), "{}", error);
+}
+
+#[test]
+fn missing_migrations_are_reported() {
+    let mut document = json!({"type": "root", "name": "Shopping", "tags": ""});
+
+    let error = semantic::migrate(&Note::CLASS, &MIGRATIONS[1..], &mut document).unwrap_err();
+
+    assert!(error.message.contains("from schema version 1 to 2"), "{}", error);
+}
+
+#[test]
+fn failing_migrations_are_described() {
+    let mut document = json!({"type": "root", "tags": ""});
+
+    let error = semantic::migrate(&Note::CLASS, MIGRATIONS, &mut document).unwrap_err();
+
+    assert!(error.message.contains("rename name to title"), "{}", error);
+    assert!(error.message.contains("missing name"), "{}", error);
+}
+
+#[test]
+fn documents_are_written_with_their_version() {
+    let note = Note {
+        title: "Shopping".to_owned(),
+        tags: vec![],
+    };
+
+    let document = json::to_json(&note);
+
+    assert_eq!(json!(3), document[json::VERSION_KEY]);
+}
+
+#[test]
+fn outdated_documents_are_not_read() {
+    let document = json!({"type": "root", "schemaVersion": 2, "title": "Shopping", "tags": []});
+
+    let error = json::from_json::<Note>(&document).unwrap_err();
+
+    assert!(error.message.contains("needs to be migrated"), "{}", error);
+}
+
+mod same {
+    /// A note with a description, which doesn't change its shape.
+    #[derive(Debug, Semantic)]
+    #[semantic(role = "document", version = 3)]
+    pub struct Note {
+        /// The title of the note.
+        title: String,
+        tags: Vec<String>,
+    }
+}
+
+mod bumped {
+    #[derive(Debug, Semantic)]
+    #[semantic(role = "document", version = 4)]
+    pub struct Note {
+        title: String,
+        tags: Vec<String>,
+    }
+}
+
+mod changed {
+    #[derive(Debug, Semantic)]
+    #[semantic(role = "document", version = 3)]
+    pub struct Note {
+        title: String,
+        tags: Vec<u32>,
+    }
+}
+
+#[test]
+fn schema_hashes_only_depend_on_the_shape_of_classes() {
+    let hash = Registry::of::<Note>().schema_hash();
+
+    assert_eq!(hash, Registry::of::<Note>().schema_hash());
+    assert_eq!(hash, Registry::of::<same::Note>().schema_hash());
+    assert_ne!(hash, Registry::of::<bumped::Note>().schema_hash());
+    assert_ne!(hash, Registry::of::<changed::Note>().schema_hash());
+}
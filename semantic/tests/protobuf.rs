@@ -0,0 +1,154 @@
+#![feature(const_type_id)]
+
+extern crate semantic;
+#[macro_use]
+extern crate semantic_derive;
+
+use semantic::Registry;
+
+/// A page of text.
+#[derive(Debug, Semantic)]
+#[semantic(role = "document")]
+struct Page {
+    title: String,
+    /// The number of views, if known.
+    views: Option<u32>,
+    status: Status,
+    author: Option<Person>,
+    #[semantic(children)]
+    blocks: Vec<Block>,
+}
+
+#[derive(Debug, Semantic)]
+#[semantic(role = "attribute")]
+enum Status {
+    /// Not published yet.
+    Draft,
+    PublishedOnline,
+}
+
+#[derive(Debug, Semantic)]
+#[semantic(role = "attribute")]
+struct Person {
+    name: String,
+}
+
+#[derive(Debug, Semantic)]
+#[semantic(role = "block")]
+enum Block {
+    Heading(Heading),
+    Rule,
+    /// A quotation.
+    Quote {
+        text: String,
+        source: Option<String>,
+    },
+}
+
+#[derive(Debug, Semantic)]
+#[semantic(role = "block")]
+struct Heading {
+    depth: u8,
+    tags: Vec<String>,
+}
+
+/// Fields that protobuf can't express directly.
+#[derive(Debug, Semantic)]
+#[semantic(role = "document")]
+struct Grid {
+    cells: Vec<Vec<u32>>,
+    status: Option<Status>,
+    notes: Option<Vec<String>>,
+}
+
+#[test]
+fn generates_messages_oneofs_enums_and_optional_fields() {
+    assert_eq!(
+        semantic::protobuf::definitions(&Registry::of::<Page>(), "test.page"),
+        r#"// This file is generated from semantic classes.  Do not edit it by hand.
+
+syntax = "proto3";
+
+package test.page;
+
+import "google/protobuf/wrappers.proto";
+
+// A page of text.
+message Page {
+  string title = 1;
+  // The number of views, if known.
+  google.protobuf.UInt32Value views = 2;
+  Status status = 3;
+  Person author = 4;
+  repeated Block blocks = 5;
+}
+
+enum Status {
+  // Not published yet.
+  STATUS_DRAFT = 0;
+  STATUS_PUBLISHED_ONLINE = 1;
+}
+
+message Person {
+  string name = 1;
+}
+
+message Block {
+  message Rule {}
+  // A quotation.
+  message Quote {
+    string text = 1;
+    google.protobuf.StringValue source = 2;
+  }
+  oneof variant {
+    Heading heading = 1;
+    Rule rule = 2;
+    Quote quote = 3;
+  }
+}
+
+message Heading {
+  uint32 depth = 1;
+  repeated string tags = 2;
+}
+"#
+    );
+}
+
+#[test]
+fn wraps_what_protobuf_cannot_express() {
+    assert_eq!(
+        semantic::protobuf::definitions(&Registry::of::<Grid>(), "test.grid"),
+        r#"// This file is generated from semantic classes.  Do not edit it by hand.
+
+syntax = "proto3";
+
+package test.grid;
+
+// Fields that protobuf can't express directly.
+message Grid {
+  repeated Uint32List cells = 1;
+  OptionalStatus status = 2;
+  OptionalStringList notes = 3;
+}
+
+enum Status {
+  // Not published yet.
+  STATUS_DRAFT = 0;
+  STATUS_PUBLISHED_ONLINE = 1;
+}
+
+message OptionalStatus {
+  Status value = 1;
+}
+
+message OptionalStringList {
+  repeated string value = 1;
+}
+
+message Uint32List {
+  repeated uint32 items = 1;
+}
+"#
+    );
+}
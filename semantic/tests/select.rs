@@ -0,0 +1,239 @@
+#![feature(const_type_id)]
+
+extern crate semantic;
+#[macro_use]
+extern crate semantic_derive;
+
+#[derive(Debug, Semantic)]
+#[semantic(role = "document")]
+struct Page {
+    blocks: Vec<Block>,
+}
+
+#[derive(Debug, Semantic)]
+#[semantic(role = "block")]
+enum Block {
+    Heading(Heading),
+    Rule,
+    Quote { author: Option<String> },
+}
+
+#[derive(Debug, Semantic)]
+#[semantic(role = "block")]
+struct Heading {
+    depth: u32,
+    align: Align,
+    inlines: Vec<Inline>,
+}
+
+#[derive(Debug, Semantic)]
+#[semantic(role = "attribute")]
+enum Align {
+    Left,
+    AlignCenter,
+}
+
+#[derive(Debug, Semantic)]
+#[semantic(role = "inline")]
+enum Inline {
+    Text(Text),
+    Link(Link),
+    Strong(Strong),
+}
+
+#[derive(Debug, Semantic)]
+#[semantic(role = "inline")]
+struct Text {
+    value: String,
+}
+
+#[derive(Debug, Semantic)]
+#[semantic(role = "inline")]
+struct Link {
+    url: String,
+    title: Option<String>,
+}
+
+#[derive(Debug, Semantic)]
+#[semantic(role = "inline")]
+struct Strong {
+    inlines: Vec<Inline>,
+}
+
+fn text(value: &str) -> Inline {
+    Inline::Text(Text {
+        value: value.to_owned(),
+    })
+}
+
+fn link(url: &str, title: Option<&str>) -> Inline {
+    Inline::Link(Link {
+        url: url.to_owned(),
+        title: title.map(|t| t.to_owned()),
+    })
+}
+
+fn heading(depth: u32, align: Align, inlines: Vec<Inline>) -> Block {
+    Block::Heading(Heading {
+        depth,
+        align,
+        inlines,
+    })
+}
+
+/// A page with these paths:
+///
+/// ```text
+/// /0/0/0              heading (depth 1)
+/// /0/0/0/2/0/0        link http://a
+/// /0/0/0/2/1/0        text
+/// /0/0/0/2/2/0        strong
+/// /0/0/0/2/2/0/0/0/0  link https://b
+/// /0/1                rule
+/// /0/2                quote (with an author)
+/// /0/3                quote (without one)
+/// /0/4/0              heading (depth 2)
+/// /0/4/0/2/0/0        link http://c
+/// ```
+fn page() -> Page {
+    Page {
+        blocks: vec![
+            heading(
+                1,
+                Align::Left,
+                vec![
+                    link("http://a", Some("A")),
+                    text("and"),
+                    Inline::Strong(Strong {
+                        inlines: vec![link("https://b", None)],
+                    }),
+                ],
+            ),
+            Block::Rule,
+            Block::Quote {
+                author: Some("Ada".to_owned()),
+            },
+            Block::Quote { author: None },
+            heading(2, Align::AlignCenter, vec![link("http://c", None)]),
+        ],
+    }
+}
+
+fn select(selector: &str) -> Vec<String> {
+    semantic::select(&page(), selector)
+        .unwrap()
+        .iter()
+        .map(|p| p.to_string())
+        .collect()
+}
+
+fn error(selector: &str) -> String {
+    semantic::select(&page(), selector).unwrap_err().message
+}
+
+#[test]
+fn selects_by_name() {
+    let links = vec!["/0/0/0/2/0/0", "/0/0/0/2/2/0/0/0/0", "/0/4/0/2/0/0"];
+    assert_eq!(select("link"), links);
+    assert_eq!(select("Link"), links);
+    assert_eq!(select("rule"), vec!["/0/1"]);
+    assert_eq!(select("Quote"), vec!["/0/2", "/0/3"]);
+    assert_eq!(select("root"), vec!["/"]);
+    assert_eq!(select("Page"), vec!["/"]);
+    assert!(select("paragraph").is_empty());
+}
+
+#[test]
+fn selects_any_element() {
+    assert_eq!(select("*").len(), 11);
+}
+
+#[test]
+fn selects_by_attribute_presence() {
+    assert_eq!(select("link[title]"), vec!["/0/0/0/2/0/0"]);
+    assert_eq!(select("quote[author]"), vec!["/0/2"]);
+    assert!(select("text[url]").is_empty());
+}
+
+#[test]
+fn selects_by_attribute_value() {
+    assert_eq!(select("link[url=http://c]"), vec!["/0/4/0/2/0/0"]);
+    assert_eq!(select("link[url=\"http://c\"]"), vec!["/0/4/0/2/0/0"]);
+    assert_eq!(select("link[url^='https:']"), vec!["/0/0/0/2/2/0/0/0/0"]);
+    assert_eq!(select("link[url$=a]"), vec!["/0/0/0/2/0/0"]);
+    assert_eq!(select("text[value*=n]"), vec!["/0/0/0/2/1/0"]);
+    assert_eq!(select("heading[depth=2]"), vec!["/0/4/0"]);
+    assert_eq!(select("quote[author=Ada]"), vec!["/0/2"]);
+    assert!(select("quote[author=Bob]").is_empty());
+}
+
+#[test]
+fn compares_enumerations_by_variant_name() {
+    assert_eq!(select("heading[align=AlignCenter]"), vec!["/0/4/0"]);
+    assert_eq!(select("heading[align=alignCenter]"), vec!["/0/4/0"]);
+    assert_eq!(select("heading[align=left]"), vec!["/0/0/0"]);
+}
+
+#[test]
+fn selects_by_position() {
+    assert_eq!(select("root > :nth-child(2)"), vec!["/0/1"]);
+    assert_eq!(select("root > :nth-child(odd)"), vec!["/0/0/0", "/0/2", "/0/4/0"]);
+    assert_eq!(select("root > :nth-child(even)"), vec!["/0/1", "/0/3"]);
+    assert_eq!(select("root > :nth-child(2n+3)"), vec!["/0/2", "/0/4/0"]);
+    assert_eq!(select("root > :nth-child(-n+2)"), vec!["/0/0/0", "/0/1"]);
+    assert_eq!(select("heading > :nth-child(n+2)"), vec!["/0/0/0/2/1/0", "/0/0/0/2/2/0"]);
+}
+
+#[test]
+fn selects_descendants_and_children() {
+    assert_eq!(
+        select("heading link"),
+        vec!["/0/0/0/2/0/0", "/0/0/0/2/2/0/0/0/0", "/0/4/0/2/0/0"]
+    );
+    assert_eq!(select("heading > link"), vec!["/0/0/0/2/0/0", "/0/4/0/2/0/0"]);
+    assert_eq!(select("heading>strong>link"), vec!["/0/0/0/2/2/0/0/0/0"]);
+    assert_eq!(select("root heading[depth=1] strong link"), vec!["/0/0/0/2/2/0/0/0/0"]);
+}
+
+#[test]
+fn selects_alternatives_in_document_order() {
+    assert_eq!(
+        select("quote, heading[depth=2], rule"),
+        vec!["/0/1", "/0/2", "/0/3", "/0/4/0"]
+    );
+}
+
+#[test]
+fn reports_parse_errors_with_their_position() {
+    assert_eq!(
+        error(""),
+        "expected an element name, `*`, `[` or `:` at position 0 of the selector"
+    );
+    assert_eq!(
+        error("heading >"),
+        "expected an element name, `*`, `[` or `:` at position 9 of the selector"
+    );
+    assert_eq!(
+        error("a b,"),
+        "expected an element name, `*`, `[` or `:` at position 4 of the selector"
+    );
+    assert_eq!(error("[url"), "expected `]` at position 4 of the selector");
+    assert_eq!(error("[]"), "expected an attribute name at position 1 of the selector");
+    assert_eq!(error("[url=]"), "expected an attribute value at position 5 of the selector");
+    assert_eq!(error("[url='x]"), "unterminated string at position 8 of the selector");
+    assert_eq!(
+        error(":first-child"),
+        "unknown pseudo-class \"first-child\" at position 1 of the selector"
+    );
+    assert_eq!(error(":nth-child"), "expected `(` at position 10 of the selector");
+    assert_eq!(error(":nth-child(2"), "expected `)` at position 12 of the selector");
+    assert_eq!(
+        error("a:nth-child(x)"),
+        "invalid :nth-child formula \"x\" at position 12 of the selector"
+    );
+    assert_eq!(
+        error("a ! b"),
+        "expected an element name, `*`, `[` or `:` at position 2 of the selector"
+    );
+    assert_eq!(error("a!"), "unexpected '!' at position 1 of the selector");
+}
@@ -0,0 +1,170 @@
+#![feature(const_type_id)]
+
+extern crate semantic;
+#[macro_use]
+extern crate semantic_derive;
+
+use semantic::History;
+use semantic::Node;
+use semantic::NodePath;
+use semantic::Snapshot;
+
+#[derive(Debug, Semantic)]
+#[semantic(role = "document")]
+struct Page {
+    title: String,
+    blocks: Vec<Block>,
+    note: Option<String>,
+}
+
+#[derive(Debug, Semantic)]
+#[semantic(role = "block")]
+struct Block {
+    words: Vec<String>,
+}
+
+fn block(words: &[&str]) -> Block {
+    Block {
+        words: words.iter().map(|&w| w.to_owned()).collect(),
+    }
+}
+
+fn snapshot() -> Snapshot {
+    Snapshot::new(&Page {
+        title: "Title".to_owned(),
+        blocks: vec![block(&["a", "b"]), block(&["c"]), block(&["d"])],
+        note: None,
+    })
+}
+
+fn path(steps: &[usize]) -> NodePath {
+    NodePath::from_steps(steps.to_vec())
+}
+
+fn text(value: &str) -> Node {
+    Node::from_value(&value.to_owned())
+}
+
+fn title(snapshot: &Snapshot) -> String {
+    snapshot.to_value::<Page>().unwrap().title
+}
+
+#[test]
+fn replacing_shares_untouched_siblings() {
+    let old = snapshot();
+    let new = old.replace(&path(&[1, 0, 0, 1]), text("x")).unwrap();
+
+    assert_eq!(new.to_value::<Page>().unwrap().blocks[0].words, vec!["a", "x"]);
+    assert!(!new.shares(&old));
+    assert!(new.get(&path(&[0])).unwrap().shares(old.get(&path(&[0])).unwrap()));
+    assert!(new.get(&path(&[2])).unwrap().shares(old.get(&path(&[2])).unwrap()));
+    for sibling in &[1, 2] {
+        let sibling = path(&[1, *sibling]);
+        assert!(new.get(&sibling).unwrap().shares(old.get(&sibling).unwrap()));
+    }
+    assert!(!new.get(&path(&[1, 0])).unwrap().shares(old.get(&path(&[1, 0])).unwrap()));
+    assert!(new
+        .get(&path(&[1, 0, 0, 0]))
+        .unwrap()
+        .shares(old.get(&path(&[1, 0, 0, 0])).unwrap()));
+
+    // The old version is untouched
+    assert_eq!(old.to_value::<Page>().unwrap().blocks[0].words, vec!["a", "b"]);
+}
+
+#[test]
+fn inserting_and_removing_share_the_moved_items() {
+    let old = snapshot();
+
+    let inserted = old
+        .insert(&path(&[1, 1]), Node::from_value(&block(&["new"])))
+        .unwrap();
+    assert_eq!(inserted.get(&path(&[1])).unwrap().children().len(), 4);
+    assert!(inserted.get(&path(&[1, 0])).unwrap().shares(old.get(&path(&[1, 0])).unwrap()));
+    assert!(inserted.get(&path(&[1, 2])).unwrap().shares(old.get(&path(&[1, 1])).unwrap()));
+    assert!(inserted.get(&path(&[0])).unwrap().shares(old.get(&path(&[0])).unwrap()));
+
+    let removed = old.remove(&path(&[1, 0])).unwrap();
+    assert_eq!(removed.get(&path(&[1])).unwrap().children().len(), 2);
+    assert!(removed.get(&path(&[1, 0])).unwrap().shares(old.get(&path(&[1, 1])).unwrap()));
+    assert!(removed.get(&path(&[2])).unwrap().shares(old.get(&path(&[2])).unwrap()));
+}
+
+#[test]
+fn rejects_invalid_edits() {
+    let old = snapshot();
+    assert!(old.replace(&path(&[0]), Node::from_value(&1u32)).is_err());
+    assert!(old.replace(&path(&[1, 5]), text("x")).is_err());
+    assert!(old.insert(&path(&[1, 4]), Node::from_value(&block(&[]))).is_err());
+    assert!(old.insert(&path(&[1, 0]), text("x")).is_err());
+    assert!(old.remove(&path(&[0])).is_err());
+    assert!(old.remove(&NodePath::root()).is_err());
+}
+
+#[test]
+fn undoes_and_redoes() {
+    let first = snapshot();
+    let second = first.replace(&path(&[0]), text("Second")).unwrap();
+    let third = second.replace(&path(&[0]), text("Third")).unwrap();
+
+    let mut history = History::new(first);
+    assert!(!history.can_undo());
+    assert!(!history.undo());
+
+    history.commit(second);
+    history.commit(third);
+    assert_eq!(title(history.present()), "Third");
+    assert_eq!(history.past().len(), 2);
+
+    assert!(history.undo());
+    assert_eq!(title(history.present()), "Second");
+    assert!(history.undo());
+    assert_eq!(title(history.present()), "Title");
+    assert!(!history.can_undo());
+    assert!(history.can_redo());
+
+    assert!(history.redo());
+    assert_eq!(title(history.present()), "Second");
+    assert!(history.redo());
+    assert_eq!(title(history.present()), "Third");
+    assert!(!history.can_redo());
+    assert!(!history.redo());
+}
+
+#[test]
+fn committing_clears_the_redo_versions() {
+    let first = snapshot();
+    let second = first.replace(&path(&[0]), text("Second")).unwrap();
+    let other = first.replace(&path(&[0]), text("Other")).unwrap();
+
+    let mut history = History::new(first);
+    history.commit(second);
+    assert!(history.undo());
+    assert!(history.can_redo());
+
+    history.commit(other);
+    assert!(!history.can_redo());
+    assert!(!history.redo());
+    assert_eq!(title(history.present()), "Other");
+
+    assert!(history.undo());
+    assert_eq!(title(history.present()), "Title");
+}
+
+#[test]
+fn keeps_at_most_the_limit_of_past_versions() {
+    let mut history = History::with_limit(snapshot(), 2);
+    for name in &["1", "2", "3", "4"] {
+        let next = history.present().replace(&path(&[0]), text(name)).unwrap();
+        history.commit(next);
+    }
+
+    assert_eq!(
+        history.past().iter().map(title).collect::<Vec<_>>(),
+        vec!["2", "3"]
+    );
+    assert!(history.undo());
+    assert!(history.undo());
+    assert!(!history.undo());
+    assert_eq!(title(history.present()), "2");
+}
@@ -0,0 +1,126 @@
+#![feature(const_type_id)]
+
+extern crate semantic;
+#[macro_use]
+extern crate semantic_derive;
+
+use semantic::Node;
+use semantic::NodePath;
+use semantic::Store;
+
+#[derive(Debug, PartialEq, Semantic)]
+#[semantic(role = "document")]
+struct Page {
+    blocks: Vec<Block>,
+    aside: Vec<Block>,
+}
+
+#[derive(Debug, PartialEq, Semantic)]
+#[semantic(role = "block")]
+struct Block {
+    words: Vec<String>,
+}
+
+fn block(words: &[&str]) -> Block {
+    Block {
+        words: words.iter().map(|&w| w.to_owned()).collect(),
+    }
+}
+
+fn page() -> Page {
+    Page {
+        blocks: vec![block(&["a", "b"]), block(&["c"]), block(&["d"])],
+        aside: vec![],
+    }
+}
+
+fn store() -> Store {
+    Store::new(&page())
+}
+
+fn key(store: &Store, steps: &[usize]) -> semantic::NodeKey {
+    store.key(&NodePath::from_steps(steps.to_vec())).unwrap()
+}
+
+#[test]
+fn keeps_the_keys_of_moved_nodes() {
+    let mut store = store();
+    let moved = key(&store, &[0, 0]);
+    let word = key(&store, &[0, 0, 0, 1]);
+    let aside = key(&store, &[1]);
+
+    store.move_node(moved, aside, 0).unwrap();
+    assert_eq!(store.path(moved), Some(NodePath::from_steps(vec![1, 0])));
+    assert_eq!(store.path(word), Some(NodePath::from_steps(vec![1, 0, 0, 1])));
+    assert_eq!(store.parent(moved), Some(aside));
+
+    let blocks = key(&store, &[0]);
+    store.move_node(moved, blocks, 2).unwrap();
+    assert_eq!(
+        store.to_value::<Page>().unwrap(),
+        Page {
+            blocks: vec![block(&["c"]), block(&["d"]), block(&["a", "b"])],
+            aside: vec![],
+        }
+    );
+    assert_eq!(key(&store, &[0, 2]), moved);
+}
+
+#[test]
+fn forgets_removed_nodes_and_never_reuses_their_keys() {
+    let mut store = store();
+    let removed = key(&store, &[0, 1]);
+    let word = key(&store, &[0, 1, 0, 0]);
+    let next = key(&store, &[0, 2]);
+
+    store.remove(removed).unwrap();
+    assert!(!store.contains(removed));
+    assert!(!store.contains(word));
+    assert_eq!(key(&store, &[0, 1]), next);
+
+    let blocks = key(&store, &[0]);
+    let inserted = store
+        .insert(blocks, 1, Node::from_value(&block(&["e"])))
+        .unwrap();
+    assert_ne!(inserted, removed);
+    assert_ne!(inserted, word);
+    assert!(!store.contains(removed));
+}
+
+#[test]
+fn rejects_invalid_edits() {
+    let mut store = store();
+    let root = store.root();
+    let blocks = key(&store, &[0]);
+    let first = key(&store, &[0, 0]);
+
+    assert!(store.remove(root).is_err());
+    assert!(store.remove(blocks).is_err());
+    assert!(store.move_node(blocks, first, 0).is_err());
+    assert!(store.move_node(first, key(&store, &[0, 0, 0]), 0).is_err());
+    assert!(store.insert(blocks, 4, Node::from_value(&block(&[]))).is_err());
+    assert!(store
+        .insert(blocks, 0, Node::from_value(&"x".to_owned()))
+        .is_err());
+    assert_eq!(store.to_value::<Page>().unwrap(), page());
+}
+
+#[test]
+fn updates_without_disturbing_the_unchanged_items() {
+    let mut store = store();
+    let root = store.root();
+    let first = key(&store, &[0, 0]);
+    let last = key(&store, &[0, 2]);
+
+    store
+        .update(
+            root,
+            Node::from_value(&Page {
+                blocks: vec![block(&["a", "b"]), block(&["x"]), block(&["y"]), block(&["d"])],
+                aside: vec![],
+            }),
+        )
+        .unwrap();
+    assert_eq!(key(&store, &[0, 0]), first);
+    assert_eq!(key(&store, &[0, 3]), last);
+}
@@ -0,0 +1,245 @@
+#![feature(const_type_id)]
+
+extern crate semantic;
+#[macro_use]
+extern crate semantic_derive;
+
+use std::any;
+use std::ops::Bound;
+
+use semantic::Class;
+use semantic::Constraint;
+use semantic::DynamicSemantic;
+use semantic::Field;
+use semantic::Node;
+use semantic::NodePath;
+use semantic::Role;
+use semantic::Semantic;
+use semantic::Structure;
+use semantic::Value;
+use semantic::ViolationKind;
+
+#[derive(Debug, Semantic)]
+#[semantic(role = "block")]
+struct Heading {
+    #[semantic(range = "1..=6")]
+    depth: u32,
+    #[semantic(range = "0..10")]
+    indent: u32,
+    #[semantic(range = "..=100")]
+    percent: Option<i32>,
+    #[semantic(range = "-1..")]
+    offset: f64,
+}
+
+#[derive(Debug, Semantic)]
+#[semantic(role = "block")]
+struct Link {
+    #[semantic(non_empty)]
+    url: String,
+    #[semantic(non_empty)]
+    titles: Vec<String>,
+    #[semantic(non_empty)]
+    note: Option<String>,
+}
+
+#[derive(Debug, Semantic)]
+#[semantic(role = "document")]
+struct Page {
+    title: String,
+    #[semantic(children)]
+    blocks: Vec<Block>,
+}
+
+#[derive(Debug, Semantic)]
+#[semantic(role = "block")]
+enum Block {
+    Heading(Heading),
+    Link(Link),
+}
+
+/// A weight that must be positive, which the derive can't express since its ranges always include
+/// their start.
+#[derive(Debug)]
+struct Weight(f64);
+
+impl Semantic for Weight {
+    const CLASS: Class<'static> = Class {
+        id: any::TypeId::of::<Weight>(),
+        role: Role::Attribute,
+        structure: Structure::Record {
+            name: "Weight",
+            fields: &[Field {
+                name: "0",
+                ty: any::TypeId::of::<f64>(),
+                is_children: false,
+                default: None,
+                doc: None,
+                constraints: &[Constraint::Range {
+                    start: Bound::Excluded(0.0),
+                    end: Bound::Unbounded,
+                }],
+            }],
+        },
+        doc: None,
+        version: None,
+        tag: None,
+    };
+
+    fn from_node(_node: Node) -> semantic::Result<Self> {
+        unimplemented!()
+    }
+}
+
+impl DynamicSemantic for Weight {
+    fn class(&self) -> &'static Class<'static> {
+        &Self::CLASS
+    }
+
+    fn reflect(&self) -> Value {
+        Value::Record {
+            fields: vec![semantic::dynamic(&self.0)],
+        }
+    }
+}
+
+fn heading() -> Heading {
+    Heading {
+        depth: 1,
+        indent: 0,
+        percent: None,
+        offset: -1.0,
+    }
+}
+
+fn link() -> Link {
+    Link {
+        url: "https://example.com".to_owned(),
+        titles: vec!["Example".to_owned()],
+        note: Some("note".to_owned()),
+    }
+}
+
+fn out_of_range_fields(value: &DynamicSemantic) -> Vec<&'static str> {
+    semantic::validate(value)
+        .into_iter()
+        .map(|v| match v.kind {
+            ViolationKind::OutOfRange { field, .. } => field,
+            kind => panic!("expected a range violation, but got {:?}", kind),
+        }).collect()
+}
+
+#[test]
+fn values_within_their_ranges_are_valid() {
+    assert_eq!(Vec::<semantic::Violation>::new(), semantic::validate(&heading()));
+
+    let heading = Heading {
+        depth: 6,
+        indent: 9,
+        percent: Some(100),
+        offset: 1e9,
+    };
+    assert_eq!(Vec::<semantic::Violation>::new(), semantic::validate(&heading));
+}
+
+#[test]
+fn included_bounds_reject_values_beyond_them() {
+    let mut below = heading();
+    below.depth = 0;
+    assert_eq!(vec!["depth"], out_of_range_fields(&below));
+
+    let mut above = heading();
+    above.depth = 7;
+    let violations = semantic::validate(&above);
+    assert_eq!(
+        ViolationKind::OutOfRange {
+            field: "depth",
+            value: 7.0,
+            start: Bound::Included(1.0),
+            end: Bound::Included(6.0),
+        },
+        violations[0].kind
+    );
+    assert_eq!(NodePath::from_steps(vec![0]), violations[0].path);
+}
+
+#[test]
+fn excluded_bounds_reject_the_bound_itself() {
+    let mut heading = heading();
+    heading.indent = 10;
+    assert_eq!(vec!["indent"], out_of_range_fields(&heading));
+
+    assert_eq!(vec!["0"], out_of_range_fields(&Weight(0.0)));
+    assert_eq!(Vec::<&str>::new(), out_of_range_fields(&Weight(0.5)));
+}
+
+#[test]
+fn unbounded_ends_accept_anything_on_their_side() {
+    let mut heading = heading();
+    heading.percent = Some(i32::min_value());
+    heading.offset = 1e300;
+    assert_eq!(Vec::<&str>::new(), out_of_range_fields(&heading));
+
+    heading.percent = Some(101);
+    heading.offset = -1.5;
+    assert_eq!(vec!["percent", "offset"], out_of_range_fields(&heading));
+}
+
+#[test]
+fn non_empty_strings_and_collections_are_required() {
+    assert_eq!(Vec::<semantic::Violation>::new(), semantic::validate(&link()));
+
+    let empty = Link {
+        url: String::new(),
+        titles: vec![],
+        note: None,
+    };
+    let kinds = semantic::validate(&empty)
+        .into_iter()
+        .map(|v| v.kind)
+        .collect::<Vec<_>>();
+    assert_eq!(
+        vec![
+            ViolationKind::Empty { field: "url" },
+            ViolationKind::Empty { field: "titles" },
+            ViolationKind::Empty { field: "note" },
+        ],
+        kinds
+    );
+
+    // Empty strings inside a non-empty collection are fine
+    let mut blank_title = link();
+    blank_title.titles = vec![String::new()];
+    assert_eq!(Vec::<semantic::Violation>::new(), semantic::validate(&blank_title));
+}
+
+#[test]
+fn violations_point_to_nested_fields() {
+    let mut bad_link = link();
+    bad_link.url = String::new();
+    let mut bad_heading = heading();
+    bad_heading.indent = 11;
+    let page = Page {
+        title: "Page".to_owned(),
+        blocks: vec![
+            Block::Heading(heading()),
+            Block::Link(bad_link),
+            Block::Heading(bad_heading),
+        ],
+    };
+
+    let violations = semantic::validate(&page);
+
+    // blocks, then the item, then the newtype variant field, then the field itself
+    assert_eq!(2, violations.len());
+    assert_eq!(NodePath::from_steps(vec![1, 1, 0, 0]), violations[0].path);
+    assert_eq!(ViolationKind::Empty { field: "url" }, violations[0].kind);
+    assert_eq!(NodePath::from_steps(vec![1, 2, 0, 1]), violations[1].path);
+    assert_eq!(
+        concat!(
+            "/1/2/0/1: the value 11 of field \"indent\" is outside of the range ",
+            "Included(0.0)..Excluded(10.0)"
+        ),
+        violations[1].to_string()
+    );
+}
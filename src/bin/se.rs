@@ -9,6 +9,7 @@ fn main() {
             for cause in e.iter_chain() {
                 eprintln!("   caused by {}", cause);
             }
+            ::std::process::exit(1);
         }
     }
 }
@@ -1,8 +1,9 @@
 import * as React from "react";
+import { Value } from "slate";
 import { Editor } from "slate-react";
 
 interface Props {
-  value: any, // TODO: define schema
+  value: Value,
 }
 
 export default class MainEditor extends React.PureComponent<Props> {
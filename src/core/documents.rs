@@ -0,0 +1,188 @@
+//! The documents that are currently open in the editor.
+use std::cmp;
+use std::collections;
+use std::path;
+
+use failure;
+use semantic;
+
+use data;
+use error;
+use schema::se::slate as slate_proto;
+
+/// The replica identifier of the backend in shared documents; frames pick other identifiers.
+pub const REPLICA: u64 = 1;
+
+/// Keeps every open document in a `semantic::Store`, so that its nodes have stable keys that
+/// operations can refer to.
+///
+/// Every open document is also shared with the frames as a replica of a `semantic::Document`, so
+/// that they can edit it concurrently.
+#[derive(Debug, Default)]
+pub struct Documents {
+    open: collections::HashMap<path::PathBuf, Document>,
+}
+
+#[derive(Debug)]
+struct Document {
+    store: semantic::Store,
+    /// The document as it was opened, or after the last batch of operations that left it valid
+    /// or came from another replica, which is what an invalid batch of operations is rolled back
+    /// to.
+    valid: semantic::Snapshot,
+    /// The replica of the document that is shared with the frames, which is kept in sync with
+    /// `store`.
+    shared: semantic::Document,
+}
+
+impl Documents {
+    pub fn new() -> Self {
+        Documents::default()
+    }
+
+    /// Returns the open document at `path`, loading it from disk first if it isn't open yet.
+    pub fn open(&mut self, path: &path::Path) -> error::Result<&semantic::Store> {
+        Ok(&self.document(path)?.store)
+    }
+
+    fn document(&mut self, path: &path::Path) -> error::Result<&mut Document> {
+        if !self.open.contains_key(path) {
+            let data = data::Data::load(path)?;
+            let mut shared = semantic::Document::new(REPLICA, &data::Data::empty());
+            shared.update(&data)?;
+            let document = Document {
+                store: semantic::Store::new(&data),
+                valid: semantic::Snapshot::new(&data),
+                shared,
+            };
+            self.open.insert(path.to_owned(), document);
+        }
+
+        Ok(self.open.get_mut(path).expect("the document was just opened"))
+    }
+
+    /// Closes the document at `path`, discarding any unsaved changes.
+    ///
+    /// Returns whether the document was open.
+    pub fn close(&mut self, path: &path::Path) -> bool {
+        self.open.remove(path).is_some()
+    }
+
+    /// Applies a batch of Slate operations to the document at `path`, opening it if needed.
+    ///
+    /// Either all of the operations are applied, or none of them are.  Nodes that the operations
+    /// don't touch keep their keys, and so do blocks that they move.  Returns the operations that
+    /// bring the replicas of the shared document up to date.
+    pub fn apply_slate_operations(
+        &mut self,
+        path: &path::Path,
+        operations: &[slate_proto::Operation],
+    ) -> error::Result<Vec<semantic::Operation>> {
+        use semantic::Semantic;
+
+        let document = self.document(path)?;
+        let root = document.store.root();
+        let markdown = document.store.children(root)[0];
+        if document.store.class(markdown).map(|c| c.id) != Some(data::markdown::Markdown::CLASS.id)
+        {
+            return Err(failure::err_msg(
+                "only Markdown documents can be edited with Slate",
+            ));
+        }
+
+        let mut edited = document.valid.clone();
+        let result =
+            data::slate::apply_operations(&mut document.store, &mut edited, markdown, operations)
+                .and_then(|changed| validate(&document.store, changed));
+
+        match result {
+            Ok(()) => {
+                document.valid = edited;
+                Ok(document.shared.update(&document.valid.to_node())?)
+            }
+            Err(e) => {
+                // Only the nodes that differ from the valid version change keys
+                document.store.update(root, document.valid.to_node())?;
+                Err(e)
+            }
+        }
+    }
+
+    /// Applies operations from another replica of the shared document at `path`, opening it if
+    /// needed, and returns the operations of the document after the first `known` ones.
+    ///
+    /// The operations are applied even if concurrent edits combine into a document that violates
+    /// its schema, since the other replicas apply them too, but such a document can't be saved
+    /// until it is fixed.
+    pub fn apply_shared_operations(
+        &mut self,
+        path: &path::Path,
+        operations: Vec<semantic::Operation>,
+        known: usize,
+    ) -> error::Result<Vec<semantic::Operation>> {
+        let document = self.document(path)?;
+        for operation in operations {
+            document.shared.apply(operation);
+        }
+
+        let node = document.shared.to_node();
+        let root = document.store.root();
+        document.store.update(root, node.clone())?;
+        document.valid = semantic::Snapshot::from_node(node);
+
+        let operations = document.shared.operations();
+        Ok(operations[cmp::min(known, operations.len())..].to_vec())
+    }
+
+    /// Returns the open document at `path`, without opening it.
+    pub fn get(&self, path: &path::Path) -> error::Result<&semantic::Store> {
+        self.open.get(path).map(|d| &d.store).ok_or_else(|| {
+            failure::err_msg(format!("document is not open: {}", path.to_string_lossy()))
+        })
+    }
+
+    /// Validates the open document at `path` and writes it back to disk.
+    pub fn save(&self, path: &path::Path) -> error::Result<()> {
+        let store = self.get(path)?;
+        let data = store.to_value::<data::Data>()?;
+        data.validate()?;
+        data.save(path)
+    }
+}
+
+/// Checks the nodes that a batch of operations changed, since the rest of the document is as
+/// valid as it was before.
+fn validate(store: &semantic::Store, changed: Vec<semantic::NodeKey>) -> error::Result<()> {
+    let changed = changed.into_iter().collect::<collections::HashSet<_>>();
+    let mut outermost = changed
+        .iter()
+        .cloned()
+        .filter(|&key| {
+            let mut ancestor = store.parent(key);
+            while let Some(a) = ancestor {
+                if changed.contains(&a) {
+                    return false;
+                }
+                ancestor = store.parent(a);
+            }
+            true
+        })
+        .collect::<Vec<_>>();
+    outermost.sort();
+
+    let messages = outermost
+        .into_iter()
+        .filter_map(|key| store.to_node(key))
+        .flat_map(|node| semantic::validate(&node))
+        .map(|violation| violation.to_string())
+        .collect::<Vec<_>>();
+
+    if messages.is_empty() {
+        Ok(())
+    } else {
+        Err(failure::err_msg(format!(
+            "invalid document: {}",
+            messages.join("; ")
+        )))
+    }
+}
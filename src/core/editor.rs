@@ -1,37 +1,109 @@
+//! The editor service, which front-ends talk to over RPC.
 use std::fs;
+use std::path;
+use std::sync;
 
+use failure;
 use futures;
+use semantic;
+use serde_json;
 use slog;
 
+use core::documents;
+use data;
 use error;
 use schema::se::service as service_proto;
 
+/// The implementation of the editor service, which the RPC server dispatches requests to.
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "cargo-clippy", allow(stutter))]
 pub struct SemanticEditor {
     log: slog::Logger,
+    documents: sync::Arc<sync::Mutex<documents::Documents>>,
 }
 
 impl SemanticEditor {
+    /// Creates an editor without any open documents.
     pub fn new(log: slog::Logger) -> Self {
-        SemanticEditor { log }
+        let documents = sync::Arc::new(sync::Mutex::new(documents::Documents::new()));
+        SemanticEditor { log, documents }
+    }
+
+    /// Runs `f` with exclusive access to the open documents.
+    fn with_documents<F, A>(&self, f: F) -> error::Result<A>
+    where
+        F: FnOnce(&mut documents::Documents) -> error::Result<A>,
+    {
+        let mut documents = self
+            .documents
+            .lock()
+            .map_err(|_| failure::err_msg("the open documents are poisoned by an earlier panic"))?;
+        f(&mut documents)
     }
 }
 
 impl service_proto::SemanticEditor for SemanticEditor {
     type Error = error::NestedError;
+    type CheckCompatibilityFuture = Box<
+        ::futures::Future<Item = service_proto::CheckCompatibilityResponse, Error = Self::Error>
+            + Send,
+    >;
     type FetchSlateSchemaFuture = Box<
         ::futures::Future<Item = service_proto::FetchSlateSchemaResponse, Error = Self::Error>
             + Send,
     >;
     type ListFilesFuture =
         Box<::futures::Future<Item = service_proto::ListFilesResponse, Error = Self::Error> + Send>;
+    type SelectNodesFuture = Box<
+        ::futures::Future<Item = service_proto::SelectNodesResponse, Error = Self::Error> + Send,
+    >;
+    type OpenDocumentFuture = Box<
+        ::futures::Future<Item = service_proto::OpenDocumentResponse, Error = Self::Error> + Send,
+    >;
+    type SaveDocumentFuture = Box<
+        ::futures::Future<Item = service_proto::SaveDocumentResponse, Error = Self::Error> + Send,
+    >;
+    type CloseDocumentFuture = Box<
+        ::futures::Future<Item = service_proto::CloseDocumentResponse, Error = Self::Error>
+            + Send,
+    >;
+    type ApplySlateOperationsFuture = Box<
+        ::futures::Future<Item = service_proto::ApplySlateOperationsResponse, Error = Self::Error>
+            + Send,
+    >;
+    type ApplySharedOperationsFuture = Box<
+        ::futures::Future<Item = service_proto::ApplySharedOperationsResponse, Error = Self::Error>
+            + Send,
+    >;
+
+    fn check_compatibility(
+        &self,
+        input: service_proto::CheckCompatibilityRequest,
+    ) -> Self::CheckCompatibilityFuture {
+        info!(self.log, "check_compatibility called";
+        "schema_hash" => format!("{:016x}", input.schema_hash));
+        let schema_hash = data::schema_hash();
+        let compatible = input.schema_hash == schema_hash;
+        if !compatible {
+            warn!(self.log, "the frontend was built with a different document model";
+            "frontend" => format!("{:016x}", input.schema_hash),
+            "backend" => format!("{:016x}", schema_hash));
+        }
+        Box::new(futures::future::ok(service_proto::CheckCompatibilityResponse {
+            compatible,
+            schema_hash,
+        }))
+    }
 
     fn fetch_slate_schema(
         &self,
         _input: service_proto::FetchSlateSchemaRequest,
     ) -> Self::FetchSlateSchemaFuture {
-        unimplemented!()
+        info!(self.log, "fetch_slate_schema called");
+        let registry = semantic::Registry::of::<data::markdown::Markdown>();
+        Box::new(futures::future::ok(service_proto::FetchSlateSchemaResponse {
+            schema: Some(data::slate::schema(&registry)),
+        }))
     }
 
     fn list_files(&self, input: service_proto::ListFilesRequest) -> Self::ListFilesFuture {
@@ -58,6 +130,117 @@ impl service_proto::SemanticEditor for SemanticEditor {
             ))),
         }
     }
+
+    fn select_nodes(&self, input: service_proto::SelectNodesRequest) -> Self::SelectNodesFuture {
+        info!(self.log, "select_nodes called";
+        "path" => &input.path, "selector" => &input.selector);
+        Box::new(futures::future::result(
+            self.with_documents(|d| select_nodes(d, &input)).map_err(error::nested_error),
+        ))
+    }
+
+    fn open_document(
+        &self,
+        input: service_proto::OpenDocumentRequest,
+    ) -> Self::OpenDocumentFuture {
+        info!(self.log, "open_document called"; "path" => &input.path);
+        let result = self.with_documents(|d| {
+            let store = d.open(path::Path::new(&input.path))?;
+            let data = store.to_value::<data::Data>()?;
+            Ok(service_proto::OpenDocumentResponse {
+                root: store.root().0,
+                document: Some(data::proto::to_proto(&data)),
+            })
+        });
+        Box::new(futures::future::result(result.map_err(error::nested_error)))
+    }
+
+    fn save_document(
+        &self,
+        input: service_proto::SaveDocumentRequest,
+    ) -> Self::SaveDocumentFuture {
+        info!(self.log, "save_document called"; "path" => &input.path);
+        let result = self.with_documents(|d| {
+            d.save(path::Path::new(&input.path))?;
+            Ok(service_proto::SaveDocumentResponse {})
+        });
+        Box::new(futures::future::result(result.map_err(error::nested_error)))
+    }
+
+    fn close_document(
+        &self,
+        input: service_proto::CloseDocumentRequest,
+    ) -> Self::CloseDocumentFuture {
+        info!(self.log, "close_document called"; "path" => &input.path);
+        let result = self.with_documents(|d| {
+            Ok(service_proto::CloseDocumentResponse {
+                was_open: d.close(path::Path::new(&input.path)),
+            })
+        });
+        Box::new(futures::future::result(result.map_err(error::nested_error)))
+    }
+
+    fn apply_slate_operations(
+        &self,
+        input: service_proto::ApplySlateOperationsRequest,
+    ) -> Self::ApplySlateOperationsFuture {
+        info!(self.log, "apply_slate_operations called";
+        "path" => &input.path, "operations" => input.operation.len());
+        let result = self.with_documents(|d| {
+            d.apply_slate_operations(path::Path::new(&input.path), &input.operation)?;
+            Ok(service_proto::ApplySlateOperationsResponse {})
+        });
+        Box::new(futures::future::result(result.map_err(error::nested_error)))
+    }
+
+    fn apply_shared_operations(
+        &self,
+        input: service_proto::ApplySharedOperationsRequest,
+    ) -> Self::ApplySharedOperationsFuture {
+        info!(self.log, "apply_shared_operations called";
+        "path" => &input.path, "operations" => input.operation.len(), "known" => input.known);
+        let result = self.with_documents(|d| {
+            let registry = semantic::Registry::of::<data::Data>();
+            let operations = input
+                .operation
+                .iter()
+                .map(|o| -> error::Result<_> {
+                    Ok(semantic::Operation::from_json(&registry, &serde_json::from_str(o)?)?)
+                }).collect::<error::Result<Vec<_>>>()?;
+            let operations = d.apply_shared_operations(
+                path::Path::new(&input.path),
+                operations,
+                input.known as usize,
+            )?;
+            Ok(service_proto::ApplySharedOperationsResponse {
+                operation: operations
+                    .iter()
+                    .map(|o| -> error::Result<_> { Ok(o.to_json(&registry)?.to_string()) })
+                    .collect::<error::Result<_>>()?,
+            })
+        });
+        Box::new(futures::future::result(result.map_err(error::nested_error)))
+    }
+}
+
+fn select_nodes(
+    documents: &documents::Documents,
+    input: &service_proto::SelectNodesRequest,
+) -> error::Result<service_proto::SelectNodesResponse> {
+    use schema::se::service::select_nodes_response;
+
+    let store = documents.get(path::Path::new(&input.path))?;
+    let root = store
+        .to_node(store.root())
+        .expect("the root of a store is always present");
+    let node = semantic::select(&root, &input.selector)?
+        .into_iter()
+        .map(|path| select_nodes_response::NodePath {
+            step: path.steps().iter().map(|&s| s as u32).collect(),
+            key: store.key(&path).map(|k| k.0).unwrap_or_default(),
+        }).collect();
+
+    Ok(service_proto::SelectNodesResponse { node })
 }
 
 fn to_kind(ty: fs::FileType) -> Option<service_proto::list_files_response::file::Kind> {
@@ -0,0 +1,62 @@
+//! A git merge driver that merges documents semantically.
+//!
+//! Git passes the three versions of a document to the driver as temporary files without the
+//! original file name, so the format is picked from the path of the document in the repository
+//! (`%P`) when it is known, and by sniffing the content otherwise.  The merged document is written
+//! back in the format that our version was read in.
+//!
+//! Only the formats in `FormatRegistry::all` can be merged.  In particular, Markdown source files
+//! (`.md`) are not supported, since documents can't be written back as Markdown text; don't
+//! assign this driver to them in `.gitattributes`.
+use std::path;
+
+use failure;
+use semantic;
+use slog;
+
+use data;
+use error;
+
+/// Merges the changes from `base` to `theirs` into `ours`, and writes the result to `ours`.
+///
+/// `name` is the path of the document in the repository, which is used to pick the format of the
+/// three files.  Without it, the format is picked by sniffing them.
+///
+/// Conflicts are logged and make this fail, so that git asks the user to resolve them.  The
+/// written document then contains one side of every conflict, as described by
+/// `semantic::ConflictKind`: our version where both sides changed a node, and the surviving
+/// version where one side deleted a node that the other side changed.
+pub fn run(
+    log: &slog::Logger,
+    base: &path::Path,
+    ours: &path::Path,
+    theirs: &path::Path,
+    name: Option<&path::Path>,
+) -> error::Result<()> {
+    let registry = data::format::FormatRegistry::all();
+    let (base_data, _) = registry.load_as(base, name.unwrap_or(base))?;
+    let (ours_data, format) = registry.load_as(ours, name.unwrap_or(ours))?;
+    let (theirs_data, _) = registry.load_as(theirs, name.unwrap_or(theirs))?;
+
+    let merge = semantic::merge(&base_data, &ours_data, &theirs_data);
+    for conflict in &merge.conflicts {
+        warn!(log, "Merge conflict"; "conflict" => conflict.to_string());
+    }
+
+    let is_clean = merge.is_clean();
+    let conflicts = merge.conflicts.len();
+    let merged: data::Data = merge.node.into_value()?;
+    merged.validate()?;
+    data::format::save_in(format, &merged, ours)?;
+
+    let name = name.unwrap_or(ours).to_string_lossy().into_owned();
+    if is_clean {
+        info!(log, "Merged cleanly"; "path" => name);
+        Ok(())
+    } else {
+        Err(failure::err_msg(format!(
+            "the merge of {} has {} conflicts",
+            name, conflicts
+        )))
+    }
+}
@@ -7,9 +7,12 @@ use error;
 use schema::se::service as service_proto;
 use version;
 
-mod editor;
+mod documents;
+pub mod editor;
 mod logger;
+pub mod merge_driver;
 mod options;
+mod schema;
 mod server;
 
 /// Runs the semantic editor core.
@@ -27,10 +30,27 @@ pub fn run() -> error::Result<()> {
     info!(log, "Parsed command-line options";
     "options" => format!("{:?}", options));
 
-    let editor = editor::SemanticEditor::new(log.new(o!("component" => "editor")));
-    let server_handler = service_proto::SemanticEditorServer::new(editor);
-    let server = server::Server::new(log.new(o!("component" => "server")), server_handler);
-    server.run()?;
+    match options.command {
+        Some(options::Command::MergeDriver {
+            ref base,
+            ref ours,
+            ref theirs,
+            ref path,
+        }) => {
+            let log = log.new(o!("component" => "merge-driver"));
+            merge_driver::run(&log, base, ours, theirs, path.as_ref().map(|p| p.as_path()))?;
+        }
+        Some(options::Command::Schema { format }) => {
+            schema::run(format)?;
+        }
+        None => {
+            let editor = editor::SemanticEditor::new(log.new(o!("component" => "editor")));
+            let server_handler = service_proto::SemanticEditorServer::new(editor);
+            let server =
+                server::Server::new(log.new(o!("component" => "server")), server_handler);
+            server.run()?;
+        }
+    }
 
     info!(log, "program is terminating");
     Ok(())
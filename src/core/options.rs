@@ -1,3 +1,8 @@
+use std::path;
+use std::str;
+
+use failure;
+
 #[derive(StructOpt, Debug)]
 #[structopt(name = "se")]
 pub struct Options {
@@ -31,4 +36,62 @@ pub struct Options {
     /// Log to journald (no-op unless this program was compiled with the "journald" feature)
     #[structopt(long = "journald")]
     pub journald: bool,
+
+    /// A command to run instead of the editor.
+    #[structopt(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(StructOpt, Debug)]
+pub enum Command {
+    /// Merge three versions of a document, for use as a git merge driver.
+    ///
+    /// The merged document is written to the file with our version.  To use it, run `git config
+    /// merge.semantic.driver "se merge-driver %O %A %B %P"` and add a `merge=semantic` attribute
+    /// to the `*.mdast.json` and `*.json` documents in `.gitattributes`.
+    #[structopt(name = "merge-driver")]
+    MergeDriver {
+        /// The common ancestor version of the document.
+        #[structopt(parse(from_os_str))]
+        base: path::PathBuf,
+        /// Our version of the document, which is replaced by the merged version.
+        #[structopt(parse(from_os_str))]
+        ours: path::PathBuf,
+        /// Their version of the document.
+        #[structopt(parse(from_os_str))]
+        theirs: path::PathBuf,
+        /// The path of the document in the repository, which decides its format.  If it is
+        /// missing, the format is detected from the content of the files.
+        #[structopt(parse(from_os_str))]
+        path: Option<path::PathBuf>,
+    },
+    /// Print a schema of the documents that the editor supports, for validating them with other
+    /// tools.
+    #[structopt(name = "schema")]
+    Schema {
+        /// The kind of schema to print ("json-schema" or "proto").
+        #[structopt(long = "format", default_value = "json-schema")]
+        format: SchemaFormat,
+    },
+}
+
+/// The formats that `se schema` can print.
+#[derive(Clone, Copy, Debug)]
+pub enum SchemaFormat {
+    /// A JSON Schema of the semantic JSON format of documents.
+    JsonSchema,
+    /// Protocol Buffers definitions of messages that mirror the document model.
+    Proto,
+}
+
+impl str::FromStr for SchemaFormat {
+    type Err = failure::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "json-schema" => Ok(SchemaFormat::JsonSchema),
+            "proto" => Ok(SchemaFormat::Proto),
+            _ => Err(failure::err_msg(format!("unknown schema format: {}", s))),
+        }
+    }
 }
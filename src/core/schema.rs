@@ -0,0 +1,35 @@
+//! Prints schemas of the documents that the editor supports.
+use std::io;
+
+use semantic;
+use serde_json;
+
+use core::options;
+use data;
+use error;
+
+/// The package of generated Protocol Buffers definitions.
+const PROTO_PACKAGE: &str = "se.data";
+
+/// Writes a schema of `data::Data` in the specified format to stdout.
+pub fn run(format: options::SchemaFormat) -> error::Result<()> {
+    use std::io::Write;
+
+    let registry = semantic::Registry::of::<data::Data>();
+    let stdout = io::stdout();
+    let mut stdout = stdout.lock();
+
+    match format {
+        options::SchemaFormat::JsonSchema => {
+            let schema = semantic::json_schema::schema(&registry);
+            serde_json::to_writer_pretty(&mut stdout, &schema)?;
+            writeln!(stdout)?;
+        }
+        options::SchemaFormat::Proto => {
+            let definitions = semantic::protobuf::definitions(&registry, PROTO_PACKAGE);
+            stdout.write_all(definitions.as_bytes())?;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,628 @@
+//! Plain JSON documents, like `package.json`, and JSON with comments, like `tsconfig.json`.
+//!
+//! Reading tolerates comments and trailing commas, and keeps the order of object members.
+//! Writing always produces the same canonical layout, with two spaces of indentation and every
+//! member and item on a line of its own.
+use std::io;
+use std::mem;
+
+use failure;
+use serde_json;
+
+use data::format::Format;
+use data::json;
+use data::Data;
+use error;
+
+/// The JSON format, which also reads JSONC.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Json {
+    /// Whether to write the comments of documents.  Otherwise, the output is plain JSON, which is
+    /// what most tools other than editors expect.
+    pub keep_comments: bool,
+    /// Whether to write the members of objects sorted by key, rather than in their original order.
+    pub sort_keys: bool,
+}
+
+impl Format for Json {
+    fn name(&self) -> &'static str {
+        "json"
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &["json", "jsonc"]
+    }
+
+    fn mime_types(&self) -> &'static [&'static str] {
+        &["application/json"]
+    }
+
+    fn sniff(&self, content: &[u8]) -> bool {
+        let head = String::from_utf8_lossy(&content[..content.len().min(1024)]);
+        let head = head.trim_left();
+        head.starts_with("//")
+            || head.starts_with("/*")
+            || head.starts_with(|c: char| "{[\"-tfn".contains(c) || c.is_ascii_digit())
+    }
+
+    fn can_write(&self, data: &Data) -> bool {
+        match *data {
+            Data::Json(_) => true,
+            Data::Markdown(_) => false,
+        }
+    }
+
+    fn read(&self, reader: &mut io::Read) -> error::Result<Data> {
+        let mut text = String::new();
+        reader.read_to_string(&mut text)?;
+        Ok(Data::Json(parse(&text)?))
+    }
+
+    fn write(&self, data: &Data, writer: &mut io::Write) -> error::Result<()> {
+        let document = match *data {
+            Data::Json(ref document) => document,
+            Data::Markdown(_) => return Err(failure::err_msg("only JSON documents can be written")),
+        };
+        writer.write_all(self.print(document)?.as_bytes())?;
+        Ok(())
+    }
+}
+
+impl Json {
+    /// Prints a document in the canonical layout.
+    pub fn print(&self, document: &json::Json) -> error::Result<String> {
+        let mut printer = Printer {
+            style: *self,
+            output: String::new(),
+            indent: 0,
+        };
+
+        printer.comments(&document.comments)?;
+        printer.value(&document.value)?;
+        printer.output.push('\n');
+        printer.comments(&document.trailing_comments)?;
+        Ok(printer.output)
+    }
+}
+
+/// Parses a JSON document, which may contain comments and trailing commas.
+pub fn parse(text: &str) -> error::Result<json::Json> {
+    let mut parser = Parser { text, position: 0 };
+
+    // A byte order mark is not part of the document
+    if parser.rest().starts_with('\u{feff}') {
+        parser.position += '\u{feff}'.len_utf8();
+    }
+
+    let comments = parser.comments()?;
+    let value = parser.value()?;
+    let trailing_comments = parser.comments()?;
+    if parser.peek().is_some() {
+        return Err(parser.error("expected the end of the document"));
+    }
+
+    Ok(json::Json {
+        comments,
+        value,
+        trailing_comments,
+    })
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    /// The byte offset of the next character.
+    position: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn rest(&self) -> &'a str {
+        &self.text[self.position..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.position += c.len_utf8();
+        Some(c)
+    }
+
+    fn expect(&mut self, expected: char) -> error::Result<()> {
+        if self.peek() == Some(expected) {
+            self.bump();
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected {:?}", expected)))
+        }
+    }
+
+    /// An error at the current position, with a 1-based line and column.
+    fn error(&self, message: &str) -> error::Error {
+        let before = &self.text[..self.position];
+        let line = before.matches('\n').count() + 1;
+        let column = before.rsplit('\n').next().unwrap_or("").chars().count() + 1;
+        let found = match self.peek() {
+            Some(c) => format!("{:?}", c),
+            None => "the end of the document".to_owned(),
+        };
+        failure::err_msg(format!(
+            "{}:{}: {}, but found {}",
+            line, column, message, found
+        ))
+    }
+
+    /// Skips whitespace, and collects the comments in it.
+    fn comments(&mut self) -> error::Result<Vec<json::Comment>> {
+        let mut comments = Vec::new();
+
+        loop {
+            self.skip_whitespace(true);
+            match self.comment()? {
+                Some(comment) => comments.push(comment),
+                None => return Ok(comments),
+            }
+        }
+    }
+
+    /// Collects a comment that starts on the current line, like the one in `"a": 1, // note`.
+    fn line_comment(&mut self) -> error::Result<Option<json::Comment>> {
+        self.skip_whitespace(false);
+        self.comment()
+    }
+
+    fn skip_whitespace(&mut self, newlines: bool) {
+        while let Some(c) = self.peek() {
+            match c {
+                ' ' | '\t' | '\r' => {}
+                '\n' if newlines => {}
+                _ => return,
+            }
+            self.bump();
+        }
+    }
+
+    fn comment(&mut self) -> error::Result<Option<json::Comment>> {
+        let rest = self.rest();
+
+        if rest.starts_with("//") {
+            let end = rest.find('\n').unwrap_or(rest.len());
+            let text = rest[2..end].trim_right_matches('\r').to_owned();
+            self.position += end;
+            Ok(Some(json::Comment {
+                text,
+                is_block: false,
+            }))
+        } else if rest.starts_with("/*") {
+            let end = rest[2..]
+                .find("*/")
+                .ok_or_else(|| self.error("expected the end of the block comment"))?
+                + 2;
+            let text = rest[2..end].to_owned();
+            self.position += end + 2;
+            Ok(Some(json::Comment {
+                text,
+                is_block: true,
+            }))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn value(&mut self) -> error::Result<json::Value> {
+        match self.peek() {
+            Some('{') => Ok(json::Value::Object(self.object()?)),
+            Some('[') => Ok(json::Value::Array(self.array()?)),
+            Some('"') => Ok(json::Value::String(json::StringValue {
+                value: self.string()?,
+            })),
+            Some(c) if c == '-' || c.is_ascii_digit() => {
+                Ok(json::Value::Number(json::NumberValue {
+                    value: self.number()?,
+                }))
+            }
+            _ => self.literal(),
+        }
+    }
+
+    fn literal(&mut self) -> error::Result<json::Value> {
+        if self.keyword("true") {
+            Ok(json::Value::Boolean(json::BooleanValue { value: true }))
+        } else if self.keyword("false") {
+            Ok(json::Value::Boolean(json::BooleanValue { value: false }))
+        } else if self.keyword("null") {
+            Ok(json::Value::Null(json::NullValue))
+        } else {
+            Err(self.error("expected a value"))
+        }
+    }
+
+    fn object(&mut self) -> error::Result<json::ObjectValue> {
+        self.expect('{')?;
+        let mut members = Vec::new();
+        // Comments between a value and the following comma belong to the next member
+        let mut pending = Vec::new();
+
+        loop {
+            let mut comments = mem::replace(&mut pending, Vec::new());
+            comments.extend(self.comments()?);
+            if self.peek() == Some('}') {
+                self.bump();
+                return Ok(json::ObjectValue {
+                    members,
+                    trailing_comments: comments,
+                });
+            }
+
+            if self.peek() != Some('"') {
+                return Err(self.error("expected a key"));
+            }
+            let key = self.string()?;
+            comments.extend(self.comments()?);
+            self.expect(':')?;
+            comments.extend(self.comments()?);
+            let value = self.value()?;
+            let (line_comment, is_last) = self.separator('}', &mut pending)?;
+
+            members.push(json::Member {
+                comments,
+                key,
+                value,
+                line_comment,
+            });
+            if is_last {
+                self.bump();
+                return Ok(json::ObjectValue {
+                    members,
+                    trailing_comments: pending,
+                });
+            }
+        }
+    }
+
+    fn array(&mut self) -> error::Result<json::ArrayValue> {
+        self.expect('[')?;
+        let mut items = Vec::new();
+        let mut pending = Vec::new();
+
+        loop {
+            let mut comments = mem::replace(&mut pending, Vec::new());
+            comments.extend(self.comments()?);
+            if self.peek() == Some(']') {
+                self.bump();
+                return Ok(json::ArrayValue {
+                    items,
+                    trailing_comments: comments,
+                });
+            }
+
+            let value = self.value()?;
+            let (line_comment, is_last) = self.separator(']', &mut pending)?;
+
+            items.push(json::Item {
+                comments,
+                value,
+                line_comment,
+            });
+            if is_last {
+                self.bump();
+                return Ok(json::ArrayValue {
+                    items,
+                    trailing_comments: pending,
+                });
+            }
+        }
+    }
+
+    /// Parses what follows a member or item: a comma or the closing bracket `close`, along with
+    /// any comments around them.
+    ///
+    /// Returns the comment on the line of the value, and whether the closing bracket is next.
+    /// Comments on later lines are added to `pending`.
+    fn separator(
+        &mut self,
+        close: char,
+        pending: &mut Vec<json::Comment>,
+    ) -> error::Result<(Option<json::Comment>, bool)> {
+        let mut line_comment = self.line_comment()?;
+        pending.extend(self.comments()?);
+
+        match self.peek() {
+            Some(',') => {
+                self.bump();
+                if line_comment.is_none() && pending.is_empty() {
+                    line_comment = self.line_comment()?;
+                }
+                Ok((line_comment, false))
+            }
+            Some(c) if c == close => Ok((line_comment, true)),
+            _ => Err(self.error(&format!("expected ',' or {:?}", close))),
+        }
+    }
+
+    fn keyword(&mut self, keyword: &str) -> bool {
+        let rest = self.rest();
+        let is_keyword = rest.starts_with(keyword) && !rest[keyword.len()..]
+            .starts_with(|c: char| c.is_alphanumeric() || c == '_');
+        if is_keyword {
+            self.position += keyword.len();
+        }
+        is_keyword
+    }
+
+    fn string(&mut self) -> error::Result<String> {
+        self.expect('"')?;
+        let mut result = String::new();
+
+        loop {
+            match self.bump() {
+                Some('"') => return Ok(result),
+                Some('\\') => {
+                    let c = match self.peek() {
+                        Some('"') => '"',
+                        Some('\\') => '\\',
+                        Some('/') => '/',
+                        Some('b') => '\u{8}',
+                        Some('f') => '\u{c}',
+                        Some('n') => '\n',
+                        Some('r') => '\r',
+                        Some('t') => '\t',
+                        Some('u') => {
+                            self.bump();
+                            result.push(self.unicode_escape()?);
+                            continue;
+                        }
+                        _ => return Err(self.error("expected an escape sequence")),
+                    };
+                    self.bump();
+                    result.push(c);
+                }
+                Some(c) if c < ' ' => {
+                    return Err(self.error("expected an escape sequence instead of a control \
+                                           character"))
+                }
+                Some(c) => result.push(c),
+                None => return Err(self.error("expected the end of the string")),
+            }
+        }
+    }
+
+    /// Parses the rest of a `\u` escape, which might be the first half of a surrogate pair.
+    fn unicode_escape(&mut self) -> error::Result<char> {
+        let first = self.hex4()?;
+
+        let code = if first >= 0xd800 && first < 0xdc00 {
+            if !self.rest().starts_with("\\u") {
+                return Err(self.error("expected the second half of a surrogate pair"));
+            }
+            self.position += 2;
+            let second = self.hex4()?;
+            if second < 0xdc00 || second >= 0xe000 {
+                return Err(self.error("expected the second half of a surrogate pair"));
+            }
+            0x1_0000 + ((first - 0xd800) << 10) + (second - 0xdc00)
+        } else {
+            first
+        };
+
+        ::std::char::from_u32(code).ok_or_else(|| self.error("expected a valid code point"))
+    }
+
+    fn hex4(&mut self) -> error::Result<u32> {
+        let digits = self.rest().get(..4).unwrap_or("");
+        match u32::from_str_radix(digits, 16) {
+            Ok(code) if digits.chars().all(|c| c.is_ascii_hexdigit()) => {
+                self.position += 4;
+                Ok(code)
+            }
+            _ => Err(self.error("expected four hexadecimal digits")),
+        }
+    }
+
+    fn number(&mut self) -> error::Result<f64> {
+        let start = self.position;
+
+        if self.peek() == Some('-') {
+            self.bump();
+        }
+        match self.peek() {
+            Some('0') => {
+                self.bump();
+            }
+            Some(c) if c.is_ascii_digit() => self.digits(),
+            _ => return Err(self.error("expected a digit")),
+        }
+        if self.peek() == Some('.') {
+            self.bump();
+            self.require_digits()?;
+        }
+        if self.peek() == Some('e') || self.peek() == Some('E') {
+            self.bump();
+            if self.peek() == Some('+') || self.peek() == Some('-') {
+                self.bump();
+            }
+            self.require_digits()?;
+        }
+
+        self.text[start..self.position]
+            .parse()
+            .map_err(|_| self.error("expected a number"))
+    }
+
+    fn digits(&mut self) {
+        while self.peek().map_or(false, |c| c.is_ascii_digit()) {
+            self.bump();
+        }
+    }
+
+    fn require_digits(&mut self) -> error::Result<()> {
+        if self.peek().map_or(false, |c| c.is_ascii_digit()) {
+            self.digits();
+            Ok(())
+        } else {
+            Err(self.error("expected a digit"))
+        }
+    }
+}
+
+struct Printer {
+    style: Json,
+    output: String,
+    indent: usize,
+}
+
+impl Printer {
+    fn value(&mut self, value: &json::Value) -> error::Result<()> {
+        match *value {
+            json::Value::Object(ref object) => self.object(object),
+            json::Value::Array(ref array) => self.array(array),
+            json::Value::String(ref string) => {
+                self.output.push_str(&serde_json::to_string(&string.value)?);
+                Ok(())
+            }
+            json::Value::Number(number) => {
+                let value = number.value;
+                // Exact integers are written without a fraction, like JavaScript does
+                if value.fract() == 0.0 && value.abs() < 2f64.powi(53) {
+                    self.output.push_str(&value.to_string());
+                } else {
+                    let number = serde_json::Number::from_f64(value).ok_or_else(|| {
+                        failure::err_msg(format!("{} can't be written as a JSON number", value))
+                    })?;
+                    self.output.push_str(&number.to_string());
+                }
+                Ok(())
+            }
+            json::Value::Boolean(boolean) => {
+                self.output.push_str(if boolean.value { "true" } else { "false" });
+                Ok(())
+            }
+            json::Value::Null(_) => {
+                self.output.push_str("null");
+                Ok(())
+            }
+        }
+    }
+
+    fn object(&mut self, object: &json::ObjectValue) -> error::Result<()> {
+        let mut members = object.members.iter().collect::<Vec<_>>();
+        if self.style.sort_keys {
+            members.sort_by(|a, b| a.key.cmp(&b.key));
+        }
+
+        self.entries(
+            ('{', '}'),
+            &members,
+            &object.trailing_comments,
+            |printer, member| {
+                printer.output.push_str(&serde_json::to_string(&member.key)?);
+                printer.output.push_str(": ");
+                printer.value(&member.value)
+            },
+            |member| (&member.comments, member.line_comment.as_ref()),
+        )
+    }
+
+    fn array(&mut self, array: &json::ArrayValue) -> error::Result<()> {
+        let items = array.items.iter().collect::<Vec<_>>();
+
+        self.entries(
+            ('[', ']'),
+            &items,
+            &array.trailing_comments,
+            |printer, item| printer.value(&item.value),
+            |item| (&item.comments, item.line_comment.as_ref()),
+        )
+    }
+
+    /// Prints the members of an object or the items of an array, one per line.
+    fn entries<A, F, C>(
+        &mut self,
+        (open, close): (char, char),
+        entries: &[&A],
+        trailing_comments: &[json::Comment],
+        mut print: F,
+        comments: C,
+    ) -> error::Result<()>
+    where
+        F: FnMut(&mut Self, &A) -> error::Result<()>,
+        C: Fn(&A) -> (&Vec<json::Comment>, Option<&json::Comment>),
+    {
+        let trailing_comments = if self.style.keep_comments {
+            trailing_comments
+        } else {
+            &[]
+        };
+
+        self.output.push(open);
+        if entries.is_empty() && trailing_comments.is_empty() {
+            self.output.push(close);
+            return Ok(());
+        }
+
+        self.indent += 1;
+        for (i, entry) in entries.iter().enumerate() {
+            let (leading, line_comment) = comments(entry);
+            self.output.push('\n');
+            self.comments(leading)?;
+            self.push_indent();
+            print(self, entry)?;
+            if i + 1 < entries.len() {
+                self.output.push(',');
+            }
+            if let Some(comment) = line_comment.filter(|_| self.style.keep_comments) {
+                self.output.push(' ');
+                self.comment(comment)?;
+            }
+        }
+        if !trailing_comments.is_empty() {
+            self.output.push('\n');
+            self.comments(trailing_comments)?;
+            // Remove the last newline, which is added again before the closing bracket
+            self.output.pop();
+        }
+        self.indent -= 1;
+
+        self.output.push('\n');
+        self.push_indent();
+        self.output.push(close);
+        Ok(())
+    }
+
+    /// Prints comments on lines of their own.
+    fn comments(&mut self, comments: &[json::Comment]) -> error::Result<()> {
+        if self.style.keep_comments {
+            for comment in comments {
+                self.push_indent();
+                self.comment(comment)?;
+                self.output.push('\n');
+            }
+        }
+        Ok(())
+    }
+
+    fn comment(&mut self, comment: &json::Comment) -> error::Result<()> {
+        if comment.is_block {
+            if comment.text.contains("*/") {
+                return Err(failure::err_msg("a block comment can't contain \"*/\""));
+            }
+            self.output.push_str("/*");
+            self.output.push_str(&comment.text);
+            self.output.push_str("*/");
+        } else {
+            if comment.text.contains('\n') {
+                return Err(failure::err_msg("a line comment can't contain line breaks"));
+            }
+            self.output.push_str("//");
+            self.output.push_str(&comment.text);
+        }
+        Ok(())
+    }
+
+    fn push_indent(&mut self) {
+        for _ in 0..self.indent {
+            self.output.push_str("  ");
+        }
+    }
+}
@@ -0,0 +1,76 @@
+//! Markdown documents stored as [mdast](https://github.com/syntax-tree/mdast) JSON, which is the
+//! semantic JSON of `markdown::Markdown`.
+//!
+//! Files need the `.mdast.json` extension, since any other `.json` file is a plain JSON document.
+use std::io;
+
+use failure;
+use semantic;
+use serde_json;
+
+use data::format::Format;
+use data::markdown;
+use data::Data;
+use error;
+
+/// The mdast JSON format.
+#[derive(Clone, Copy, Debug)]
+pub struct Mdast;
+
+impl Format for Mdast {
+    fn name(&self) -> &'static str {
+        "mdast"
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &["mdast.json"]
+    }
+
+    fn mime_types(&self) -> &'static [&'static str] {
+        &["application/vnd.mdast+json"]
+    }
+
+    fn sniff(&self, content: &[u8]) -> bool {
+        // The root of an mdast tree is an object tagged with `"type": "root"`, which usually comes
+        // first.  Other JSON files can have `"root"` keys (like `.eslintrc.json`), so look for the
+        // tag itself
+        let head = String::from_utf8_lossy(&content[..content.len().min(1024)]);
+        head.trim_left().starts_with('{') && head.match_indices("\"type\"").any(|(i, tag)| {
+            let rest = head[i + tag.len()..].trim_left();
+            rest.starts_with(':') && rest[1..].trim_left().starts_with("\"root\"")
+        })
+    }
+
+    fn can_write(&self, data: &Data) -> bool {
+        match *data {
+            Data::Markdown(_) => true,
+            Data::Json(_) => false,
+        }
+    }
+
+    fn read(&self, reader: &mut io::Read) -> error::Result<Data> {
+        let mut json = serde_json::from_reader(io::BufReader::new(reader))?;
+        migrate(&mut json)?;
+        Ok(Data::Markdown(semantic::json::from_json(&json)?))
+    }
+
+    fn write(&self, data: &Data, writer: &mut io::Write) -> error::Result<()> {
+        use std::io::Write;
+
+        let markdown = match *data {
+            Data::Markdown(ref markdown) => markdown,
+            Data::Json(_) => return Err(failure::err_msg("only Markdown documents can be written")),
+        };
+        serde_json::to_writer_pretty(&mut *writer, &semantic::json::to_json(markdown))?;
+        writeln!(writer)?;
+        Ok(())
+    }
+}
+
+/// Upgrades the mdast JSON of a document from an older schema version, if needed.
+pub fn migrate(json: &mut serde_json::Value) -> error::Result<()> {
+    use semantic::Semantic;
+
+    semantic::migrate(&markdown::Markdown::CLASS, markdown::MIGRATIONS, json)?;
+    Ok(())
+}
@@ -0,0 +1,206 @@
+//! The file formats that documents can be stored in.
+//!
+//! To support a new format, implement `Format` for it in a module of its own, and register it in
+//! `FormatRegistry::all`.
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path;
+
+use failure;
+
+use data::Data;
+use error;
+
+pub mod json;
+pub mod mdast;
+
+/// A way of storing documents in files.
+pub trait Format: fmt::Debug {
+    /// A short name of the format, like `"mdast"`.
+    fn name(&self) -> &'static str;
+
+    /// The file extensions of the format, without a leading dot, like `"json"`.
+    ///
+    /// Extensions may contain dots themselves (like `"mdast.json"`), so that formats can claim
+    /// more specific names than other formats with the same basic extension.
+    fn extensions(&self) -> &'static [&'static str];
+
+    /// The MIME types of the format, like `"application/json"`.
+    fn mime_types(&self) -> &'static [&'static str];
+
+    /// Whether `content` looks like it is in this format.
+    ///
+    /// This is used to tell apart formats that share an extension, or to pick a format for files
+    /// without a known extension, so it should be quick and need not be exact.
+    fn sniff(&self, content: &[u8]) -> bool;
+
+    /// Whether this format can store `data`, since most formats can only store one kind of
+    /// document.
+    fn can_write(&self, data: &Data) -> bool;
+
+    /// Reads a document in this format.
+    fn read(&self, reader: &mut io::Read) -> error::Result<Data>;
+
+    /// Writes a document in this format.
+    fn write(&self, data: &Data, writer: &mut io::Write) -> error::Result<()>;
+}
+
+/// A collection of formats, which picks the right one for a file.
+#[derive(Debug, Default)]
+pub struct FormatRegistry {
+    formats: Vec<Box<Format>>,
+}
+
+impl FormatRegistry {
+    /// Creates a registry without any formats.
+    pub fn new() -> Self {
+        FormatRegistry::default()
+    }
+
+    /// Creates a registry with all of the formats that the editor supports.
+    pub fn all() -> Self {
+        // Edited files are written back with their comments, unlike plain JSON exports
+        FormatRegistry::with_json_style(json::Json {
+            keep_comments: true,
+            sort_keys: false,
+        })
+    }
+
+    /// Creates a registry with all of the formats that the editor supports, which writes JSON
+    /// documents in the specified style.
+    pub fn with_json_style(style: json::Json) -> Self {
+        let mut registry = FormatRegistry::new();
+        registry.register(mdast::Mdast);
+        registry.register(style);
+        registry
+    }
+
+    /// Adds a format to this registry.
+    ///
+    /// When several formats match a file equally well, the one that was registered first wins.
+    pub fn register<F>(&mut self, format: F)
+    where
+        F: Format + 'static,
+    {
+        self.formats.push(Box::new(format));
+    }
+
+    /// All of the formats in this registry, in the order that they were registered.
+    pub fn formats<'a>(&'a self) -> impl Iterator<Item = &'a Format> + 'a {
+        self.formats.iter().map(|f| &**f)
+    }
+
+    /// The format with the specified name.
+    pub fn by_name(&self, name: &str) -> Option<&Format> {
+        self.formats().find(|f| f.name() == name)
+    }
+
+    /// The format with the specified MIME type.
+    pub fn by_mime_type(&self, mime_type: &str) -> Option<&Format> {
+        self.formats()
+            .find(|f| f.mime_types().iter().any(|m| m.eq_ignore_ascii_case(mime_type)))
+    }
+
+    /// The formats with an extension that matches the file name of `path`, with the most
+    /// specific extensions first.
+    pub fn by_path(&self, path: &path::Path) -> Vec<&Format> {
+        let file_name = match path.file_name().and_then(|n| n.to_str()) {
+            Some(file_name) => file_name.to_lowercase(),
+            None => return Vec::new(),
+        };
+
+        let mut matches = self
+            .formats()
+            .filter_map(|format| {
+                format
+                    .extensions()
+                    .iter()
+                    .filter(|e| file_name.ends_with(&format!(".{}", e)))
+                    .map(|e| e.len())
+                    .max()
+                    .map(|length| (length, format))
+            }).collect::<Vec<_>>();
+        // The sort is stable, so formats with equally specific extensions keep their order
+        matches.sort_by(|a, b| b.0.cmp(&a.0));
+        matches.into_iter().map(|(_, format)| format).collect()
+    }
+
+    /// Picks the format of a file with the specified path and content.
+    ///
+    /// Formats that match the extension of the file are preferred, and sniffing decides between
+    /// them.  Files without a known extension are sniffed against every format.
+    pub fn detect(&self, path: &path::Path, content: &[u8]) -> error::Result<&Format> {
+        let candidates = self.by_path(path);
+
+        if let Some(&format) = candidates.iter().find(|f| f.sniff(content)) {
+            Ok(format)
+        } else if let Some(&format) = candidates.first() {
+            // Let the format explain what is wrong with the content
+            Ok(format)
+        } else {
+            self.formats()
+                .find(|f| f.sniff(content))
+                .ok_or_else(|| unsupported_format(path))
+        }
+    }
+
+    /// Loads a document from a file, in whichever format it is in.
+    pub fn load(&self, path: &path::Path) -> error::Result<Data> {
+        self.load_as(path, path).map(|(data, _)| data)
+    }
+
+    /// Loads a document from a file, picking the format as if the file was at `name`, and returns
+    /// the format that was used.
+    ///
+    /// This is for copies of files that don't have the original file name, like the temporary
+    /// files that git passes to merge drivers.  If `name` has no known extension either, the
+    /// format is picked by sniffing the content.
+    pub fn load_as(
+        &self,
+        path: &path::Path,
+        name: &path::Path,
+    ) -> error::Result<(Data, &Format)> {
+        let content = fs::read(path)?;
+        let format = self.detect(name, &content)?;
+        let data = format.read(&mut content.as_slice())?;
+        Ok((data, format))
+    }
+
+    /// Saves a document to a file, in the first format that matches the extension of the file and
+    /// can store the document.
+    ///
+    /// To save a file without a known extension, pick the format with `load_as` or `by_name` and
+    /// use `save_in` instead.
+    pub fn save(&self, data: &Data, path: &path::Path) -> error::Result<()> {
+        let format = self
+            .by_path(path)
+            .into_iter()
+            .find(|f| f.can_write(data))
+            .ok_or_else(|| unsupported_format(path))?;
+
+        save_in(format, data, path)
+    }
+}
+
+/// Saves a document to a file in the specified format, regardless of the name of the file.
+pub fn save_in(format: &Format, data: &Data, path: &path::Path) -> error::Result<()> {
+    use std::io::Write;
+
+    if !format.can_write(data) {
+        return Err(failure::err_msg(format!(
+            "the {} format can't store this document: {}",
+            format.name(),
+            path.to_string_lossy()
+        )));
+    }
+
+    let mut file = io::BufWriter::new(fs::File::create(path)?);
+    format.write(data, &mut file)?;
+    file.flush()?;
+    Ok(())
+}
+
+fn unsupported_format(path: &path::Path) -> error::Error {
+    failure::err_msg(format!("unsupported file format: {}", path.to_string_lossy()))
+}
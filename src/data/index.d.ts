@@ -0,0 +1,1329 @@
+// This file is generated from semantic classes.  Do not edit it by hand, since it is
+// overwritten on every build.
+
+/**
+ * Some data that can be edited.
+ */
+export type Data =
+  | Markdown
+  | Json;
+
+/**
+ * Houses all nodes.
+ */
+export interface Markdown {
+  type: "root";
+  /**
+   * Child elements.
+   */
+  children: Block[];
+  /**
+   * Document declarations.
+   */
+  declarations: Declaration[];
+  schemaVersion?: 1;
+}
+
+/**
+ * A union of all possible block elements.
+ */
+export type Block =
+  | Paragraph
+  | Blockquote
+  | Heading
+  | Code
+  | Yaml
+  | Html
+  | List
+  | Table
+  | ThematicBreak
+  | Image;
+
+/**
+ * Represents a unit of discourse dealing
+ * with a particular point or idea.
+ *
+ * ```idl
+ * interface Paragraph <: Parent {
+ *   type: "paragraph";
+ * }
+ * ```
+ *
+ * For example, the following markdown:
+ *
+ * ```md
+ * Alpha bravo charlie.
+ * ```
+ *
+ * Yields:
+ *
+ * ```json
+ * {
+ *   "type": "paragraph",
+ *   "children": [{
+ *     "type": "text",
+ *     "value": "Alpha bravo charlie."
+ *   }]
+ * }
+ * ```
+ */
+export interface Paragraph {
+  type: "paragraph";
+  /**
+   * Child elements.
+   */
+  children: Block[];
+}
+
+/**
+ * Represents a quote.
+ *
+ * ```idl
+ * interface Blockquote <: Parent {
+ *   type: "blockquote";
+ * }
+ * ```
+ *
+ * For example, the following markdown:
+ *
+ * ```md
+ * > Alpha bravo charlie.
+ * ```
+ *
+ * Yields:
+ *
+ * ```json
+ * {
+ *   "type": "blockquote",
+ *   "children": [{
+ *     "type": "paragraph",
+ *     "children": [{
+ *       "type": "text",
+ *       "value": "Alpha bravo charlie."
+ *     }]
+ *   }]
+ * }
+ * ```
+ */
+export interface Blockquote {
+  type: "blockquote";
+  /**
+   * Child elements.
+   */
+  children: Block[];
+}
+
+/**
+ * `Heading` ([`Parent`](./struct.Parent.html)), just like with HTML, with a level greater
+ * than or equal to 1, lower than or equal to 6.
+ *
+ * ```idl
+ * interface Heading <: Parent {
+ *   type: "heading";
+ *   depth: 1 <= uint32 <= 6;
+ * }
+ * ```
+ *
+ * For example, the following markdown:
+ *
+ * ```md
+ * # Alpha
+ * ```
+ *
+ * Yields:
+ *
+ * ```json
+ * {
+ *   "type": "heading",
+ *   "depth": 1,
+ *   "children": [{
+ *     "type": "text",
+ *     "value": "Alpha"
+ *   }]
+ * }
+ * ```
+ */
+export interface Heading {
+  type: "heading";
+  /**
+   * The nesting depth of the heading (1-6).
+   */
+  depth: number;
+  /**
+   * Child elements.
+   */
+  children: Inline[];
+}
+
+/**
+ * A union of all possible inline elements.
+ */
+export type Inline =
+  | Text
+  | InlineCode
+  | Break
+  | Emphasis
+  | Strong
+  | Delete
+  | Link
+  | Footnote;
+
+/**
+ * Represents everything that is just text.
+ * Note that its `type` property is `text`, but it is different from
+ * [`Text`](./struct.Text.html).
+ *
+ * ```idl
+ * interface TextNode <: Text {
+ *   type: "text";
+ * }
+ * ```
+ *
+ * For example, the following markdown:
+ *
+ * ```md
+ * Alpha bravo charlie.
+ * ```
+ *
+ * Yields:
+ *
+ * ```json
+ * {
+ *   "type": "text",
+ *   "value": "Alpha bravo charlie."
+ * }
+ * ```
+ */
+export interface Text {
+  type: "text";
+  /**
+   * The text content.
+   */
+  value: string;
+}
+
+/**
+ * Occurs inline (see [`Code`](./struct.Code.html) for
+ * blocks). Inline code does not sport a `lang` attribute.
+ *
+ * ```idl
+ * interface InlineCode <: Text {
+ *   type: "inlineCode";
+ * }
+ * ```
+ *
+ * For example, the following markdown:
+ *
+ * ```md
+ * `foo()`
+ * ```
+ *
+ * Yields:
+ *
+ * ```json
+ * {
+ *   "type": "inlineCode",
+ *   "value": "foo()"
+ * }
+ * ```
+ */
+export interface InlineCode {
+  type: "inlineCode";
+  /**
+   * The code contents.
+   */
+  value: string;
+}
+
+/**
+ * Represents an explicit line break.
+ *
+ * ```idl
+ * interface Break <: Node {
+ *   type: "break";
+ * }
+ * ```
+ *
+ * For example, the following markdown (interpuncts represent spaces):
+ *
+ * ```md
+ * foo··
+ * bar
+ * ```
+ *
+ * Yields:
+ *
+ * ```json
+ * {
+ *   "type": "paragraph",
+ *   "children": [
+ *     {
+ *       "type": "text",
+ *       "value": "foo"
+ *     },
+ *     {
+ *       "type": "break"
+ *     },
+ *     {
+ *       "type": "text",
+ *       "value": "bar"
+ *     }
+ *   ]
+ * }
+ * ```
+ */
+export interface Break {
+  type: "break";
+}
+
+/**
+ * Represents slight emphasis.
+ *
+ * ```idl
+ * interface Emphasis <: Parent {
+ *   type: "emphasis";
+ * }
+ * ```
+ *
+ * For example, the following markdown:
+ *
+ * ```md
+ * *alpha* _bravo_
+ * ```
+ *
+ * Yields:
+ *
+ * ```json
+ * {
+ *   "type": "paragraph",
+ *   "children": [
+ *     {
+ *       "type": "emphasis",
+ *       "children": [{
+ *         "type": "text",
+ *         "value": "alpha"
+ *       }]
+ *     },
+ *     {
+ *       "type": "text",
+ *       "value": " "
+ *     },
+ *     {
+ *       "type": "emphasis",
+ *       "children": [{
+ *         "type": "text",
+ *         "value": "bravo"
+ *       }]
+ *     }
+ *   ]
+ * }
+ * ```
+ */
+export interface Emphasis {
+  type: "emphasis";
+  /**
+   * Child elements.
+   */
+  children: Inline[];
+}
+
+/**
+ * Represents strong emphasis.
+ *
+ * ```idl
+ * interface Strong <: Parent {
+ *   type: "strong";
+ * }
+ * ```
+ *
+ * For example, the following markdown:
+ *
+ * ```md
+ * **alpha** __bravo__
+ * ```
+ *
+ * Yields:
+ *
+ * ```json
+ * {
+ *   "type": "paragraph",
+ *   "children": [
+ *     {
+ *       "type": "strong",
+ *       "children": [{
+ *         "type": "text",
+ *         "value": "alpha"
+ *       }]
+ *     },
+ *     {
+ *       "type": "text",
+ *       "value": " "
+ *     },
+ *     {
+ *       "type": "strong",
+ *       "children": [{
+ *         "type": "text",
+ *         "value": "bravo"
+ *       }]
+ *     }
+ *   ]
+ * }
+ * ```
+ */
+export interface Strong {
+  type: "strong";
+  /**
+   * Child elements.
+   */
+  children: Inline[];
+}
+
+/**
+ * Represents text ready for removal.
+ *
+ * ```idl
+ * interface Delete <: Parent {
+ *   type: "delete";
+ * }
+ * ```
+ *
+ * For example, the following markdown:
+ *
+ * ```md
+ * ~~alpha~~
+ * ```
+ *
+ * Yields:
+ *
+ * ```json
+ * {
+ *   "type": "delete",
+ *   "children": [{
+ *     "type": "text",
+ *     "value": "alpha"
+ *   }]
+ * }
+ * ```
+ */
+export interface Delete {
+  type: "delete";
+  /**
+   * Child elements.
+   */
+  children: Inline[];
+}
+
+/**
+ * Represents the humble hyperlink.
+ *
+ * ```idl
+ * interface Link <: Parent {
+ *   type: "link";
+ *   title: string | null;
+ *   url: string;
+ * }
+ * ```
+ *
+ * For example, the following markdown:
+ *
+ * ```md
+ * [alpha](http://example.com "bravo")
+ * ```
+ *
+ * Yields:
+ *
+ * ```json
+ * {
+ *   "type": "link",
+ *   "title": "bravo",
+ *   "url": "http://example.com",
+ *   "children": [{
+ *     "type": "text",
+ *     "value": "alpha"
+ *   }]
+ * }
+ * ```
+ */
+export interface Link {
+  type: "link";
+  /**
+   * The title of the link.
+   */
+  title: string | null;
+  /**
+   * The URL of the link.
+   */
+  url: string;
+  /**
+   * Child elements.
+   */
+  children: Inline[];
+}
+
+/**
+ * Represents an inline marker, whose
+ * content relates to the document but is outside its flow.
+ *
+ * ```idl
+ * interface Footnote <: Parent {
+ *   type: "footnote";
+ * }
+ * ```
+ *
+ * For example, the following markdown:
+ *
+ * ```md
+ * [^alpha bravo]
+ * ```
+ *
+ * Yields:
+ *
+ * ```json
+ * {
+ *   "type": "footnote",
+ *   "children": [{
+ *     "type": "text",
+ *     "value": "alpha bravo"
+ *   }]
+ * }
+ * ```
+ */
+export interface Footnote {
+  type: "footnote";
+  /**
+   * Child elements.
+   */
+  children: Inline[];
+}
+
+/**
+ * Occurs at block level (see
+ * [`InlineCode`](./struct.InlineCode.html) for code spans).  `Code` sports a language
+ * tag (when using GitHub Flavoured Markdown fences with a flag, `null`
+ * otherwise).
+ *
+ * ```idl
+ * interface Code <: Text {
+ *   type: "code";
+ *   lang: string | null;
+ * }
+ * ```
+ *
+ * For example, the following markdown:
+ *
+ * ```md
+ *     foo()
+ * ```
+ *
+ * Yields:
+ *
+ * ```json
+ * {
+ *   "type": "code",
+ *   "lang": null,
+ *   "value": "foo()"
+ * }
+ * ```
+ */
+export interface Code {
+  type: "code";
+  /**
+   * The language that the code is written in, if it was specified.
+   */
+  lang: string | null;
+  /**
+   * The code contents.
+   */
+  value: string;
+}
+
+/**
+ * Can occur at the start of a document, and
+ * contains embedded YAML data.
+ *
+ * ```idl
+ * interface YAML <: Text {
+ *   type: "yaml";
+ * }
+ * ```
+ *
+ * > **Note**: YAML used to be available through the core of remark and thus
+ * > is specified here.  Support for it now moved to
+ * > [`remark-frontmatter`][frontmatter], and the definition here may be removed
+ * > in the future.
+ *
+ * For example, the following markdown:
+ *
+ * ```md
+ * ---
+ * foo: bar
+ * ---
+ * ```
+ *
+ * Yields:
+ *
+ * ```json
+ * {
+ *   "type": "yaml",
+ *   "value": "foo: bar"
+ * }
+ * ```
+ */
+export interface Yaml {
+  type: "yaml";
+  /**
+   * The YAML contents.
+   */
+  value: string;
+}
+
+/**
+ * Contains embedded HTML.
+ *
+ * ```idl
+ * interface HTML <: Text {
+ *   type: "html";
+ * }
+ * ```
+ *
+ * For example, the following markdown:
+ *
+ * ```md
+ * <div>
+ * ```
+ *
+ * Yields:
+ *
+ * ```json
+ * {
+ *   "type": "html",
+ *   "value": "<div>"
+ * }
+ * ```
+ */
+export interface Html {
+  type: "html";
+  /**
+   * The HTML contents.
+   */
+  value: string;
+}
+
+/**
+ * Contains [`ListItem`s](./struct.ListItem.html).  No other nodes
+ * may occur in lists.
+ *
+ * The `start` property contains the starting number of the list when
+ * `ordered: true`; `null` otherwise.
+ *
+ * When all list items have `loose: false`, the list’s `loose` property is also
+ * `false`.  Otherwise, `loose: true`.
+ *
+ * ```idl
+ * interface List <: Parent {
+ *   type: "list";
+ *   ordered: true | false;
+ *   start: uint32 | null;
+ *   loose: true | false;
+ * }
+ * ```
+ *
+ * For example, the following markdown:
+ *
+ * ```md
+ * 1. [x] foo
+ * ```
+ *
+ * Yields:
+ *
+ * ```json
+ * {
+ *   "type": "list",
+ *   "ordered": true,
+ *   "start": 1,
+ *   "loose": false,
+ *   "children": [{
+ *     "type": "listItem",
+ *     "loose": false,
+ *     "checked": true,
+ *     "children": [{
+ *       "type": "paragraph",
+ *       "children": [{
+ *         "type": "text",
+ *         "value": "foo",
+ *       }]
+ *     }]
+ *   }]
+ * }
+ * ```
+ */
+export interface List {
+  type: "list";
+  /**
+   * Whether the list is ordered (with numbers) or not.
+   */
+  ordered: boolean;
+  /**
+   * The start number for an ordered list, or `None` if numbers should be auto-assigned.
+   */
+  start: number | null;
+  /**
+   * Whether any of the children are `loose`.
+   */
+  loose: boolean;
+  /**
+   * Child elements.
+   */
+  children: ListItem[];
+}
+
+/**
+ * Is a child of a [`List`](./struct.List.html).
+ *
+ * Loose `ListItem`s often contain more than one block-level elements.
+ *
+ * A checked property exists on `ListItem`s, set to `true` (when checked),
+ * `false` (when unchecked), or `null` (when not containing a checkbox).
+ *
+ * ```idl
+ * interface ListItem <: Parent {
+ *   type: "listItem";
+ *   loose: true | false;
+ *   checked: true | false | null;
+ * }
+ * ```
+ *
+ * For an example, see the definition of [`List`](./struct.List.html).
+ */
+export interface ListItem {
+  type: "listItem";
+  /**
+   * Whether this item can contain more than one block element.
+   */
+  loose: boolean;
+  /**
+   * Whether this item is checked, or `None` if it does not contain a checkbox.
+   */
+  checked: boolean | null;
+  /**
+   * Child elements.
+   */
+  children: Block[];
+}
+
+/**
+ * Represents tabular data, with alignment.
+ * Its children are [`TableRow`](./struct.TableRow.html)s, the first of which acts as
+ * a table header row.
+ *
+ * `table.align` represents the alignment of columns.
+ *
+ * ```idl
+ * interface Table <: Parent {
+ *   type: "table";
+ *   align: [alignType];
+ * }
+ * ```
+ *
+ * ```idl
+ * enum alignType {
+ *   "left" | "right" | "center" | null;
+ * }
+ * ```
+ *
+ * For example, the following markdown:
+ *
+ * ```md
+ * | foo | bar |
+ * | :-- | :-: |
+ * | baz | qux |
+ * ```
+ *
+ * Yields:
+ *
+ * ```json
+ * {
+ *   "type": "table",
+ *   "align": ["left", "center"],
+ *   "children": [
+ *     {
+ *       "type": "tableRow",
+ *       "children": [
+ *         {
+ *           "type": "tableCell",
+ *           "children": [{
+ *             "type": "text",
+ *             "value": "foo"
+ *           }]
+ *         },
+ *         {
+ *           "type": "tableCell",
+ *           "children": [{
+ *             "type": "text",
+ *             "value": "bar"
+ *           }]
+ *         }
+ *       ]
+ *     },
+ *     {
+ *       "type": "tableRow",
+ *       "children": [
+ *         {
+ *           "type": "tableCell",
+ *           "children": [{
+ *             "type": "text",
+ *             "value": "baz"
+ *           }]
+ *         },
+ *         {
+ *           "type": "tableCell",
+ *           "children": [{
+ *             "type": "text",
+ *             "value": "qux"
+ *           }]
+ *         }
+ *       ]
+ *     }
+ *   ]
+ * }
+ * ```
+ */
+export interface Table {
+  type: "table";
+  /**
+   * The alignment of each table column, if it has one.
+   */
+  align: Array<AlignType | null>;
+  /**
+   * Child elements.
+   */
+  children: TableRow[];
+}
+
+/**
+ * The align type for a `Table`.
+ */
+export type AlignType = "left" | "right" | "center";
+
+/**
+ * `TableRow` ([`Parent`](./struct.Parent.html)).  Its children are always
+ * [`TableCell`](./struct.TableCell.html).
+ *
+ * ```idl
+ * interface TableRow <: Parent {
+ *   type: "tableRow";
+ * }
+ * ```
+ *
+ * For an example, see the definition of `Table`.
+ */
+export interface TableRow {
+  type: "tableRow";
+  /**
+   * Child elements.
+   */
+  children: TableCell[];
+}
+
+/**
+ * `TableCell` ([`Parent`](./struct.Parent.html)).  Contains a single tabular field.
+ *
+ * ```idl
+ * interface TableCell <: Parent {
+ *   type: "tableCell";
+ * }
+ * ```
+ *
+ * For an example, see the definition of [`Table`](./struct.Table.html).
+ */
+export interface TableCell {
+  type: "tableCell";
+  /**
+   * Child elements.
+   */
+  children: Block[];
+}
+
+/**
+ * A Represents a break in content,
+ * often shown as a horizontal rule, or by two HTML section elements.
+ *
+ * ```idl
+ * interface ThematicBreak <: Node {
+ *   type: "thematicBreak";
+ * }
+ * ```
+ *
+ * For example, the following markdown:
+ *
+ * ```md
+ * ***
+ * ```
+ *
+ * Yields:
+ *
+ * ```json
+ * {
+ *   "type": "thematicBreak"
+ * }
+ * ```
+ */
+export interface ThematicBreak {
+  type: "thematicBreak";
+}
+
+/**
+ * Represents the figurative figure.
+ *
+ * ```idl
+ * interface Image <: Node {
+ *   type: "image";
+ *   title: string | null;
+ *   alt: string | null;
+ *   url: string;
+ * }
+ * ```
+ *
+ * For example, the following markdown:
+ *
+ * ```md
+ * ![alpha](http://example.com/favicon.ico "bravo")
+ * ```
+ *
+ * Yields:
+ *
+ * ```json
+ * {
+ *   "type": "image",
+ *   "title": "bravo",
+ *   "url": "http://example.com",
+ *   "alt": "alpha"
+ * }
+ * ```
+ */
+export interface Image {
+  type: "image";
+  /**
+   * The title of the image.
+   */
+  title: string | null;
+  /**
+   * The alternative title of the image.
+   */
+  alt: string | null;
+  /**
+   * The URL of the image.
+   */
+  url: string;
+}
+
+/**
+ * A union of all possible declaration elements.
+ */
+export type Declaration =
+  | LinkReference
+  | ImageReference
+  | FootnoteReference
+  | Definition
+  | FootnoteDefinition;
+
+/**
+ * Represents a humble hyperlink,
+ * its `url` and `title` defined somewhere else in the document by a
+ * [`Definition`](./struct.Definition.html).
+ *
+ * `referenceType` is needed to detect if a reference was meant as a
+ * reference (`[foo][]`) or just unescaped brackets (`[foo]`).
+ *
+ * ```idl
+ * interface LinkReference <: Parent {
+ *   type: "linkReference";
+ *   identifier: string;
+ *   referenceType: referenceType;
+ * }
+ * ```
+ *
+ * ```idl
+ * enum referenceType {
+ *   "shortcut" | "collapsed" | "full";
+ * }
+ * ```
+ *
+ * For example, the following markdown:
+ *
+ * ```md
+ * [alpha][bravo]
+ * ```
+ *
+ * Yields:
+ *
+ * ```json
+ * {
+ *   "type": "linkReference",
+ *   "identifier": "bravo",
+ *   "referenceType": "full",
+ *   "children": [{
+ *     "type": "text",
+ *     "value": "alpha"
+ *   }]
+ * }
+ * ```
+ */
+export interface LinkReference {
+  type: "linkReference";
+  /**
+   * The identifier/label that is the reference target.
+   */
+  identifier: string;
+  /**
+   * The type of link reference this is.
+   */
+  referenceType: ReferenceType;
+}
+
+/**
+ * The reference type for a `LinkReference`.
+ */
+export type ReferenceType = "shortcut" | "collapsed" | "full";
+
+/**
+ * Represents a figurative figure,
+ * its `url` and `title` defined somewhere else in the document by a
+ * [`Definition`](./struct.Definition.html).
+ *
+ * `referenceType` is needed to detect if a reference was meant as a
+ * reference (`![foo][]`) or just unescaped brackets (`![foo]`).
+ * See [`LinkReference`](./struct.LinkReference.html) for the definition of `referenceType`.
+ *
+ * ```idl
+ * interface ImageReference <: Node {
+ *   type: "imageReference";
+ *   identifier: string;
+ *   referenceType: referenceType;
+ *   alt: string | null;
+ * }
+ * ```
+ *
+ * For example, the following markdown:
+ *
+ * ```md
+ * ![alpha][bravo]
+ * ```
+ *
+ * Yields:
+ *
+ * ```json
+ * {
+ *   "type": "imageReference",
+ *   "identifier": "bravo",
+ *   "referenceType": "full",
+ *   "alt": "alpha"
+ * }
+ * ```
+ */
+export interface ImageReference {
+  type: "imageReference";
+  /**
+   * The identifier/label that is the reference target.
+   */
+  identifier: string;
+  /**
+   * The type of link reference this is.
+   */
+  referenceType: ReferenceType;
+  /**
+   * An inline alternate title.
+   */
+  alt: string | null;
+}
+
+/**
+ * Is like [`Footnote`](./struct.Footnote.html),
+ * but its content is already outside the documents flow: placed in a
+ * [`FootnoteDefinition`](./struct.FootnoteDefinition.html).
+ *
+ * ```idl
+ * interface FootnoteReference <: Node {
+ *   type: "footnoteReference";
+ *   identifier: string;
+ * }
+ * ```
+ *
+ * For example, the following markdown:
+ *
+ * ```md
+ * [^alpha]
+ * ```
+ *
+ * Yields:
+ *
+ * ```json
+ * {
+ *   "type": "footnoteReference",
+ *   "identifier": "alpha"
+ * }
+ * ```
+ */
+export interface FootnoteReference {
+  type: "footnoteReference";
+  /**
+   * The identifier/label that is the reference target.
+   */
+  identifier: string;
+}
+
+/**
+ * Represents the definition (i.e., location
+ * and title) of a [`LinkReference`](./struct.LinkReference.html) or an
+ * [`ImageReference`](./struct.ImageReference.html).
+ *
+ * ```idl
+ * interface Definition <: Node {
+ *   type: "definition";
+ *   identifier: string;
+ *   title: string | null;
+ *   url: string;
+ * }
+ * ```
+ *
+ * For example, the following markdown:
+ *
+ * ```md
+ * [alpha]: http://example.com
+ * ```
+ *
+ * Yields:
+ *
+ * ```json
+ * {
+ *   "type": "definition",
+ *   "identifier": "alpha",
+ *   "title": null,
+ *   "url": "http://example.com"
+ * }
+ * ```
+ */
+export interface Definition {
+  type: "definition";
+  /**
+   * The identifier/label of the definition.
+   */
+  identifier: string;
+  /**
+   * The title of the definition.
+   */
+  title: string | null;
+  /**
+   * The URL of the definition.
+   */
+  url: string;
+}
+
+/**
+ * Represents the definition
+ * (i.e., content) of a [`FootnoteReference`](./struct.FootnoteReference.html).
+ *
+ * ```idl
+ * interface FootnoteDefinition <: Parent {
+ *   type: "footnoteDefinition";
+ *   identifier: string;
+ * }
+ * ```
+ *
+ * For example, the following markdown:
+ *
+ * ```md
+ * [^alpha]: bravo and charlie.
+ * ```
+ *
+ * Yields:
+ *
+ * ```json
+ * {
+ *   "type": "footnoteDefinition",
+ *   "identifier": "alpha",
+ *   "children": [{
+ *     "type": "paragraph",
+ *     "children": [{
+ *       "type": "text",
+ *       "value": "bravo and charlie."
+ *     }]
+ *   }]
+ * }
+ * ```
+ */
+export interface FootnoteDefinition {
+  type: "footnoteDefinition";
+  /**
+   * The identifier/label that is the reference target.
+   */
+  identifier: string;
+  /**
+   * Child elements.
+   */
+  children: Inline[];
+}
+
+/**
+ * A JSON document, which holds a single value.
+ *
+ * Its semantic JSON is tagged `"json"` rather than `"root"`, so that it can't be mistaken for a
+ * Markdown document.
+ */
+export interface Json {
+  type: "json";
+  /**
+   * Comments before the value.
+   */
+  comments: Comment[];
+  /**
+   * The value of the document.
+   */
+  value: Value;
+  /**
+   * Comments after the value, at the end of the document.
+   */
+  trailingComments: Comment[];
+  schemaVersion?: 1;
+}
+
+/**
+ * A JSONC comment.
+ */
+export interface Comment {
+  type: "comment";
+  /**
+   * The text of the comment, without the comment markers.
+   */
+  text: string;
+  /**
+   * Whether this is a `/* block *\/` comment rather than a `// line` comment.
+   */
+  isBlock: boolean;
+}
+
+/**
+ * A union of all possible JSON values.
+ */
+export type Value =
+  | ObjectValue
+  | ArrayValue
+  | StringValue
+  | NumberValue
+  | BooleanValue
+  | NullValue;
+
+/**
+ * An object, like `{"a": 1}`.
+ */
+export interface ObjectValue {
+  type: "objectValue";
+  /**
+   * The members of the object, in order.
+   */
+  members: Member[];
+  /**
+   * Comments after the last member, before the closing brace.
+   */
+  trailingComments: Comment[];
+}
+
+/**
+ * A key and value of an object, like `"a": 1`.
+ */
+export interface Member {
+  type: "member";
+  /**
+   * Comments on the lines before the member.
+   */
+  comments: Comment[];
+  /**
+   * The key of the member.
+   */
+  key: string;
+  /**
+   * The value of the member.
+   */
+  value: Value;
+  /**
+   * A comment at the end of the line of the member.
+   */
+  lineComment: Comment | null;
+}
+
+/**
+ * An array, like `[1, 2]`.
+ */
+export interface ArrayValue {
+  type: "arrayValue";
+  /**
+   * The items of the array, in order.
+   */
+  items: Item[];
+  /**
+   * Comments after the last item, before the closing bracket.
+   */
+  trailingComments: Comment[];
+}
+
+/**
+ * A value in an array.
+ */
+export interface Item {
+  type: "item";
+  /**
+   * Comments on the lines before the item.
+   */
+  comments: Comment[];
+  /**
+   * The value of the item.
+   */
+  value: Value;
+  /**
+   * A comment at the end of the line of the item.
+   */
+  lineComment: Comment | null;
+}
+
+/**
+ * A string, like `"a"`.
+ */
+export interface StringValue {
+  type: "stringValue";
+  /**
+   * The content of the string, without escapes.
+   */
+  value: string;
+}
+
+/**
+ * A number, like `1.5`.
+ *
+ * Numbers are stored as 64-bit floats (like in JavaScript), so integers beyond 2^53 lose
+ * precision.
+ */
+export interface NumberValue {
+  type: "numberValue";
+  /**
+   * The value of the number.
+   */
+  value: number;
+}
+
+/**
+ * `true` or `false`.
+ */
+export interface BooleanValue {
+  type: "booleanValue";
+  /**
+   * The value of the boolean.
+   */
+  value: boolean;
+}
+
+/**
+ * `null`.
+ */
+export interface NullValue {
+  type: "nullValue";
+}
@@ -0,0 +1,145 @@
+//! The JSON format, including the comments and trailing commas of
+//! [JSONC](https://code.visualstudio.com/docs/languages/json#_json-with-comments) (like
+//! `tsconfig.json`).
+//!
+//! Objects keep the order of their members, and comments are kept next to the member or item
+//! that they precede.
+
+/// A JSON document, which holds a single value.
+///
+/// Its semantic JSON is tagged `"json"` rather than `"root"`, so that it can't be mistaken for a
+/// Markdown document.
+#[derive(Clone, Debug, Semantic)]
+#[semantic(role = "document", version = 1, tag = "json")]
+#[semantic(visitors(
+    Json,
+    Value,
+    ObjectValue,
+    Member,
+    ArrayValue,
+    Item,
+    StringValue,
+    NumberValue,
+    BooleanValue,
+    NullValue,
+    Comment
+))]
+pub struct Json {
+    /// Comments before the value.
+    pub comments: Vec<Comment>,
+    /// The value of the document.
+    #[semantic(children)]
+    pub value: Value,
+    /// Comments after the value, at the end of the document.
+    pub trailing_comments: Vec<Comment>,
+}
+
+/// A union of all possible JSON values.
+#[derive(Clone, Debug, Semantic)]
+#[semantic(role = "block")]
+pub enum Value {
+    /// The `Object` variant.
+    Object(ObjectValue),
+    /// The `Array` variant.
+    Array(ArrayValue),
+    /// The `String` variant.
+    String(StringValue),
+    /// The `Number` variant.
+    Number(NumberValue),
+    /// The `Boolean` variant.
+    Boolean(BooleanValue),
+    /// The `Null` variant.
+    Null(NullValue),
+}
+
+/// An object, like `{"a": 1}`.
+#[derive(Clone, Debug, Semantic)]
+#[semantic(role = "block")]
+pub struct ObjectValue {
+    /// The members of the object, in order.
+    #[semantic(children)]
+    pub members: Vec<Member>,
+    /// Comments after the last member, before the closing brace.
+    pub trailing_comments: Vec<Comment>,
+}
+
+/// A key and value of an object, like `"a": 1`.
+#[derive(Clone, Debug, Semantic)]
+#[semantic(role = "block")]
+pub struct Member {
+    /// Comments on the lines before the member.
+    pub comments: Vec<Comment>,
+    /// The key of the member.
+    pub key: String,
+    /// The value of the member.
+    #[semantic(children)]
+    pub value: Value,
+    /// A comment at the end of the line of the member.
+    pub line_comment: Option<Comment>,
+}
+
+/// An array, like `[1, 2]`.
+#[derive(Clone, Debug, Semantic)]
+#[semantic(role = "block")]
+pub struct ArrayValue {
+    /// The items of the array, in order.
+    #[semantic(children)]
+    pub items: Vec<Item>,
+    /// Comments after the last item, before the closing bracket.
+    pub trailing_comments: Vec<Comment>,
+}
+
+/// A value in an array.
+#[derive(Clone, Debug, Semantic)]
+#[semantic(role = "block")]
+pub struct Item {
+    /// Comments on the lines before the item.
+    pub comments: Vec<Comment>,
+    /// The value of the item.
+    #[semantic(children)]
+    pub value: Value,
+    /// A comment at the end of the line of the item.
+    pub line_comment: Option<Comment>,
+}
+
+/// A string, like `"a"`.
+#[derive(Clone, Debug, Semantic)]
+#[semantic(role = "inline")]
+pub struct StringValue {
+    /// The content of the string, without escapes.
+    pub value: String,
+}
+
+/// A number, like `1.5`.
+///
+/// Numbers are stored as 64-bit floats (like in JavaScript), so integers beyond 2^53 lose
+/// precision.
+#[derive(Clone, Copy, Debug, Semantic)]
+#[semantic(role = "inline")]
+pub struct NumberValue {
+    /// The value of the number.
+    pub value: f64,
+}
+
+/// `true` or `false`.
+#[derive(Clone, Copy, Debug, Semantic)]
+#[semantic(role = "inline")]
+pub struct BooleanValue {
+    /// The value of the boolean.
+    pub value: bool,
+}
+
+/// `null`.
+#[derive(Clone, Copy, Debug, Semantic)]
+#[semantic(role = "inline")]
+pub struct NullValue;
+
+/// A JSONC comment.
+#[derive(Clone, Debug, Semantic)]
+#[semantic(role = "attribute")]
+pub struct Comment {
+    /// The text of the comment, without the comment markers.
+    pub text: String,
+    /// Whether this is a `/* block */` comment rather than a `// line` comment.
+    pub is_block: bool,
+}
@@ -4,10 +4,49 @@
 use error;
 use pulldown_cmark;
 use std::io;
+use semantic;
+
+/// The migrations from older schema versions of `Markdown` documents.
+///
+/// Whenever the serialized form of documents changes, bump the version of `Markdown` and add a
+/// migration from the previous version here.
+pub const MIGRATIONS: &[semantic::Migration] = &[];
 
 /// Houses all nodes.
-#[derive(Clone, Debug, Semantic, TypeInfo)]
-#[semantic(role = "document")]
+#[derive(Clone, Debug, Semantic)]
+#[semantic(role = "document", version = 1)]
+#[semantic(visitors(
+    Markdown,
+    Block,
+    Inline,
+    Declaration,
+    Paragraph,
+    Blockquote,
+    Heading,
+    Code,
+    InlineCode,
+    Yaml,
+    Html,
+    List,
+    ListItem,
+    Table,
+    TableRow,
+    TableCell,
+    ThematicBreak,
+    Break,
+    Emphasis,
+    Strong,
+    Delete,
+    Link,
+    Image,
+    Footnote,
+    LinkReference,
+    ImageReference,
+    FootnoteReference,
+    Definition,
+    FootnoteDefinition,
+    Text
+))]
 pub struct Markdown {
     /// Child elements.
     #[semantic(children)]
@@ -17,7 +56,7 @@ pub struct Markdown {
 }
 
 /// A union of all possible block elements.
-#[derive(Clone, Debug, Semantic, TypeInfo)]
+#[derive(Clone, Debug, Semantic)]
 #[semantic(role = "block")]
 pub enum Block {
     /// The `Paragraph` variant.
@@ -43,7 +82,7 @@ pub enum Block {
 }
 
 /// A union of all possible inline elements.
-#[derive(Clone, Debug, Semantic, TypeInfo)]
+#[derive(Clone, Debug, Semantic)]
 #[semantic(role = "inline")]
 pub enum Inline {
     /// The `Text` variant.
@@ -65,7 +104,7 @@ pub enum Inline {
 }
 
 /// A union of all possible declaration elements.
-#[derive(Clone, Debug, Semantic, TypeInfo)]
+#[derive(Clone, Debug, Semantic)]
 #[semantic(role = "block")]
 pub enum Declaration {
     /// The `LinkReference` variant.
@@ -106,7 +145,7 @@ pub enum Declaration {
 ///   }]
 /// }
 /// ```
-#[derive(Clone, Debug, Semantic, TypeInfo)]
+#[derive(Clone, Debug, Semantic)]
 #[semantic(role = "block")]
 pub struct Paragraph {
     /// Child elements.
@@ -142,7 +181,7 @@ pub struct Paragraph {
 ///   }]
 /// }
 /// ```
-#[derive(Clone, Debug, Semantic, TypeInfo)]
+#[derive(Clone, Debug, Semantic)]
 #[semantic(role = "block")]
 pub struct Blockquote {
     /// Child elements.
@@ -178,10 +217,11 @@ pub struct Blockquote {
 ///   }]
 /// }
 /// ```
-#[derive(Clone, Debug, Semantic, TypeInfo)]
+#[derive(Clone, Debug, Semantic)]
 #[semantic(role = "block")]
 pub struct Heading {
     /// The nesting depth of the heading (1-6).
+    #[semantic(range = "1..=6", default = "1")]
     pub depth: u32,
     /// Child elements.
     #[semantic(children)]
@@ -215,11 +255,11 @@ pub struct Heading {
 ///   "value": "foo()"
 /// }
 /// ```
-#[derive(Clone, Debug, Semantic, TypeInfo)]
+#[derive(Clone, Debug, Semantic)]
 #[semantic(role = "inline")]
 pub struct Code {
-    /// The language that the code is written in.
-    pub lang: String,
+    /// The language that the code is written in, if it was specified.
+    pub lang: Option<String>,
     // TODO(dflemstr): insert foreign code AST here
     /// The code contents.
     pub value: String,
@@ -248,7 +288,7 @@ pub struct Code {
 ///   "value": "foo()"
 /// }
 /// ```
-#[derive(Clone, Debug, Semantic, TypeInfo)]
+#[derive(Clone, Debug, Semantic)]
 #[semantic(role = "inline")]
 pub struct InlineCode {
     /// The code contents.
@@ -285,7 +325,7 @@ pub struct InlineCode {
 ///   "value": "foo: bar"
 /// }
 /// ```
-#[derive(Clone, Debug, Semantic, TypeInfo)]
+#[derive(Clone, Debug, Semantic)]
 #[semantic(role = "block")]
 pub struct Yaml {
     // TODO(dflemstr): insert YAML AST here
@@ -315,7 +355,7 @@ pub struct Yaml {
 ///   "value": "<div>"
 /// }
 /// ```
-#[derive(Clone, Debug, Semantic, TypeInfo)]
+#[derive(Clone, Debug, Semantic)]
 #[semantic(role = "inline")]
 pub struct Html {
     // TODO(dflemstr): insert HTML AST here
@@ -369,7 +409,7 @@ pub struct Html {
 ///   }]
 /// }
 /// ```
-#[derive(Clone, Debug, Semantic, TypeInfo)]
+#[derive(Clone, Debug, Semantic)]
 #[semantic(role = "block")]
 pub struct List {
     /// Whether the list is ordered (with numbers) or not.
@@ -399,7 +439,7 @@ pub struct List {
 /// ```
 ///
 /// For an example, see the definition of [`List`](./struct.List.html).
-#[derive(Clone, Debug, Semantic, TypeInfo)]
+#[derive(Clone, Debug, Semantic)]
 #[semantic(role = "block")]
 pub struct ListItem {
     /// Whether this item can contain more than one block element.
@@ -412,7 +452,7 @@ pub struct ListItem {
 }
 
 /// The align type for a `Table`.
-#[derive(Clone, Copy, Debug, Semantic, TypeInfo)]
+#[derive(Clone, Copy, Debug, Semantic)]
 #[semantic(role = "attribute")]
 pub enum AlignType {
     /// Align to the left.
@@ -498,11 +538,11 @@ pub enum AlignType {
 ///   ]
 /// }
 /// ```
-#[derive(Clone, Debug, Semantic, TypeInfo)]
+#[derive(Clone, Debug, Semantic)]
 #[semantic(role = "block")]
 pub struct Table {
-    /// The alignment of the table columns.
-    pub align: Vec<AlignType>,
+    /// The alignment of each table column, if it has one.
+    pub align: Vec<Option<AlignType>>,
     /// Child elements.
     #[semantic(children)]
     pub children: Vec<TableRow>,
@@ -518,7 +558,7 @@ pub struct Table {
 /// ```
 ///
 /// For an example, see the definition of `Table`.
-#[derive(Clone, Debug, Semantic, TypeInfo)]
+#[derive(Clone, Debug, Semantic)]
 #[semantic(role = "block")]
 pub struct TableRow {
     /// Child elements.
@@ -535,7 +575,7 @@ pub struct TableRow {
 /// ```
 ///
 /// For an example, see the definition of [`Table`](./struct.Table.html).
-#[derive(Clone, Debug, Semantic, TypeInfo)]
+#[derive(Clone, Debug, Semantic)]
 #[semantic(role = "block")]
 pub struct TableCell {
     /// Child elements.
@@ -565,7 +605,7 @@ pub struct TableCell {
 ///   "type": "thematicBreak"
 /// }
 /// ```
-#[derive(Clone, Copy, Debug, Semantic, TypeInfo)]
+#[derive(Clone, Copy, Debug, Semantic)]
 #[semantic(role = "block")]
 pub struct ThematicBreak;
 
@@ -604,7 +644,7 @@ pub struct ThematicBreak;
 ///   ]
 /// }
 /// ```
-#[derive(Clone, Copy, Debug, Semantic, TypeInfo)]
+#[derive(Clone, Copy, Debug, Semantic)]
 #[semantic(role = "inline")]
 pub struct Break;
 
@@ -649,7 +689,7 @@ pub struct Break;
 ///   ]
 /// }
 /// ```
-#[derive(Clone, Debug, Semantic, TypeInfo)]
+#[derive(Clone, Debug, Semantic)]
 #[semantic(role = "inline")]
 pub struct Emphasis {
     /// Child elements.
@@ -698,7 +738,7 @@ pub struct Emphasis {
 ///   ]
 /// }
 /// ```
-#[derive(Clone, Debug, Semantic, TypeInfo)]
+#[derive(Clone, Debug, Semantic)]
 #[semantic(role = "inline")]
 pub struct Strong {
     /// Child elements.
@@ -731,7 +771,7 @@ pub struct Strong {
 ///   }]
 /// }
 /// ```
-#[derive(Clone, Debug, Semantic, TypeInfo)]
+#[derive(Clone, Debug, Semantic)]
 #[semantic(role = "inline")]
 pub struct Delete {
     /// Child elements.
@@ -768,13 +808,14 @@ pub struct Delete {
 ///   }]
 /// }
 /// ```
-#[derive(Clone, Debug, Semantic, TypeInfo)]
+#[derive(Clone, Debug, Semantic)]
 #[semantic(role = "inline")]
 pub struct Link {
     /// The title of the link.
     pub title: Option<String>,
     // TODO: use symbol
     /// The URL of the link.
+    #[semantic(non_empty)]
     pub url: String,
     /// Child elements.
     #[semantic(children)]
@@ -808,7 +849,7 @@ pub struct Link {
 ///   "alt": "alpha"
 /// }
 /// ```
-#[derive(Clone, Debug, Semantic, TypeInfo)]
+#[derive(Clone, Debug, Semantic)]
 #[semantic(role = "inline")]
 pub struct Image {
     /// The title of the image.
@@ -817,6 +858,7 @@ pub struct Image {
     pub alt: Option<String>,
     // TODO: use symbol
     /// The URL of the image.
+    #[semantic(non_empty)]
     pub url: String,
 }
 
@@ -846,7 +888,7 @@ pub struct Image {
 ///   }]
 /// }
 /// ```
-#[derive(Clone, Debug, Semantic, TypeInfo)]
+#[derive(Clone, Debug, Semantic)]
 #[semantic(role = "inline")]
 pub struct Footnote {
     /// Child elements.
@@ -855,7 +897,7 @@ pub struct Footnote {
 }
 
 /// The reference type for a `LinkReference`.
-#[derive(Clone, Copy, Debug, Semantic, TypeInfo)]
+#[derive(Clone, Copy, Debug, Semantic)]
 #[semantic(role = "attribute")]
 pub enum ReferenceType {
     /// The reference is implicit, like `[foo]`.
@@ -906,7 +948,7 @@ pub enum ReferenceType {
 ///   }]
 /// }
 /// ```
-#[derive(Clone, Debug, Semantic, TypeInfo)]
+#[derive(Clone, Debug, Semantic)]
 #[semantic(role = "inline")]
 pub struct LinkReference {
     // TODO: use symbol
@@ -949,7 +991,7 @@ pub struct LinkReference {
 ///   "alt": "alpha"
 /// }
 /// ```
-#[derive(Clone, Debug, Semantic, TypeInfo)]
+#[derive(Clone, Debug, Semantic)]
 #[semantic(role = "inline")]
 pub struct ImageReference {
     // TODO: use symbol
@@ -986,7 +1028,7 @@ pub struct ImageReference {
 ///   "identifier": "alpha"
 /// }
 /// ```
-#[derive(Clone, Debug, Semantic, TypeInfo)]
+#[derive(Clone, Debug, Semantic)]
 #[semantic(role = "inline")]
 pub struct FootnoteReference {
     // TODO: use symbol
@@ -1023,7 +1065,7 @@ pub struct FootnoteReference {
 ///   "url": "http://example.com"
 /// }
 /// ```
-#[derive(Clone, Debug, Semantic, TypeInfo)]
+#[derive(Clone, Debug, Semantic)]
 #[semantic(role = "block")]
 pub struct Definition {
     // TODO: use symbol
@@ -1033,6 +1075,7 @@ pub struct Definition {
     pub title: Option<String>,
     // TODO: use symbol
     /// The URL of the definition.
+    #[semantic(non_empty)]
     pub url: String,
 }
 
@@ -1067,7 +1110,7 @@ pub struct Definition {
 ///   }]
 /// }
 /// ```
-#[derive(Clone, Debug, Semantic, TypeInfo)]
+#[derive(Clone, Debug, Semantic)]
 #[semantic(role = "block")]
 pub struct FootnoteDefinition {
     // TODO: use symbol
@@ -1102,10 +1145,11 @@ pub struct FootnoteDefinition {
 ///   "value": "Alpha bravo charlie."
 /// }
 /// ```
-#[derive(Clone, Debug, Semantic, TypeInfo)]
+#[derive(Clone, Debug, Semantic)]
 #[semantic(role = "inline")]
 pub struct Text {
     /// The text content.
+    #[semantic(rename = "value")]
     pub content: String,
 }
 
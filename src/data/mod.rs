@@ -1,12 +1,67 @@
 //! Definitions of data types that can be edited.
 //!
-//! Here's where you would add support for new file types and formats.
+//! Here's where you would add support for new kinds of documents.  File formats live in the
+//! `format` module.
+use std::path;
+
+use failure;
+use semantic;
+
+use error;
+
+pub mod format;
+pub mod json;
 pub mod markdown;
+mod model;
+pub mod proto;
+pub mod slate;
+
+pub use self::model::Data;
+
+impl Data {
+    /// An empty Markdown document, which all replicas of a shared document (see
+    /// `semantic::Document`) start out as, before they receive the contents of the file.
+    pub fn empty() -> Data {
+        Data::Markdown(markdown::Markdown {
+            children: Vec::new(),
+            declarations: Vec::new(),
+        })
+    }
+
+    /// Loads data from a file, picking the format based on the file extension and content.
+    pub fn load(path: &path::Path) -> error::Result<Data> {
+        format::FormatRegistry::all().load(path)
+    }
+
+    /// Saves data to a file, picking the format based on the file extension.
+    pub fn save(&self, path: &path::Path) -> error::Result<()> {
+        format::FormatRegistry::all().save(self, path)
+    }
+
+    /// Checks that this data conforms to the constraints of its semantic classes.
+    ///
+    /// This should be done after every edit and before every save, so that invalid structures never
+    /// make it into a document.
+    pub fn validate(&self) -> error::Result<()> {
+        let violations = semantic::validate(self);
+
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            let messages = violations
+                .iter()
+                .map(|v| v.to_string())
+                .collect::<Vec<_>>();
+            Err(failure::err_msg(format!(
+                "invalid document: {}",
+                messages.join("; ")
+            )))
+        }
+    }
+}
 
-/// Some data that can be edited.
-#[derive(Debug, Semantic, TypeInfo)]
-#[semantic(role = "root")]
-pub enum Data {
-    /// The `Markdown` variant.
-    Markdown(markdown::Markdown),
+/// A hash of the document model, which the frontend and the backend compare to make sure that
+/// they were built from the same version of it.
+pub fn schema_hash() -> u64 {
+    semantic::Registry::of::<Data>().schema_hash()
 }
@@ -0,0 +1,16 @@
+//! The root of the document model.
+//!
+//! This only defines types, without any I/O, so that `build.rs` can include it (along with the
+//! modules of the formats) to generate code from the semantic classes.
+use super::json;
+use super::markdown;
+
+/// Some data that can be edited.
+#[derive(Debug, Semantic)]
+#[semantic(role = "root")]
+pub enum Data {
+    /// The `Markdown` variant.
+    Markdown(markdown::Markdown),
+    /// The `Json` variant.
+    Json(json::Json),
+}
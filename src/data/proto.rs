@@ -0,0 +1,237 @@
+//! Conversion between semantic values and the generic `se.document` Protobuf encoding.
+//!
+//! Tags and attribute names are the same as in the JSON encoding of `semantic::json`, so a
+//! Markdown document uses the mdast names (`"heading"`, `"referenceType"` and so on).
+use semantic;
+use semantic::json;
+
+use error;
+use schema::se::document as document_proto;
+
+/// Encodes any semantic value as a generic document node.
+pub fn to_proto(value: &semantic::DynamicSemantic) -> document_proto::Node {
+    use semantic::Structure;
+    use semantic::Value;
+
+    let class = value.class();
+    let mut node = document_proto::Node::default();
+
+    match (value.reflect(), class.structure) {
+        (Value::Primitive(primitive), _) => node.atom = Some(atom_to_proto(primitive)),
+        (Value::Unit, _) => node.tag = tag(class),
+        (Value::Enumeration { variant }, Structure::Enumeration { variants, .. }) => {
+            node.tag = json::camel_case(variants[variant].name);
+        }
+        (Value::Record { fields }, Structure::Record { fields: metas, .. }) => {
+            node.tag = tag(class);
+            node.attributes = attributes_to_proto(metas, fields);
+        }
+        (Value::Union { variant, fields }, Structure::Union { variants, .. }) => {
+            let variant = &variants[variant];
+            node.tag = json::camel_case(variant.name);
+            match variant.kind {
+                semantic::VariantKind::Record { fields: metas } => {
+                    node.attributes = attributes_to_proto(metas, fields);
+                }
+                _ => node.children = fields.into_iter().map(to_proto).collect(),
+            }
+        }
+        (Value::Collection { items }, _) => {
+            node.children = items.into_iter().map(to_proto).collect();
+        }
+        (Value::Optional { item }, _) => {
+            node.children = item.into_iter().map(to_proto).collect();
+        }
+        (value, structure) => panic!(
+            "value {:?} does not match its class structure {:?}",
+            value, structure
+        ),
+    }
+
+    node
+}
+
+/// Decodes a generic document node into a semantic value of type `A`.
+pub fn from_proto<A>(node: &document_proto::Node) -> error::Result<A>
+where
+    A: semantic::Semantic,
+{
+    let registry = semantic::Registry::of::<A>();
+    let node = node_from_proto(&registry, registry.root(), node)?;
+    Ok(node.into_value()?)
+}
+
+fn node_from_proto(
+    registry: &semantic::Registry,
+    class: &'static semantic::Class<'static>,
+    node: &document_proto::Node,
+) -> semantic::Result<semantic::Node> {
+    use semantic::Content;
+    use semantic::Structure;
+
+    let content = match class.structure {
+        Structure::Primitive { kind } => Content::Primitive(atom_from_proto(kind, node)?),
+        Structure::Unit { .. } => {
+            check_tag(class, node)?;
+            Content::Unit
+        }
+        Structure::Enumeration { variants, .. } => Content::Enumeration {
+            variant: variant_index(variants, &node.tag)?,
+        },
+        Structure::Record { fields, .. } => {
+            check_tag(class, node)?;
+            Content::Record {
+                fields: attributes_from_proto(registry, fields, &node.attributes)?,
+            }
+        }
+        Structure::Union { variants, .. } => {
+            let variant = variant_index(variants, &node.tag)?;
+            let fields = match variants[variant].kind {
+                semantic::VariantKind::Unit => Vec::new(),
+                semantic::VariantKind::Newtype { ty } => {
+                    let child = node
+                        .children
+                        .first()
+                        .ok_or_else(|| semantic::Error::new("missing the value of the variant"))?;
+                    let node = node_from_proto(registry, lookup(registry, ty)?, child)
+                        .map_err(|e| e.within(0))?;
+                    vec![node]
+                }
+                semantic::VariantKind::Record { fields } => {
+                    attributes_from_proto(registry, fields, &node.attributes)?
+                }
+            };
+            Content::Union { variant, fields }
+        }
+        Structure::Collection { item } => Content::Collection {
+            items: node
+                .children
+                .iter()
+                .enumerate()
+                .map(|(i, child)| node_from_proto(registry, item, child).map_err(|e| e.within(i)))
+                .collect::<semantic::Result<_>>()?,
+        },
+        Structure::Optional { item } => Content::Optional {
+            item: match node.children.first() {
+                Some(child) => Some(Box::new(
+                    node_from_proto(registry, item, child).map_err(|e| e.within(0))?,
+                )),
+                None => None,
+            },
+        },
+    };
+
+    Ok(semantic::Node { class, content })
+}
+
+fn tag(class: &semantic::Class) -> String {
+    json::type_tag(class).unwrap_or_default()
+}
+
+fn check_tag(class: &semantic::Class, node: &document_proto::Node) -> semantic::Result<()> {
+    let expected = tag(class);
+    if node.tag == expected {
+        Ok(())
+    } else {
+        Err(semantic::Error::new(format!(
+            "expected tag {:?} but got {:?}",
+            expected, node.tag
+        )))
+    }
+}
+
+fn variant_index(variants: &[semantic::Variant], tag: &str) -> semantic::Result<usize> {
+    variants
+        .iter()
+        .position(|v| json::camel_case(v.name) == tag)
+        .ok_or_else(|| semantic::Error::new(format!("unknown variant {:?}", tag)))
+}
+
+fn lookup(
+    registry: &semantic::Registry,
+    id: ::std::any::TypeId,
+) -> semantic::Result<&'static semantic::Class<'static>> {
+    registry
+        .get(id)
+        .ok_or_else(|| semantic::Error::new(format!("unknown class {:?}", id)))
+}
+
+fn attributes_to_proto(
+    metas: &[semantic::Field],
+    fields: Vec<&semantic::DynamicSemantic>,
+) -> Vec<document_proto::Attribute> {
+    metas
+        .iter()
+        .zip(fields)
+        .map(|(meta, field)| document_proto::Attribute {
+            name: json::camel_case(meta.name),
+            value: Some(to_proto(field)),
+        }).collect()
+}
+
+fn attributes_from_proto(
+    registry: &semantic::Registry,
+    metas: &'static [semantic::Field<'static>],
+    attributes: &[document_proto::Attribute],
+) -> semantic::Result<Vec<semantic::Node>> {
+    metas
+        .iter()
+        .enumerate()
+        .map(|(i, meta)| {
+            let class = lookup(registry, meta.ty)?;
+            let name = json::camel_case(meta.name);
+
+            match attributes
+                .iter()
+                .find(|a| a.name == name)
+                .and_then(|a| a.value.as_ref())
+            {
+                Some(value) => node_from_proto(registry, class, value),
+                None => semantic::Node::empty(class).ok_or_else(|| {
+                    semantic::Error::new(format!("missing attribute {:?}", name))
+                }),
+            }.map_err(|e| e.within(i))
+        }).collect()
+}
+
+fn atom_to_proto(primitive: semantic::Primitive) -> document_proto::Atom {
+    use schema::se::document::atom::Value;
+    use semantic::Primitive;
+
+    let value = match primitive {
+        Primitive::Bool(v) => Value::Boolean(v),
+        Primitive::U8(v) => Value::Unsigned(u64::from(v)),
+        Primitive::I8(v) => Value::Signed(i64::from(v)),
+        Primitive::U16(v) => Value::Unsigned(u64::from(v)),
+        Primitive::I16(v) => Value::Signed(i64::from(v)),
+        Primitive::U32(v) => Value::Unsigned(u64::from(v)),
+        Primitive::I32(v) => Value::Signed(i64::from(v)),
+        Primitive::F32(v) => Value::Float(f64::from(v)),
+        Primitive::U64(v) => Value::Unsigned(v),
+        Primitive::I64(v) => Value::Signed(v),
+        Primitive::F64(v) => Value::Float(v),
+        Primitive::String(v) => Value::String(v.to_owned()),
+    };
+
+    document_proto::Atom { value: Some(value) }
+}
+
+fn atom_from_proto(
+    kind: semantic::PrimitiveKind,
+    node: &document_proto::Node,
+) -> semantic::Result<semantic::Atom> {
+    use schema::se::document::atom::Value;
+    use semantic::Atom;
+    use semantic::PrimitiveKind;
+
+    let atom = match (kind, node.atom.as_ref().and_then(|a| a.value.as_ref())) {
+        (PrimitiveKind::Bool, Some(&Value::Boolean(v))) => Some(Atom::Bool(v)),
+        (PrimitiveKind::String, Some(&Value::String(ref v))) => Some(Atom::String(v.clone())),
+        (_, Some(&Value::Signed(v))) => Atom::from_integer(kind, i128::from(v)),
+        (_, Some(&Value::Unsigned(v))) => Atom::from_integer(kind, i128::from(v)),
+        (_, Some(&Value::Float(v))) => Atom::from_float(kind, v),
+        _ => None,
+    };
+
+    atom.ok_or_else(|| semantic::Error::new(format!("expected a value of type {:?}", kind)))
+}
@@ -0,0 +1,1930 @@
+//! Integration with the Slate editor used by the browser frontend.
+//!
+//! Slate node types are the same as the tags of `semantic::json`, so a Markdown heading is a
+//! `"heading"` block and its `depth` is in the `data` of the block.
+use std::collections;
+use std::mem;
+use std::ops;
+use std::slice;
+
+use failure;
+use semantic;
+use semantic::json;
+use serde_json;
+
+use data::markdown;
+use error;
+use schema::se::slate as slate_proto;
+
+/// The key of the text content of text nodes and text marks, in both formats.
+const VALUE_KEY: &str = "value";
+/// The tag of plain text nodes in the semantic JSON format.
+const TEXT_TAG: &str = "text";
+/// The key in the `data` of a Slate inline node that holds the marks around the inline.
+///
+/// Slate only allows marks on text, but Markdown also allows emphasis around links and the like.
+const MARKS_KEY: &str = "marks";
+
+/// Generates a Slate schema that enforces the structure of the classes in `registry`.
+///
+/// The class with `Role::Document` becomes the document rule, and every block or inline record
+/// and unit becomes a rule for its type.  Children fields constrain the child nodes, other
+/// attribute fields are checked as `data`, and units are void nodes.  Every rule also gets a
+/// `normalize` function that repairs violations of the constraints of the class, like the depth
+/// of a heading being out of range.
+///
+/// Plain text and the inlines that become marks are Slate text nodes rather than inline nodes, so
+/// they don't get rules of their own, and nodes that contain them allow those marks instead.
+pub fn schema(registry: &semantic::Registry) -> slate_proto::Schema {
+    use semantic::Role;
+
+    let mut schema = slate_proto::Schema::default();
+
+    for class in registry.classes() {
+        let tag = match json::type_tag(class) {
+            Some(_) if is_text_class(class) => continue,
+            Some(tag) => tag,
+            None => continue,
+        };
+
+        match class.role {
+            Role::Document => schema.document = Some(rule(registry, class)),
+            Role::Block => {
+                schema.blocks.insert(tag, rule(registry, class));
+            }
+            Role::Inline => {
+                schema.inlines.insert(tag, rule(registry, class));
+            }
+            Role::Root | Role::Attribute => {}
+        }
+    }
+
+    schema
+}
+
+fn rule(
+    registry: &semantic::Registry,
+    class: &'static semantic::Class<'static>,
+) -> slate_proto::Rule {
+    use semantic::Structure;
+
+    let mut rule = slate_proto::Rule::default();
+    let mut fixes = Vec::new();
+
+    match class.structure {
+        Structure::Unit { .. } => rule.is_void = true,
+        Structure::Record { fields, .. } => {
+            for field in fields {
+                let field_class = match registry.get(field.ty) {
+                    Some(field_class) => field_class,
+                    None => continue,
+                };
+
+                if field.is_children {
+                    rule.nodes.push(children_node(registry, field, field_class));
+                } else if field_class.role == semantic::Role::Attribute {
+                    let key = json::camel_case(field.name);
+                    rule.data.insert(
+                        key.clone(),
+                        slate_proto::Validation {
+                            js_code: format!(
+                                "function (value) {{ return {}; }}",
+                                js_check(registry, field, field_class, "value")
+                            ),
+                        },
+                    );
+                    if let Some(fix) = js_fix(registry, field, field_class) {
+                        fixes.push((key, fix));
+                    }
+                }
+            }
+        }
+        _ => {}
+    }
+
+    if rule.nodes.iter().any(|n| n.objects.iter().any(|o| o == "text")) {
+        rule.marks = mark_classes()
+            .iter()
+            .filter_map(|c| json::type_tag(c))
+            .collect();
+    }
+    rule.normalize = Some(slate_proto::Normalization {
+        js_code: js_normalize(&fixes, class.role == semantic::Role::Document),
+    });
+    rule
+}
+
+/// Generates a Slate `normalize` function that repairs the violations of a rule.
+///
+/// Invalid `data` is fixed with the functions in `fixes` (by data key), children that aren't
+/// allowed are removed, and nodes that lack required children are removed as a whole, except for
+/// the document itself.
+fn js_normalize(fixes: &[(String, String)], is_document: bool) -> String {
+    let mut code = String::from("function (change, error) {\n  switch (error.code) {\n");
+
+    if !fixes.is_empty() {
+        code.push_str("    case \"node_data_invalid\":\n      var fixes = {\n");
+        for (key, fix) in fixes {
+            code.push_str(&format!("        {}: {},\n", js_string(key), fix));
+        }
+        code.push_str(
+            "      };\n      \
+             if (fixes.hasOwnProperty(error.key)) {\n        \
+             var data = error.node.data.set(error.key, fixes[error.key](error.value));\n        \
+             change.setNodeByKey(error.node.key, { data: data });\n      \
+             }\n      \
+             break;\n",
+        );
+    }
+
+    code.push_str(
+        "    case \"child_object_invalid\":\n    \
+         case \"child_type_invalid\":\n    \
+         case \"child_unknown\":\n      \
+         change.removeNodeByKey(error.child.key);\n      \
+         break;\n",
+    );
+    if !is_document {
+        code.push_str(
+            "    case \"child_required\":\n      \
+             change.removeNodeByKey(error.node.key);\n      \
+             break;\n",
+        );
+    }
+
+    code.push_str("  }\n}");
+    code
+}
+
+/// Generates a JavaScript function that turns any `value` into a valid value for `field`.
+///
+/// Numbers are rounded and clamped into their range if possible, and anything else that is
+/// invalid becomes the value that `Registry::instantiate_field` would pick.
+fn js_fix(
+    registry: &semantic::Registry,
+    field: &'static semantic::Field<'static>,
+    class: &semantic::Class,
+) -> Option<String> {
+    use semantic::PrimitiveKind;
+    use semantic::Structure;
+
+    let default = json::to_json(&registry.instantiate_field(field).ok()?);
+    let mut code = format!(
+        "function (value) {{ if ({}) {{ return value; }} ",
+        js_check(registry, field, class, "value")
+    );
+
+    let kind = match class.structure {
+        Structure::Optional { item } => item.structure,
+        structure => structure,
+    };
+    if let Structure::Primitive { kind } = kind {
+        let integer = match kind {
+            PrimitiveKind::Bool | PrimitiveKind::String => None,
+            PrimitiveKind::F32 | PrimitiveKind::F64 => Some(false),
+            _ => Some(true),
+        };
+        if let Some(integer) = integer {
+            let (mut start, end) = js_bounds(field.constraints, integer);
+            let unsigned = match kind {
+                PrimitiveKind::U8
+                | PrimitiveKind::U16
+                | PrimitiveKind::U32
+                | PrimitiveKind::U64 => true,
+                _ => false,
+            };
+            if unsigned {
+                start = max_option(start, Some(0.0));
+            }
+            let mut fixed = if integer {
+                "Math.round(value)".to_owned()
+            } else {
+                "value".to_owned()
+            };
+            if let Some(start) = start {
+                fixed = format!("Math.max({}, {})", fixed, start);
+            }
+            if let Some(end) = end {
+                fixed = format!("Math.min({}, {})", fixed, end);
+            }
+            code.push_str(&format!(
+                "if (typeof value === \"number\" && isFinite(value)) {{ \
+                 var fixed = {}; if ({}) {{ return fixed; }} }} ",
+                fixed,
+                js_check(registry, field, class, "fixed")
+            ));
+        }
+    }
+
+    code.push_str(&format!("return {}; }}", default));
+    Some(code)
+}
+
+/// The closest values that the range constraints allow at either end.
+///
+/// Excluded bounds of integers become the next integer inside the range, while excluded bounds of
+/// other numbers are left out, so that a value that is clamped onto them is still rejected.
+fn js_bounds(constraints: &[semantic::Constraint], integer: bool) -> (Option<f64>, Option<f64>) {
+    use std::ops::Bound;
+
+    let mut start = None;
+    let mut end = None;
+
+    for constraint in constraints {
+        if let semantic::Constraint::Range {
+            start: range_start,
+            end: range_end,
+        } = *constraint
+        {
+            let s = match range_start {
+                Bound::Included(s) if integer => Some(s.ceil()),
+                Bound::Included(s) => Some(s),
+                Bound::Excluded(s) if integer => Some(s.floor() + 1.0),
+                Bound::Excluded(_) | Bound::Unbounded => None,
+            };
+            let e = match range_end {
+                Bound::Included(e) if integer => Some(e.floor()),
+                Bound::Included(e) => Some(e),
+                Bound::Excluded(e) if integer => Some(e.ceil() - 1.0),
+                Bound::Excluded(_) | Bound::Unbounded => None,
+            };
+            start = max_option(start, s);
+            end = min_option(end, e);
+        }
+    }
+
+    (start, end)
+}
+
+fn max_option(a: Option<f64>, b: Option<f64>) -> Option<f64> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.max(b)),
+        (a, b) => a.or(b),
+    }
+}
+
+fn min_option(a: Option<f64>, b: Option<f64>) -> Option<f64> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    }
+}
+
+/// Describes the child nodes that a children field allows.
+///
+/// Slate requires a child to match both the `objects` and the `types` of a node, and text nodes
+/// have no type.  So when text is allowed, only the objects are checked, and any inline type is
+/// accepted next to it.
+fn children_node(
+    registry: &semantic::Registry,
+    field: &semantic::Field,
+    class: &semantic::Class,
+) -> slate_proto::Node {
+    use semantic::Structure;
+
+    let non_empty = is_non_empty(field);
+    let (item, min, max) = match class.structure {
+        Structure::Collection { item } => (item, if non_empty { 1 } else { 0 }, 0),
+        Structure::Optional { item } => (item, if non_empty { 1 } else { 0 }, 1),
+        _ => (class, 1, 1),
+    };
+
+    let mut types = collections::BTreeSet::new();
+    let mut objects = collections::BTreeSet::new();
+    collect_types(registry, item, &mut types, &mut objects);
+    if objects.contains("text") {
+        types.clear();
+    }
+
+    slate_proto::Node {
+        types: types.into_iter().collect(),
+        objects: objects.into_iter().collect(),
+        min,
+        max,
+    }
+}
+
+/// Collects the Slate types and objects that a value of `class` can be.
+fn collect_types(
+    registry: &semantic::Registry,
+    class: &semantic::Class,
+    types: &mut collections::BTreeSet<String>,
+    objects: &mut collections::BTreeSet<String>,
+) {
+    use semantic::Structure;
+    use semantic::VariantKind;
+
+    match class.structure {
+        Structure::Union { variants, .. } => {
+            for variant in variants {
+                match variant.kind {
+                    VariantKind::Newtype { ty } => {
+                        if let Some(class) = registry.get(ty) {
+                            collect_types(registry, class, types, objects);
+                        }
+                    }
+                    VariantKind::Unit | VariantKind::Record { .. } => {
+                        types.insert(json::camel_case(variant.name));
+                        objects.insert(object_kind(class).to_owned());
+                    }
+                }
+            }
+        }
+        Structure::Primitive {
+            kind: semantic::PrimitiveKind::String,
+        } => {
+            objects.insert("text".to_owned());
+        }
+        _ if is_text_class(class) => {
+            objects.insert("text".to_owned());
+        }
+        _ => {
+            if let Some(tag) = json::type_tag(class) {
+                types.insert(tag);
+                objects.insert(object_kind(class).to_owned());
+            }
+        }
+    }
+}
+
+/// The classes of the inlines that become marks on the text inside of them, rather than Slate
+/// nodes of their own.
+fn mark_classes() -> [&'static semantic::Class<'static>; 4] {
+    use semantic::Semantic;
+
+    [
+        &markdown::Emphasis::CLASS,
+        &markdown::Strong::CLASS,
+        &markdown::Delete::CLASS,
+        &markdown::InlineCode::CLASS,
+    ]
+}
+
+/// Whether values of a class become Slate text nodes, either as plain text or as marks.
+fn is_text_class(class: &semantic::Class) -> bool {
+    json::type_tag(class).map_or(false, |tag| tag == TEXT_TAG)
+        || mark_classes().iter().any(|c| c.id == class.id)
+}
+
+/// The Slate object that nodes of a class become.
+fn object_kind(class: &semantic::Class) -> &'static str {
+    match class.role {
+        semantic::Role::Inline => "inline",
+        _ => "block",
+    }
+}
+
+/// Generates a JavaScript expression that checks whether `value` is a valid JSON encoding of a
+/// value of `class` for `field`.
+fn js_check(
+    registry: &semantic::Registry,
+    field: &semantic::Field,
+    class: &semantic::Class,
+    value: &str,
+) -> String {
+    use semantic::Constraint;
+    use semantic::PrimitiveKind;
+    use semantic::Structure;
+    use std::ops::Bound;
+
+    if let Structure::Optional { item } = class.structure {
+        // The constraints of the field apply to the item, if there is one
+        return format!(
+            "({0} === null || {0} === undefined || {1})",
+            value,
+            js_check(registry, field, item, value)
+        );
+    }
+
+    let mut checks = vec![match class.structure {
+        Structure::Primitive { kind } => match kind {
+            PrimitiveKind::Bool => format!("typeof {} === \"boolean\"", value),
+            PrimitiveKind::String => format!("typeof {} === \"string\"", value),
+            PrimitiveKind::F32 | PrimitiveKind::F64 => format!("typeof {} === \"number\"", value),
+            PrimitiveKind::U8 | PrimitiveKind::U16 | PrimitiveKind::U32 | PrimitiveKind::U64 => {
+                format!("Number.isInteger({0}) && {0} >= 0", value)
+            }
+            PrimitiveKind::I8 | PrimitiveKind::I16 | PrimitiveKind::I32 | PrimitiveKind::I64 => {
+                format!("Number.isInteger({})", value)
+            }
+        },
+        Structure::Enumeration { variants, .. } => format!(
+            "[{}].indexOf({}) >= 0",
+            variants
+                .iter()
+                .map(|v| js_string(&json::camel_case(v.name)))
+                .collect::<Vec<_>>()
+                .join(", "),
+            value
+        ),
+        Structure::Collection { item } => format!(
+            "Array.isArray({0}) && {0}.every(function (item) {{ return {1}; }})",
+            value,
+            js_check(registry, &no_constraints(field), item, "item")
+        ),
+        Structure::Unit { .. }
+        | Structure::Record { .. }
+        | Structure::Union { .. }
+        | Structure::Optional { .. } => {
+            format!("typeof {0} === \"object\" && {0} !== null", value)
+        }
+    }];
+
+    for constraint in field.constraints {
+        match *constraint {
+            Constraint::Range { start, end } => {
+                match start {
+                    Bound::Included(s) => checks.push(format!("{} >= {}", value, s)),
+                    Bound::Excluded(s) => checks.push(format!("{} > {}", value, s)),
+                    Bound::Unbounded => {}
+                }
+                match end {
+                    Bound::Included(e) => checks.push(format!("{} <= {}", value, e)),
+                    Bound::Excluded(e) => checks.push(format!("{} < {}", value, e)),
+                    Bound::Unbounded => {}
+                }
+            }
+            Constraint::NonEmpty => checks.push(format!("{}.length > 0", value)),
+        }
+    }
+
+    checks.join(" && ")
+}
+
+/// Quotes a string as a JavaScript string literal.
+fn js_string(string: &str) -> String {
+    serde_json::to_string(string).expect("strings can always be serialized")
+}
+
+fn is_non_empty(field: &semantic::Field) -> bool {
+    field.constraints.iter().any(|c| match *c {
+        semantic::Constraint::NonEmpty => true,
+        semantic::Constraint::Range { .. } => false,
+    })
+}
+
+/// The same field without any constraints, for checking the items of a collection field.
+fn no_constraints<'a>(field: &semantic::Field<'a>) -> semantic::Field<'a> {
+    semantic::Field {
+        constraints: &[],
+        ..*field
+    }
+}
+
+/// Converts a Markdown document into a Slate `Value`, in the JSON format of `Value.toJSON()`.
+///
+/// Blocks and inlines become Slate nodes of the same type, with their attributes in `data`.
+/// `Emphasis`, `Strong`, `Delete` and `InlineCode` become marks on the text inside of them.
+pub fn to_slate_value(markdown: &markdown::Markdown) -> error::Result<serde_json::Value> {
+    let converter = Converter::markdown();
+    let (_, data, children) = converter.split(&json::to_json(markdown))?;
+
+    Ok(object(vec![
+        ("object", string("value")),
+        (
+            "document",
+            object(vec![
+                ("object", string("document")),
+                ("data", serde_json::Value::Object(data)),
+                ("nodes", converter.to_slate_nodes(&children, &[])?.into()),
+            ]),
+        ),
+    ]))
+}
+
+/// Converts a Slate `Value` that was produced by `to_slate_value` (and maybe edited since) back
+/// into a Markdown document.
+pub fn from_slate_value(value: &serde_json::Value) -> error::Result<markdown::Markdown> {
+    let converter = Converter::markdown();
+    let document = value
+        .get("document")
+        .ok_or_else(|| failure::err_msg("the Slate value has no document"))?;
+    let root = json::type_tag(&<markdown::Markdown as semantic::Semantic>::CLASS)
+        .expect("a document has a type tag");
+
+    let mdast = converter.from_slate_node(&root, document)?;
+    Ok(json::from_json(&mdast)?)
+}
+
+/// Parses an operation in the JSON format of Slate's `Operation.toJSON()`.
+pub fn operation_from_json(json: &serde_json::Value) -> error::Result<slate_proto::Operation> {
+    use schema::se::slate::operation::Kind;
+
+    let field = |name: &str| {
+        json.get(name)
+            .ok_or_else(|| failure::err_msg(format!("the operation has no {:?}", name)))
+    };
+    let path = |name: &str| -> error::Result<Option<slate_proto::Path>> {
+        let index = field(name)?
+            .as_array()
+            .and_then(|p| p.iter().map(|i| i.as_u64().map(|i| i as u32)).collect())
+            .ok_or_else(|| failure::err_msg(format!("expected {:?} to be a path", name)))?;
+        Ok(Some(slate_proto::Path { index }))
+    };
+    let number = |name: &str| -> error::Result<u32> {
+        field(name)?
+            .as_u64()
+            .map(|n| n as u32)
+            .ok_or_else(|| failure::err_msg(format!("expected {:?} to be a number", name)))
+    };
+    let text = |name: &str| -> error::Result<String> {
+        field(name)?
+            .as_str()
+            .map(str::to_owned)
+            .ok_or_else(|| failure::err_msg(format!("expected {:?} to be a string", name)))
+    };
+    let encoded =
+        |name: &str| -> error::Result<String> { Ok(serde_json::to_string(field(name)?)?) };
+
+    let kind = match json.get("type").and_then(|t| t.as_str()) {
+        Some("insert_node") => Kind::InsertNode(slate_proto::InsertNode {
+            path: path("path")?,
+            node_json: encoded("node")?,
+        }),
+        Some("remove_node") => Kind::RemoveNode(slate_proto::RemoveNode {
+            path: path("path")?,
+        }),
+        Some("move_node") => Kind::MoveNode(slate_proto::MoveNode {
+            path: path("path")?,
+            new_path: path("newPath")?,
+        }),
+        Some("split_node") => Kind::SplitNode(slate_proto::SplitNode {
+            path: path("path")?,
+            position: number("position")?,
+            properties_json: encoded("properties")?,
+        }),
+        Some("merge_node") => Kind::MergeNode(slate_proto::MergeNode {
+            path: path("path")?,
+            position: number("position")?,
+        }),
+        Some("set_node") => Kind::SetNode(slate_proto::SetNode {
+            path: path("path")?,
+            properties_json: encoded("properties")?,
+        }),
+        Some("insert_text") => Kind::InsertText(slate_proto::InsertText {
+            path: path("path")?,
+            offset: number("offset")?,
+            text: text("text")?,
+            mark_json: array(json.get("marks"))
+                .iter()
+                .map(serde_json::to_string)
+                .collect::<Result<_, _>>()?,
+        }),
+        Some("remove_text") => Kind::RemoveText(slate_proto::RemoveText {
+            path: path("path")?,
+            offset: number("offset")?,
+            text: text("text")?,
+        }),
+        Some("add_mark") => Kind::AddMark(slate_proto::AddMark {
+            path: path("path")?,
+            offset: number("offset")?,
+            length: number("length")?,
+            mark_json: encoded("mark")?,
+        }),
+        Some("remove_mark") => Kind::RemoveMark(slate_proto::RemoveMark {
+            path: path("path")?,
+            offset: number("offset")?,
+            length: number("length")?,
+            mark_json: encoded("mark")?,
+        }),
+        other => {
+            return Err(failure::err_msg(format!(
+                "unsupported operation type {:?}",
+                other
+            )))
+        }
+    };
+
+    Ok(slate_proto::Operation { kind: Some(kind) })
+}
+
+/// Applies an operation to a Slate `Value` in JSON form, the same way that Slate does.
+pub fn apply_operation(
+    value: &mut serde_json::Value,
+    operation: &slate_proto::Operation,
+) -> error::Result<()> {
+    use schema::se::slate::operation::Kind;
+
+    let document = value
+        .get_mut("document")
+        .ok_or_else(|| failure::err_msg("the Slate value has no document"))?;
+
+    match operation.kind {
+        Some(Kind::InsertNode(ref op)) => {
+            let (parent, index) = split_path(&op.path)?;
+            let nodes = nodes_mut(node_at(document, &parent)?)?;
+            if index > nodes.len() {
+                return Err(invalid_path());
+            }
+            nodes.insert(index, serde_json::from_str(&op.node_json)?);
+        }
+        Some(Kind::RemoveNode(ref op)) => {
+            remove_node(document, &op.path)?;
+        }
+        Some(Kind::MoveNode(ref op)) => {
+            let node = remove_node(document, &op.path)?;
+            let new_path = moved_path(&steps(&op.path)?, steps(&op.new_path)?)?;
+
+            let (&index, parent) = new_path.split_last().ok_or_else(invalid_path)?;
+            let nodes = nodes_mut(node_at(document, parent)?)?;
+            if index > nodes.len() {
+                return Err(invalid_path());
+            }
+            nodes.insert(index, node);
+        }
+        Some(Kind::SplitNode(ref op)) => {
+            let (parent, index) = split_path(&op.path)?;
+            let nodes = nodes_mut(node_at(document, &parent)?)?;
+            let mut new_node = {
+                let node = nodes.get_mut(index).ok_or_else(invalid_path)?;
+                let position = op.position as usize;
+
+                if is_text(node) {
+                    let mut leaves = leaves(node)?;
+                    split_leaves(&mut leaves, position)?;
+                    let rest = split_off_leaves(&mut leaves, position);
+                    let mut new_node = node.clone();
+                    set_leaves(node, leaves)?;
+                    set_leaves(&mut new_node, rest)?;
+                    new_node
+                } else {
+                    let children = nodes_mut(node)?;
+                    if position > children.len() {
+                        return Err(failure::err_msg("the split position is out of range"));
+                    }
+                    let rest = children.split_off(position);
+                    let mut new_node = node.clone();
+                    *nodes_mut(&mut new_node)? = rest;
+                    new_node
+                }
+            };
+
+            set_properties(&mut new_node, &op.properties_json)?;
+            nodes.insert(index + 1, new_node);
+        }
+        Some(Kind::MergeNode(ref op)) => {
+            let (parent, index) = split_path(&op.path)?;
+            let nodes = nodes_mut(node_at(document, &parent)?)?;
+            if index == 0 || index >= nodes.len() {
+                return Err(invalid_path());
+            }
+
+            let node = nodes.remove(index);
+            let previous = &mut nodes[index - 1];
+            match (is_text(previous), is_text(&node)) {
+                (true, true) => {
+                    let mut merged = leaves(previous)?;
+                    merged.extend(leaves(&node)?);
+                    set_leaves(previous, merged)?;
+                }
+                (false, false) => {
+                    let children = node
+                        .get("nodes")
+                        .and_then(|n| n.as_array())
+                        .cloned()
+                        .unwrap_or_default();
+                    nodes_mut(previous)?.extend(children);
+                }
+                _ => return Err(failure::err_msg("only nodes of the same kind can be merged")),
+            }
+        }
+        Some(Kind::SetNode(ref op)) => {
+            set_properties(node_at(document, &steps(&op.path)?)?, &op.properties_json)?;
+        }
+        Some(Kind::InsertText(ref op)) => {
+            let node = node_at(document, &steps(&op.path)?)?;
+            let marks = op
+                .mark_json
+                .iter()
+                .map(|m| serde_json::from_str(m))
+                .collect::<Result<Vec<_>, _>>()?;
+            let offset = op.offset as usize;
+
+            let mut leaves = leaves(node)?;
+            split_leaves(&mut leaves, offset)?;
+            let index = leaf_index(&leaves, offset);
+            // Text that is inserted without marks continues the text before it, so that typing at
+            // the end of some bold text is bold as well
+            let marks = match index.checked_sub(1).and_then(|i| leaves.get(i)) {
+                Some(previous) if marks.is_empty() => previous.marks.clone(),
+                _ => marks,
+            };
+            leaves.insert(
+                index,
+                Leaf {
+                    text: op.text.encode_utf16().collect(),
+                    marks,
+                },
+            );
+            set_leaves(node, leaves)?;
+        }
+        Some(Kind::RemoveText(ref op)) => {
+            let node = node_at(document, &steps(&op.path)?)?;
+            let start = op.offset as usize;
+            let end = start + op.text.encode_utf16().count();
+
+            let mut leaves = leaves(node)?;
+            split_leaves(&mut leaves, start)?;
+            split_leaves(&mut leaves, end)?;
+            let mut position = 0;
+            leaves.retain(|leaf| {
+                let leaf_start = position;
+                position += leaf.text.len();
+                leaf_start < start || leaf_start >= end
+            });
+            set_leaves(node, leaves)?;
+        }
+        Some(Kind::AddMark(ref op)) => {
+            let mark: serde_json::Value = serde_json::from_str(&op.mark_json)?;
+            edit_marks(document, &op.path, op.offset, op.length, |marks| {
+                if !marks.contains(&mark) {
+                    marks.push(mark.clone());
+                }
+            })?;
+        }
+        Some(Kind::RemoveMark(ref op)) => {
+            let mark: serde_json::Value = serde_json::from_str(&op.mark_json)?;
+            edit_marks(document, &op.path, op.offset, op.length, |marks| {
+                marks.retain(|m| *m != mark)
+            })?;
+        }
+        None => return Err(failure::err_msg("the operation is empty")),
+    }
+
+    Ok(())
+}
+
+/// Applies Slate operations to the Markdown document at `key` in `store`, and makes the same edits
+/// to `snapshot`, a snapshot of the whole store.
+///
+/// Every operation becomes the matching edit of the store: moving, inserting, removing, splitting
+/// or merging a block moves, inserts or removes its semantic node, so that the block and
+/// everything in it keep their keys.  Slate nodes inside of inline content don't match semantic
+/// nodes one to one, so other operations update the innermost block around them instead.
+///
+/// Only the top-level blocks that an operation touches are converted to Slate and back, so typing
+/// costs the same in a short document as in a long one.  Operations on the document itself and
+/// moves from one top-level block into another still convert the whole document.
+///
+/// Returns the keys of the nodes that were changed or inserted, which are the only ones that can
+/// have become invalid.  If an operation fails, the ones before it may have been applied already.
+pub fn apply_operations(
+    store: &mut semantic::Store,
+    snapshot: &mut semantic::Snapshot,
+    key: semantic::NodeKey,
+    operations: &[slate_proto::Operation],
+) -> error::Result<Vec<semantic::NodeKey>> {
+    let converter = Converter::markdown();
+    let mut editing = Editing {
+        store,
+        snapshot,
+        changed: Vec::new(),
+    };
+    // The Slate nodes of the top-level blocks that earlier operations touched, which can have
+    // changes that don't show in the store, like a text that was split in two
+    let mut blocks = collections::HashMap::new();
+
+    for operation in operations {
+        let mut relative = operation.clone();
+        match scope(operation)? {
+            Scope::Block(index) => {
+                for path in paths_mut(&mut relative)? {
+                    path.remove(0);
+                }
+                edit_block(&converter, &mut editing, &mut blocks, key, index, &relative)?;
+            }
+            Scope::Blocks(range) => {
+                for path in paths_mut(&mut relative)? {
+                    path[0] -= range.start as u32;
+                }
+                edit_blocks(&converter, &mut editing, &mut blocks, key, range, &relative)?;
+            }
+            Scope::Move(from, to) => {
+                let items = top_level(editing.store, key)?;
+                let item = *editing
+                    .store
+                    .children(items)
+                    .get(from)
+                    .ok_or_else(invalid_path)?;
+                editing.move_node(item, items, to)?;
+            }
+            Scope::Document => {
+                edit_document(&mut editing, &mut blocks, key, operation)?;
+            }
+        }
+    }
+
+    Ok(editing.changed)
+}
+
+/// The part of a document that an operation changes.
+enum Scope {
+    /// The contents of the top-level block at an index.
+    Block(usize),
+    /// The top-level blocks in a range, which the operation replaces with any number of blocks.
+    Blocks(ops::Range<usize>),
+    /// A top-level block that moves from one index to another.
+    Move(usize, usize),
+    /// Anything in the document.
+    Document,
+}
+
+fn scope(operation: &slate_proto::Operation) -> error::Result<Scope> {
+    use schema::se::slate::operation::Kind;
+
+    let mut operation = operation.clone();
+    let paths = paths_mut(&mut operation)?
+        .into_iter()
+        .map(|p| p.iter().map(|&i| i as usize).collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+    let first = match paths[0].first() {
+        Some(&first) => first,
+        None => return Ok(Scope::Document),
+    };
+
+    if paths.iter().all(|p| p.len() > 1 && p[0] == first) {
+        return Ok(Scope::Block(first));
+    }
+    if paths.iter().any(|p| p.len() != 1) {
+        return Ok(Scope::Document);
+    }
+
+    Ok(match operation.kind {
+        Some(Kind::MoveNode(_)) => Scope::Move(first, moved_path(&paths[0], paths[1].clone())?[0]),
+        Some(Kind::InsertNode(_)) => Scope::Blocks(first..first),
+        Some(Kind::MergeNode(_)) => {
+            Scope::Blocks(first.checked_sub(1).ok_or_else(invalid_path)?..first + 1)
+        }
+        _ => Scope::Blocks(first..first + 1),
+    })
+}
+
+/// The paths of an operation, as indices into the children of each node on the way.
+fn paths_mut(operation: &mut slate_proto::Operation) -> error::Result<Vec<&mut Vec<u32>>> {
+    use schema::se::slate::operation::Kind;
+
+    let paths = match operation.kind {
+        Some(Kind::InsertNode(ref mut op)) => vec![&mut op.path],
+        Some(Kind::RemoveNode(ref mut op)) => vec![&mut op.path],
+        Some(Kind::MoveNode(ref mut op)) => vec![&mut op.path, &mut op.new_path],
+        Some(Kind::SplitNode(ref mut op)) => vec![&mut op.path],
+        Some(Kind::MergeNode(ref mut op)) => vec![&mut op.path],
+        Some(Kind::SetNode(ref mut op)) => vec![&mut op.path],
+        Some(Kind::InsertText(ref mut op)) => vec![&mut op.path],
+        Some(Kind::RemoveText(ref mut op)) => vec![&mut op.path],
+        Some(Kind::AddMark(ref mut op)) => vec![&mut op.path],
+        Some(Kind::RemoveMark(ref mut op)) => vec![&mut op.path],
+        None => return Err(failure::err_msg("the operation is empty")),
+    };
+
+    paths
+        .into_iter()
+        .map(|p| {
+            p.as_mut()
+                .map(|p| &mut p.index)
+                .ok_or_else(|| failure::err_msg("the operation has no path"))
+        })
+        .collect()
+}
+
+/// Applies an operation with paths relative to the top-level block at `index`.
+fn edit_block(
+    converter: &Converter,
+    editing: &mut Editing,
+    blocks: &mut collections::HashMap<semantic::NodeKey, serde_json::Value>,
+    key: semantic::NodeKey,
+    index: usize,
+    operation: &slate_proto::Operation,
+) -> error::Result<()> {
+    let items = top_level(editing.store, key)?;
+    let item = *editing
+        .store
+        .children(items)
+        .get(index)
+        .ok_or_else(invalid_path)?;
+
+    // The block takes the place of the document, so that the paths work out
+    let node = match blocks.remove(&item) {
+        Some(node) => node,
+        None => converter
+            .to_slate_items(editing.store, &[item])?
+            .pop()
+            .ok_or_else(invalid_path)?,
+    };
+    let mut value = object(vec![("document", node)]);
+    apply_operation(&mut value, operation)?;
+    let node = mem::replace(&mut value["document"], serde_json::Value::Null);
+
+    let class = item_class(editing.store, items)?;
+    let mut after = converter.from_slate_items(class, slice::from_ref(&node))?;
+    if after.len() != 1 {
+        return Err(failure::err_msg("the block can't be represented in the document"));
+    }
+    let after = after.remove(0);
+    let before = editing
+        .store
+        .to_node(item)
+        .ok_or_else(|| failure::err_msg("the block is not in the store"))?;
+
+    // Slate paths go through unions without taking a step, like from a `Block` to its `Heading`
+    match (newtype(&before), newtype(&after)) {
+        (Some(inner), Some(after_inner)) if inner.class.id == after_inner.class.id => {
+            let inner_key = editing.store.children(item)[0];
+            edit_store(editing, inner_key, inner, after_inner, operation)?;
+        }
+        (None, None) => edit_store(editing, item, &before, &after, operation)?,
+        _ => {}
+    }
+    // Converting to Slate and back can change more than the operation did, like joining adjacent
+    // texts, so catch up with whatever is left
+    editing.update(item, after)?;
+
+    blocks.insert(item, node);
+    Ok(())
+}
+
+/// Applies an operation that replaces the top-level blocks in `range`, with paths relative to the
+/// start of the range.
+fn edit_blocks(
+    converter: &Converter,
+    editing: &mut Editing,
+    blocks: &mut collections::HashMap<semantic::NodeKey, serde_json::Value>,
+    key: semantic::NodeKey,
+    range: ops::Range<usize>,
+    operation: &slate_proto::Operation,
+) -> error::Result<()> {
+    let items = top_level(editing.store, key)?;
+    let old = editing
+        .store
+        .children(items)
+        .get(range.clone())
+        .ok_or_else(invalid_path)?
+        .to_vec();
+
+    let mut nodes = Vec::new();
+    for item in &old {
+        match blocks.remove(item) {
+            Some(node) => nodes.push(node),
+            None => nodes.extend(converter.to_slate_items(editing.store, &[*item])?),
+        }
+    }
+    let mut value = object(vec![(
+        "document",
+        object(vec![("object", string("document")), ("nodes", nodes.into())]),
+    )]);
+    apply_operation(&mut value, operation)?;
+    let nodes = array(value["document"].get("nodes"));
+    let after = converter.from_slate_items(item_class(editing.store, items)?, &nodes)?;
+
+    // The blocks at the start of the range keep their keys, like the first part of a split block
+    let mut new = Vec::new();
+    for (i, node) in after.into_iter().enumerate() {
+        match old.get(i) {
+            Some(&item) => {
+                editing.update(item, node)?;
+                new.push(item);
+            }
+            None => new.push(editing.insert(items, range.start + i, node)?),
+        }
+    }
+    for &item in &old[new.len().min(old.len())..] {
+        editing.remove(item)?;
+    }
+
+    if nodes.len() == new.len() {
+        blocks.extend(new.into_iter().zip(nodes));
+    }
+    Ok(())
+}
+
+/// Applies an operation to the whole document at `key`.
+fn edit_document(
+    editing: &mut Editing,
+    blocks: &mut collections::HashMap<semantic::NodeKey, serde_json::Value>,
+    key: semantic::NodeKey,
+    operation: &slate_proto::Operation,
+) -> error::Result<()> {
+    let before = editing
+        .store
+        .to_node(key)
+        .ok_or_else(|| failure::err_msg("the document is not in the store"))?;
+    let mut value = to_slate_value(&before.clone().into_value()?)?;
+
+    let items = top_level(editing.store, key)?;
+    let old = editing.store.children(items).to_vec();
+    for (item, node) in old.iter().zip(nodes_mut(&mut value["document"])?) {
+        if let Some(edited) = blocks.remove(item) {
+            *node = edited;
+        }
+    }
+
+    apply_operation(&mut value, operation)?;
+    let after = semantic::Node::from_value(&from_slate_value(&value)?);
+    edit_store(editing, key, &before, &after, operation)?;
+    editing.update(key, after)?;
+
+    let new = editing.store.children(items).to_vec();
+    let nodes = array(value["document"].get("nodes"));
+    blocks.clear();
+    if nodes.len() == new.len() {
+        blocks.extend(new.into_iter().zip(nodes));
+    }
+    Ok(())
+}
+
+/// The key of the collection of top-level blocks of the document at `key`.
+fn top_level(store: &semantic::Store, key: semantic::NodeKey) -> error::Result<semantic::NodeKey> {
+    let field = match store.class(key).map(|c| c.structure) {
+        Some(semantic::Structure::Record { fields, .. }) => {
+            fields.iter().position(|f| f.is_children)
+        }
+        _ => None,
+    };
+    field
+        .and_then(|f| store.children(key).get(f).cloned())
+        .ok_or_else(|| failure::err_msg("the document is not in the store"))
+}
+
+/// The class of the items of the collection at `key`.
+fn item_class(
+    store: &semantic::Store,
+    key: semantic::NodeKey,
+) -> error::Result<&'static semantic::Class<'static>> {
+    match store.class(key).map(|c| c.structure) {
+        Some(semantic::Structure::Collection { item }) => Ok(item),
+        _ => Err(failure::err_msg("expected a collection of blocks")),
+    }
+}
+
+/// The value inside of a node of a union variant like `Block::Heading`, if it is one.
+fn newtype(node: &semantic::Node) -> Option<&semantic::Node> {
+    match (&node.content, node.class.structure) {
+        (
+            &semantic::Content::Union {
+                variant,
+                ref fields,
+            },
+            semantic::Structure::Union { variants, .. },
+        ) => match variants[variant].kind {
+            semantic::VariantKind::Newtype { .. } => fields.first(),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// A store that operations are being applied to, along with a snapshot of it that gets the same
+/// edits.
+struct Editing<'a> {
+    store: &'a mut semantic::Store,
+    snapshot: &'a mut semantic::Snapshot,
+    /// The nodes that were changed or inserted so far.
+    changed: Vec<semantic::NodeKey>,
+}
+
+impl<'a> Editing<'a> {
+    fn path(&self, key: semantic::NodeKey) -> error::Result<semantic::NodePath> {
+        self.store.path(key).ok_or_else(invalid_path)
+    }
+
+    fn update(&mut self, key: semantic::NodeKey, node: semantic::Node) -> error::Result<()> {
+        *self.snapshot = self.snapshot.replace(&self.path(key)?, node.clone())?;
+        self.store.update(key, node)?;
+        self.changed.push(key);
+        Ok(())
+    }
+
+    fn insert(
+        &mut self,
+        parent: semantic::NodeKey,
+        index: usize,
+        node: semantic::Node,
+    ) -> error::Result<semantic::NodeKey> {
+        *self.snapshot = self
+            .snapshot
+            .insert(&self.path(parent)?.child(index), node.clone())?;
+        let key = self.store.insert(parent, index, node)?;
+        self.changed.push(key);
+        Ok(key)
+    }
+
+    fn remove(&mut self, key: semantic::NodeKey) -> error::Result<()> {
+        *self.snapshot = self.snapshot.remove(&self.path(key)?)?;
+        self.store.remove(key)?;
+        Ok(())
+    }
+
+    fn move_node(
+        &mut self,
+        key: semantic::NodeKey,
+        parent: semantic::NodeKey,
+        index: usize,
+    ) -> error::Result<()> {
+        let from = self.path(key)?;
+        self.store.move_node(key, parent, index)?;
+
+        // The parent has the same path with and without the node in it
+        let node = self.snapshot.get(&from).ok_or_else(invalid_path)?.to_node();
+        let removed = self.snapshot.remove(&from)?;
+        *self.snapshot = removed.insert(&self.path(parent)?.child(index), node)?;
+        Ok(())
+    }
+}
+
+/// Makes the edit of the store that matches a Slate operation, which changed the Markdown document
+/// at `key` from `before` into `after`.
+fn edit_store(
+    editing: &mut Editing,
+    key: semantic::NodeKey,
+    before: &semantic::Node,
+    after: &semantic::Node,
+    operation: &slate_proto::Operation,
+) -> error::Result<()> {
+    use schema::se::slate::operation::Kind;
+
+    // The Slate path of the innermost node that contains everything that changed, for when the
+    // operation has no matching edit
+    let changed = match operation.kind {
+        Some(Kind::InsertNode(ref op)) => {
+            let path = steps(&op.path)?;
+            if let Some(item) = block_path(after, &path) {
+                let (parent, index) = split_item(&item)?;
+                let node = subtree(after, &item)?.clone();
+                editing.insert(store_key(editing.store, key, &parent, None)?, index, node)?;
+                return Ok(());
+            }
+            parent_steps(&path)
+        }
+        Some(Kind::RemoveNode(ref op)) => {
+            let path = steps(&op.path)?;
+            if let Some(item) = block_path(before, &path) {
+                editing.remove(store_key(editing.store, key, &item, None)?)?;
+                return Ok(());
+            }
+            parent_steps(&path)
+        }
+        Some(Kind::MoveNode(ref op)) => {
+            let path = steps(&op.path)?;
+            let new_path = moved_path(&path, steps(&op.new_path)?)?;
+            if let (Some(old_item), Some(new_item)) =
+                (block_path(before, &path), block_path(after, &new_path))
+            {
+                // The new path is in the tree without the node, and the store still has it
+                let moved = store_key(editing.store, key, &old_item, None)?;
+                let (parent, index) = split_item(&new_item)?;
+                let parent = store_key(editing.store, key, &parent, Some(moved))?;
+                editing.move_node(moved, parent, index)?;
+                return Ok(());
+            }
+            let old_parent = parent_steps(&path);
+            parent_steps(&new_path)
+                .into_iter()
+                .zip(old_parent)
+                .take_while(|&(a, b)| a == b)
+                .map(|(a, _)| a)
+                .collect()
+        }
+        Some(Kind::SplitNode(ref op)) => {
+            let path = steps(&op.path)?;
+            if let Some(item) = block_path(before, &path) {
+                let (parent, index) = split_item(&item)?;
+                let first = subtree(after, &item)?.clone();
+                let second = subtree(after, &parent.child(index + 1))?.clone();
+                editing.update(store_key(editing.store, key, &item, None)?, first)?;
+                editing.insert(store_key(editing.store, key, &parent, None)?, index + 1, second)?;
+                return Ok(());
+            }
+            parent_steps(&path)
+        }
+        Some(Kind::MergeNode(ref op)) => {
+            let path = steps(&op.path)?;
+            if let Some(item) = block_path(before, &path) {
+                let (parent, index) = split_item(&item)?;
+                let previous = parent.child(index - 1);
+                let merged = subtree(after, &previous)?.clone();
+                editing.remove(store_key(editing.store, key, &item, None)?)?;
+                editing.update(store_key(editing.store, key, &previous, None)?, merged)?;
+                return Ok(());
+            }
+            parent_steps(&path)
+        }
+        Some(Kind::SetNode(ref op)) => steps(&op.path)?,
+        Some(Kind::InsertText(ref op)) => steps(&op.path)?,
+        Some(Kind::RemoveText(ref op)) => steps(&op.path)?,
+        Some(Kind::AddMark(ref op)) => steps(&op.path)?,
+        Some(Kind::RemoveMark(ref op)) => steps(&op.path)?,
+        None => return Err(failure::err_msg("the operation is empty")),
+    };
+
+    let (item, _) = semantic_path(after, &changed);
+    let node = subtree(after, &item)?.clone();
+    editing.update(store_key(editing.store, key, &item, None)?, node)?;
+    Ok(())
+}
+
+/// Finds the semantic node of the Slate node at `steps` in the Markdown document `document`.
+///
+/// Slate nodes only match semantic nodes one to one down to the blocks whose children are
+/// inlines, because Slate turns some inlines into marks and puts texts around the others.  So this
+/// returns the path of the innermost semantic node along `steps`, and how many of the steps lead
+/// to it.  The path is that of the item in the children field of the parent, rather than that of
+/// the record inside of a `Block`.
+fn semantic_path(document: &semantic::Node, steps: &[usize]) -> (semantic::NodePath, usize) {
+    use semantic::Structure;
+
+    let mut item_path = semantic::NodePath::root();
+    let mut path = semantic::NodePath::root();
+    let mut node = document;
+
+    for (covered, &step) in steps.iter().enumerate() {
+        let field = match node.class.structure {
+            Structure::Record { fields, .. } => fields.iter().position(|f| f.is_children),
+            _ => None,
+        };
+        let items = match field {
+            Some(field) => node.children()[field],
+            None => return (item_path, covered),
+        };
+        let has_blocks = match items.class.structure {
+            Structure::Collection { item } | Structure::Optional { item } => {
+                item.role != semantic::Role::Inline
+            }
+            _ => false,
+        };
+        let item = match items.children().get(step) {
+            Some(&item) if has_blocks => item,
+            _ => return (item_path, covered),
+        };
+
+        item_path = path.child(field.expect("the items are in a field")).child(step);
+        path = item_path.clone();
+        node = item;
+        if let semantic::Content::Union { variant, .. } = item.content {
+            if let Structure::Union { variants, .. } = item.class.structure {
+                if let semantic::VariantKind::Newtype { .. } = variants[variant].kind {
+                    path = path.child(0);
+                    node = item.children()[0];
+                }
+            }
+        }
+    }
+
+    (item_path, steps.len())
+}
+
+/// The semantic path of the Slate node at `steps`, if it has a semantic node of its own.
+fn block_path(document: &semantic::Node, steps: &[usize]) -> Option<semantic::NodePath> {
+    match semantic_path(document, steps) {
+        (path, covered) if covered == steps.len() && !steps.is_empty() => Some(path),
+        _ => None,
+    }
+}
+
+fn subtree<'a>(
+    node: &'a semantic::Node,
+    path: &semantic::NodePath,
+) -> error::Result<&'a semantic::Node> {
+    let mut current = node;
+    for &step in path.steps() {
+        current = *current.children().get(step).ok_or_else(invalid_path)?;
+    }
+    Ok(current)
+}
+
+/// The key of the node at `path` below `key`, as if the node `moved` wasn't in the store.
+fn store_key(
+    store: &semantic::Store,
+    key: semantic::NodeKey,
+    path: &semantic::NodePath,
+    moved: Option<semantic::NodeKey>,
+) -> error::Result<semantic::NodeKey> {
+    let mut current = key;
+    for &step in path.steps() {
+        current = store
+            .children(current)
+            .iter()
+            .cloned()
+            .filter(|&c| Some(c) != moved)
+            .nth(step)
+            .ok_or_else(invalid_path)?;
+    }
+    Ok(current)
+}
+
+/// Splits the path of an item into the path of its collection and its index.
+fn split_item(path: &semantic::NodePath) -> error::Result<(semantic::NodePath, usize)> {
+    match (path.parent(), path.last()) {
+        (Some(parent), Some(index)) => Ok((parent, index)),
+        _ => Err(invalid_path()),
+    }
+}
+
+fn parent_steps(steps: &[usize]) -> Vec<usize> {
+    steps[..steps.len().saturating_sub(1)].to_vec()
+}
+
+/// The path that a moved node ends up at.
+///
+/// The parent of the new path of a `MoveNode` operation refers to the tree before the node was
+/// removed, so it shifts if the node came before one of the ancestors of its new position.
+fn moved_path(path: &[usize], mut new_path: Vec<usize>) -> error::Result<Vec<usize>> {
+    let (&old_index, old_parent) = path.split_last().ok_or_else(invalid_path)?;
+    if new_path.len() > old_parent.len() + 1
+        && new_path.starts_with(old_parent)
+        && new_path[old_parent.len()] > old_index
+    {
+        new_path[old_parent.len()] -= 1;
+    }
+    Ok(new_path)
+}
+
+/// Converts between the semantic JSON format and Slate JSON for the classes of a format.
+struct Converter {
+    registry: semantic::Registry,
+    classes: collections::HashMap<String, &'static semantic::Class<'static>>,
+    marks: collections::HashSet<String>,
+}
+
+/// A piece of the contents of a Slate node, along with the marks that apply to it.
+struct Piece {
+    marks: Vec<serde_json::Value>,
+    content: PieceContent,
+}
+
+enum PieceContent {
+    Text(String),
+    /// A node that was already converted to the semantic JSON format.
+    Node(serde_json::Value),
+}
+
+impl Converter {
+    fn markdown() -> Converter {
+        use semantic::Semantic;
+
+        let registry = semantic::Registry::of::<markdown::Markdown>();
+        let classes = registry
+            .classes()
+            .filter_map(|c| json::type_tag(c).map(|t| (t, c)))
+            .collect();
+        let marks = mark_classes()
+            .iter()
+            .filter_map(|c| json::type_tag(c))
+            .collect();
+
+        Converter {
+            registry,
+            classes,
+            marks,
+        }
+    }
+
+    fn class(&self, tag: &str) -> error::Result<&'static semantic::Class<'static>> {
+        self.classes
+            .get(tag)
+            .cloned()
+            .ok_or_else(|| failure::err_msg(format!("unknown node type {:?}", tag)))
+    }
+
+    /// Splits a node in the semantic JSON format into its class, its attributes and its children.
+    fn split(
+        &self,
+        node: &serde_json::Value,
+    ) -> error::Result<(
+        &'static semantic::Class<'static>,
+        serde_json::Map<String, serde_json::Value>,
+        Vec<serde_json::Value>,
+    )> {
+        let mut data = node
+            .as_object()
+            .cloned()
+            .ok_or_else(|| failure::err_msg("expected a node object"))?;
+        let tag = data
+            .remove(json::TYPE_KEY)
+            .and_then(|t| t.as_str().map(str::to_owned))
+            .ok_or_else(|| failure::err_msg("expected a node with a type"))?;
+        let class = self.class(&tag)?;
+
+        let children = self
+            .children_field(class)
+            .and_then(|(name, _)| data.remove(&name));
+        let children = match children {
+            Some(serde_json::Value::Array(children)) => children,
+            Some(serde_json::Value::Null) | None => Vec::new(),
+            Some(child) => vec![child],
+        };
+
+        Ok((class, data, children))
+    }
+
+    fn to_slate_nodes(
+        &self,
+        children: &[serde_json::Value],
+        marks: &[serde_json::Value],
+    ) -> error::Result<Vec<serde_json::Value>> {
+        let mut nodes = Vec::new();
+        for child in children {
+            nodes.extend(self.to_slate_node(child, marks)?);
+        }
+        Ok(nodes)
+    }
+
+    /// Converts the nodes at `keys` in `store` into Slate nodes.
+    fn to_slate_items(
+        &self,
+        store: &semantic::Store,
+        keys: &[semantic::NodeKey],
+    ) -> error::Result<Vec<serde_json::Value>> {
+        let nodes = keys
+            .iter()
+            .map(|&k| {
+                store
+                    .to_node(k)
+                    .map(|n| json::to_json(&n))
+                    .ok_or_else(|| failure::err_msg("the node is not in the store"))
+            })
+            .collect::<error::Result<Vec<_>>>()?;
+        self.to_slate_nodes(&nodes, &[])
+    }
+
+    /// Converts Slate nodes back into semantic nodes of `class`, like the items of a children
+    /// field.
+    fn from_slate_items(
+        &self,
+        class: &'static semantic::Class<'static>,
+        nodes: &[serde_json::Value],
+    ) -> error::Result<Vec<semantic::Node>> {
+        self.group(self.pieces(nodes)?)?
+            .iter()
+            .map(|item| Ok(json::node_from_json(&self.registry, class, item)?))
+            .collect()
+    }
+
+    /// Converts a node in the semantic JSON format into Slate nodes, applying `marks` to them.
+    fn to_slate_node(
+        &self,
+        node: &serde_json::Value,
+        marks: &[serde_json::Value],
+    ) -> error::Result<Vec<serde_json::Value>> {
+        let (class, mut data, children) = self.split(node)?;
+        let tag = json::type_tag(class).expect("classes are looked up by their type tag");
+
+        if tag == TEXT_TAG {
+            let text = data.remove(VALUE_KEY).unwrap_or_else(|| string(""));
+            return Ok(vec![text_node(text, marks)]);
+        }
+
+        if self.marks.contains(&tag) {
+            let text = data.remove(VALUE_KEY);
+            let mut marks = marks.to_vec();
+            marks.push(object(vec![
+                ("object", string("mark")),
+                ("type", string(&tag)),
+                ("data", serde_json::Value::Object(data)),
+            ]));
+
+            return match text {
+                Some(text) => Ok(vec![text_node(text, &marks)]),
+                None => self.to_slate_nodes(&children, &marks),
+            };
+        }
+
+        let kind = object_kind(class);
+        if kind == "inline" && !marks.is_empty() {
+            data.insert(MARKS_KEY.to_owned(), marks.to_vec().into());
+        }
+
+        Ok(vec![object(vec![
+            ("object", string(kind)),
+            ("type", string(&tag)),
+            ("data", serde_json::Value::Object(data)),
+            ("nodes", self.to_slate_nodes(&children, &[])?.into()),
+        ])])
+    }
+
+    /// Converts a Slate node of the specified type into the semantic JSON format.
+    fn from_slate_node(
+        &self,
+        tag: &str,
+        node: &serde_json::Value,
+    ) -> error::Result<serde_json::Value> {
+        let class = self.class(tag)?;
+        let mut result = node
+            .get("data")
+            .and_then(|d| d.as_object())
+            .cloned()
+            .unwrap_or_default();
+        result.remove(MARKS_KEY);
+        result.insert(json::TYPE_KEY.to_owned(), string(tag));
+
+        if let Some((name, field_class)) = self.children_field(class) {
+            let nodes = node
+                .get("nodes")
+                .and_then(|n| n.as_array())
+                .map(|n| &n[..])
+                .unwrap_or(&[]);
+            let mut children = self.group(self.pieces(nodes)?)?;
+
+            let children = match field_class.structure {
+                semantic::Structure::Collection { .. } => children.into(),
+                _ => children.pop().unwrap_or(serde_json::Value::Null),
+            };
+            result.insert(name, children);
+        }
+
+        Ok(serde_json::Value::Object(result))
+    }
+
+    /// Flattens Slate nodes into a sequence of marked pieces.
+    fn pieces(&self, nodes: &[serde_json::Value]) -> error::Result<Vec<Piece>> {
+        let mut pieces = Vec::new();
+
+        for node in nodes {
+            match node.get("object").and_then(|o| o.as_str()) {
+                Some("text") => {
+                    let leaves = node
+                        .get("leaves")
+                        .and_then(|l| l.as_array())
+                        .map(|l| &l[..])
+                        .unwrap_or(&[]);
+                    for leaf in leaves {
+                        let text = leaf.get("text").and_then(|t| t.as_str()).unwrap_or("");
+                        // Slate surrounds inline nodes with empty texts, which have no equivalent
+                        if !text.is_empty() {
+                            pieces.push(Piece {
+                                marks: array(leaf.get("marks")),
+                                content: PieceContent::Text(text.to_owned()),
+                            });
+                        }
+                    }
+                }
+                Some("block") | Some("inline") => {
+                    let tag = node
+                        .get("type")
+                        .and_then(|t| t.as_str())
+                        .ok_or_else(|| failure::err_msg("expected a node with a type"))?;
+                    pieces.push(Piece {
+                        marks: array(node.get("data").and_then(|d| d.get(MARKS_KEY))),
+                        content: PieceContent::Node(self.from_slate_node(tag, node)?),
+                    });
+                }
+                other => {
+                    return Err(failure::err_msg(format!(
+                        "unexpected Slate object {:?}",
+                        other
+                    )))
+                }
+            }
+        }
+
+        Ok(pieces)
+    }
+
+    /// Rebuilds the nodes that marks stand for around a sequence of pieces.
+    fn group(&self, mut pieces: Vec<Piece>) -> error::Result<Vec<serde_json::Value>> {
+        let mut result = Vec::new();
+
+        while !pieces.is_empty() {
+            let mark = match self.outer_mark(&pieces[0])? {
+                Some(mark) => mark,
+                None => {
+                    result.push(match pieces.remove(0).content {
+                        PieceContent::Text(text) => object(vec![
+                            (json::TYPE_KEY, string(TEXT_TAG)),
+                            (VALUE_KEY, string(&text)),
+                        ]),
+                        PieceContent::Node(node) => node,
+                    });
+                    continue;
+                }
+            };
+
+            let tag = mark.get("type").and_then(|t| t.as_str()).unwrap_or("");
+            let mut node = mark
+                .get("data")
+                .and_then(|d| d.as_object())
+                .cloned()
+                .unwrap_or_default();
+            node.insert(json::TYPE_KEY.to_owned(), string(tag));
+
+            match self.children_field(self.class(tag)?) {
+                Some((name, _)) => {
+                    // The mark covers the longest run of pieces that all have it
+                    let end = pieces
+                        .iter()
+                        .position(|p| !p.marks.contains(&mark))
+                        .unwrap_or_else(|| pieces.len());
+                    let rest = pieces.split_off(end);
+                    for piece in &mut pieces {
+                        let index = piece
+                            .marks
+                            .iter()
+                            .position(|m| *m == mark)
+                            .expect("the piece has the mark");
+                        piece.marks.remove(index);
+                    }
+
+                    node.insert(name, self.group(pieces)?.into());
+                    pieces = rest;
+                }
+                None => match pieces.remove(0).content {
+                    PieceContent::Text(text) => {
+                        node.insert(VALUE_KEY.to_owned(), string(&text));
+                    }
+                    PieceContent::Node(_) => unreachable!("nodes don't have text marks"),
+                },
+            }
+
+            result.push(serde_json::Value::Object(node));
+        }
+
+        Ok(result)
+    }
+
+    /// The JSON name and class of the children field of a record class, if it has one.
+    fn children_field(
+        &self,
+        class: &semantic::Class,
+    ) -> Option<(String, &'static semantic::Class<'static>)> {
+        match class.structure {
+            semantic::Structure::Record { fields, .. } => fields
+                .iter()
+                .find(|f| f.is_children)
+                .and_then(|f| self.registry.get(f.ty).map(|c| (json::camel_case(f.name), c))),
+            _ => None,
+        }
+    }
+
+    /// The mark that should become the outermost node around a piece.
+    ///
+    /// Marks with children (like `Strong`) go around marks that hold text (like `InlineCode`),
+    /// and text marks only apply to text.
+    fn outer_mark(&self, piece: &Piece) -> error::Result<Option<serde_json::Value>> {
+        let mut text_mark = None;
+
+        for mark in &piece.marks {
+            let tag = mark.get("type").and_then(|t| t.as_str()).unwrap_or("");
+            if !self.marks.contains(tag) {
+                return Err(failure::err_msg(format!("unknown mark type {:?}", tag)));
+            }
+
+            if self.children_field(self.class(tag)?).is_some() {
+                return Ok(Some(mark.clone()));
+            } else if text_mark.is_none() {
+                text_mark = Some(mark.clone());
+            }
+        }
+
+        match piece.content {
+            PieceContent::Text(_) => Ok(text_mark),
+            PieceContent::Node(_) => Ok(None),
+        }
+    }
+}
+
+fn text_node(text: serde_json::Value, marks: &[serde_json::Value]) -> serde_json::Value {
+    object(vec![
+        ("object", string("text")),
+        (
+            "leaves",
+            vec![object(vec![
+                ("object", string("leaf")),
+                ("text", text),
+                ("marks", marks.to_vec().into()),
+            ])].into(),
+        ),
+    ])
+}
+
+fn object(entries: Vec<(&str, serde_json::Value)>) -> serde_json::Value {
+    serde_json::Value::Object(
+        entries
+            .into_iter()
+            .map(|(k, v)| (k.to_owned(), v))
+            .collect(),
+    )
+}
+
+fn string(s: &str) -> serde_json::Value {
+    serde_json::Value::String(s.to_owned())
+}
+
+fn array(value: Option<&serde_json::Value>) -> Vec<serde_json::Value> {
+    value
+        .and_then(|v| v.as_array())
+        .cloned()
+        .unwrap_or_default()
+}
+
+/// A leaf of a Slate text node, with its text in UTF-16 code units like Slate's offsets.
+struct Leaf {
+    text: Vec<u16>,
+    marks: Vec<serde_json::Value>,
+}
+
+fn invalid_path() -> error::Error {
+    failure::err_msg("there is no node at the path of the operation")
+}
+
+fn steps(path: &Option<slate_proto::Path>) -> error::Result<Vec<usize>> {
+    path.as_ref()
+        .map(|p| p.index.iter().map(|&i| i as usize).collect())
+        .ok_or_else(|| failure::err_msg("the operation has no path"))
+}
+
+/// Splits a path into the steps to the parent and the index within the parent.
+fn split_path(path: &Option<slate_proto::Path>) -> error::Result<(Vec<usize>, usize)> {
+    let mut steps = steps(path)?;
+    let index = steps
+        .pop()
+        .ok_or_else(|| failure::err_msg("the document itself can't be changed that way"))?;
+    Ok((steps, index))
+}
+
+fn node_at<'a>(
+    document: &'a mut serde_json::Value,
+    steps: &[usize],
+) -> error::Result<&'a mut serde_json::Value> {
+    let mut current = document;
+    for &step in steps {
+        current = { current }
+            .get_mut("nodes")
+            .and_then(|n| n.as_array_mut())
+            .and_then(|n| n.get_mut(step))
+            .ok_or_else(invalid_path)?;
+    }
+    Ok(current)
+}
+
+fn nodes_mut(node: &mut serde_json::Value) -> error::Result<&mut Vec<serde_json::Value>> {
+    node.get_mut("nodes")
+        .and_then(|n| n.as_array_mut())
+        .ok_or_else(|| failure::err_msg("expected a node with child nodes"))
+}
+
+fn remove_node(
+    document: &mut serde_json::Value,
+    path: &Option<slate_proto::Path>,
+) -> error::Result<serde_json::Value> {
+    let (parent, index) = split_path(path)?;
+    let nodes = nodes_mut(node_at(document, &parent)?)?;
+    if index < nodes.len() {
+        Ok(nodes.remove(index))
+    } else {
+        Err(invalid_path())
+    }
+}
+
+fn is_text(node: &serde_json::Value) -> bool {
+    node.get("object").and_then(|o| o.as_str()) == Some("text")
+}
+
+/// Merges `properties` into the top level of a node, except for its kind and contents.
+fn set_properties(node: &mut serde_json::Value, properties: &str) -> error::Result<()> {
+    let properties: serde_json::Map<String, serde_json::Value> =
+        serde_json::from_str(properties)?;
+    let node = node
+        .as_object_mut()
+        .ok_or_else(|| failure::err_msg("expected a node"))?;
+
+    for (key, value) in properties {
+        match key.as_str() {
+            "object" | "nodes" | "leaves" => {
+                return Err(failure::err_msg(format!("the {:?} of a node can't be set", key)))
+            }
+            _ => {
+                node.insert(key, value);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn leaves(node: &serde_json::Value) -> error::Result<Vec<Leaf>> {
+    if !is_text(node) {
+        return Err(failure::err_msg("expected a text node"));
+    }
+
+    Ok(array(node.get("leaves"))
+        .iter()
+        .map(|leaf| Leaf {
+            text: leaf
+                .get("text")
+                .and_then(|t| t.as_str())
+                .unwrap_or("")
+                .encode_utf16()
+                .collect(),
+            marks: array(leaf.get("marks")),
+        })
+        .collect())
+}
+
+/// Stores `leaves` in a text node, merging neighbouring leaves that have the same marks the way
+/// that Slate normalizes them.
+fn set_leaves(node: &mut serde_json::Value, leaves: Vec<Leaf>) -> error::Result<()> {
+    let mut merged: Vec<Leaf> = Vec::new();
+    for leaf in leaves {
+        if leaf.text.is_empty() {
+            continue;
+        }
+        match merged.last_mut() {
+            Some(ref mut last) if last.marks == leaf.marks => {
+                last.text.extend(leaf.text);
+                continue;
+            }
+            _ => {}
+        }
+        merged.push(leaf);
+    }
+    if merged.is_empty() {
+        merged.push(Leaf {
+            text: Vec::new(),
+            marks: Vec::new(),
+        });
+    }
+
+    let leaves = merged
+        .into_iter()
+        .map(|leaf| {
+            let text = String::from_utf16(&leaf.text)
+                .map_err(|_| failure::err_msg("an offset splits a character in two"))?;
+            Ok(object(vec![
+                ("object", string("leaf")),
+                ("text", serde_json::Value::String(text)),
+                ("marks", leaf.marks.into()),
+            ]))
+        })
+        .collect::<error::Result<Vec<_>>>()?;
+
+    node.as_object_mut()
+        .ok_or_else(|| failure::err_msg("expected a text node"))?
+        .insert("leaves".to_owned(), leaves.into());
+    Ok(())
+}
+
+/// Makes sure that a leaf starts at `offset`.
+fn split_leaves(leaves: &mut Vec<Leaf>, offset: usize) -> error::Result<()> {
+    let mut start = 0;
+    for index in 0..leaves.len() {
+        let end = start + leaves[index].text.len();
+        if start < offset && offset < end {
+            let text = leaves[index].text.split_off(offset - start);
+            let marks = leaves[index].marks.clone();
+            leaves.insert(index + 1, Leaf { text, marks });
+            return Ok(());
+        }
+        start = end;
+    }
+
+    if offset <= start {
+        Ok(())
+    } else {
+        Err(failure::err_msg("the offset is past the end of the text"))
+    }
+}
+
+/// The index of the first leaf that starts at or after `offset`.
+fn leaf_index(leaves: &[Leaf], offset: usize) -> usize {
+    let mut start = 0;
+    leaves
+        .iter()
+        .position(|leaf| {
+            let found = start >= offset;
+            start += leaf.text.len();
+            found
+        })
+        .unwrap_or_else(|| leaves.len())
+}
+
+/// Splits leaves at `offset`, returning the ones after it.
+fn split_off_leaves(leaves: &mut Vec<Leaf>, offset: usize) -> Vec<Leaf> {
+    let index = leaf_index(leaves, offset);
+    leaves.split_off(index)
+}
+
+/// Changes the marks of the text from `offset` to `offset + length` of the text node at `path`.
+fn edit_marks<F>(
+    document: &mut serde_json::Value,
+    path: &Option<slate_proto::Path>,
+    offset: u32,
+    length: u32,
+    mut edit: F,
+) -> error::Result<()>
+where
+    F: FnMut(&mut Vec<serde_json::Value>),
+{
+    let node = node_at(document, &steps(path)?)?;
+    let start = offset as usize;
+    let end = start + length as usize;
+
+    let mut leaves = leaves(node)?;
+    split_leaves(&mut leaves, start)?;
+    split_leaves(&mut leaves, end)?;
+    let mut position = 0;
+    for leaf in &mut leaves {
+        if position >= start && position < end {
+            edit(&mut leaf.marks);
+        }
+        position += leaf.text.len();
+    }
+    set_leaves(node, leaves)
+}
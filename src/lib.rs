@@ -36,6 +36,7 @@ extern crate pulldown_cmark;
 extern crate semantic;
 #[macro_use]
 extern crate semantic_derive;
+extern crate serde_json;
 #[macro_use]
 extern crate slog;
 #[cfg(not(target_arch = "wasm32"))]
@@ -54,9 +55,6 @@ extern crate structopt;
 #[cfg(not(target_arch = "wasm32"))]
 extern crate tokio;
 extern crate tokio_executor;
-extern crate type_info;
-#[macro_use]
-extern crate type_info_derive;
 extern crate uuid;
 extern crate wasm_bindgen;
 extern crate wasm_bindgen_futures;
@@ -1,7 +1,8 @@
 import { Action } from "redux";
+import { Data } from "../data";
 
 interface State {
-
+  document?: Data;
 }
 
 const DEFAULT_STATE: State = {};
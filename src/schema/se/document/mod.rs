@@ -0,0 +1,5 @@
+//! Generic encoding of semantic document trees.
+#![allow(missing_docs)]
+#![allow(missing_copy_implementations)]
+
+include!(concat!(env!("OUT_DIR"), "/se.document.rs"));
@@ -1,4 +1,5 @@
 //! Semantic Editor-specific schemata.
+pub mod document;
 pub mod service;
 pub mod slate;
 pub mod transport;
@@ -6,12 +6,17 @@
 
 use std::panic;
 
+use failure;
 use js_sys;
+use semantic;
+use serde_json;
 use slog_scope;
 use slog_stdlog;
 use wasm_bindgen::prelude::*;
 use wasm_bindgen_futures;
 
+use data;
+use error;
 use logger;
 use rpc;
 use schema::se::service;
@@ -38,6 +43,22 @@ pub struct File {
     is_directory: bool,
 }
 
+/// A replica of a shared document, which frames edit locally and keep in sync by exchanging
+/// operations with the backend (see `SemanticEditor::apply_shared_operations`) and each other.
+#[wasm_bindgen]
+#[derive(Debug)]
+pub struct SharedDocument {
+    document: semantic::Document,
+    registry: semantic::Registry,
+}
+
+#[wasm_bindgen]
+#[derive(Clone, Debug)]
+pub struct NodeSelection {
+    paths: Vec<Vec<u32>>,
+    keys: Vec<u64>,
+}
+
 #[wasm_bindgen]
 impl SemanticEditor {
     pub fn new(url: &str) -> js_sys::Promise {
@@ -60,12 +81,32 @@ impl SemanticEditor {
 
         let future = rpc::RpcClient::new(log, url.to_owned())
             .map(|rpc| service::SemanticEditorClient::new(rpc))
-            .map(|client| {
+            .map_err(|e| JsValue::from(e.to_string()))
+            .and_then(|client| {
+                use schema::se::service::SemanticEditor;
+
+                // A cached frontend might be older than the backend that it talks to
+                let schema_hash = data::schema_hash();
+                client
+                    .check_compatibility(service::CheckCompatibilityRequest { schema_hash })
+                    .map_err(|e| JsValue::from(e.to_string()))
+                    .and_then(move |r| {
+                        if r.compatible {
+                            Ok(client)
+                        } else {
+                            Err(JsValue::from(format!(
+                                "the editor was built with document model {:016x}, but the \
+                                 backend uses {:016x}; try reloading the page",
+                                schema_hash, r.schema_hash
+                            )))
+                        }
+                    })
+            }).map(|client| {
                 SemanticEditor {
                     client,
                     _log_guard: log_guard,
                 }.into()
-            }).map_err(|e| e.to_string().into());
+            });
 
         wasm_bindgen_futures::future_to_promise(future)
     }
@@ -101,6 +142,252 @@ impl SemanticEditor {
 
         wasm_bindgen_futures::future_to_promise(future)
     }
+
+    pub fn select_nodes(&self, path: &str, selector: &str) -> js_sys::Promise {
+        use futures::Future;
+        use schema::se::service::SemanticEditor;
+
+        let path = path.to_owned();
+        let selector = selector.to_owned();
+        let future = self
+            .client
+            .select_nodes(service::SelectNodesRequest { path, selector })
+            .map(move |r| {
+                NodeSelection {
+                    keys: r.node.iter().map(|n| n.key).collect(),
+                    paths: r.node.into_iter().map(|n| n.step).collect(),
+                }.into()
+            }).map_err(|e| e.to_string().into());
+
+        wasm_bindgen_futures::future_to_promise(future)
+    }
+
+    /// Opens the document at `path`, and resolves to the key of its root node.
+    pub fn open_document(&self, path: &str) -> js_sys::Promise {
+        use futures::Future;
+        use schema::se::service::SemanticEditor;
+
+        let path = path.to_owned();
+        let future = self
+            .client
+            .open_document(service::OpenDocumentRequest { path })
+            .map(|r| (r.root as f64).into())
+            .map_err(|e| e.to_string().into());
+
+        wasm_bindgen_futures::future_to_promise(future)
+    }
+
+    pub fn save_document(&self, path: &str) -> js_sys::Promise {
+        use futures::Future;
+        use schema::se::service::SemanticEditor;
+
+        let path = path.to_owned();
+        let future = self
+            .client
+            .save_document(service::SaveDocumentRequest { path })
+            .map(|_| JsValue::undefined())
+            .map_err(|e| e.to_string().into());
+
+        wasm_bindgen_futures::future_to_promise(future)
+    }
+
+    /// Closes the document at `path`, and resolves to whether it was open.
+    pub fn close_document(&self, path: &str) -> js_sys::Promise {
+        use futures::Future;
+        use schema::se::service::SemanticEditor;
+
+        let path = path.to_owned();
+        let future = self
+            .client
+            .close_document(service::CloseDocumentRequest { path })
+            .map(|r| r.was_open.into())
+            .map_err(|e| e.to_string().into());
+
+        wasm_bindgen_futures::future_to_promise(future)
+    }
+
+    /// Applies a JSON array of operations of a `SharedDocument` to the document at `path`, and
+    /// resolves to a JSON array of the operations of the document after the first `known` ones.
+    pub fn apply_shared_operations(
+        &self,
+        path: &str,
+        operations: &str,
+        known: f64,
+    ) -> js_sys::Promise {
+        use futures::Future;
+        use schema::se::service::SemanticEditor;
+
+        let parse = || -> error::Result<Vec<String>> {
+            let operations: Vec<serde_json::Value> = serde_json::from_str(operations)?;
+            Ok(operations.iter().map(|o| o.to_string()).collect())
+        };
+        let operation = match parse() {
+            Ok(operation) => operation,
+            Err(e) => return js_sys::Promise::reject(&e.to_string().into()),
+        };
+
+        let path = path.to_owned();
+        let future = self
+            .client
+            .apply_shared_operations(service::ApplySharedOperationsRequest {
+                path,
+                operation,
+                known: known as u64,
+            }).map(|r| format!("[{}]", r.operation.join(",")).into())
+            .map_err(|e| e.to_string().into());
+
+        wasm_bindgen_futures::future_to_promise(future)
+    }
+
+    /// Applies a JSON array of Slate operations to the document at `path`, all or nothing.
+    pub fn apply_slate_operations(&self, path: &str, operations: &str) -> js_sys::Promise {
+        use futures::Future;
+        use schema::se::service::SemanticEditor;
+
+        let parse = || -> error::Result<Vec<_>> {
+            let operations: Vec<serde_json::Value> = serde_json::from_str(operations)?;
+            operations.iter().map(data::slate::operation_from_json).collect()
+        };
+        let operation = match parse() {
+            Ok(operation) => operation,
+            Err(e) => return js_sys::Promise::reject(&e.to_string().into()),
+        };
+
+        let path = path.to_owned();
+        let future = self
+            .client
+            .apply_slate_operations(service::ApplySlateOperationsRequest { path, operation })
+            .map(|_| JsValue::undefined())
+            .map_err(|e| e.to_string().into());
+
+        wasm_bindgen_futures::future_to_promise(future)
+    }
+}
+
+#[wasm_bindgen]
+impl SharedDocument {
+    /// Creates a replica with the identifier `replica`, which must be unique among the frames and
+    /// different from the backend's.
+    ///
+    /// Replicas start out as an empty document, and catch up by applying the operations of the
+    /// other replicas.
+    pub fn new(replica: f64) -> SharedDocument {
+        SharedDocument {
+            document: semantic::Document::new(replica as u64, &data::Data::empty()),
+            registry: semantic::Registry::of::<data::Data>(),
+        }
+    }
+
+    /// Applies a JSON array of operations from other replicas, in any order.
+    pub fn apply(&mut self, operations: &str) -> Result<(), JsValue> {
+        let operations = operations_from_json(&self.registry, operations)
+            .map_err(|e| JsValue::from(e.to_string()))?;
+        for operation in operations {
+            self.document.apply(operation);
+        }
+        Ok(())
+    }
+
+    /// All of the operations that this replica has applied, as a JSON array.
+    pub fn operations(&self) -> Result<String, JsValue> {
+        operations_to_json(&self.registry, self.document.operations())
+            .map_err(|e| e.to_string().into())
+    }
+
+    /// Converts the Markdown document into the JSON of a Slate `Value`.
+    pub fn to_slate(&self) -> Result<String, JsValue> {
+        let convert = || -> error::Result<String> {
+            match self.document.to_value::<data::Data>()? {
+                data::Data::Markdown(markdown) => {
+                    Ok(serde_json::to_string(&data::slate::to_slate_value(&markdown)?)?)
+                }
+                data::Data::Json(_) => Err(failure::err_msg(
+                    "only Markdown documents can be edited with Slate",
+                )),
+            }
+        };
+
+        convert().map_err(|e| e.to_string().into())
+    }
+
+    /// Edits the document to match the JSON of a Slate `Value`, and returns the operations to send
+    /// to the other replicas as a JSON array.
+    pub fn update_from_slate(&mut self, value: &str) -> Result<String, JsValue> {
+        let mut update = || -> error::Result<String> {
+            let markdown = data::slate::from_slate_value(&serde_json::from_str(value)?)?;
+            let operations = self.document.update(&data::Data::Markdown(markdown))?;
+            operations_to_json(&self.registry, &operations)
+        };
+
+        update().map_err(|e| e.to_string().into())
+    }
+}
+
+/// Converts a JSON array of operations of a shared document.
+fn operations_from_json(
+    registry: &semantic::Registry,
+    operations: &str,
+) -> error::Result<Vec<semantic::Operation>> {
+    let operations: Vec<serde_json::Value> = serde_json::from_str(operations)?;
+    operations
+        .iter()
+        .map(|o| -> error::Result<_> { Ok(semantic::Operation::from_json(registry, o)?) })
+        .collect()
+}
+
+/// Converts operations of a shared document into a JSON array.
+fn operations_to_json(
+    registry: &semantic::Registry,
+    operations: &[semantic::Operation],
+) -> error::Result<String> {
+    let operations = operations
+        .iter()
+        .map(|o| o.to_json(registry))
+        .collect::<semantic::Result<Vec<_>>>()?;
+    Ok(serde_json::to_string(&operations)?)
+}
+
+/// Converts a Markdown document from mdast JSON into the JSON of a Slate `Value`.
+#[wasm_bindgen]
+pub fn markdown_to_slate(mdast: &str) -> Result<String, JsValue> {
+    let convert = || -> error::Result<String> {
+        let mut mdast = serde_json::from_str(mdast)?;
+        data::format::mdast::migrate(&mut mdast)?;
+        let markdown = semantic::json::from_json(&mdast)?;
+        let value = data::slate::to_slate_value(&markdown)?;
+        Ok(serde_json::to_string(&value)?)
+    };
+
+    convert().map_err(|e| e.to_string().into())
+}
+
+/// Converts the JSON of a Slate `Value` back into a Markdown document in mdast JSON.
+#[wasm_bindgen]
+pub fn slate_to_markdown(value: &str) -> Result<String, JsValue> {
+    let convert = || -> error::Result<String> {
+        let markdown = data::slate::from_slate_value(&serde_json::from_str(value)?)?;
+        Ok(serde_json::to_string(&semantic::json::to_json(&markdown))?)
+    };
+
+    convert().map_err(|e| e.to_string().into())
+}
+
+#[wasm_bindgen]
+impl NodeSelection {
+    #[allow(non_snake_case)]
+    pub fn pathLength(&self) -> usize {
+        self.paths.len()
+    }
+
+    /// The steps of the path at `index`, as indices into the children of each node on the way.
+    pub fn path(&self, index: usize) -> Vec<u32> {
+        self.paths[index].clone()
+    }
+
+    /// The stable key of the node at `index`, which stays the same while the document is open.
+    pub fn key(&self, index: usize) -> f64 {
+        self.keys[index] as f64
+    }
 }
 
 #[wasm_bindgen]
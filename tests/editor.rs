@@ -0,0 +1,174 @@
+extern crate futures_await as futures;
+extern crate semantic;
+extern crate semantic_editor;
+#[macro_use]
+extern crate serde_json;
+#[macro_use]
+extern crate slog;
+extern crate tempdir;
+
+use std::fs;
+
+use futures::Future;
+
+use semantic_editor::core::editor;
+use semantic_editor::data;
+use semantic_editor::schema::se::service as service_proto;
+use semantic_editor::schema::se::service::SemanticEditor;
+
+fn editor() -> editor::SemanticEditor {
+    editor::SemanticEditor::new(slog::Logger::root(slog::Discard, o!()))
+}
+
+fn check_compatibility(schema_hash: u64) -> service_proto::CheckCompatibilityResponse {
+    editor()
+        .check_compatibility(service_proto::CheckCompatibilityRequest { schema_hash })
+        .wait()
+        .unwrap()
+}
+
+#[test]
+fn accepts_a_frontend_with_the_same_document_model() {
+    let response = check_compatibility(data::schema_hash());
+    assert!(response.compatible);
+    assert_eq!(response.schema_hash, data::schema_hash());
+}
+
+#[test]
+fn rejects_a_frontend_with_a_different_document_model() {
+    let response = check_compatibility(!data::schema_hash());
+    assert!(!response.compatible);
+    assert_eq!(response.schema_hash, data::schema_hash());
+
+    let response = check_compatibility(0);
+    assert!(!response.compatible);
+    assert_eq!(response.schema_hash, data::schema_hash());
+}
+
+#[test]
+fn serves_the_slate_schema_of_markdown() {
+    let schema = editor()
+        .fetch_slate_schema(service_proto::FetchSlateSchemaRequest {})
+        .wait()
+        .unwrap()
+        .schema
+        .unwrap();
+    assert_eq!(
+        schema,
+        data::slate::schema(&semantic::Registry::of::<data::markdown::Markdown>())
+    );
+    assert!(schema.document.is_some());
+    assert!(schema.blocks["thematicBreak"].is_void);
+    assert!(schema.inlines["link"].data.contains_key("url"));
+}
+
+#[test]
+fn rolls_back_operations_that_make_the_document_invalid() {
+    let dir = tempdir::TempDir::new("editor").unwrap();
+    let path = dir.path().join("readme.mdast.json");
+    fs::write(
+        &path,
+        r#"{"type": "root", "schemaVersion": 1, "children": [
+            {"type": "heading", "depth": 1, "children": [{"type": "text", "value": "One"}]}
+        ]}"#,
+    ).unwrap();
+    let path = path.to_string_lossy().into_owned();
+
+    let editor = editor();
+    let apply = |operations: &[serde_json::Value]| {
+        let operation = operations
+            .iter()
+            .map(|o| data::slate::operation_from_json(o).unwrap())
+            .collect();
+        editor
+            .apply_slate_operations(service_proto::ApplySlateOperationsRequest {
+                path: path.clone(),
+                operation,
+            })
+            .wait()
+    };
+    editor
+        .open_document(service_proto::OpenDocumentRequest { path: path.clone() })
+        .wait()
+        .unwrap();
+
+    let insert = json!({"type": "insert_text", "path": [0, 0], "offset": 3, "text": "!"});
+    let too_deep = json!({"type": "set_node", "path": [0], "properties": {"data": {"depth": 9}}});
+    assert!(apply(&[insert.clone(), too_deep]).is_err());
+    apply(&[insert]).unwrap();
+    editor
+        .save_document(service_proto::SaveDocumentRequest { path: path.clone() })
+        .wait()
+        .unwrap();
+
+    let saved: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+    assert_eq!(saved["children"][0]["depth"], json!(1));
+    assert_eq!(saved["children"][0]["children"][0]["value"], json!("One!"));
+}
+
+#[test]
+fn shares_edits_with_replicas_of_the_document() {
+    let dir = tempdir::TempDir::new("editor").unwrap();
+    let path = dir.path().join("readme.mdast.json");
+    let heading = |text: &str| {
+        json!({"type": "root", "schemaVersion": 1, "children": [
+            {"type": "heading", "depth": 1, "children": [{"type": "text", "value": text}]}
+        ]})
+    };
+    fs::write(&path, heading("One").to_string()).unwrap();
+    let path = path.to_string_lossy().into_owned();
+
+    let editor = editor();
+    let registry = semantic::Registry::of::<data::Data>();
+    let mut frame = semantic::Document::new(2, &data::Data::empty());
+    let mut known = 0;
+    let mut sync = |frame: &mut semantic::Document, operations: &[semantic::Operation]| {
+        let response = editor
+            .apply_shared_operations(service_proto::ApplySharedOperationsRequest {
+                path: path.clone(),
+                operation: operations
+                    .iter()
+                    .map(|o| o.to_json(&registry).unwrap().to_string())
+                    .collect(),
+                known: known as u64,
+            }).wait()
+            .unwrap();
+        known += response.operation.len();
+        for operation in response.operation {
+            let json = serde_json::from_str(&operation).unwrap();
+            frame.apply(semantic::Operation::from_json(&registry, &json).unwrap());
+        }
+    };
+
+    sync(&mut frame, &[]);
+    assert_eq!(
+        semantic::json::to_json(&frame.to_node())["children"],
+        heading("One")["children"]
+    );
+
+    let edited = semantic::json::from_json::<data::Data>(&heading("Two")).unwrap();
+    let operations = frame.update(&edited).unwrap();
+    sync(&mut frame, &operations);
+
+    let insert = json!({"type": "insert_text", "path": [0, 0], "offset": 3, "text": "!"});
+    editor
+        .apply_slate_operations(service_proto::ApplySlateOperationsRequest {
+            path: path.clone(),
+            operation: vec![data::slate::operation_from_json(&insert).unwrap()],
+        }).wait()
+        .unwrap();
+    sync(&mut frame, &[]);
+    assert_eq!(
+        semantic::json::to_json(&frame.to_node())["children"],
+        heading("Two!")["children"]
+    );
+
+    editor
+        .save_document(service_proto::SaveDocumentRequest { path: path.clone() })
+        .wait()
+        .unwrap();
+    let saved: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+    assert_eq!(saved["children"], heading("Two!")["children"]);
+}
@@ -0,0 +1,150 @@
+extern crate semantic;
+extern crate semantic_editor;
+extern crate tempdir;
+
+use std::path;
+
+use semantic_editor::data;
+use semantic_editor::data::format;
+use semantic_editor::data::markdown;
+
+const MDAST: &str = r#"{"type": "root", "schemaVersion": 1, "children": []}"#;
+
+fn names(formats: &[&format::Format]) -> Vec<&'static str> {
+    formats.iter().map(|f| f.name()).collect()
+}
+
+fn by_path(file_name: &str) -> Vec<&'static str> {
+    names(&format::FormatRegistry::all().by_path(path::Path::new(file_name)))
+}
+
+fn detect(file_name: &str, content: &str) -> Option<&'static str> {
+    format::FormatRegistry::all()
+        .detect(path::Path::new(file_name), content.as_bytes())
+        .ok()
+        .map(|f| f.name())
+}
+
+fn markdown() -> data::Data {
+    data::Data::Markdown(markdown::Markdown {
+        children: vec![markdown::Block::Code(markdown::Code {
+            lang: Some("rust".to_owned()),
+            value: "fn main() {}\n".to_owned(),
+        })],
+        declarations: vec![],
+    })
+}
+
+fn json() -> data::Data {
+    data::Data::Json(
+        format::json::parse(
+            "// Settings\n{\n  \"b\": [1, 2.5, \"x\"], // Last\n  \"a\": {\"c\": null},\n}\n",
+        ).unwrap(),
+    )
+}
+
+#[test]
+fn matches_the_most_specific_extension_first() {
+    assert_eq!(by_path("docs/readme.mdast.json"), vec!["mdast", "json"]);
+    assert_eq!(by_path("README.MDAST.JSON"), vec!["mdast", "json"]);
+    assert_eq!(by_path("package.json"), vec!["json"]);
+    assert_eq!(by_path("tsconfig.JSONC"), vec!["json"]);
+    assert_eq!(by_path("mdast.json"), vec!["json"]);
+    assert!(by_path("readme.md").is_empty());
+    assert!(by_path("json").is_empty());
+}
+
+#[test]
+fn finds_formats_by_name_and_mime_type() {
+    let registry = format::FormatRegistry::all();
+    assert_eq!(registry.by_name("mdast").map(|f| f.name()), Some("mdast"));
+    assert!(registry.by_name("markdown").is_none());
+    assert_eq!(
+        registry.by_mime_type("Application/JSON").map(|f| f.name()),
+        Some("json")
+    );
+    assert_eq!(
+        registry
+            .by_mime_type("application/vnd.mdast+json")
+            .map(|f| f.name()),
+        Some("mdast")
+    );
+    assert!(registry.by_mime_type("text/markdown").is_none());
+}
+
+#[test]
+fn detects_formats_by_extension_first() {
+    assert_eq!(detect("readme.mdast.json", MDAST), Some("mdast"));
+    assert_eq!(detect("readme.json", MDAST), Some("json"));
+    assert_eq!(detect("readme.mdast.json", r#"{"a": 1}"#), Some("json"));
+    // The format of the extension reports what is wrong with the content
+    assert_eq!(detect("package.json", "hello"), Some("json"));
+}
+
+#[test]
+fn sniffs_files_without_a_known_extension() {
+    assert_eq!(detect("merge-base", MDAST), Some("mdast"));
+    assert_eq!(detect("merge-base", "  [1, 2]"), Some("json"));
+    assert_eq!(detect("notes.txt", "// Comment\n{}"), Some("json"));
+    assert_eq!(detect("merge-base", "hello"), None);
+}
+
+#[test]
+fn saves_and_loads_every_format() {
+    let dir = tempdir::TempDir::new("format").unwrap();
+    let registry = format::FormatRegistry::all();
+
+    for format in registry.formats() {
+        let data = vec![markdown(), json()]
+            .into_iter()
+            .find(|d| format.can_write(d))
+            .unwrap();
+        let path = dir.path().join(format!("document.{}", format.extensions()[0]));
+
+        registry.save(&data, &path).unwrap();
+        let (loaded, detected) = registry.load_as(&path, &path).unwrap();
+        assert_eq!(detected.name(), format.name());
+        assert_eq!(
+            semantic::json::to_json(&loaded),
+            semantic::json::to_json(&data)
+        );
+    }
+}
+
+#[test]
+fn refuses_to_save_documents_that_the_format_cant_store() {
+    let dir = tempdir::TempDir::new("format").unwrap();
+    let registry = format::FormatRegistry::all();
+
+    assert!(registry.save(&json(), &dir.path().join("a.mdast.json")).is_ok());
+    assert!(registry.save(&markdown(), &dir.path().join("a.json")).is_err());
+    assert!(registry.save(&markdown(), &dir.path().join("a.md")).is_err());
+    assert!(
+        format::save_in(registry.by_name("mdast").unwrap(), &json(), &dir.path().join("a")).is_err()
+    );
+}
+
+#[test]
+fn reads_tables_with_unaligned_columns() {
+    let table = r#"{"type": "root", "schemaVersion": 1, "children": [{
+        "type": "table",
+        "align": ["left", null, "center"],
+        "children": []
+    }]}"#;
+    let data = format::FormatRegistry::all()
+        .by_name("mdast")
+        .unwrap()
+        .read(&mut table.as_bytes())
+        .unwrap();
+    let document = match data {
+        data::Data::Markdown(document) => document,
+        data::Data::Json(_) => panic!("expected a Markdown document"),
+    };
+    match document.children.first() {
+        Some(&markdown::Block::Table(ref table)) => assert_eq!(
+            format!("{:?}", table.align),
+            "[Some(Left), None, Some(Center)]"
+        ),
+        other => panic!("expected a table, but found {:?}", other),
+    }
+}
@@ -0,0 +1,160 @@
+extern crate semantic_editor;
+#[macro_use]
+extern crate serde_json;
+
+use semantic_editor::data;
+use semantic_editor::data::format;
+use semantic_editor::data::format::json;
+
+const TSCONFIG: &str = r#"// Settings for the compiler
+{
+  /* Options */ "compilerOptions": {
+    "target": "es2017", // Modern browsers
+    "lib": ["dom", "es2017",],
+    "strict": true,
+    // Left out
+  },
+  "include": [
+    "src", /* Sources */
+    // Tests
+    "tests"
+  ],
+  "exclude": null,
+}
+// End
+"#;
+
+fn print(text: &str, keep_comments: bool) -> String {
+    json::Json {
+        keep_comments,
+        sort_keys: false,
+    }.print(&json::parse(text).unwrap())
+        .unwrap()
+}
+
+fn canonical(text: &str) -> String {
+    print(text, true)
+}
+
+#[test]
+fn keeps_comments_next_to_their_members_and_items() {
+    let printed = canonical(TSCONFIG);
+    assert_eq!(
+        printed,
+        r#"// Settings for the compiler
+{
+  /* Options */
+  "compilerOptions": {
+    "target": "es2017", // Modern browsers
+    "lib": [
+      "dom",
+      "es2017"
+    ],
+    "strict": true
+    // Left out
+  },
+  "include": [
+    "src", /* Sources */
+    // Tests
+    "tests"
+  ],
+  "exclude": null
+}
+// End
+"#
+    );
+    assert_eq!(canonical(&printed), printed);
+}
+
+#[test]
+fn drops_comments_and_trailing_commas_for_plain_json() {
+    let printed = print(TSCONFIG, false);
+    assert!(!printed.contains("//") && !printed.contains("/*"));
+    assert_eq!(
+        serde_json::from_str::<serde_json::Value>(&printed).unwrap(),
+        json!({
+            "compilerOptions": {"target": "es2017", "lib": ["dom", "es2017"], "strict": true},
+            "include": ["src", "tests"],
+            "exclude": null,
+        })
+    );
+}
+
+#[test]
+fn keeps_the_order_of_members() {
+    assert_eq!(
+        canonical(r#"{"z": 1, "a": {"y": [], "b": {}}, "m": 2,}"#),
+        "{\n  \"z\": 1,\n  \"a\": {\n    \"y\": [],\n    \"b\": {}\n  },\n  \"m\": 2\n}\n"
+    );
+}
+
+#[test]
+fn sorts_members_by_key_if_asked_to() {
+    let style = json::Json {
+        keep_comments: true,
+        sort_keys: true,
+    };
+    let document = json::parse(
+        r#"{
+          "z": 1, // Last
+          // Nested
+          "a": {"y": [{"c": 1, "b": 2}], "b": {}},
+          "m": 2,
+        }"#,
+    ).unwrap();
+    let sorted = r#"{
+  // Nested
+  "a": {
+    "b": {},
+    "y": [
+      {
+        "b": 2,
+        "c": 1
+      }
+    ]
+  },
+  "m": 2,
+  "z": 1 // Last
+}
+"#;
+    assert_eq!(style.print(&document).unwrap(), sorted);
+
+    let mut written = Vec::new();
+    format::FormatRegistry::with_json_style(style)
+        .by_name("json")
+        .unwrap()
+        .write(&data::Data::Json(document), &mut written)
+        .unwrap();
+    assert_eq!(String::from_utf8(written).unwrap(), sorted);
+}
+
+#[test]
+fn round_trips_string_escapes() {
+    let text = r#"["\"\\\/\b\f\n\r\t", "\u00e9\ud83d\ude00", "é😀", "\u001f"]"#;
+    assert_eq!(
+        canonical(text),
+        "[\n  \"\\\"\\\\/\\b\\f\\n\\r\\t\",\n  \"é😀\",\n  \"é😀\",\n  \"\\u001f\"\n]\n"
+    );
+    assert_eq!(canonical(&canonical(text)), canonical(text));
+}
+
+#[test]
+fn round_trips_numbers() {
+    let printed = canonical("[0, -0.5, 1.5e3, 2E-2, 12345678901, 9007199254740993, 1e300]");
+    assert_eq!(
+        serde_json::from_str::<Vec<f64>>(&printed).unwrap(),
+        vec![0.0, -0.5, 1500.0, 0.02, 12_345_678_901.0, 9_007_199_254_740_992.0, 1e300]
+    );
+    assert!(printed.contains("\n  1500,\n") && printed.contains("\n  12345678901,\n"));
+    assert_eq!(canonical(&printed), printed);
+}
+
+#[test]
+fn reports_parse_errors_with_their_position() {
+    let error = |text| json::parse(text).unwrap_err().to_string();
+    assert_eq!(error("{\n  \"a\": x\n}"), "2:8: expected a value, but found 'x'");
+    assert_eq!(error("[1 2]"), "1:4: expected ',' or ']', but found '2'");
+    for text in &["{,}", "[1,,]", "{\"a\" 1}", "01", "\"\\x\"", "/* open", "{} x", "tru", ""] {
+        assert!(json::parse(text).is_err(), "{:?} was parsed", text);
+    }
+}
@@ -0,0 +1,137 @@
+extern crate semantic;
+extern crate semantic_editor;
+extern crate serde_json;
+#[macro_use]
+extern crate slog;
+extern crate tempdir;
+
+use std::fs;
+use std::path;
+
+use semantic_editor::core::merge_driver;
+use semantic_editor::data;
+use semantic_editor::data::markdown;
+
+fn document(blocks: &[&str]) -> markdown::Markdown {
+    markdown::Markdown {
+        children: blocks
+            .iter()
+            .map(|&value| {
+                markdown::Block::Code(markdown::Code {
+                    lang: None,
+                    value: value.to_owned(),
+                })
+            }).collect(),
+        declarations: Vec::new(),
+    }
+}
+
+/// Temporary files like the ones git passes to a merge driver, which have no extension.
+struct Files {
+    dir: tempdir::TempDir,
+}
+
+impl Files {
+    fn new(
+        base: &markdown::Markdown,
+        ours: &markdown::Markdown,
+        theirs: &markdown::Markdown,
+    ) -> Files {
+        let files = Files {
+            dir: tempdir::TempDir::new("merge-driver").unwrap(),
+        };
+        write(&files.path("base"), base);
+        write(&files.path("ours"), ours);
+        write(&files.path("theirs"), theirs);
+        files
+    }
+
+    fn path(&self, name: &str) -> path::PathBuf {
+        self.dir.path().join(format!(".merge_file_{}", name))
+    }
+
+    fn run(&self, name: Option<&path::Path>) -> Result<(), String> {
+        let log = slog::Logger::root(slog::Discard, o!());
+        merge_driver::run(
+            &log,
+            &self.path("base"),
+            &self.path("ours"),
+            &self.path("theirs"),
+            name,
+        ).map_err(|e| e.to_string())
+    }
+
+    fn merged(&self) -> semantic::Node {
+        let content = fs::read(self.path("ours")).unwrap();
+        let json = serde_json::from_slice(&content).unwrap();
+        semantic::Node::from_value(&semantic::json::from_json::<markdown::Markdown>(&json).unwrap())
+    }
+}
+
+fn write(path: &path::Path, document: &markdown::Markdown) {
+    let json = semantic::json::to_json(document);
+    fs::write(path, serde_json::to_vec_pretty(&json).unwrap()).unwrap();
+}
+
+fn node(document: &markdown::Markdown) -> semantic::Node {
+    semantic::Node::from_value(document)
+}
+
+#[test]
+fn merges_files_named_by_the_repository_path() {
+    let files = Files::new(
+        &document(&["a", "b"]),
+        &document(&["a", "b", "c"]),
+        &document(&["z", "a", "b"]),
+    );
+
+    files
+        .run(Some(path::Path::new("docs/readme.mdast.json")))
+        .unwrap();
+    assert_eq!(files.merged(), node(&document(&["z", "a", "b", "c"])));
+}
+
+#[test]
+fn sniffs_files_without_a_repository_path() {
+    let files = Files::new(
+        &document(&["a", "b"]),
+        &document(&["a"]),
+        &document(&["a", "b", "c"]),
+    );
+
+    files.run(None).unwrap();
+    assert_eq!(files.merged(), node(&document(&["a", "c"])));
+
+    // The result is still in the format that it was read in
+    let registry = data::format::FormatRegistry::all();
+    let (_, format) = registry
+        .load_as(&files.path("ours"), path::Path::new("unknown"))
+        .unwrap();
+    assert_eq!(format.name(), "mdast");
+}
+
+#[test]
+fn fails_on_conflicts_and_keeps_our_version() {
+    let files = Files::new(
+        &document(&["a"]),
+        &document(&["ours"]),
+        &document(&["theirs"]),
+    );
+
+    let error = files
+        .run(Some(path::Path::new("readme.mdast.json")))
+        .unwrap_err();
+    assert!(error.contains("1 conflicts"), "unexpected error: {}", error);
+    assert_eq!(files.merged(), node(&document(&["ours"])));
+}
+
+#[test]
+fn rejects_markdown_source_files() {
+    let dir = tempdir::TempDir::new("merge-driver").unwrap();
+    let file = dir.path().join(".merge_file");
+    fs::write(&file, "# Title\n\nSome text.\n").unwrap();
+
+    let log = slog::Logger::root(slog::Discard, o!());
+    let name = path::Path::new("README.md");
+    assert!(merge_driver::run(&log, &file, &file, &file, Some(name)).is_err());
+}
@@ -0,0 +1,104 @@
+extern crate semantic;
+extern crate semantic_editor;
+
+use semantic_editor::data;
+use semantic_editor::data::markdown;
+use semantic_editor::data::proto;
+use semantic_editor::schema::se::document as document_proto;
+
+fn text(content: &str) -> markdown::Inline {
+    markdown::Inline::Text(markdown::Text {
+        content: content.to_owned(),
+    })
+}
+
+fn sample() -> markdown::Markdown {
+    markdown::Markdown {
+        children: vec![
+            markdown::Block::Heading(markdown::Heading {
+                depth: 2,
+                children: vec![
+                    text("Hello, "),
+                    markdown::Inline::Emphasis(markdown::Emphasis {
+                        children: vec![text("world")],
+                    }),
+                ],
+            }),
+            markdown::Block::Code(markdown::Code {
+                lang: None,
+                value: "plain".to_owned(),
+            }),
+            markdown::Block::List(markdown::List {
+                ordered: true,
+                start: Some(3),
+                loose: false,
+                children: vec![markdown::ListItem {
+                    loose: false,
+                    checked: Some(true),
+                    children: vec![markdown::Block::ThematicBreak(markdown::ThematicBreak)],
+                }],
+            }),
+            markdown::Block::Table(markdown::Table {
+                align: vec![Some(markdown::AlignType::Left), None],
+                children: Vec::new(),
+            }),
+        ],
+        declarations: vec![
+            markdown::Declaration::LinkReference(markdown::LinkReference {
+                identifier: "docs".to_owned(),
+                reference_type: markdown::ReferenceType::Full,
+            }),
+            markdown::Declaration::Definition(markdown::Definition {
+                identifier: "docs".to_owned(),
+                title: None,
+                url: "https://example.com".to_owned(),
+            }),
+        ],
+    }
+}
+
+fn attribute<'a>(node: &'a document_proto::Node, name: &str) -> &'a document_proto::Node {
+    node.attributes
+        .iter()
+        .find(|a| a.name == name)
+        .and_then(|a| a.value.as_ref())
+        .unwrap_or_else(|| panic!("missing attribute {:?}", name))
+}
+
+#[test]
+fn round_trips_markdown() {
+    let markdown = sample();
+    let decoded = proto::from_proto::<markdown::Markdown>(&proto::to_proto(&markdown)).unwrap();
+    assert_eq!(
+        semantic::Node::from_value(&decoded),
+        semantic::Node::from_value(&markdown)
+    );
+}
+
+#[test]
+fn round_trips_data() {
+    let data = data::Data::Markdown(sample());
+    let decoded = proto::from_proto::<data::Data>(&proto::to_proto(&data)).unwrap();
+    assert_eq!(
+        semantic::Node::from_value(&decoded),
+        semantic::Node::from_value(&data)
+    );
+}
+
+#[test]
+fn uses_the_json_names() {
+    let node = proto::to_proto(&sample());
+    let heading = &attribute(&node, "children").children[0];
+    assert_eq!(heading.tag, "heading");
+    assert_eq!(attribute(&node, "declarations").children[0].tag, "linkReference");
+
+    let reference = &attribute(&node, "declarations").children[0].children[0];
+    assert_eq!(attribute(reference, "referenceType").tag, "full");
+}
+
+#[test]
+fn rejects_an_unknown_tag() {
+    let mut node = proto::to_proto(&sample());
+    node.attributes[0].value.as_mut().unwrap().children[0].tag = "nonsense".to_owned();
+    assert!(proto::from_proto::<markdown::Markdown>(&node).is_err());
+}
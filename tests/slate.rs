@@ -0,0 +1,687 @@
+extern crate semantic;
+extern crate semantic_editor;
+#[macro_use]
+extern crate serde_json;
+
+use std::collections;
+use std::io;
+use std::process;
+
+use semantic_editor::data;
+use semantic_editor::data::markdown;
+use semantic_editor::data::slate;
+use semantic_editor::schema::se::slate as slate_proto;
+
+fn text(content: &str) -> markdown::Inline {
+    markdown::Inline::Text(markdown::Text {
+        content: content.to_owned(),
+    })
+}
+
+fn heading(content: &str) -> markdown::Block {
+    markdown::Block::Heading(markdown::Heading {
+        depth: 1,
+        children: vec![text(content)],
+    })
+}
+
+fn code(value: &str) -> markdown::Block {
+    markdown::Block::Code(markdown::Code {
+        lang: None,
+        value: value.to_owned(),
+    })
+}
+
+fn paragraph(children: Vec<markdown::Block>) -> markdown::Block {
+    markdown::Block::Paragraph(markdown::Paragraph { children })
+}
+
+fn inlines(children: Vec<markdown::Inline>) -> markdown::Block {
+    markdown::Block::Heading(markdown::Heading { depth: 2, children })
+}
+
+fn assert_round_trips(children: Vec<markdown::Block>) {
+    let document = markdown::Markdown {
+        children,
+        declarations: vec![],
+    };
+    let value = slate::to_slate_value(&document).unwrap();
+    assert_eq!(
+        semantic::json::to_json(&slate::from_slate_value(&value).unwrap()),
+        semantic::json::to_json(&document)
+    );
+}
+
+#[test]
+fn round_trips_lists() {
+    let item = |checked, children| markdown::ListItem {
+        loose: false,
+        checked,
+        children,
+    };
+    assert_round_trips(vec![
+        markdown::Block::List(markdown::List {
+            ordered: false,
+            start: None,
+            loose: false,
+            children: vec![
+                item(None, vec![heading("a")]),
+                item(
+                    Some(true),
+                    vec![markdown::Block::List(markdown::List {
+                        ordered: true,
+                        start: Some(3),
+                        loose: true,
+                        children: vec![item(Some(false), vec![heading("b")]), item(None, vec![])],
+                    })],
+                ),
+            ],
+        }),
+        markdown::Block::List(markdown::List {
+            ordered: true,
+            start: None,
+            loose: false,
+            children: vec![],
+        }),
+    ]);
+}
+
+#[test]
+fn round_trips_code_with_and_without_a_language() {
+    assert_round_trips(vec![
+        code("plain"),
+        markdown::Block::Code(markdown::Code {
+            lang: Some("rust".to_owned()),
+            value: "fn main() {}\n".to_owned(),
+        }),
+        markdown::Block::Code(markdown::Code {
+            lang: Some(String::new()),
+            value: String::new(),
+        }),
+    ]);
+}
+
+#[test]
+fn round_trips_inline_marks() {
+    assert_round_trips(vec![inlines(vec![
+        text("plain "),
+        markdown::Inline::Emphasis(markdown::Emphasis {
+            children: vec![text("emphasis")],
+        }),
+        markdown::Inline::Strong(markdown::Strong {
+            children: vec![
+                text("strong "),
+                markdown::Inline::Delete(markdown::Delete {
+                    children: vec![text("deleted")],
+                }),
+                markdown::Inline::InlineCode(markdown::InlineCode {
+                    value: "code".to_owned(),
+                }),
+            ],
+        }),
+        markdown::Inline::Break(markdown::Break),
+        markdown::Inline::InlineCode(markdown::InlineCode {
+            value: "x < y".to_owned(),
+        }),
+    ])]);
+}
+
+#[test]
+fn round_trips_links() {
+    let link = |title: Option<&str>, url: &str, children| {
+        markdown::Inline::Link(markdown::Link {
+            title: title.map(str::to_owned),
+            url: url.to_owned(),
+            children,
+        })
+    };
+    assert_round_trips(vec![inlines(vec![
+        link(None, "http://a", vec![text("a")]),
+        text(" and "),
+        link(
+            Some("B"),
+            "http://b",
+            vec![
+                text("b "),
+                markdown::Inline::Emphasis(markdown::Emphasis {
+                    children: vec![text("emphasized")],
+                }),
+            ],
+        ),
+        markdown::Inline::Strong(markdown::Strong {
+            children: vec![link(None, "http://c", vec![text("strong")])],
+        }),
+    ])]);
+}
+
+#[test]
+fn round_trips_tables() {
+    let row = |cells: &[&str]| markdown::TableRow {
+        children: cells
+            .iter()
+            .map(|c| markdown::TableCell {
+                children: vec![heading(c)],
+            })
+            .collect(),
+    };
+    assert_round_trips(vec![markdown::Block::Table(markdown::Table {
+        align: vec![
+            Some(markdown::AlignType::Left),
+            None,
+            Some(markdown::AlignType::Right),
+        ],
+        children: vec![row(&["a", "b", "c"]), row(&["1", "2", "3"])],
+    })]);
+}
+
+fn schema() -> slate_proto::Schema {
+    slate::schema(&semantic::Registry::of::<markdown::Markdown>())
+}
+
+fn keys(rules: &collections::HashMap<String, slate_proto::Rule>) -> Vec<&str> {
+    let mut keys = rules.keys().map(|k| k.as_str()).collect::<Vec<_>>();
+    keys.sort();
+    keys
+}
+
+fn validation<'a>(rule: &'a slate_proto::Rule, key: &str) -> &'a str {
+    &rule.data[key].js_code
+}
+
+#[test]
+fn schema_lists_the_top_level_nodes_of_the_document() {
+    let document = schema().document.unwrap();
+    assert!(document.data.is_empty());
+    assert_eq!(document.nodes.len(), 1);
+    assert_eq!(
+        document.nodes[0].types,
+        vec![
+            "blockquote",
+            "code",
+            "heading",
+            "html",
+            "image",
+            "list",
+            "paragraph",
+            "table",
+            "thematicBreak",
+            "yaml",
+        ]
+    );
+    assert_eq!(document.nodes[0].objects, vec!["block", "inline"]);
+    assert_eq!((document.nodes[0].min, document.nodes[0].max), (0, 0));
+    assert!(document.marks.is_empty());
+}
+
+#[test]
+fn schema_has_rules_for_blocks_and_inlines_but_not_for_marks() {
+    let schema = schema();
+    assert_eq!(
+        keys(&schema.blocks),
+        vec![
+            "blockquote",
+            "definition",
+            "footnoteDefinition",
+            "heading",
+            "list",
+            "listItem",
+            "paragraph",
+            "table",
+            "tableCell",
+            "tableRow",
+            "thematicBreak",
+            "yaml",
+        ]
+    );
+    assert_eq!(
+        keys(&schema.inlines),
+        vec![
+            "break",
+            "code",
+            "footnote",
+            "footnoteReference",
+            "html",
+            "image",
+            "imageReference",
+            "link",
+            "linkReference",
+        ]
+    );
+}
+
+#[test]
+fn schema_checks_the_depth_and_children_of_headings() {
+    let heading = &schema().blocks["heading"];
+    assert!(!heading.is_void);
+    assert_eq!(
+        validation(heading, "depth"),
+        "function (value) { \
+         return Number.isInteger(value) && value >= 0 && value >= 1 && value <= 6; }"
+    );
+    assert_eq!(heading.nodes.len(), 1);
+    assert!(heading.nodes[0].types.is_empty());
+    assert_eq!(heading.nodes[0].objects, vec!["inline", "text"]);
+    assert_eq!((heading.nodes[0].min, heading.nodes[0].max), (0, 0));
+    assert_eq!(heading.marks, vec!["emphasis", "strong", "delete", "inlineCode"]);
+}
+
+#[test]
+fn schema_makes_thematic_breaks_void() {
+    let thematic_break = &schema().blocks["thematicBreak"];
+    assert!(thematic_break.is_void);
+    assert!(thematic_break.data.is_empty());
+    assert!(thematic_break.nodes.is_empty());
+    assert!(thematic_break.marks.is_empty());
+}
+
+#[test]
+fn schema_requires_links_to_have_a_url() {
+    let link = &schema().inlines["link"];
+    assert_eq!(
+        validation(link, "url"),
+        r#"function (value) { return typeof value === "string" && value.length > 0; }"#
+    );
+    assert_eq!(
+        validation(link, "title"),
+        "function (value) { \
+         return (value === null || value === undefined || typeof value === \"string\"); }"
+    );
+    assert_eq!(link.nodes[0].objects, vec!["inline", "text"]);
+}
+
+#[test]
+fn schema_only_allows_the_children_of_a_class() {
+    let schema = schema();
+    let list = &schema.blocks["list"];
+    assert_eq!(list.nodes[0].types, vec!["listItem"]);
+    assert_eq!(list.nodes[0].objects, vec!["block"]);
+    assert_eq!(
+        validation(list, "start"),
+        "function (value) { \
+         return (value === null || value === undefined || Number.isInteger(value) && value >= 0); }"
+    );
+    assert_eq!(schema.blocks["tableRow"].nodes[0].types, vec!["tableCell"]);
+}
+
+fn normalization(rule: &slate_proto::Rule) -> &str {
+    &rule.normalize.as_ref().unwrap().js_code
+}
+
+/// Runs a script with Node.js and parses what it logs as JSON, or returns `None` if Node.js isn't
+/// installed.
+fn run_js(script: &str) -> Option<serde_json::Value> {
+    let output = match process::Command::new("node").arg("-e").arg(script).output() {
+        Ok(output) => output,
+        Err(ref e) if e.kind() == io::ErrorKind::NotFound => {
+            eprintln!("skipping the evaluation of the generated JavaScript without Node.js");
+            return None;
+        }
+        Err(e) => panic!("couldn't run node: {}", e),
+    };
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    Some(serde_json::from_slice(&output.stdout).unwrap())
+}
+
+/// Checks each of `values` for the `key` of a node of a rule with its `data` validation, and fixes
+/// it with its `normalize` function, returning the results of both as `[checks, fixes]`.
+fn check_and_fix(
+    rule: &slate_proto::Rule,
+    key: &str,
+    values: &serde_json::Value,
+) -> Option<serde_json::Value> {
+    run_js(&format!(
+        r#"
+        var check = {check};
+        var normalize = {normalize};
+        function fix(value) {{
+          var fixed;
+          var data = {{ set: function (key, value) {{ var d = {{}}; d[key] = value; return d; }} }};
+          var change = {{
+            setNodeByKey: function (node, properties) {{ fixed = properties.data[{key}]; }},
+          }};
+          var error = {{
+            code: "node_data_invalid",
+            key: {key},
+            value: value,
+            node: {{ key: "1", data: data }},
+          }};
+          normalize(change, error);
+          return fixed === undefined ? "unchanged" : fixed;
+        }}
+        var values = {values};
+        console.log(JSON.stringify([values.map(check), values.map(fix)]));
+        "#,
+        check = validation(rule, key),
+        normalize = normalization(rule),
+        key = serde_json::to_string(key).unwrap(),
+        values = values,
+    ))
+}
+
+/// Reports the Slate change that the `normalize` function of a rule makes for an error.
+fn normalize(rule: &slate_proto::Rule, code: &str) -> Option<serde_json::Value> {
+    run_js(&format!(
+        r#"
+        var normalize = {normalize};
+        var changes = [];
+        var change = {{
+          removeNodeByKey: function (key) {{ changes.push(key); }},
+        }};
+        normalize(change, {{ code: {code}, node: {{ key: "node" }}, child: {{ key: "child" }} }});
+        console.log(JSON.stringify(changes));
+        "#,
+        normalize = normalization(rule),
+        code = serde_json::to_string(code).unwrap(),
+    ))
+}
+
+#[test]
+fn schema_clamps_the_depth_of_headings_into_its_range() {
+    let heading = &schema().blocks["heading"];
+    assert!(normalization(heading).contains(
+        "\"depth\": function (value) { \
+         if (Number.isInteger(value) && value >= 0 && value >= 1 && value <= 6) { return value; } \
+         if (typeof value === \"number\" && isFinite(value)) { \
+         var fixed = Math.min(Math.max(Math.round(value), 1), 6); "
+    ));
+
+    let values = json!([2, 0, 7, 3.6, -5.5, 1e300, "3", null]);
+    if let Some(results) = check_and_fix(heading, "depth", &values) {
+        assert_eq!(
+            results,
+            json!([
+                [true, false, false, false, false, false, false, false],
+                [2, 1, 6, 4, 1, 6, 1, 1],
+            ])
+        );
+    }
+}
+
+#[test]
+fn schema_fixes_optional_unsigned_numbers() {
+    let list = &schema().blocks["list"];
+    let values = json!([null, 3, -3, 2.4, "x"]);
+    if let Some(results) = check_and_fix(list, "start", &values) {
+        assert_eq!(
+            results,
+            json!([[true, true, false, false, false], [null, 3, 0, 2, null]])
+        );
+    }
+}
+
+#[test]
+fn schema_replaces_empty_urls_of_links() {
+    let link = &schema().inlines["link"];
+    assert!(normalization(link).contains(
+        "\"url\": function (value) { \
+         if (typeof value === \"string\" && value.length > 0) { return value; } \
+         return \"url\"; }"
+    ));
+
+    let values = json!(["https://example.com", "", null, 1]);
+    if let Some(results) = check_and_fix(link, "url", &values) {
+        assert_eq!(
+            results,
+            json!([
+                [true, false, false, false],
+                ["https://example.com", "url", "url", "url"],
+            ])
+        );
+    }
+}
+
+#[test]
+fn schema_checks_enumerations_by_their_tags() {
+    let table = &schema().blocks["table"];
+    assert_eq!(
+        validation(table, "align"),
+        "function (value) { return Array.isArray(value) && value.every(function (item) { \
+         return (item === null || item === undefined || \
+         [\"left\", \"right\", \"center\"].indexOf(item) >= 0); }); }"
+    );
+
+    let values = json!([["left", null, "center"], ["middle"], "left"]);
+    if let Some(results) = check_and_fix(table, "align", &values) {
+        assert_eq!(
+            results,
+            json!([[true, false, false], [["left", null, "center"], [], []]])
+        );
+    }
+}
+
+#[test]
+fn schema_removes_invalid_children_and_nodes_missing_children() {
+    let schema = schema();
+    let heading = &schema.blocks["heading"];
+    let document = schema.document.as_ref().unwrap();
+
+    if let Some(changes) = normalize(heading, "child_type_invalid") {
+        assert_eq!(changes, json!(["child"]));
+    }
+    if let Some(changes) = normalize(heading, "child_required") {
+        assert_eq!(changes, json!(["node"]));
+    }
+    if let Some(changes) = normalize(document, "child_object_invalid") {
+        assert_eq!(changes, json!(["child"]));
+    }
+    if let Some(changes) = normalize(document, "child_required") {
+        assert_eq!(changes, json!([]));
+    }
+}
+
+/// A store holding a document with the Slate paths `[0]` to `[3]`, and the key of the document.
+fn store() -> (semantic::Store, semantic::NodeKey) {
+    let store = semantic::Store::new(&data::Data::Markdown(markdown::Markdown {
+        children: vec![
+            heading("One"),
+            paragraph(vec![code("a"), code("b")]),
+            heading("Two"),
+            paragraph(vec![code("c")]),
+        ],
+        declarations: vec![],
+    }));
+    let key = store.children(store.root())[0];
+    (store, key)
+}
+
+/// Applies operations to the document, and returns the keys of the nodes that they changed.
+fn apply(
+    store: &mut semantic::Store,
+    key: semantic::NodeKey,
+    operations: &[serde_json::Value],
+) -> Vec<semantic::NodeKey> {
+    let operations = operations
+        .iter()
+        .map(slate::operation_from_json)
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    let mut snapshot = semantic::Snapshot::from_node(store.to_node(store.root()).unwrap());
+    let changed = slate::apply_operations(store, &mut snapshot, key, &operations).unwrap();
+    // The snapshot gets the same edits as the store
+    assert_eq!(snapshot.to_node(), store.to_node(store.root()).unwrap());
+    changed
+}
+
+/// The key of the top-level block at `index`.
+fn block(store: &semantic::Store, key: semantic::NodeKey, index: usize) -> semantic::NodeKey {
+    store.children(store.children(key)[0])[index]
+}
+
+/// The keys of a node and all of its descendants.
+fn subtree(store: &semantic::Store, key: semantic::NodeKey) -> Vec<semantic::NodeKey> {
+    let mut keys = vec![key];
+    for &child in store.children(key) {
+        keys.extend(subtree(store, child));
+    }
+    keys
+}
+
+fn markdown(store: &semantic::Store) -> serde_json::Value {
+    match store.to_value::<data::Data>().unwrap() {
+        data::Data::Markdown(markdown) => semantic::json::to_json(&markdown),
+        data::Data::Json(_) => panic!("expected a Markdown document"),
+    }
+}
+
+#[test]
+fn moving_a_paragraph_keeps_its_keys() {
+    let (mut store, key) = store();
+    let moved = subtree(&store, block(&store, key, 1));
+    let others = [0, 2, 3]
+        .iter()
+        .map(|&i| block(&store, key, i))
+        .collect::<Vec<_>>();
+
+    apply(
+        &mut store,
+        key,
+        &[json!({"type": "move_node", "path": [1], "newPath": [3]})],
+    );
+
+    assert_eq!(subtree(&store, block(&store, key, 3)), moved);
+    assert_eq!(
+        [0, 1, 2]
+            .iter()
+            .map(|&i| block(&store, key, i))
+            .collect::<Vec<_>>(),
+        others
+    );
+    assert_eq!(
+        markdown(&store)["children"][3],
+        semantic::json::to_json(&paragraph(vec![code("a"), code("b")]))
+    );
+}
+
+#[test]
+fn moving_a_block_into_another_keeps_its_keys() {
+    let (mut store, key) = store();
+    let items = |store: &semantic::Store, index| {
+        let paragraph = store.children(block(store, key, index))[0];
+        store.children(store.children(paragraph)[0]).to_vec()
+    };
+    let moved = subtree(&store, items(&store, 3)[0]);
+
+    apply(
+        &mut store,
+        key,
+        &[json!({"type": "move_node", "path": [3, 0], "newPath": [1, 1]})],
+    );
+
+    assert_eq!(items(&store, 1).len(), 3);
+    assert_eq!(subtree(&store, items(&store, 1)[1]), moved);
+    assert!(items(&store, 3).is_empty());
+    assert_eq!(
+        markdown(&store)["children"][1]["children"][1]["value"],
+        json!("c")
+    );
+}
+
+#[test]
+fn inserting_and_removing_keep_the_keys_of_the_siblings() {
+    let (mut store, key) = store();
+    let blocks = (0..4).map(|i| block(&store, key, i)).collect::<Vec<_>>();
+    let node = json!({
+        "object": "block",
+        "type": "thematicBreak",
+        "data": {},
+        "nodes": [],
+    });
+
+    apply(
+        &mut store,
+        key,
+        &[
+            json!({"type": "insert_node", "path": [2], "node": node}),
+            json!({"type": "remove_node", "path": [0]}),
+        ],
+    );
+
+    assert_eq!(block(&store, key, 0), blocks[1]);
+    assert!(!blocks.contains(&block(&store, key, 1)));
+    assert_eq!(block(&store, key, 2), blocks[2]);
+    assert_eq!(block(&store, key, 3), blocks[3]);
+    assert!(!store.contains(blocks[0]));
+    assert_eq!(markdown(&store)["children"][1]["type"], json!("thematicBreak"));
+}
+
+#[test]
+fn editing_text_keeps_the_keys_of_the_blocks() {
+    let (mut store, key) = store();
+    let blocks = (0..4).map(|i| block(&store, key, i)).collect::<Vec<_>>();
+
+    let changed = apply(
+        &mut store,
+        key,
+        &[json!({"type": "insert_text", "path": [2, 0], "offset": 3, "text": "!"})],
+    );
+
+    assert_eq!((0..4).map(|i| block(&store, key, i)).collect::<Vec<_>>(), blocks);
+    let edited = subtree(&store, blocks[2]);
+    assert!(!changed.is_empty() && changed.iter().all(|k| edited.contains(k)));
+    assert_eq!(
+        markdown(&store)["children"][2]["children"][0]["value"],
+        json!("Two!")
+    );
+}
+
+#[test]
+fn splitting_and_merging_keep_the_key_of_the_first_part() {
+    let (mut store, key) = store();
+    let first = block(&store, key, 1);
+    let codes = store.children(store.children(store.children(first)[0])[0]).to_vec();
+
+    apply(
+        &mut store,
+        key,
+        &[json!({"type": "split_node", "path": [1], "position": 1, "properties": {}})],
+    );
+    assert_eq!(block(&store, key, 1), first);
+    let second = block(&store, key, 2);
+    assert_eq!(
+        markdown(&store)["children"][2],
+        semantic::json::to_json(&paragraph(vec![code("b")]))
+    );
+
+    apply(
+        &mut store,
+        key,
+        &[json!({"type": "merge_node", "path": [2], "position": 1})],
+    );
+    assert_eq!(block(&store, key, 1), first);
+    assert!(!store.contains(second));
+    assert_eq!(
+        store.children(store.children(store.children(first)[0])[0])[0],
+        codes[0]
+    );
+    assert_eq!(
+        markdown(&store)["children"][1],
+        semantic::json::to_json(&paragraph(vec![code("a"), code("b")]))
+    );
+}
+
+#[test]
+fn later_operations_see_the_empty_texts_that_earlier_ones_left() {
+    let (mut store, key) = store();
+    let first = block(&store, key, 2);
+
+    // This is how Slate splits a block: first the text, and then the block between the texts.
+    // Splitting at the start leaves an empty text, which only Slate has
+    apply(
+        &mut store,
+        key,
+        &[
+            json!({"type": "split_node", "path": [2, 0], "position": 0, "properties": {}}),
+            json!({"type": "split_node", "path": [2], "position": 1, "properties": {}}),
+        ],
+    );
+
+    assert_eq!(block(&store, key, 2), first);
+    let markdown = markdown(&store);
+    assert_eq!(markdown["children"][2]["children"], json!([]));
+    assert_eq!(markdown["children"][3], semantic::json::to_json(&heading("Two")));
+    assert_eq!(markdown["children"][4]["type"], json!("paragraph"));
+}